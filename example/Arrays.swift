@@ -0,0 +1,36 @@
+var numbers = [3, 1, 4]
+numbers.append(1)
+numbers.insert(9, at: 0)
+numbers[1] += 10
+print(numbers)
+print(numbers.count)
+print(numbers.isEmpty)
+
+let removed = numbers.remove(at: 2)
+print(removed)
+
+// copies share a buffer until one of them is mutated
+var copy = numbers
+copy[0] = 0
+print(numbers)
+print(copy)
+
+var grid: [[Int]] = [[1, 2], [3]]
+var other = grid
+other[1].append(4)
+print(grid)
+print(other)
+
+let names: [String] = ["Ann", "Bob"]
+print(names)
+
+var total = 0
+for number in numbers {
+    if number == 9 { continue }
+    total += number
+}
+print(total)
+
+for _ in names {
+    print("name")
+}
@@ -0,0 +1,31 @@
+let flags = 0b1100
+let mask = 0b1010
+print(flags & mask)
+print(flags | mask)
+print(flags ^ mask)
+print(~flags)
+print(1 << 4)
+print(256 >> 2)
+print(-16 >> 2)
+
+print(2 * 1 << 3)
+print(flags & mask == 8)
+print(1 + 6 & 3)
+
+let amount = -2
+print(16 << amount)
+print(1 << 64)
+print(-1 >> 100)
+
+let byte: UInt8 = 0b1000_0001
+print(byte << 1)
+print(byte >> 7)
+print(~byte)
+print(byte &<< 9)
+print(byte &>> 15)
+let small: Int8 = -128
+print(small >> 3)
+print(small &>> 8)
+
+let maybeShift: Int? = 1 << 2
+print(maybeShift)
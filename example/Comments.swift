@@ -0,0 +1,29 @@
+// Line comments run to the end of the line.
+/* Block comments can span
+   several lines, /* and they nest */ like in Swift. */
+
+/// A compass direction.
+enum Direction {
+    case north, south // trailing comment
+}
+
+/// Returns the sum of two numbers.
+/// The second line of the doc comment.
+func add(_ a: Int, _ b: Int) -> Int {
+    return a + b // the result
+}
+
+/**
+ * Doubles a number.
+ */
+func double(_ value: Int) -> Int {
+    return value * 2
+}
+
+let total = add(1, /* inline */ 2)
+print(total)
+print(double(total)) /* after a statement */
+print(10/2)
+print("// not a comment")
+let divided = 8 /* eight */ / 2
+print(divided)
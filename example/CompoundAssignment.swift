@@ -0,0 +1,40 @@
+var total = 10
+total += 5
+total -= 3
+total *= 4
+total /= 6
+print(total)
+total %= 5
+print(total)
+print(17 % 5)
+print(-17 % 5)
+
+var bits: UInt8 = 0b1111_0000
+bits &= 0b1010_1010
+print(bits)
+bits |= 0b0000_0011
+print(bits)
+bits ^= 0xFF
+print(bits)
+bits <<= 2
+print(bits)
+bits >>= 3
+print(bits)
+
+var ratio = 1.5
+ratio *= 4
+ratio -= 0.5
+print(ratio)
+
+var greeting = "Hello"
+greeting += ", world"
+print(greeting)
+
+var point = (x: 1, y: 2)
+point.x += 10
+point.y = point.x * 2
+print(point)
+
+var nested = (1, (2, 3))
+nested.1.0 += 40
+print(nested)
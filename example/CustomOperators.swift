@@ -0,0 +1,63 @@
+precedencegroup ExponentiationPrecedence {
+    associativity: right
+    higherThan: MultiplicationPrecedence
+}
+
+infix operator **: ExponentiationPrecedence
+infix operator <>
+prefix operator √
+postfix operator %%
+
+func ** (base: Int, exponent: Int) -> Int {
+    var result = 1
+    var count = 0
+    while count < exponent {
+        result *= base
+        count += 1
+    }
+    return result
+}
+
+func ** (base: Double, exponent: Int) -> Double {
+    var result = 1.0
+    var count = 0
+    while count < exponent {
+        result *= base
+        count += 1
+    }
+    return result
+}
+
+prefix func √ (value: Double) -> Double {
+    var guess = value / 2.0
+    var step = 0
+    while step < 20 {
+        guess = (guess + value / guess) / 2.0
+        step += 1
+    }
+    return guess
+}
+
+postfix func %% (value: Int) -> Double {
+    return Double(value) / 100.0
+}
+
+enum Direction {
+    case north, east, south, west
+
+    static func <> (lhs: Direction, rhs: Direction) -> Bool {
+        return lhs != rhs
+    }
+}
+
+print(2 ** 10)
+print(2 ** 3 ** 2)
+print(1 + 2 * 3 ** 2)
+print(1.5 ** 2)
+print(√16.0)
+print(√(2.0 ** 2))
+print(25%%)
+print(Direction.north <> Direction.south)
+print(Direction.east <> .east)
+let base = 3
+print(-base ** 3)
@@ -0,0 +1,139 @@
+enum LookupError: Error {
+    case missing(String)
+}
+
+/// the deferred bodies run last to first once the function returns
+func describe(_ value: Int) -> String {
+    defer { print("cleanup 1 for \(value)") }
+    defer { print("cleanup 2 for \(value)") }
+    if value < 0 {
+        return "negative"
+    }
+    defer { print("cleanup 3 for \(value)") }
+    return value == 0 ? "zero" : "positive"
+}
+
+print(describe(-1))
+print(describe(0))
+print(describe(5))
+
+/// the result is computed before the deferred body changes the counter
+var counter = 0
+func next() -> Int {
+    defer { counter += 1 }
+    return counter
+}
+print(next())
+print(next())
+print(counter)
+
+// every iteration leaves the loop body, whether it breaks, continues or falls through
+for i in [1, 2, 3, 4, 5] {
+    defer { print("end of iteration \(i)") }
+    if i == 2 {
+        continue
+    }
+    if i == 4 {
+        break
+    }
+    print("iteration \(i)")
+}
+
+var n = 0
+while n < 3 {
+    n += 1
+    defer { print("while body \(n) done") }
+    if n == 2 {
+        continue
+    }
+    print("while body \(n)")
+}
+
+// scopes without a loop run their deferred bodies too
+do {
+    defer { print("leaving do") }
+    print("inside do")
+}
+if counter == 2 {
+    defer { print("leaving if") }
+    print("inside if")
+}
+
+let grade = 2
+switch grade {
+case 1:
+    print("one")
+case 2:
+    defer { print("leaving case 2") }
+    if counter > 0 {
+        break
+    }
+    print("not printed")
+default:
+    print("other")
+}
+
+/// thrown errors leave through the deferred bodies as well
+func find(_ key: String, among keys: [String]) throws -> Int {
+    defer { print("searched for \(key)") }
+    var index = 0
+    for candidate in keys {
+        defer { index += 1 }
+        if candidate == key {
+            return index
+        }
+    }
+    throw LookupError.missing(key)
+}
+
+let keys = ["a", "b", "c"]
+do {
+    defer { print("do body finished") }
+    print(try find("b", among: keys))
+    print(try find("z", among: keys))
+    print("not printed")
+} catch LookupError.missing(let key) {
+    print("missing \(key)")
+}
+print(try? find("c", among: keys))
+print((try? find("q", among: keys)) ?? -1)
+
+// a deferred body only sees the variables declared before it
+func shadowing() {
+    let name = "outer"
+    defer { print("deferred sees \(name)") }
+    if true {
+        let name = "inner"
+        print("block sees \(name)")
+        return
+    }
+}
+shadowing()
+
+// closures inlined into map keep their own deferred bodies
+let doubled = [1, 2, 3].map { (x: Int) -> Int in
+    defer { print("mapped \(x)") }
+    if x == 2 {
+        return 0
+    }
+    return x * 2
+}
+print(doubled)
+
+// a deferred body can contain loops, with breaks of its own
+func nested() {
+    defer {
+        for i in [0, 1, 2, 3] {
+            if i == 2 {
+                break
+            }
+            defer { print("inner deferred \(i)") }
+        }
+        print("outer deferred")
+    }
+    print("nested body")
+}
+nested()
+
+defer { print("end of program") }
+print("last statement")
@@ -0,0 +1,44 @@
+var stock = ["apple": 3, "pear": 0]
+stock["plum"] = 7
+stock["apple"] = 5
+print(stock)
+print(stock["apple"])
+print(stock["kiwi"])
+
+// assigning nil removes a key
+stock["pear"] = nil
+print(stock.count)
+print(stock.keys)
+print(stock.values)
+
+let plums = stock.removeValue(forKey: "plum")
+print(plums)
+
+var backup = stock
+backup["cherry"] = 12
+print(stock)
+print(backup)
+
+for (fruit, count) in backup {
+    print("\(fruit): \(count)")
+}
+
+var counts: [Int: Int] = [:]
+for number in [1, 2, 2, 3, 3, 3] {
+    counts[number] = (counts[number] ?? 0) + 1
+}
+print(counts)
+
+var primes: Set<Int> = [2, 3, 5, 7]
+print(primes.contains(3))
+print(primes.contains(4))
+print(primes.insert(11))
+print(primes.insert(2))
+print(primes.remove(7))
+print(primes)
+
+var total = 0
+for prime in primes {
+    total += prime
+}
+print(total)
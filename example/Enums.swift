@@ -0,0 +1,45 @@
+enum Direction: Int {
+    case north = 1, east, south, west
+}
+
+enum Shape {
+    case point(x: Int, y: Int)
+    case circle(Int)
+    case empty
+}
+
+let heading = Direction.east
+print(heading)
+print(heading.rawValue)
+print(Direction(rawValue: 3))
+print(Direction(rawValue: 9))
+
+let shapes: Shape = .point(x: 1, y: 2)
+print(shapes)
+
+switch shapes {
+case let .point(x, y):
+    print(x + y)
+case .circle(let r):
+    print(r * r)
+case .empty:
+    print(0)
+}
+
+switch Direction(rawValue: 4) {
+case .some(.west):
+    print(4)
+case .some(let direction):
+    print(direction)
+case .none:
+    print(0)
+}
+
+var current = Shape.circle(3)
+current = .empty
+switch current {
+case .circle(1), .empty:
+    print(1)
+default:
+    print(2)
+}
@@ -0,0 +1,130 @@
+enum ParseError: Error {
+    case empty
+    case invalid(String)
+    case outOfRange(Int)
+}
+
+enum NetworkError: Error, CustomStringConvertible {
+    case offline
+    var description: String { "the network is offline" }
+}
+
+/// the value of a digit name such as "two"
+func parseDigit(_ text: String) throws -> Int {
+    if text == "" {
+        throw ParseError.empty
+    }
+    let names = ["zero", "one", "two", "three"]
+    var index = 0
+    for name in names {
+        if name == text {
+            return index
+        }
+        index += 1
+    }
+    if text == "ten" {
+        throw ParseError.outOfRange(10)
+    }
+    throw ParseError.invalid(text)
+}
+
+// errors of calls marked with `try` propagate out of a throwing function
+func sum(_ texts: [String]) throws -> Int {
+    var total = 0
+    for text in texts {
+        total += try parseDigit(text)
+    }
+    return total
+}
+
+do {
+    print(try sum(["one", "two", "three"]))
+    print(try sum(["one", "seven"]))
+    print("not reached")
+} catch ParseError.invalid(let text) {
+    print("invalid digit: \(text)")
+} catch {
+    print("other error: \(error)")
+}
+
+for text in ["", "ten", "two"] {
+    do {
+        print(try parseDigit(text))
+    } catch let error as ParseError {
+        switch error {
+        case .empty: print("empty input")
+        case .outOfRange(let value): print("\(value) is out of range")
+        case .invalid(let text): print("invalid \(text)")
+        }
+    }
+}
+
+// `try?` turns an error into nil, `try!` traps on one
+print(try? parseDigit("three"))
+print(try? parseDigit("many"))
+if let digit = try? parseDigit("one") {
+    print("parsed \(digit)")
+}
+print(try! parseDigit("zero"))
+
+func connect(online: Bool) throws {
+    if !online {
+        throw NetworkError.offline
+    }
+    print("connected")
+}
+
+do {
+    try connect(online: true)
+    try connect(online: false)
+} catch is ParseError {
+    print("parse error")
+} catch let failure {
+    print("failed: \(failure)")
+}
+
+// the collection methods rethrow the errors of their closure
+print(try? ["one", "two"].map { try parseDigit($0) })
+print(try? ["one", "lots"].map { text in try parseDigit(text) })
+do {
+    let even = try ["zero", "one", "two"].filter { try parseDigit($0) % 2 == 0 }
+    print(even)
+} catch {
+    print(error)
+}
+
+protocol Loader {
+    func load(_ id: Int) throws -> String
+}
+
+enum Disk: Loader {
+    case main
+    func load(_ id: Int) throws -> String {
+        if id < 0 {
+            throw ParseError.outOfRange(id)
+        }
+        return "file \(id)"
+    }
+}
+
+// a method that cannot throw meets a throwing requirement
+enum Cache: Loader {
+    case shared
+    func load(_ id: Int) -> String {
+        return "cached \(id)"
+    }
+}
+
+let loaders: [any Loader] = [Disk.main, Cache.shared]
+for loader in loaders {
+    print(try? loader.load(-1))
+    print(try loader.load(3))
+}
+
+// a rethrows function only throws when the function passed to it does
+func applyTwice(_ text: String, _ transform: (String) throws -> Int) rethrows -> Int {
+    return try transform(text) + transform(text)
+}
+print(applyTwice("abc") { $0 == "abc" ? 1 : 0 })
+print(try applyTwice("three", parseDigit))
+print(try? applyTwice("x", parseDigit))
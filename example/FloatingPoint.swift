@@ -0,0 +1,31 @@
+let pi = 3.14159
+print(pi)
+print(1.0)
+print(0.1 + 0.2)
+print(1.5e3)
+print(2.5e-7)
+print(1e16)
+print(0x1p-2)
+print(0x1.8p1)
+print(-0.0)
+
+let ratio: Double = 1
+print(ratio / 3)
+let half: Float = 0.5
+print(half * 3)
+print(Float(0.1))
+
+let count = 7
+let average = Double(count) / 2
+print(average)
+print(Int(average))
+print(Int(-2.9))
+print(average > 3 && average <= 3.5)
+print(1 + average)
+print(-average)
+print(average == 3.5)
+
+let maybe: Double? = 2
+print(maybe)
+print("area: \(pi * 2 * 2)")
+print((1.5, half))
@@ -0,0 +1,151 @@
+/// exchanges two values of any type
+func swapValues<T>(_ a: inout T, _ b: inout T) {
+    let temporary = a
+    a = b
+    b = temporary
+}
+
+func firstIndex<T: Equatable>(of target: T, among values: [T]) -> Int? {
+    var index = 0
+    for value in values {
+        if value == target {
+            return index
+        }
+        index += 1
+    }
+    return nil
+}
+
+func maximum<T>(_ values: [T]) -> T? where T: Comparable {
+    var best: T? = nil
+    for value in values {
+        if let current = best {
+            if current < value {
+                best = value
+            }
+        } else {
+            best = value
+        }
+    }
+    return best
+}
+
+func pair<A, B>(_ first: A, _ second: B) -> (A, B) {
+    return (first, second)
+}
+
+func increment(_ value: inout Int, by amount: Int) {
+    value += amount
+}
+
+var x = 1
+var y = 2
+swapValues(&x, &y)
+print("x = \(x), y = \(y)")
+
+var greeting = "hello"
+var name = "world"
+swapValues(&greeting, &name)
+print("\(greeting) \(name)")
+
+var numbers = [10, 20, 30]
+increment(&numbers[1], by: 5)
+swapValues(&numbers[0], &x)
+print("\(numbers) \(x)")
+
+print(firstIndex(of: 30, among: numbers) ?? -1)
+print(firstIndex(of: "b", among: ["a", "b"]))
+print(maximum([3.5, 1.25, 9.0]))
+let empty: [Int] = []
+print(maximum(empty))
+print(pair(1, "one"))
+
+protocol Shape {
+    var name: String { get }
+    func area() -> Double
+}
+
+enum Square: Shape {
+    case side(Double)
+    var name: String { "square" }
+    func area() -> Double {
+        switch self {
+        case .side(let side): return side * side
+        }
+    }
+}
+
+enum Circle: Shape {
+    case radius(Double)
+    var name: String { "circle" }
+    func area() -> Double {
+        switch self {
+        case .radius(let radius): return 3.0 * radius * radius
+        }
+    }
+}
+
+// one specialization per shape type, without a witness table
+func describe<S: Shape>(_ shapes: [S]) -> String {
+    var total = 0.0
+    for shape in shapes {
+        total += shape.area()
+    }
+    return "\(shapes.count) \(shapes[0].name)s with area \(total)"
+}
+
+print(describe([Square.side(2.0), Square.side(3.0)]))
+print(describe([Circle.radius(1.0)]))
+
+/// a value that may still be loading, for any type of value
+enum Loadable<Value> {
+    case loading
+    case loaded(Value)
+
+    func value(or fallback: Value) -> Value {
+        switch self {
+        case .loaded(let value): return value
+        case .loading: return fallback
+        }
+    }
+}
+
+extension Loadable {
+    var isLoaded: Bool {
+        switch self {
+        case .loaded: return true
+        case .loading: return false
+        }
+    }
+}
+
+enum Either<Left, Right> {
+    case left(Left)
+    case right(Right)
+}
+
+func loadedValues<T>(_ items: [Loadable<T>]) -> [T] {
+    var values: [T] = []
+    for item in items {
+        switch item {
+        case .loaded(let value): values.append(value)
+        case .loading: break
+        }
+    }
+    return values
+}
+
+let count = Loadable.loaded(3)
+let title: Loadable<String> = .loading
+print(count)
+print(count.value(or: 0))
+print(title.value(or: "untitled"))
+print(title.isLoaded)
+print([Loadable<Double>.loaded(1.5), .loading])
+print(loadedValues([count, .loading, .loaded(7)]))
+
+let parsed: Either<Int, String> = .right("not a number")
+switch parsed {
+case .left(let number): print("number \(number)")
+case .right(let message): print("error: \(message)")
+}
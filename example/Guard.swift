@@ -0,0 +1,42 @@
+let limit = 3
+
+func describe(_ value: Int?) -> Int {
+    guard let value = value else {
+        return -1
+    }
+    guard value < 100 else {
+        fatalError()
+    }
+    return value * 2
+}
+
+func countdown(from start: Int) {
+    var current = start
+    while true {
+        guard current > 0 else {
+            break
+        }
+        print(current)
+        current = current - 1
+    }
+}
+
+func firstOdd(upTo end: Int) -> Int? {
+    var n = 0
+    while n < end {
+        n = n + 1
+        guard n > limit else {
+            continue
+        }
+        if n / 2 * 2 != n {
+            return n
+        }
+    }
+    return nil
+}
+
+print(describe(21))
+print(describe(nil))
+countdown(from: 3)
+print(firstOdd(upTo: 10))
+print(firstOdd(upTo: 2))
@@ -0,0 +1,73 @@
+let numbers = [5, 3, 8, 1, 9, 2]
+
+// closures take their parameter types from the collection
+print(numbers.map { $0 * 2 })
+print(numbers.filter { n in n % 2 == 0 })
+print(numbers.reduce(0, +))
+print(numbers.reduce(0) { sum, n in sum + n * n })
+
+// sorting is stable
+print(numbers.sorted())
+print(numbers.sorted(by: >))
+let pairs = [(1, "b"), (0, "c"), (1, "a")]
+print(pairs.sorted { $0.0 < $1.0 })
+
+print(numbers.contains { $0 > 8 })
+print(numbers.first(where: { $0 > 4 }))
+print(numbers.first { $0 > 100 })
+
+let stock = ["apple": 3, "pear": 0, "plum": 7]
+let names = ["pear", "kiwi", "plum"]
+print(names.compactMap { stock[$0] })
+print(stock.filter { $0.value > 0 })
+
+// closures can have several statements and an explicit signature
+let sizes = numbers.map { (n: Int) -> String in
+    if n > 4 {
+        return "big"
+    }
+    return "small"
+}
+print(sizes)
+
+// closures can update the variables around them
+var total = 0
+numbers.forEach { total += $0 }
+print(total)
+
+let evens: Set<Int> = [2, 4, 6]
+print(evens.map { $0 / 2 }.sorted())
+
+// closures and functions are values of function types
+func double(_ n: Int) -> Int {
+    return n * 2
+}
+let increment = { (n: Int) -> Int in n + 1 }
+let transforms: [(Int) -> Int] = [double, increment]
+for transform in transforms {
+    print(numbers.map(transform))
+}
+
+func makeCounter() -> () -> Int {
+    var count = 0
+    return {
+        count += 1
+        return count
+    }
+}
+let counter = makeCounter()
+print(counter())
+print(counter())
+print(numbers.compactMap { $0 > 4 ? $0 : nil })
+
+// a closure passed as an argument shares an inout parameter with its function
+func repeatTwice(_ body: () -> Void) {
+    body()
+    body()
+}
+func addTwice(_ value: inout Int, _ amount: Int) {
+    repeatTwice { value += amount }
+}
+var score = 1
+addTwice(&score, 5)
+print(score)
@@ -0,0 +1,38 @@
+let million = 1_000_000
+print(million)
+print(0xFF)
+print(0o17)
+print(0b1010)
+print(0xdead_beef)
+print(1_000.5)
+
+let small: Int8 = -128
+let byte: UInt8 = 255
+print(small)
+print(byte)
+print(byte / 2)
+print(byte > 200)
+
+let big: UInt64 = 18_446_744_073_709_551_615
+print(big)
+print(big / 3 > 1)
+let word: Int32 = 0x7FFF_FFFF
+print(word)
+print(Int64(word) + 1)
+
+let total = Int(byte) + million
+print(total)
+print(UInt16(300))
+print(Int8(-2.9))
+print(Double(byte) / 2)
+print(UInt8(byte - 5))
+let bytes: (UInt8, Int16) = (1, -1)
+print(bytes)
+print("byte: \(byte), word: \(word)")
+
+switch byte {
+case 0xFF:
+    print("all bits set")
+default:
+    print("some bits clear")
+}
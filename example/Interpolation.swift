@@ -0,0 +1,19 @@
+enum Direction {
+    case north, south
+}
+
+let x = 41
+let name = "Swift"
+print("value: \(x + 1)")
+print("hello, \(name)!")
+print("flag: \(x == 41), nothing: \(x)")
+
+let heading = Direction.south
+let maybe: Int? = 3
+print("heading \(heading), maybe \(maybe), pair \((1, "two"))")
+print("nested \("inner \(x * 2)") done")
+print("percent 100% \(x)%")
+
+let greeting = "Hi \(name)"
+print(greeting + "!")
+print(greeting == "Hi Swift")
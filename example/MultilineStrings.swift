@@ -0,0 +1,32 @@
+let name = "Swift"
+let poem = """
+    Roses are red,
+      indented line
+    "quotes" need no escaping
+    Hello, \(name)!
+
+    joined \
+    line
+    """
+print(poem)
+
+func banner() {
+    let text = """
+        inside a function
+        """
+    print(text)
+}
+banner()
+
+let empty = """
+    """
+print(empty == "")
+
+print(#"raw \n "quoted" \(name)"#)
+print(#"escaped \#(name) and tab:\#tend"#)
+print(##"double "#hash"# \#n"##)
+let rawLines = #"""
+    C:\path\to\file
+    \#(name)
+    """#
+print(rawLines)
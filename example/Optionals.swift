@@ -0,0 +1,40 @@
+enum Direction: Int {
+    case north, east, south, west
+}
+
+var maybe: Int? = nil
+print(maybe)
+print(maybe ?? 42)
+maybe = 7
+print(maybe)
+print(maybe! + 1)
+
+if let value = maybe, value > 5 {
+    print(value)
+} else {
+    print(0)
+}
+
+let heading: Direction? = .south
+print(heading?.rawValue)
+let nothing: Optional<Direction> = nil
+print(nothing?.rawValue ?? -1)
+print(Direction(rawValue: 3) == .west)
+print(nothing == nil)
+
+var countdown: Int? = 3
+while let current = countdown {
+    print(current)
+    if current > 1 {
+        countdown = current - 1
+    } else {
+        countdown = nil
+    }
+}
+
+let inner: Int?? = 5
+print(inner)
+let fallback: Int? = nothing?.rawValue ?? maybe
+print(fallback)
+print(!(1 < 2) || true && 3 >= 3)
+print(maybe!)
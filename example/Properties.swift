@@ -0,0 +1,127 @@
+/// a computed variable stores nothing itself
+var celsius = 20
+var fahrenheit: Int {
+    get {
+        return celsius * 9 / 5 + 32
+    }
+    set(degrees) {
+        celsius = (degrees - 32) * 5 / 9
+    }
+}
+print(fahrenheit)
+fahrenheit = 212
+print(celsius)
+fahrenheit += 18
+print(celsius)
+
+// a getter can be written without `get`, and a single expression is its result
+var description: String {
+    "\(celsius) degrees"
+}
+print(description)
+
+/// observers run around every assignment, but not for the initial value
+var steps: Int = 0 {
+    willSet {
+        print("about to set steps to \(newValue)")
+    }
+    didSet(previous) {
+        if steps > previous {
+            print("added \(steps - previous) steps")
+        }
+    }
+}
+steps = 10
+steps += 5
+steps = 3
+
+var log: [String] = [] {
+    didSet {
+        print("log has \(log.count) entries")
+    }
+}
+log.append("start")
+log.append("stop")
+print(log)
+
+/// a lazy variable is computed on first use
+func expensive() -> Int {
+    print("computing")
+    return 42
+}
+lazy var answer = expensive()
+print("before")
+print(answer)
+print(answer)
+lazy var names: [String] = ["a"]
+names.append("b")
+print(names)
+
+enum Shape {
+    case square(Int)
+    case rectangle(Int, Int)
+
+    static let sides = 4
+    static var created = 0 {
+        didSet {
+            print("created \(Shape.created) shapes")
+        }
+    }
+    static var unit: Shape {
+        return .square(1)
+    }
+
+    /// setting the area keeps the shape, scaling it to a square when needed
+    var area: Int {
+        get {
+            switch self {
+            case .square(let side):
+                return side * side
+            case .rectangle(let width, let height):
+                return width * height
+            }
+        }
+        set {
+            var side = 1
+            while side * side < newValue {
+                side += 1
+            }
+            self = .square(side)
+        }
+    }
+
+    var perimeter: Int {
+        switch self {
+        case .square(let side):
+            return Shape.sides * side
+        case .rectangle(let width, let height):
+            return 2 * (width + height)
+        }
+    }
+}
+
+func makeSquare(_ side: Int) -> Shape {
+    Shape.created += 1
+    return .square(side)
+}
+
+extension Shape {
+    static var largest = 0
+}
+
+print(Shape.sides)
+var shape = makeSquare(3)
+print(shape.area)
+shape.area = 16
+print(shape.perimeter)
+shape.area += 9
+print(shape.area)
+let other = makeSquare(2)
+print(Shape.created)
+print(Shape.unit.area)
+Shape.largest = shape.area > other.area ? shape.area : other.area
+print(Shape.largest)
+
+var shapes = [Shape.rectangle(2, 3), Shape.square(2)]
+shapes[0].area = 50
+print(shapes[0].area)
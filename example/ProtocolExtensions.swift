@@ -0,0 +1,86 @@
+protocol Greeter {
+    var name: String { get }
+    func greet() -> String
+    func farewell() -> String
+}
+
+// default implementations for every conforming type
+extension Greeter {
+    func greet() -> String {
+        return "Hello from \(name)"
+    }
+    func farewell() -> String { return "Goodbye from \(name)" }
+    var shout: String { greet() + "!" }
+}
+
+enum English: Greeter {
+    case formal, casual
+
+    var name: String { self == .formal ? "Sir" : "pal" }
+    // a type's own implementation wins over the default
+    func greet() -> String {
+        return self == .formal ? "Good day" : "Hi"
+    }
+}
+
+enum Robot {
+    case unit(Int)
+}
+
+// extensions can add conformances and members to an enum
+extension Robot: Greeter, CustomStringConvertible {
+    var name: String {
+        switch self {
+        case .unit(let number): return "unit \(number)"
+        }
+    }
+    var description: String { "Robot(\(name))" }
+}
+
+let greeters: [any Greeter] = [English.formal, English.casual, Robot.unit(7)]
+for greeter in greeters {
+    print(greeter.greet())
+    print(greeter.farewell())
+    print(greeter.shout)
+}
+print(English.casual.farewell())
+print(Robot.unit(3).shout)
+print(Robot.unit(5))
+
+extension Int {
+    var squared: Int { self * self }
+    func isMultiple(of divisor: Int) -> Bool {
+        return self % divisor == 0
+    }
+}
+
+let seven = 7
+print(seven.squared)
+print(seven.isMultiple(of: 7))
+print(seven.isMultiple(of: 2))
+
+extension Array where Element: Equatable {
+    func count(of target: Element) -> Int {
+        var total = 0
+        for element in self {
+            if element == target {
+                total += 1
+            }
+        }
+        return total
+    }
+    func removingAll(_ target: Element) -> [Element] {
+        return self.filter { $0 != target }
+    }
+}
+
+extension Array {
+    var second: Element? { count > 1 ? self[1] : nil }
+}
+
+let numbers = [1, 2, 3, 2, 2]
+print(numbers.count(of: 2))
+print(numbers.removingAll(2))
+print(["a", "b", "a"].count(of: "a"))
+print(numbers.second)
+print([English.formal].second)
@@ -0,0 +1,76 @@
+/// anything with an area
+protocol Shape {
+    var name: String { get }
+    func area() -> Double
+    func scaled(by factor: Double) -> Double
+}
+
+enum Circle: Shape {
+    case unit
+    case sized(radius: Double)
+
+    var radius: Double {
+        switch self {
+        case .unit: return 1.0
+        case .sized(let radius): return radius
+        }
+    }
+    var name: String { "circle" }
+
+    // members of `self` can be used without `self.`
+    func area() -> Double { return 3.0 * radius * radius }
+    func scaled(by factor: Double) -> Double { return area() * factor }
+}
+
+enum Square: Int, Shape, CustomStringConvertible {
+    case small = 1, big = 3
+
+    var name: String { "square" }
+    var description: String { "Square(\(rawValue))" }
+    func area() -> Double { return Double(rawValue * rawValue) }
+    func scaled(by factor: Double) -> Double { return self.area() * factor }
+}
+
+func describe(_ shape: any Shape) -> String {
+    return "\(shape.name): \(shape.area())"
+}
+
+// `some Shape` parameters are generic: all the elements have one conforming type
+func largestArea(_ shapes: [some Shape]) -> Double {
+    var largest = 0.0
+    for shape in shapes {
+        if shape.area() > largest {
+            largest = shape.area()
+        }
+    }
+    return largest
+}
+
+// every return of an opaque result has the same underlying type
+func sizedCircle(_ radius: Double) -> some Shape {
+    if radius <= 1.0 {
+        return Circle.unit
+    }
+    return Circle.sized(radius: radius)
+}
+
+// calls on existentials go through the witness table of the boxed value
+let shapes: [any Shape] = [Circle.unit, Square.big, Circle.sized(radius: 2.0)]
+for shape in shapes {
+    print(describe(shape))
+    print(shape.scaled(by: 2.0))
+}
+print(largestArea([Circle.unit, Circle.sized(radius: 2.0)]))
+print(largestArea([Square.small, Square.big]))
+print(describe(sizedCircle(0.5)))
+print(sizedCircle(3.0).area())
+print(shapes.map { $0.name })
+
+// existentials print like the value they hold
+print(shapes)
+let small: Shape = Square.small
+print("small: \(small)")
+
+// methods can also be called on the enum directly
+print(Circle.sized(radius: 0.5).area())
+print([Square.big, Square.small])
@@ -0,0 +1,19 @@
+var total = 0; total += 1; total += 2
+print(total)
+
+let difference = 10
+    - 4
+print(difference)
+
+let separate = 10
+-4
+print(separate)
+
+enum Light { case red; case green }
+switch Light.green {
+case .red: print("stop"); print("wait")
+case .green: print("go")
+}
+
+var count = 0
+while count < 3 { count += 1; print(count) }
@@ -0,0 +1,22 @@
+let greeting = "Hello, world!"
+print(greeting)
+print("tab:\tend")
+print("quote: \"Swift\" \\ backslash")
+print("snowman: \u{2603} \u{1F600}")
+
+let name: String = "Swift"
+let message = greeting + " " + name
+print(message)
+print(name == "Swift")
+print(name != "swift")
+
+let maybe: String? = "text"
+print(maybe)
+print(("a\n", 1))
+print("")
+
+// strings are ordered by their bytes, so uppercase letters come first
+print("apple" < "banana")
+print("app" < "apple")
+print("Zebra" < "apple")
+print(["pear", "fig", "apple"].sorted())
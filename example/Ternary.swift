@@ -0,0 +1,22 @@
+let temperature = 25
+print(temperature > 20 ? "warm" : "cold")
+
+let score = 72
+let grade = score >= 90 ? "A" : score >= 80 ? "B" : score >= 70 ? "C" : "F"
+print(grade)
+
+let flag = true
+print(flag ? 1 : 2.5)
+let maybe: Int? = flag ? nil : 3
+print(maybe)
+print(1 + (flag ? 10 : 20) * 2)
+print(flag && score < 50 ? "both" : "not both")
+
+let bits = 1 << 2 + 3 * 4 & 0xF
+print(bits)
+let fallback: Int? = nil
+print(fallback ?? 0 < 1 ? "small" : "large")
+
+var count = 0
+count += flag ? 5 : 1
+print(count)
@@ -0,0 +1,32 @@
+func divide(_ dividend: Int, by divisor: Int) -> (quotient: Int, remainder: Int) {
+    return (dividend / divisor, dividend - dividend / divisor * divisor)
+}
+
+let pair = (1, 2)
+print(pair)
+print(pair.0 + pair.1)
+
+var point: (x: Int, y: Int) = (3, 4)
+print(point)
+print(point.x * point.y)
+point = (x: 5, y: 6)
+print(point.1)
+
+let (a, b) = pair
+print(a - b)
+
+let result = divide(17, by: 5)
+print(result.quotient)
+print(result.remainder)
+let (quotient, _) = divide(9, by: 2)
+print(quotient)
+
+let two: Int? = 2
+let nested = (1, (true, two))
+print(nested)
+print(nested.1.0)
+
+let maybe: (Int, Int)? = (7, 8)
+print(maybe?.1)
+print(pair == (1, 2))
+print(point != (5, 6))
@@ -0,0 +1,32 @@
+let π = 3.14159
+let café = "coffee"
+let _private = 42
+let 名前 = "Swift"
+let 🐶 = "dog"
+let `let` = 5
+var `var` = `let` + 1
+`var` += 1
+
+func área(ancho: Double, alto: Double) -> Double {
+    return ancho * alto
+}
+
+func `func`(_ `in`: Int) -> Int {
+    return `in` * 2
+}
+
+enum Día {
+    case lunes, martes
+}
+
+print(π)
+print(café)
+print(_private)
+print(名前)
+print(🐶)
+print(`var`)
+print(área(ancho: 2.0, alto: π))
+print(`func`(21))
+print(Día.martes)
+let (_, second) = (1, 2)
+print(second)
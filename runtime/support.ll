@@ -0,0 +1,438 @@
+; Support library linked into every program by llvm_backend.
+;
+; The hash table behind Dictionary and Set. Entries are stored densely in
+; insertion order as the key followed by the value (sets store no value),
+; each rounded up to 8 bytes. `index` is an open addressing table with
+; linear probing whose buckets hold an entry number + 1, 0 marks an empty
+; bucket. Keys are compared with the `equal` function the compiler
+; generates for the key type.
+;
+; Tables are shared between copies like arrays: `refcount` counts the
+; variables referring to the table and mutations go through
+; swift_hashtable_make_unique first.
+
+%HashTable = type {
+  i64,  ; refcount
+  i64,  ; count
+  i64,  ; capacity of the entries, a power of two
+  i64,  ; key size
+  i64,  ; value size
+  i8*,  ; entries
+  i64*, ; hashes of the entries
+  i64*  ; index with 2 * capacity buckets
+}
+
+declare i8* @malloc(i64)
+declare i8* @calloc(i64, i64)
+declare i8* @realloc(i8*, i64)
+declare void @free(i8*)
+declare void @llvm.memcpy.p0i8.p0i8.i64(i8*, i8*, i64, i1)
+declare void @llvm.memmove.p0i8.p0i8.i64(i8*, i8*, i64, i1)
+
+define internal i64 @round_to_word(i64 %size) {
+entry:
+  %padded = add i64 %size, 7
+  %rounded = and i64 %padded, -8
+  ret i64 %rounded
+}
+
+define internal i64 @stride(%HashTable* %table) {
+entry:
+  %key_size_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 3
+  %key_size = load i64, i64* %key_size_ptr
+  %value_size_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 4
+  %value_size = load i64, i64* %value_size_ptr
+  %key_words = call i64 @round_to_word(i64 %key_size)
+  %value_words = call i64 @round_to_word(i64 %value_size)
+  %stride = add i64 %key_words, %value_words
+  ret i64 %stride
+}
+
+; spreads the bits of a hash over the buckets (the splitmix64 finalizer)
+define internal i64 @mix(i64 %hash) {
+entry:
+  %shift1 = lshr i64 %hash, 33
+  %xor1 = xor i64 %hash, %shift1
+  %mul1 = mul i64 %xor1, -49064778989728563
+  %shift2 = lshr i64 %mul1, 33
+  %xor2 = xor i64 %mul1, %shift2
+  %mul2 = mul i64 %xor2, -4265267296055464877
+  %shift3 = lshr i64 %mul2, 33
+  %xor3 = xor i64 %mul2, %shift3
+  ret i64 %xor3
+}
+
+; FNV-1a over the bytes of a string
+define i64 @swift_hash_bytes(i8* %bytes, i64 %count) {
+entry:
+  br label %loop
+
+loop:
+  %index = phi i64 [ 0, %entry ], [ %next, %body ]
+  %hash = phi i64 [ -3750763034362895579, %entry ], [ %multiplied, %body ]
+  %done = icmp sge i64 %index, %count
+  br i1 %done, label %end, label %body
+
+body:
+  %byte_ptr = getelementptr i8, i8* %bytes, i64 %index
+  %byte = load i8, i8* %byte_ptr
+  %wide = zext i8 %byte to i64
+  %xored = xor i64 %hash, %wide
+  %multiplied = mul i64 %xored, 1099511628211
+  %next = add i64 %index, 1
+  br label %loop
+
+end:
+  ret i64 %hash
+}
+
+define i8* @swift_hashtable_create(i64 %key_size, i64 %value_size, i64 %minimum_capacity) {
+entry:
+  br label %size
+
+size:
+  %capacity = phi i64 [ 4, %entry ], [ %doubled, %grow ]
+  %fits = icmp sge i64 %capacity, %minimum_capacity
+  br i1 %fits, label %allocate, label %grow
+
+grow:
+  %doubled = mul i64 %capacity, 2
+  br label %size
+
+allocate:
+  %raw = call i8* @malloc(i64 ptrtoint (%HashTable* getelementptr (%HashTable, %HashTable* null, i32 1) to i64))
+  %table = bitcast i8* %raw to %HashTable*
+  %refcount_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 0
+  store i64 0, i64* %refcount_ptr
+  %count_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 1
+  store i64 0, i64* %count_ptr
+  %capacity_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 2
+  store i64 %capacity, i64* %capacity_ptr
+  %key_size_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 3
+  store i64 %key_size, i64* %key_size_ptr
+  %value_size_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 4
+  store i64 %value_size, i64* %value_size_ptr
+
+  %stride = call i64 @stride(%HashTable* %table)
+  %entries_size = mul i64 %capacity, %stride
+  %entries = call i8* @malloc(i64 %entries_size)
+  %entries_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 5
+  store i8* %entries, i8** %entries_ptr
+  %hashes_size = mul i64 %capacity, 8
+  %hashes_raw = call i8* @malloc(i64 %hashes_size)
+  %hashes = bitcast i8* %hashes_raw to i64*
+  %hashes_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 6
+  store i64* %hashes, i64** %hashes_ptr
+  %buckets = mul i64 %capacity, 2
+  %index_raw = call i8* @calloc(i64 %buckets, i64 8)
+  %index = bitcast i8* %index_raw to i64*
+  %index_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 7
+  store i64* %index, i64** %index_ptr
+  ret i8* %raw
+}
+
+define void @swift_hashtable_retain(i8* %raw) {
+entry:
+  %table = bitcast i8* %raw to %HashTable*
+  %refcount_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 0
+  %refcount = load i64, i64* %refcount_ptr
+  %incremented = add i64 %refcount, 1
+  store i64 %incremented, i64* %refcount_ptr
+  ret void
+}
+
+define i64 @swift_hashtable_count(i8* %raw) {
+entry:
+  %table = bitcast i8* %raw to %HashTable*
+  %count_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 1
+  %count = load i64, i64* %count_ptr
+  ret i64 %count
+}
+
+define i8* @swift_hashtable_key(i8* %raw, i64 %position) {
+entry:
+  %table = bitcast i8* %raw to %HashTable*
+  %stride = call i64 @stride(%HashTable* %table)
+  %entries_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 5
+  %entries = load i8*, i8** %entries_ptr
+  %offset = mul i64 %position, %stride
+  %key = getelementptr i8, i8* %entries, i64 %offset
+  ret i8* %key
+}
+
+define i8* @swift_hashtable_value(i8* %raw, i64 %position) {
+entry:
+  %table = bitcast i8* %raw to %HashTable*
+  %key = call i8* @swift_hashtable_key(i8* %raw, i64 %position)
+  %key_size_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 3
+  %key_size = load i64, i64* %key_size_ptr
+  %key_words = call i64 @round_to_word(i64 %key_size)
+  %value = getelementptr i8, i8* %key, i64 %key_words
+  ret i8* %value
+}
+
+; the entry whose key equals `key`, or -1
+define i64 @swift_hashtable_find(i8* %raw, i64 %hash, i8* %key, i1 (i8*, i8*)* %equal) {
+entry:
+  %table = bitcast i8* %raw to %HashTable*
+  %capacity_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 2
+  %capacity = load i64, i64* %capacity_ptr
+  %buckets = mul i64 %capacity, 2
+  %mask = sub i64 %buckets, 1
+  %hashes_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 6
+  %hashes = load i64*, i64** %hashes_ptr
+  %index_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 7
+  %index = load i64*, i64** %index_ptr
+  %mixed = call i64 @mix(i64 %hash)
+  %first = and i64 %mixed, %mask
+  br label %probe
+
+probe:
+  %bucket = phi i64 [ %first, %entry ], [ %next, %next_bucket ]
+  %slot_ptr = getelementptr i64, i64* %index, i64 %bucket
+  %slot = load i64, i64* %slot_ptr
+  %empty = icmp eq i64 %slot, 0
+  br i1 %empty, label %missing, label %compare_hash
+
+compare_hash:
+  %candidate = sub i64 %slot, 1
+  %candidate_hash_ptr = getelementptr i64, i64* %hashes, i64 %candidate
+  %candidate_hash = load i64, i64* %candidate_hash_ptr
+  %same_hash = icmp eq i64 %candidate_hash, %hash
+  br i1 %same_hash, label %compare_key, label %next_bucket
+
+compare_key:
+  %candidate_key = call i8* @swift_hashtable_key(i8* %raw, i64 %candidate)
+  %same_key = call i1 %equal(i8* %candidate_key, i8* %key)
+  br i1 %same_key, label %found, label %next_bucket
+
+next_bucket:
+  %incremented = add i64 %bucket, 1
+  %next = and i64 %incremented, %mask
+  br label %probe
+
+found:
+  ret i64 %candidate
+
+missing:
+  ret i64 -1
+}
+
+; records `entry` in the first free bucket for its hash
+define internal void @place(%HashTable* %table, i64 %position) {
+entry:
+  %capacity_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 2
+  %capacity = load i64, i64* %capacity_ptr
+  %buckets = mul i64 %capacity, 2
+  %mask = sub i64 %buckets, 1
+  %hashes_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 6
+  %hashes = load i64*, i64** %hashes_ptr
+  %hash_ptr = getelementptr i64, i64* %hashes, i64 %position
+  %hash = load i64, i64* %hash_ptr
+  %index_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 7
+  %index = load i64*, i64** %index_ptr
+  %mixed = call i64 @mix(i64 %hash)
+  %first = and i64 %mixed, %mask
+  br label %probe
+
+probe:
+  %bucket = phi i64 [ %first, %entry ], [ %next, %occupied ]
+  %slot_ptr = getelementptr i64, i64* %index, i64 %bucket
+  %slot = load i64, i64* %slot_ptr
+  %empty = icmp eq i64 %slot, 0
+  br i1 %empty, label %store, label %occupied
+
+occupied:
+  %incremented = add i64 %bucket, 1
+  %next = and i64 %incremented, %mask
+  br label %probe
+
+store:
+  %number = add i64 %position, 1
+  store i64 %number, i64* %slot_ptr
+  ret void
+}
+
+; rebuilds the index from the hashes, after entries moved or the capacity changed
+define internal void @rebuild(%HashTable* %table) {
+entry:
+  %capacity_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 2
+  %capacity = load i64, i64* %capacity_ptr
+  %buckets = mul i64 %capacity, 2
+  %index_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 7
+  %old_index = load i64*, i64** %index_ptr
+  %old_raw = bitcast i64* %old_index to i8*
+  call void @free(i8* %old_raw)
+  %index_raw = call i8* @calloc(i64 %buckets, i64 8)
+  %index = bitcast i8* %index_raw to i64*
+  store i64* %index, i64** %index_ptr
+  %count_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 1
+  %count = load i64, i64* %count_ptr
+  br label %loop
+
+loop:
+  %entry_number = phi i64 [ 0, %entry ], [ %next, %body ]
+  %done = icmp sge i64 %entry_number, %count
+  br i1 %done, label %end, label %body
+
+body:
+  call void @place(%HashTable* %table, i64 %entry_number)
+  %next = add i64 %entry_number, 1
+  br label %loop
+
+end:
+  ret void
+}
+
+; the entry for `key`, appending a new one when the key is missing;
+; the value of a new entry is left for the caller to initialize
+define i64 @swift_hashtable_insert(i8* %raw, i64 %hash, i8* %key, i1 (i8*, i8*)* %equal) {
+entry:
+  %existing = call i64 @swift_hashtable_find(i8* %raw, i64 %hash, i8* %key, i1 (i8*, i8*)* %equal)
+  %exists = icmp sge i64 %existing, 0
+  br i1 %exists, label %found, label %append
+
+found:
+  ret i64 %existing
+
+append:
+  %table = bitcast i8* %raw to %HashTable*
+  %stride = call i64 @stride(%HashTable* %table)
+  %count_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 1
+  %count = load i64, i64* %count_ptr
+  %capacity_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 2
+  %capacity = load i64, i64* %capacity_ptr
+  %entries_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 5
+  %hashes_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 6
+  %full = icmp eq i64 %count, %capacity
+  br i1 %full, label %grow, label %store
+
+grow:
+  %new_capacity = mul i64 %capacity, 2
+  store i64 %new_capacity, i64* %capacity_ptr
+  %old_entries = load i8*, i8** %entries_ptr
+  %entries_size = mul i64 %new_capacity, %stride
+  %new_entries = call i8* @realloc(i8* %old_entries, i64 %entries_size)
+  store i8* %new_entries, i8** %entries_ptr
+  %old_hashes = load i64*, i64** %hashes_ptr
+  %old_hashes_raw = bitcast i64* %old_hashes to i8*
+  %hashes_size = mul i64 %new_capacity, 8
+  %new_hashes_raw = call i8* @realloc(i8* %old_hashes_raw, i64 %hashes_size)
+  %new_hashes = bitcast i8* %new_hashes_raw to i64*
+  store i64* %new_hashes, i64** %hashes_ptr
+  call void @rebuild(%HashTable* %table)
+  br label %store
+
+store:
+  %entry_key = call i8* @swift_hashtable_key(i8* %raw, i64 %count)
+  %key_size_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 3
+  %key_size = load i64, i64* %key_size_ptr
+  call void @llvm.memcpy.p0i8.p0i8.i64(i8* %entry_key, i8* %key, i64 %key_size, i1 false)
+  %hashes = load i64*, i64** %hashes_ptr
+  %hash_ptr = getelementptr i64, i64* %hashes, i64 %count
+  store i64 %hash, i64* %hash_ptr
+  %new_count = add i64 %count, 1
+  store i64 %new_count, i64* %count_ptr
+  call void @place(%HashTable* %table, i64 %count)
+  ret i64 %count
+}
+
+; removes an entry, keeping the remaining ones in insertion order
+define void @swift_hashtable_remove(i8* %raw, i64 %position) {
+entry:
+  %table = bitcast i8* %raw to %HashTable*
+  %stride = call i64 @stride(%HashTable* %table)
+  %count_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 1
+  %count = load i64, i64* %count_ptr
+  %following = add i64 %position, 1
+  %moved = sub i64 %count, %following
+
+  %destination = call i8* @swift_hashtable_key(i8* %raw, i64 %position)
+  %source = call i8* @swift_hashtable_key(i8* %raw, i64 %following)
+  %entries_size = mul i64 %moved, %stride
+  call void @llvm.memmove.p0i8.p0i8.i64(i8* %destination, i8* %source, i64 %entries_size, i1 false)
+
+  %hashes_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 6
+  %hashes = load i64*, i64** %hashes_ptr
+  %hash_destination = getelementptr i64, i64* %hashes, i64 %position
+  %hash_destination_raw = bitcast i64* %hash_destination to i8*
+  %hash_source = getelementptr i64, i64* %hashes, i64 %following
+  %hash_source_raw = bitcast i64* %hash_source to i8*
+  %hashes_size = mul i64 %moved, 8
+  call void @llvm.memmove.p0i8.p0i8.i64(i8* %hash_destination_raw, i8* %hash_source_raw, i64 %hashes_size, i1 false)
+
+  %new_count = sub i64 %count, 1
+  store i64 %new_count, i64* %count_ptr
+  call void @rebuild(%HashTable* %table)
+  ret void
+}
+
+; Gives the table stored at `slot` a copy of its own while other variables
+; share it. `retain_value` is called for every copied value, or null when the
+; values need no retaining.
+define i8* @swift_hashtable_make_unique(i8** %slot, void (i8*)* %retain_value) {
+entry:
+  %raw = load i8*, i8** %slot
+  %table = bitcast i8* %raw to %HashTable*
+  %refcount_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 0
+  %refcount = load i64, i64* %refcount_ptr
+  %shared = icmp sgt i64 %refcount, 1
+  br i1 %shared, label %copy, label %unique
+
+unique:
+  ret i8* %raw
+
+copy:
+  %count_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 1
+  %count = load i64, i64* %count_ptr
+  %capacity_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 2
+  %capacity = load i64, i64* %capacity_ptr
+  %key_size_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 3
+  %key_size = load i64, i64* %key_size_ptr
+  %value_size_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 4
+  %value_size = load i64, i64* %value_size_ptr
+  %copy_raw = call i8* @swift_hashtable_create(i64 %key_size, i64 %value_size, i64 %capacity)
+  %copy_table = bitcast i8* %copy_raw to %HashTable*
+
+  %stride = call i64 @stride(%HashTable* %table)
+  %entries_size = mul i64 %count, %stride
+  %entries_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 5
+  %entries = load i8*, i8** %entries_ptr
+  %copy_entries_ptr = getelementptr %HashTable, %HashTable* %copy_table, i32 0, i32 5
+  %copy_entries = load i8*, i8** %copy_entries_ptr
+  call void @llvm.memcpy.p0i8.p0i8.i64(i8* %copy_entries, i8* %entries, i64 %entries_size, i1 false)
+
+  %hashes_size = mul i64 %count, 8
+  %hashes_ptr = getelementptr %HashTable, %HashTable* %table, i32 0, i32 6
+  %hashes = load i64*, i64** %hashes_ptr
+  %hashes_raw = bitcast i64* %hashes to i8*
+  %copy_hashes_ptr = getelementptr %HashTable, %HashTable* %copy_table, i32 0, i32 6
+  %copy_hashes = load i64*, i64** %copy_hashes_ptr
+  %copy_hashes_raw = bitcast i64* %copy_hashes to i8*
+  call void @llvm.memcpy.p0i8.p0i8.i64(i8* %copy_hashes_raw, i8* %hashes_raw, i64 %hashes_size, i1 false)
+
+  %copy_count_ptr = getelementptr %HashTable, %HashTable* %copy_table, i32 0, i32 1
+  store i64 %count, i64* %copy_count_ptr
+  call void @rebuild(%HashTable* %copy_table)
+  %copy_refcount_ptr = getelementptr %HashTable, %HashTable* %copy_table, i32 0, i32 0
+  store i64 1, i64* %copy_refcount_ptr
+  %released = sub i64 %refcount, 1
+  store i64 %released, i64* %refcount_ptr
+  %retains = icmp ne void (i8*)* %retain_value, null
+  br i1 %retains, label %retain_loop, label %done
+
+retain_loop:
+  %entry_number = phi i64 [ 0, %copy ], [ %next, %retain ]
+  %more = icmp slt i64 %entry_number, %count
+  br i1 %more, label %retain, label %done
+
+retain:
+  %value = call i8* @swift_hashtable_value(i8* %copy_raw, i64 %entry_number)
+  call void %retain_value(i8* %value)
+  %next = add i64 %entry_number, 1
+  br label %retain_loop
+
+done:
+  store i8* %copy_raw, i8** %slot
+  ret i8* %copy_raw
+}
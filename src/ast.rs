@@ -1,4 +1,3 @@
-
 #[derive(Debug, Clone, PartialEq)]
 pub enum AstNode {
     Program(Vec<Statement>)
@@ -9,25 +8,243 @@ pub enum Statement {
     Print(Expression),
     VarDecl {
         name: String,
+        type_annotation: Option<Type>,
+        value: Expression,
+        is_mutable: bool,
+    },
+    /// `let (a, _) = pair`, `None` stands for an ignored element
+    Destructure {
+        names: Vec<Option<String>>,
+        type_annotation: Option<Type>,
         value: Expression,
         is_mutable: bool,
     },
+    /// `x = 1` or a compound assignment such as `pair.0 += 1`, whose
+    /// `operator` combines the current value of `target` with `value`
     Assignment {
-        name: String,
+        target: Expression,
+        operator: Option<BinaryOperator>,
         value: Expression,
     },
+    /// `var area: Int { ... }` at the top level
+    ComputedVar(PropertyDecl),
+    /// `lazy var` or a `var` with `willSet`/`didSet` observers at the top level
+    StoredVar(StoredPropertyDecl),
+    EnumDecl(EnumDecl),
+    ProtocolDecl(ProtocolDecl),
+    Extension(ExtensionDecl),
+    Switch {
+        subject: Expression,
+        cases: Vec<SwitchCase>,
+    },
+    If {
+        conditions: Vec<Condition>,
+        then_branch: Vec<Statement>,
+        /// `else if` is stored as an else branch holding a single `If`
+        else_branch: Option<Vec<Statement>>,
+    },
+    While {
+        conditions: Vec<Condition>,
+        body: Vec<Statement>,
+    },
+    /// `for name in sequence { ... }`
+    ForIn {
+        pattern: ForPattern,
+        sequence: Expression,
+        body: Vec<Statement>,
+    },
+    /// `guard conditions else { ... }`, bindings stay visible after the guard
+    Guard {
+        conditions: Vec<Condition>,
+        else_branch: Vec<Statement>,
+    },
+    FunctionDecl(FunctionDecl),
+    /// `throw value`, where the value conforms to `Error`
+    Throw(Expression),
+    /// `do { ... } catch ... { ... }`, a `do` without catch clauses is just a scope
+    Do {
+        body: Vec<Statement>,
+        catches: Vec<CatchClause>,
+    },
+    /// `defer { ... }`, run when the enclosing scope is left in any way
+    Defer(Vec<Statement>),
+    Return(Option<Expression>),
+    Break,
+    Continue,
     Expression(Expression)
 }
 
+/// the loop variable of a for-in loop
+#[derive(Debug, Clone, PartialEq)]
+pub enum ForPattern {
+    /// `for x in`, `None` for `for _ in`
+    Name(Option<String>),
+    /// `for (key, value) in`, `None` stands for an ignored element
+    Tuple(Vec<Option<String>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionDecl {
+    pub name: String,
+    /// `<T: Equatable, U>`, constraints of the `where` clause included
+    pub generic_parameters: Vec<GenericParameter>,
+    pub parameters: Vec<Parameter>,
+    pub return_type: Type,
+    pub body: Vec<Statement>,
+    /// set for operator implementations such as `func ** (lhs: Int, rhs: Int)`,
+    /// which are overloaded by parameter type instead of argument label
+    pub operator: Option<Fixity>,
+    /// declared `throws`, calls must be marked with `try`
+    pub throws: bool,
+    /// declared `rethrows`, which also sets `throws`: a call only throws
+    /// when an argument for a throwing function parameter does
+    pub rethrows: bool,
+    /// declared `-> some P`, with `any P` as its return type
+    pub opaque_result: bool,
+    /// set for setters, which get the address of `self` and may assign to it
+    pub is_mutating: bool,
+    /// the `///` comment before the declaration
+    pub doc_comment: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fixity {
+    Prefix,
+    Infix,
+    Postfix,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    /// argument label, None when declared with `_`
+    pub label: Option<String>,
+    pub name: String,
+    pub param_type: Type,
+    /// `inout`, the argument is passed by address as `&variable`
+    pub is_inout: bool,
+}
+
+/// a type parameter of a generic function and the protocols its type must conform to
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenericParameter {
+    pub name: String,
+    pub constraints: Vec<String>,
+}
+
+/// one element of the comma separated condition list of `if` and `while`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Expression(Expression),
+    /// `let name = value`, succeeds when `value` is not nil
+    OptionalBinding {
+        name: String,
+        is_mutable: bool,
+        type_annotation: Option<Type>,
+        value: Expression,
+    },
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expression {
-    Number(i64),
+    /// integer literal, wide enough for every value of `UInt64` and `Int64`
+    Number(i128),
+    FloatLiteral(f64),
+    Bool(bool),
+    /// string literal with its escape sequences already resolved
+    StringLiteral(String),
+    /// `"sum: \(a + b)"`
+    Interpolation(Vec<InterpolationPart>),
+    Nil,
     Variable(String),
+    /// a generic enum specialized before a member: `Box<Int>` in `Box<Int>.empty`
+    SpecializedType(Type),
+    Unary {
+        operator: UnaryOperator,
+        operand: Box<Expression>,
+    },
     Binary {
         left: Box<Expression>,
         operator: BinaryOperator,
         right: Box<Expression>,
-    }
+    },
+    /// `base.name`, e.g. `Direction.north` or `direction.rawValue`
+    Member {
+        base: Box<Expression>,
+        name: String,
+    },
+    /// `.name` whose base type is taken from the context
+    ImplicitMember(String),
+    /// `(1, 2)` or `(x: 1, y: 2)`, elements are labeled like call arguments
+    Tuple(Vec<Argument>),
+    /// `[1, 2, 3]`, also used for sets
+    ArrayLiteral(Vec<Expression>),
+    /// `["a": 1, "b": 2]`, or `[:]` without entries
+    DictionaryLiteral(Vec<(Expression, Expression)>),
+    /// `base[index]`
+    Subscript {
+        base: Box<Expression>,
+        index: Box<Expression>,
+    },
+    /// use of a user-defined operator, resolved like a call to the
+    /// function named `symbol`; infix operators have two operands
+    OperatorCall {
+        symbol: String,
+        fixity: Fixity,
+        operands: Vec<Expression>,
+    },
+    /// `condition ? then_value : else_value`
+    Ternary {
+        condition: Box<Expression>,
+        then_value: Box<Expression>,
+        else_value: Box<Expression>,
+    },
+    Call {
+        callee: Box<Expression>,
+        arguments: Vec<Argument>,
+    },
+    /// `value!`
+    ForceUnwrap(Box<Expression>),
+    /// the `value?` part of an optional chain, jumps out of the enclosing
+    /// `OptionalEvaluation` when the value is nil
+    BindOptional(Box<Expression>),
+    /// a whole optional chain such as `a?.b`, evaluates to nil when any link is nil
+    OptionalEvaluation(Box<Expression>),
+    /// `&value`, an argument for an `inout` parameter
+    InOut(Box<Expression>),
+    /// `try value`, marks the calls in `value` that can throw
+    Try(Box<Expression>),
+    /// `try? value`, nil when `value` throws
+    OptionalTry(Box<Expression>),
+    /// `try! value`, a runtime error when `value` throws
+    ForceTry(Box<Expression>),
+    /// `{ x in x * 2 }` or `{ $0 * 2 }`, inlined into the collection method it
+    /// is passed to and otherwise a function value capturing the variables it
+    /// uses; a body that is a single expression returns its value
+    Closure {
+        parameters: Vec<ClosureParameter>,
+        return_type: Option<Type>,
+        body: Vec<Statement>,
+    },
+}
+
+/// a closure parameter, whose type comes from the context unless annotated;
+/// a closure without a parameter list gets `$0`, `$1`, ... for the ones its body uses
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosureParameter {
+    pub name: String,
+    pub param_type: Option<Type>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterpolationPart {
+    Literal(String),
+    Expression(Expression),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Argument {
+    pub label: Option<String>,
+    pub value: Expression,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -36,4 +253,1265 @@ pub enum BinaryOperator {
     Subtract,
     Multiply,
     Divide,
-}
\ No newline at end of file
+    Remainder,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    And,
+    Or,
+    NilCoalescing,
+    BitwiseAnd,
+    BitwiseOr,
+    BitwiseXor,
+    /// `<<` and `>>` shift the other way for negative amounts and
+    /// saturate for amounts of at least the bit width
+    ShiftLeft,
+    ShiftRight,
+    /// `&<<` and `&>>` only use the low bits of the amount
+    MaskingShiftLeft,
+    MaskingShiftRight,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnaryOperator {
+    Negate,
+    Not,
+    BitwiseNot,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Type {
+    Int,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    UInt,
+    UInt8,
+    UInt16,
+    UInt32,
+    UInt64,
+    Double,
+    Float,
+    Bool,
+    String,
+    /// `()`, the result of functions without a return type
+    Void,
+    /// result of functions that never return, such as `fatalError(_:)`
+    Never,
+    /// user defined type such as an enum, with the type arguments of a
+    /// generic enum: `Box<Int>`
+    Named(String, Vec<Type>),
+    /// type parameter `T` of the generic function being checked, which is
+    /// replaced by the actual type when the function is specialized
+    Generic(String),
+    /// `any P` (or just `P`), a value of any type conforming to the protocol
+    /// `P`, whose requirements are called through the witness table stored
+    /// next to the value
+    Existential(String),
+    /// `some P` as written, which only function signatures take: a parameter
+    /// of that type is generic over `P`, a result is returned as `any P`
+    /// and has one underlying type
+    Opaque(String),
+    Optional(Box<Type>),
+    /// `(Int, Int)` or `(x: Int, y: Int)`, always with two or more elements
+    Tuple(Vec<TupleElement>),
+    /// `[Int]`, a value type whose elements live in a shared buffer that is
+    /// copied when it is mutated while shared
+    Array(Box<Type>),
+    /// `[String: Int]`, a hash table in the support library shared like an array's buffer
+    Dictionary(Box<Type>, Box<Type>),
+    /// `Set<Int>`, a hash table without values
+    Set(Box<Type>),
+    /// `(Int, Int) -> Bool`, a function or closure value; `throws` when
+    /// calling it must be marked with `try`
+    Function {
+        parameters: Vec<Type>,
+        result: Box<Type>,
+        throws: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TupleElement {
+    pub label: Option<String>,
+    pub element_type: Type,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumDecl {
+    pub name: String,
+    /// `<T>` of a generic enum, whose members use them as `Type::Generic`
+    pub generic_parameters: Vec<GenericParameter>,
+    pub raw_type: Option<Type>,
+    /// protocols after the raw type in the inheritance clause
+    pub conformances: Vec<String>,
+    pub cases: Vec<EnumCase>,
+    /// `static func` members, which are operator implementations
+    pub static_functions: Vec<FunctionDecl>,
+    /// instance methods, whose body sees the value as `self`
+    pub methods: Vec<FunctionDecl>,
+    /// computed properties such as `var description: String { ... }`
+    pub properties: Vec<PropertyDecl>,
+    /// `static var` properties with accessors
+    pub static_properties: Vec<PropertyDecl>,
+    /// `static let` and `static var` properties with a stored value
+    pub static_stored_properties: Vec<StoredPropertyDecl>,
+    /// the `///` comment before the declaration
+    pub doc_comment: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyDecl {
+    pub name: String,
+    pub property_type: Type,
+    pub getter: Vec<Statement>,
+    /// `set { ... }`, only settable properties have one
+    pub setter: Option<Accessor>,
+    /// the `///` comment before the declaration
+    pub doc_comment: Option<String>,
+}
+
+/// the body of a setter or an observer, which sees the value as `parameter`:
+/// `newValue` or `oldValue` unless another name is given in parentheses
+#[derive(Debug, Clone, PartialEq)]
+pub struct Accessor {
+    pub parameter: String,
+    pub body: Vec<Statement>,
+}
+
+/// A stored variable that is accessed through functions: a `lazy var`, a
+/// `var` with observers, or a `static` property, whose value is computed on
+/// first access like that of a lazy one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredPropertyDecl {
+    pub name: String,
+    pub type_annotation: Option<Type>,
+    pub value: Expression,
+    pub is_mutable: bool,
+    pub is_lazy: bool,
+    /// `willSet { ... }`, run with the new value before it is stored
+    pub will_set: Option<Accessor>,
+    /// `didSet { ... }`, run with the old value after the new one is stored
+    pub did_set: Option<Accessor>,
+    /// the `///` comment before the declaration
+    pub doc_comment: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtocolDecl {
+    pub name: String,
+    pub requirements: Vec<Requirement>,
+    /// the `///` comment before the declaration
+    pub doc_comment: Option<String>,
+}
+
+/// `extension Shape { ... }` or `extension Array where Element: Equatable { ... }`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtensionDecl {
+    /// an enum, a protocol or a standard library type such as `Int` or `Array`
+    pub extended_type: String,
+    /// protocols after the extended type, only enums can gain conformances
+    pub conformances: Vec<String>,
+    /// `where Element: Equatable`, pairs of a generic parameter and a protocol
+    pub constraints: Vec<(String, String)>,
+    /// `static func` members, which are operator implementations of an enum
+    pub static_functions: Vec<FunctionDecl>,
+    pub methods: Vec<FunctionDecl>,
+    pub properties: Vec<PropertyDecl>,
+    /// static properties, which only enums and their extensions can have
+    pub static_properties: Vec<PropertyDecl>,
+    pub static_stored_properties: Vec<StoredPropertyDecl>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Requirement {
+    /// `func area() -> Double`, a function declaration without a body
+    Method(FunctionDecl),
+    /// `var name: String { get }`
+    Property {
+        name: String,
+        property_type: Type,
+        settable: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumCase {
+    pub name: String,
+    /// explicit raw value (`case north = 1`), implicit values are assigned by the semantic pass
+    pub raw_value: Option<i64>,
+    pub associated_values: Vec<AssociatedValue>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssociatedValue {
+    pub label: Option<String>,
+    pub value_type: Type,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwitchCase {
+    /// `default` is represented as a single wildcard pattern
+    pub patterns: Vec<Pattern>,
+    pub body: Vec<Statement>,
+}
+
+/// `catch MyError.notFound { ... }`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CatchClause {
+    /// matched against the error, a bare `catch` binds it as `error`
+    pub pattern: Pattern,
+    /// the enum of `catch let e as MyError`, `catch is MyError` and enum case
+    /// patterns; the pattern is matched against the error as any `Error` without it
+    pub error_type: Option<Type>,
+    pub body: Vec<Statement>,
+}
+
+impl CatchClause {
+    /// whether the clause catches every error
+    pub fn is_exhaustive(&self) -> bool {
+        self.error_type.is_none() && matches!(self.pattern, Pattern::Wildcard | Pattern::Binding(_))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    Wildcard,
+    Binding(String),
+    Number(i64),
+    /// `.point(let x, let y)` or `Shape.point(...)`
+    EnumCase {
+        enum_name: Option<String>,
+        case_name: String,
+        subpatterns: Option<Vec<Pattern>>,
+    },
+}
+
+impl EnumDecl {
+    /// the declaration of the specialization of a generic enum for
+    /// `arguments`, such as `Box<Int>`, whose members use them instead of its type parameters
+    pub fn specialize(&self, arguments: &[Type]) -> EnumDecl {
+        let bindings = self.generic_parameters.iter()
+            .map(|parameter| parameter.name.clone())
+            .zip(arguments.iter().cloned())
+            .collect::<Vec<_>>();
+        EnumDecl { generic_parameters: Vec::new(), ..self.substitute(&bindings) }
+    }
+
+    /// what names stand for inside a generic enum: its type parameters for
+    /// themselves and its bare name for the enum specialized with them
+    pub fn generic_bindings(&self) -> Vec<(String, Type)> {
+        if self.generic_parameters.is_empty() {
+            return Vec::new();
+        }
+        let mut bindings = self.generic_parameters.iter()
+            .map(|parameter| (parameter.name.clone(), Type::Generic(parameter.name.clone())))
+            .collect::<Vec<_>>();
+        bindings.push((self.name.clone(), self.declared_type()));
+        bindings
+    }
+
+    /// `case pair(T, label: U)` as the function `pair(_:label:) -> Enum<T, U>`
+    /// creating it, whose type parameters are inferred from the arguments of
+    /// a call like those of a generic function
+    pub fn case_constructor(&self, case: &EnumCase) -> FunctionDecl {
+        FunctionDecl {
+            name: case.name.clone(),
+            generic_parameters: self.generic_parameters.clone(),
+            parameters: case.associated_values.iter()
+                .map(|value| Parameter {
+                    label: value.label.clone(),
+                    name: value.label.clone().unwrap_or_else(|| "_".to_string()),
+                    param_type: value.value_type.clone(),
+                    is_inout: false,
+                })
+                .collect(),
+            return_type: self.declared_type(),
+            body: Vec::new(),
+            operator: None,
+            throws: false,
+            rethrows: false,
+            opaque_result: false,
+            is_mutating: false,
+            doc_comment: None,
+        }
+    }
+
+    /// the type of `self` in the members of the enum, `Box<T>` for a generic enum
+    pub fn declared_type(&self) -> Type {
+        let arguments = self.generic_parameters.iter()
+            .map(|parameter| Type::Generic(parameter.name.clone()))
+            .collect();
+        Type::Named(self.name.clone(), arguments)
+    }
+
+    /// a copy with generic parameters replaced by the types bound to them in every member
+    pub fn substitute(&self, bindings: &[(String, Type)]) -> EnumDecl {
+        let mut decl = self.clone();
+        for case in &mut decl.cases {
+            for value in &mut case.associated_values {
+                value.value_type = value.value_type.substitute(bindings);
+            }
+        }
+        decl.static_functions = self.static_functions.iter().map(|function| function.substitute(bindings)).collect();
+        decl.methods = self.methods.iter().map(|method| method.substitute(bindings)).collect();
+        for property in decl.properties.iter_mut().chain(&mut decl.static_properties) {
+            property.substitute_types(bindings);
+        }
+        for property in &mut decl.static_stored_properties {
+            property.type_annotation = property.type_annotation.as_ref().map(|ty| ty.substitute(bindings));
+        }
+        decl
+    }
+
+    pub fn find_case(&self, name: &str) -> Option<(usize, &EnumCase)> {
+        self.cases.iter().enumerate().find(|(_, case)| case.name == name)
+    }
+
+    pub fn has_associated_values(&self) -> bool {
+        self.cases.iter().any(|case| !case.associated_values.is_empty())
+    }
+
+    /// raw values of every case, following Swift's rule that an implicit
+    /// raw value is one more than the previous case (starting from 0)
+    pub fn raw_values(&self) -> Result<Vec<i64>, String> {
+        let mut values = Vec::new();
+        let mut next = Some(0);
+        for case in &self.cases {
+            let value = match (case.raw_value, next) {
+                (Some(value), _) | (None, Some(value)) => value,
+                (None, None) => return Err(format!("raw value for enum case '{}' overflows", case.name)),
+            };
+            values.push(value);
+            next = value.checked_add(1);
+        }
+        Ok(values)
+    }
+
+    pub fn find_method(&self, name: &str, arguments: &[Argument]) -> Option<&FunctionDecl> {
+        self.methods.iter().find(|method| method.name == name && method.matches_labels(arguments))
+    }
+
+    pub fn find_property(&self, name: &str) -> Option<&PropertyDecl> {
+        self.properties.iter().find(|property| property.name == name)
+    }
+}
+
+impl PropertyDecl {
+    /// replace generic parameters in the type and the accessors of the property
+    pub fn substitute_types(&mut self, bindings: &[(String, Type)]) {
+        self.property_type = self.property_type.substitute(bindings);
+        for statement in &mut self.getter {
+            statement.substitute_types(bindings);
+        }
+        if let Some(setter) = &mut self.setter {
+            for statement in &mut setter.body {
+                statement.substitute_types(bindings);
+            }
+        }
+    }
+
+    /// the getter as a method without parameters, which is how it is checked and emitted
+    pub fn getter_function(&self) -> FunctionDecl {
+        FunctionDecl {
+            name: self.name.clone(),
+            generic_parameters: Vec::new(),
+            parameters: Vec::new(),
+            return_type: self.property_type.clone(),
+            body: self.getter.clone(),
+            operator: None,
+            throws: false,
+            rethrows: false,
+            opaque_result: false,
+            is_mutating: false,
+            doc_comment: None,
+        }
+    }
+
+    /// the setter as a mutating method taking the new value
+    pub fn setter_function(&self) -> Option<FunctionDecl> {
+        let setter = self.setter.as_ref()?;
+        Some(FunctionDecl { is_mutating: true, ..setter.function(&self.name, &self.property_type) })
+    }
+}
+
+impl Accessor {
+    /// the accessor as a function of a property of type `ty`, taking the value as its only parameter
+    pub fn function(&self, name: &str, ty: &Type) -> FunctionDecl {
+        FunctionDecl {
+            name: name.to_string(),
+            generic_parameters: Vec::new(),
+            parameters: vec![Parameter {
+                label: None,
+                name: self.parameter.clone(),
+                param_type: ty.clone(),
+                is_inout: false,
+            }],
+            return_type: Type::Void,
+            body: self.body.clone(),
+            operator: None,
+            throws: false,
+            rethrows: false,
+            opaque_result: false,
+            is_mutating: false,
+            doc_comment: None,
+        }
+    }
+}
+
+impl ExtensionDecl {
+    /// replace generic parameters in the types of every member
+    pub fn substitute_types(&mut self, bindings: &[(String, Type)]) {
+        for function in self.static_functions.iter_mut().chain(&mut self.methods) {
+            *function = function.substitute(bindings);
+        }
+        for property in self.properties.iter_mut().chain(&mut self.static_properties) {
+            property.substitute_types(bindings);
+        }
+        for property in &mut self.static_stored_properties {
+            property.type_annotation = property.type_annotation.as_ref().map(|ty| ty.substitute(bindings));
+        }
+    }
+
+    /// The generic arguments of `ty` by parameter name, when this extension
+    /// of a standard library type applies to values of `ty`.
+    pub fn bindings(&self, ty: &Type) -> Option<Vec<(String, Type)>> {
+        let bindings = match (self.extended_type.as_str(), ty) {
+            ("Array", Type::Array(element)) | ("Set", Type::Set(element)) => {
+                vec![("Element".to_string(), (**element).clone())]
+            }
+            ("Dictionary", Type::Dictionary(key, value)) => {
+                vec![("Key".to_string(), (**key).clone()), ("Value".to_string(), (**value).clone())]
+            }
+            (name, ty) if Type::builtin(name).as_ref() == Some(ty) => Vec::new(),
+            _ => return None,
+        };
+        Some(bindings)
+    }
+
+    /// generic parameters of the extended standard library type
+    pub fn generic_parameters(&self) -> &'static [&'static str] {
+        match self.extended_type.as_str() {
+            "Array" | "Set" => &["Element"],
+            "Dictionary" => &["Key", "Value"],
+            _ => &[],
+        }
+    }
+}
+
+impl ProtocolDecl {
+    /// the standard library's `Error` protocol, which has no requirements
+    pub fn error() -> ProtocolDecl {
+        ProtocolDecl {
+            name: "Error".to_string(),
+            requirements: Vec::new(),
+            doc_comment: None,
+        }
+    }
+
+    /// position of the requirement a member access or call refers to
+    pub fn find_requirement(&self, name: &str, arguments: Option<&[Argument]>) -> Option<(usize, &Requirement)> {
+        self.requirements.iter().enumerate().find(|(_, requirement)| match (requirement, arguments) {
+            (Requirement::Method(method), Some(arguments)) => method.name == name && method.matches_labels(arguments),
+            (Requirement::Property { name: property, .. }, None) => property == name,
+            _ => false,
+        })
+    }
+}
+
+/// `map`, `filter` and the other methods that take a closure, which every
+/// collection has; `contains(_:)` without a closure is the lookup of a set
+pub fn is_higher_order_method(name: &str, arguments: &[Argument]) -> bool {
+    match name {
+        "map" | "compactMap" | "filter" | "forEach" | "reduce" | "sorted" | "first" => true,
+        "contains" => arguments.first().is_some_and(|argument| {
+            argument.label.as_deref() == Some("where") || matches!(argument.value, Expression::Closure { .. })
+        }),
+        _ => false,
+    }
+}
+
+impl FunctionDecl {
+    /// Swift style name including argument labels, e.g. `move(_:by:)`;
+    /// operators list their parameter types instead: `**(Int, Int)`
+    pub fn signature(&self) -> String {
+        if let Some(fixity) = self.operator {
+            let types = self.parameters.iter()
+                .map(|parameter| parameter.param_type.to_string())
+                .collect::<Vec<_>>();
+            return format!("{}{}({})", fixity.modifier(), self.name, types.join(", "));
+        }
+        let labels = self.parameters.iter()
+            .map(|parameter| format!("{}:", parameter.label.as_deref().unwrap_or("_")))
+            .collect::<String>();
+        format!("{}({})", self.name, labels)
+    }
+
+    /// whether a call with these argument labels refers to this function
+    pub fn matches_labels(&self, arguments: &[Argument]) -> bool {
+        self.parameters.len() == arguments.len()
+            && self.parameters.iter().zip(arguments).all(|(parameter, argument)| parameter.label == argument.label)
+    }
+
+    /// `(Int, Int) -> Int`, the type of the function used as a value
+    pub fn function_type(&self) -> Type {
+        Type::Function {
+            parameters: self.parameters.iter().map(|parameter| parameter.param_type.clone()).collect(),
+            result: Box::new(self.return_type.clone()),
+            throws: self.throws,
+        }
+    }
+
+    /// The overloads a function name used as a value can refer to: those
+    /// of the expected function type, regardless of `throws`, if any.
+    pub fn referenced<'a>(overloads: &'a [FunctionDecl], expected: Option<&Type>) -> Vec<&'a FunctionDecl> {
+        let overloads = overloads.iter().filter(|decl| decl.operator.is_none()).collect::<Vec<_>>();
+        let Some(Type::Function { parameters, result, .. }) = expected.map(Type::innermost_wrapped) else {
+            return overloads;
+        };
+        let matching = overloads.iter()
+            .filter(|decl| {
+                decl.parameters.iter().map(|parameter| &parameter.param_type).eq(parameters)
+                    && decl.return_type == **result
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        if matching.is_empty() { overloads } else { matching }
+    }
+}
+
+impl FunctionDecl {
+    /// Types of the type parameters for a call with `arguments`, which
+    /// `type_of` types; the argument of an `inout` parameter is typed without
+    /// its `&`. Arguments other than literals decide first, so `maximum(1, x)`
+    /// takes the type of `x`, and closures come last: they are typed with the
+    /// parameter type as a hint once the other arguments have bound its type parameters.
+    pub fn infer_generic_arguments<E>(
+        &self,
+        arguments: &[Argument],
+        mut type_of: impl FnMut(&Expression, Option<&Type>) -> Result<Type, E>,
+    ) -> Result<Vec<(String, Type)>, E> {
+        let mut bindings = Vec::new();
+        let order = |argument: &Argument| match &argument.value {
+            Expression::Closure { .. } => 2,
+            Expression::Nil | Expression::ArrayLiteral(_) | Expression::DictionaryLiteral(_) | Expression::ImplicitMember(_) => 1,
+            value if value.is_number_literal() => 1,
+            _ => 0,
+        };
+        let mut pairs = arguments.iter().zip(&self.parameters).collect::<Vec<_>>();
+        pairs.sort_by_key(|(argument, _)| order(argument));
+        for (argument, parameter) in pairs {
+            let value = match &argument.value {
+                Expression::InOut(target) => target.as_ref(),
+                value => value,
+            };
+            let expected = parameter.param_type.substitute(&bindings);
+            let is_unbound = |ty: &Type| self.generic_parameters.iter().any(|generic| ty.mentions(&Type::Generic(generic.name.clone())));
+            if is_unbound(&expected) {
+                let hint = match (value, &expected) {
+                    (Expression::Closure { .. }, Type::Function { parameters, result, throws }) if !parameters.iter().any(is_unbound) => {
+                        // the result type is inferred from the closure body when it isn't bound yet
+                        let result = if is_unbound(result) { Type::inferred() } else { (**result).clone() };
+                        Some(Type::Function { parameters: parameters.clone(), result: Box::new(result), throws: *throws })
+                    }
+                    _ => None,
+                };
+                let ty = type_of(value, hint.as_ref())?;
+                self.bind_generic_arguments(&expected, &ty, &mut bindings);
+            }
+        }
+        Ok(bindings)
+    }
+
+    /// match a parameter type against an argument type, binding the type parameters it contains
+    fn bind_generic_arguments(&self, parameter: &Type, argument: &Type, bindings: &mut Vec<(String, Type)>) {
+        match (parameter, argument) {
+            (Type::Generic(name), _)
+                if self.generic_parameters.iter().any(|generic| generic.name == *name)
+                    && !bindings.iter().any(|(bound, _)| bound == name) =>
+            {
+                bindings.push((name.clone(), argument.clone()));
+            }
+            (Type::Optional(parameter), Type::Optional(argument))
+            | (Type::Array(parameter), Type::Array(argument))
+            | (Type::Set(parameter), Type::Set(argument)) => {
+                self.bind_generic_arguments(parameter, argument, bindings);
+            }
+            // a value is wrapped when an optional is expected
+            (Type::Optional(parameter), _) => self.bind_generic_arguments(parameter, argument, bindings),
+            (Type::Dictionary(parameter_key, parameter_value), Type::Dictionary(argument_key, argument_value)) => {
+                self.bind_generic_arguments(parameter_key, argument_key, bindings);
+                self.bind_generic_arguments(parameter_value, argument_value, bindings);
+            }
+            (Type::Tuple(parameters), Type::Tuple(arguments)) if parameters.len() == arguments.len() => {
+                for (parameter, argument) in parameters.iter().zip(arguments) {
+                    self.bind_generic_arguments(&parameter.element_type, &argument.element_type, bindings);
+                }
+            }
+            (Type::Named(name, parameters), Type::Named(argument_name, arguments)) if name == argument_name => {
+                for (parameter, argument) in parameters.iter().zip(arguments) {
+                    self.bind_generic_arguments(parameter, argument, bindings);
+                }
+            }
+            (
+                Type::Function { parameters, result, .. },
+                Type::Function { parameters: argument_parameters, result: argument_result, .. },
+            ) if parameters.len() == argument_parameters.len() => {
+                for (parameter, argument) in parameters.iter().zip(argument_parameters) {
+                    self.bind_generic_arguments(parameter, argument, bindings);
+                }
+                self.bind_generic_arguments(result, argument_result, bindings);
+            }
+            _ => {}
+        }
+    }
+
+    /// a copy with generic parameters such as `Element` replaced by the types bound to them
+    pub fn substitute(&self, bindings: &[(String, Type)]) -> FunctionDecl {
+        let mut decl = self.clone();
+        decl.generic_parameters.retain(|parameter| bindings.iter().all(|(name, _)| *name != parameter.name));
+        for parameter in &mut decl.parameters {
+            parameter.param_type = parameter.param_type.substitute(bindings);
+        }
+        decl.return_type = decl.return_type.substitute(bindings);
+        for statement in &mut decl.body {
+            statement.substitute_types(bindings);
+        }
+        decl
+    }
+}
+
+impl Statement {
+    /// replace generic parameters in every type written inside the statement
+    pub fn substitute_types(&mut self, bindings: &[(String, Type)]) {
+        let substitute_all = |statements: &mut Vec<Statement>| {
+            for statement in statements {
+                statement.substitute_types(bindings);
+            }
+        };
+        match self {
+            Statement::Print(expr) | Statement::Expression(expr) | Statement::Return(Some(expr)) => {
+                expr.substitute_types(bindings);
+            }
+            Statement::VarDecl { type_annotation, value, .. } | Statement::Destructure { type_annotation, value, .. } => {
+                if let Some(annotation) = type_annotation {
+                    *annotation = annotation.substitute(bindings);
+                }
+                value.substitute_types(bindings);
+            }
+            Statement::Assignment { target, value, .. } => {
+                target.substitute_types(bindings);
+                value.substitute_types(bindings);
+            }
+            Statement::Switch { subject, cases } => {
+                subject.substitute_types(bindings);
+                for case in cases {
+                    substitute_all(&mut case.body);
+                }
+            }
+            Statement::If { conditions, then_branch, else_branch } => {
+                for condition in conditions {
+                    condition.substitute_types(bindings);
+                }
+                substitute_all(then_branch);
+                if let Some(else_branch) = else_branch {
+                    substitute_all(else_branch);
+                }
+            }
+            Statement::While { conditions, body } | Statement::Guard { conditions, else_branch: body } => {
+                for condition in conditions {
+                    condition.substitute_types(bindings);
+                }
+                substitute_all(body);
+            }
+            Statement::ForIn { sequence, body, .. } => {
+                sequence.substitute_types(bindings);
+                substitute_all(body);
+            }
+            Statement::Throw(value) => value.substitute_types(bindings),
+            Statement::Do { body, catches } => {
+                substitute_all(body);
+                for clause in catches {
+                    substitute_all(&mut clause.body);
+                }
+            }
+            Statement::Defer(body) => substitute_all(body),
+            // declarations that are only valid at the top level
+            Statement::EnumDecl(_) | Statement::ProtocolDecl(_) | Statement::Extension(_) | Statement::FunctionDecl(_)
+            | Statement::ComputedVar(_) | Statement::StoredVar(_)
+            | Statement::Return(None) | Statement::Break | Statement::Continue => {}
+        }
+    }
+}
+
+impl Statement {
+    /// call `visit` for every expression inside the statement, including
+    /// those in the bodies of closures, but not in nested declarations
+    pub fn walk_expressions(&self, visit: &mut dyn FnMut(&Expression)) {
+        let walk_all = |statements: &[Statement], visit: &mut dyn FnMut(&Expression)| {
+            for statement in statements {
+                statement.walk_expressions(visit);
+            }
+        };
+        match self {
+            Statement::Print(expr) | Statement::Expression(expr) | Statement::Return(Some(expr)) | Statement::Throw(expr)
+            | Statement::VarDecl { value: expr, .. } | Statement::Destructure { value: expr, .. } => expr.walk(visit),
+            Statement::Assignment { target, value, .. } => {
+                target.walk(visit);
+                value.walk(visit);
+            }
+            Statement::Switch { subject, cases } => {
+                subject.walk(visit);
+                for case in cases {
+                    walk_all(&case.body, visit);
+                }
+            }
+            Statement::If { conditions, then_branch, else_branch } => {
+                for condition in conditions {
+                    condition.walk(visit);
+                }
+                walk_all(then_branch, visit);
+                if let Some(else_branch) = else_branch {
+                    walk_all(else_branch, visit);
+                }
+            }
+            Statement::While { conditions, body } | Statement::Guard { conditions, else_branch: body } => {
+                for condition in conditions {
+                    condition.walk(visit);
+                }
+                walk_all(body, visit);
+            }
+            Statement::ForIn { sequence, body, .. } => {
+                sequence.walk(visit);
+                walk_all(body, visit);
+            }
+            Statement::Do { body, catches } => {
+                walk_all(body, visit);
+                for clause in catches {
+                    walk_all(&clause.body, visit);
+                }
+            }
+            Statement::Defer(body) => walk_all(body, visit),
+            Statement::EnumDecl(_) | Statement::ProtocolDecl(_) | Statement::Extension(_) | Statement::FunctionDecl(_)
+            | Statement::ComputedVar(_) | Statement::StoredVar(_)
+            | Statement::Return(None) | Statement::Break | Statement::Continue => {}
+        }
+    }
+}
+
+impl Condition {
+    fn walk(&self, visit: &mut dyn FnMut(&Expression)) {
+        match self {
+            Condition::Expression(expr) | Condition::OptionalBinding { value: expr, .. } => expr.walk(visit),
+        }
+    }
+
+    pub fn substitute_types(&mut self, bindings: &[(String, Type)]) {
+        match self {
+            Condition::Expression(expr) => expr.substitute_types(bindings),
+            Condition::OptionalBinding { type_annotation, value, .. } => {
+                if let Some(annotation) = type_annotation {
+                    *annotation = annotation.substitute(bindings);
+                }
+                value.substitute_types(bindings);
+            }
+        }
+    }
+}
+
+impl Fixity {
+    /// the declaration modifier, which is implied for infix operators
+    pub fn modifier(&self) -> &'static str {
+        match self {
+            Fixity::Prefix => "prefix ",
+            Fixity::Infix => "",
+            Fixity::Postfix => "postfix ",
+        }
+    }
+}
+
+impl Expression {
+    /// a number literal, possibly negated, whose type comes from the context
+    pub fn is_number_literal(&self) -> bool {
+        match self {
+            Expression::Number(_) | Expression::FloatLiteral(_) => true,
+            Expression::Unary { operator: UnaryOperator::Negate, operand } => operand.is_number_literal(),
+            _ => false,
+        }
+    }
+
+    /// the value of integer literals, negated or combined with `+`, `-` and `*`,
+    /// which the compiler can check for overflow
+    pub fn constant_integer(&self) -> Option<i128> {
+        match self {
+            Expression::Number(value) => Some(*value),
+            Expression::Unary { operator: UnaryOperator::Negate, operand } => operand.constant_integer()?.checked_neg(),
+            Expression::Binary { left, operator, right } => {
+                let (left, right) = (left.constant_integer()?, right.constant_integer()?);
+                match operator {
+                    BinaryOperator::Add => left.checked_add(right),
+                    BinaryOperator::Subtract => left.checked_sub(right),
+                    BinaryOperator::Multiply => left.checked_mul(right),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// a floating point literal, possibly negated, which is a `Double` by default
+    pub fn is_float_literal(&self) -> bool {
+        match self {
+            Expression::FloatLiteral(_) => true,
+            Expression::Unary { operator: UnaryOperator::Negate, operand } => operand.is_float_literal(),
+            _ => false,
+        }
+    }
+
+    /// the branch of `flag ? value : nil` or `flag ? nil : value` that isn't `nil`
+    pub fn non_nil_branch<'a>(then_value: &'a Expression, else_value: &'a Expression) -> Option<&'a Expression> {
+        match (then_value, else_value) {
+            (Expression::Nil, Expression::Nil) => None,
+            (Expression::Nil, value) | (value, Expression::Nil) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// replace generic parameters in the closures inside the expression
+    pub fn substitute_types(&mut self, bindings: &[(String, Type)]) {
+        match self {
+            Expression::Number(_) | Expression::FloatLiteral(_) | Expression::Bool(_) | Expression::StringLiteral(_)
+            | Expression::Nil | Expression::Variable(_) | Expression::ImplicitMember(_) => {}
+            Expression::Interpolation(parts) => {
+                for part in parts {
+                    if let InterpolationPart::Expression(expr) = part {
+                        expr.substitute_types(bindings);
+                    }
+                }
+            }
+            Expression::Unary { operand, .. } | Expression::ForceUnwrap(operand) | Expression::BindOptional(operand)
+            | Expression::OptionalEvaluation(operand) | Expression::Member { base: operand, .. }
+            | Expression::InOut(operand) | Expression::Try(operand) | Expression::OptionalTry(operand)
+            | Expression::ForceTry(operand) => {
+                operand.substitute_types(bindings);
+            }
+            Expression::SpecializedType(ty) => {
+                *ty = ty.substitute(bindings);
+            }
+            Expression::Binary { left, right, .. } | Expression::Subscript { base: left, index: right } => {
+                left.substitute_types(bindings);
+                right.substitute_types(bindings);
+            }
+            Expression::Tuple(arguments) => {
+                for argument in arguments {
+                    argument.value.substitute_types(bindings);
+                }
+            }
+            Expression::Call { callee, arguments } => {
+                callee.substitute_types(bindings);
+                for argument in arguments {
+                    argument.value.substitute_types(bindings);
+                }
+            }
+            Expression::ArrayLiteral(elements) | Expression::OperatorCall { operands: elements, .. } => {
+                for element in elements {
+                    element.substitute_types(bindings);
+                }
+            }
+            Expression::DictionaryLiteral(entries) => {
+                for (key, value) in entries {
+                    key.substitute_types(bindings);
+                    value.substitute_types(bindings);
+                }
+            }
+            Expression::Ternary { condition, then_value, else_value } => {
+                condition.substitute_types(bindings);
+                then_value.substitute_types(bindings);
+                else_value.substitute_types(bindings);
+            }
+            Expression::Closure { parameters, return_type, body } => {
+                for parameter in parameters {
+                    if let Some(param_type) = &mut parameter.param_type {
+                        *param_type = param_type.substitute(bindings);
+                    }
+                }
+                if let Some(return_type) = return_type {
+                    *return_type = return_type.substitute(bindings);
+                }
+                for statement in body {
+                    statement.substitute_types(bindings);
+                }
+            }
+        }
+    }
+
+    /// call `visit` for this expression and every expression inside it,
+    /// including those in the bodies of closures
+    pub fn walk(&self, visit: &mut dyn FnMut(&Expression)) {
+        visit(self);
+        match self {
+            Expression::Number(_) | Expression::FloatLiteral(_) | Expression::Bool(_) | Expression::StringLiteral(_)
+            | Expression::Nil | Expression::Variable(_) | Expression::SpecializedType(_) | Expression::ImplicitMember(_) => {}
+            Expression::Interpolation(parts) => {
+                for part in parts {
+                    if let InterpolationPart::Expression(expr) = part {
+                        expr.walk(visit);
+                    }
+                }
+            }
+            Expression::Unary { operand, .. } | Expression::ForceUnwrap(operand) | Expression::BindOptional(operand)
+            | Expression::OptionalEvaluation(operand) | Expression::Member { base: operand, .. }
+            | Expression::InOut(operand) | Expression::Try(operand) | Expression::OptionalTry(operand)
+            | Expression::ForceTry(operand) => operand.walk(visit),
+            Expression::Binary { left, right, .. } | Expression::Subscript { base: left, index: right } => {
+                left.walk(visit);
+                right.walk(visit);
+            }
+            Expression::Tuple(arguments) => {
+                for argument in arguments {
+                    argument.value.walk(visit);
+                }
+            }
+            Expression::Call { callee, arguments } => {
+                callee.walk(visit);
+                for argument in arguments {
+                    argument.value.walk(visit);
+                }
+            }
+            Expression::ArrayLiteral(elements) | Expression::OperatorCall { operands: elements, .. } => {
+                for element in elements {
+                    element.walk(visit);
+                }
+            }
+            Expression::DictionaryLiteral(entries) => {
+                for (key, value) in entries {
+                    key.walk(visit);
+                    value.walk(visit);
+                }
+            }
+            Expression::Ternary { condition, then_value, else_value } => {
+                condition.walk(visit);
+                then_value.walk(visit);
+                else_value.walk(visit);
+            }
+            Expression::Closure { body, .. } => {
+                for statement in body {
+                    statement.walk_expressions(visit);
+                }
+            }
+        }
+    }
+
+    /// `{ $0 + $1 }`, what an operator passed as an argument stands for: `reduce(0, +)`
+    pub fn operator_closure(apply: impl FnOnce(Expression, Expression) -> Expression) -> Expression {
+        let parameters = ["$0", "$1"].map(|name| ClosureParameter { name: name.to_string(), param_type: None });
+        let value = apply(Expression::Variable("$0".to_string()), Expression::Variable("$1".to_string()));
+        Expression::Closure {
+            parameters: parameters.to_vec(),
+            return_type: None,
+            body: vec![Statement::Return(Some(value))],
+        }
+    }
+}
+
+impl Type {
+    /// standard library type spelled `name`, e.g. `UInt8`
+    pub fn builtin(name: &str) -> Option<Type> {
+        let ty = match name {
+            "Int" => Type::Int,
+            "Int8" => Type::Int8,
+            "Int16" => Type::Int16,
+            "Int32" => Type::Int32,
+            "Int64" => Type::Int64,
+            "UInt" => Type::UInt,
+            "UInt8" => Type::UInt8,
+            "UInt16" => Type::UInt16,
+            "UInt32" => Type::UInt32,
+            "UInt64" => Type::UInt64,
+            "Double" => Type::Double,
+            "Float" => Type::Float,
+            "Bool" => Type::Bool,
+            "String" => Type::String,
+            "Void" => Type::Void,
+            "Never" => Type::Never,
+            _ => return None,
+        };
+        Some(ty)
+    }
+
+    /// `any Error`, the type of a thrown error
+    pub fn error() -> Type {
+        Type::Existential("Error".to_string())
+    }
+
+    /// `_`, the result of a closure type hint that is left to the closure body
+    pub fn inferred() -> Type {
+        Type::Generic("_".to_string())
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.is_floating_point()
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.integer_bits().is_some()
+    }
+
+    /// width of a fixed-size integer type, `Int` and `UInt` are 64 bits wide
+    pub fn integer_bits(&self) -> Option<u32> {
+        match self {
+            Type::Int8 | Type::UInt8 => Some(8),
+            Type::Int16 | Type::UInt16 => Some(16),
+            Type::Int32 | Type::UInt32 => Some(32),
+            Type::Int | Type::Int64 | Type::UInt | Type::UInt64 => Some(64),
+            _ => None,
+        }
+    }
+
+    pub fn is_unsigned_integer(&self) -> bool {
+        matches!(self, Type::UInt | Type::UInt8 | Type::UInt16 | Type::UInt32 | Type::UInt64)
+    }
+
+    /// `(min, max)` of an integer type
+    pub fn integer_range(&self) -> Option<(i128, i128)> {
+        let bits = self.integer_bits()?;
+        if self.is_unsigned_integer() {
+            Some((0, (1i128 << bits) - 1))
+        } else {
+            Some((-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1))
+        }
+    }
+
+    pub fn is_floating_point(&self) -> bool {
+        matches!(self, Type::Double | Type::Float)
+    }
+
+    /// the type inside any number of optionals: `Int` for `Int??`
+    pub fn innermost_wrapped(&self) -> &Type {
+        match self {
+            Type::Optional(wrapped) => wrapped.innermost_wrapped(),
+            ty => ty,
+        }
+    }
+
+    /// `(key: Key, value: Value)`, the elements a dictionary iterates over
+    pub fn dictionary_element(key_type: Type, value_type: Type) -> Type {
+        Type::Tuple(vec![
+            TupleElement { label: Some("key".to_string()), element_type: key_type },
+            TupleElement { label: Some("value".to_string()), element_type: value_type },
+        ])
+    }
+
+    /// replace generic parameters such as `Element` by the types bound to them
+    pub fn substitute(&self, bindings: &[(String, Type)]) -> Type {
+        self.replace(&mut |ty| {
+            let name = match ty {
+                Type::Named(name, arguments) if arguments.is_empty() => name,
+                Type::Generic(name) => name,
+                _ => return None,
+            };
+            bindings.iter()
+                .find(|(parameter, _)| parameter == name)
+                .map(|(_, ty)| ty.clone())
+        })
+    }
+
+    /// the type with every type inside it for which `replacement` returns a type replaced
+    pub fn replace(&self, replacement: &mut dyn FnMut(&Type) -> Option<Type>) -> Type {
+        if let Some(ty) = replacement(self) {
+            return ty;
+        }
+        match self {
+            Type::Optional(wrapped) => Type::Optional(Box::new(wrapped.replace(replacement))),
+            Type::Array(element) => Type::Array(Box::new(element.replace(replacement))),
+            Type::Set(element) => Type::Set(Box::new(element.replace(replacement))),
+            Type::Dictionary(key, value) => {
+                Type::Dictionary(Box::new(key.replace(replacement)), Box::new(value.replace(replacement)))
+            }
+            Type::Tuple(elements) => Type::Tuple(elements.iter()
+                .map(|element| TupleElement {
+                    label: element.label.clone(),
+                    element_type: element.element_type.replace(replacement),
+                })
+                .collect()),
+            Type::Function { parameters, result, throws } => Type::Function {
+                parameters: parameters.iter().map(|parameter| parameter.replace(replacement)).collect(),
+                result: Box::new(result.replace(replacement)),
+                throws: *throws,
+            },
+            Type::Named(name, arguments) => Type::Named(
+                name.clone(),
+                arguments.iter().map(|argument| argument.replace(replacement)).collect(),
+            ),
+            _ => self.clone(),
+        }
+    }
+
+    /// whether `ty` is this type or part of it, e.g. `T` of `[T?]`
+    pub fn mentions(&self, ty: &Type) -> bool {
+        if self == ty {
+            return true;
+        }
+        match self {
+            Type::Optional(inner) | Type::Array(inner) | Type::Set(inner) => inner.mentions(ty),
+            Type::Dictionary(key, value) => key.mentions(ty) || value.mentions(ty),
+            Type::Tuple(elements) => elements.iter().any(|element| element.element_type.mentions(ty)),
+            Type::Function { parameters, result, .. } => {
+                parameters.iter().any(|parameter| parameter.mentions(ty)) || result.mentions(ty)
+            }
+            Type::Named(_, arguments) => arguments.iter().any(|argument| argument.mentions(ty)),
+            _ => false,
+        }
+    }
+
+    /// the elements `for-in` and the higher-order methods go through
+    pub fn sequence_element(&self) -> Option<Type> {
+        match self {
+            Type::Array(element_type) | Type::Set(element_type) => Some((**element_type).clone()),
+            Type::Dictionary(key_type, value_type) => {
+                Some(Type::dictionary_element((**key_type).clone(), (**value_type).clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// element of a tuple type accessed as `.0` or by its label `.x`
+    pub fn tuple_element(&self, name: &str) -> Option<(usize, &Type)> {
+        let Type::Tuple(elements) = self else {
+            return None;
+        };
+        elements.iter().enumerate()
+            .find(|(index, element)| {
+                index.to_string() == name || element.label.as_deref() == Some(name)
+            })
+            .map(|(index, element)| (index, &element.element_type))
+    }
+}
+
+impl BinaryOperator {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Remainder => "%",
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::Less => "<",
+            BinaryOperator::LessEqual => "<=",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::GreaterEqual => ">=",
+            BinaryOperator::And => "&&",
+            BinaryOperator::Or => "||",
+            BinaryOperator::NilCoalescing => "??",
+            BinaryOperator::BitwiseAnd => "&",
+            BinaryOperator::BitwiseOr => "|",
+            BinaryOperator::BitwiseXor => "^",
+            BinaryOperator::ShiftLeft => "<<",
+            BinaryOperator::ShiftRight => ">>",
+            BinaryOperator::MaskingShiftLeft => "&<<",
+            BinaryOperator::MaskingShiftRight => "&>>",
+        }
+    }
+
+    pub fn is_shift(&self) -> bool {
+        matches!(
+            self,
+            BinaryOperator::ShiftLeft
+                | BinaryOperator::ShiftRight
+                | BinaryOperator::MaskingShiftLeft
+                | BinaryOperator::MaskingShiftRight
+        )
+    }
+
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            BinaryOperator::Equal
+                | BinaryOperator::NotEqual
+                | BinaryOperator::Less
+                | BinaryOperator::LessEqual
+                | BinaryOperator::Greater
+                | BinaryOperator::GreaterEqual
+        )
+    }
+}
+
+impl UnaryOperator {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            UnaryOperator::Negate => "-",
+            UnaryOperator::Not => "!",
+            UnaryOperator::BitwiseNot => "~",
+        }
+    }
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Int => write!(f, "Int"),
+            Type::Int8 => write!(f, "Int8"),
+            Type::Int16 => write!(f, "Int16"),
+            Type::Int32 => write!(f, "Int32"),
+            Type::Int64 => write!(f, "Int64"),
+            Type::UInt => write!(f, "UInt"),
+            Type::UInt8 => write!(f, "UInt8"),
+            Type::UInt16 => write!(f, "UInt16"),
+            Type::UInt32 => write!(f, "UInt32"),
+            Type::UInt64 => write!(f, "UInt64"),
+            Type::Double => write!(f, "Double"),
+            Type::Float => write!(f, "Float"),
+            Type::Bool => write!(f, "Bool"),
+            Type::String => write!(f, "String"),
+            Type::Void => write!(f, "()"),
+            Type::Never => write!(f, "Never"),
+            Type::Named(name, arguments) if arguments.is_empty() => write!(f, "{}", name),
+            Type::Named(name, arguments) => {
+                let arguments = arguments.iter().map(Type::to_string).collect::<Vec<_>>();
+                write!(f, "{}<{}>", name, arguments.join(", "))
+            }
+            // the type parameter of a `some P` parameter is named `some P#1`
+            Type::Generic(name) => write!(f, "{}", name.split('#').next().unwrap_or(name)),
+            Type::Existential(name) => write!(f, "any {}", name),
+            Type::Opaque(name) => write!(f, "some {}", name),
+            Type::Optional(wrapped) if matches!(**wrapped, Type::Function { .. }) => write!(f, "({})?", wrapped),
+            Type::Optional(wrapped) => write!(f, "{}?", wrapped),
+            Type::Array(element) => write!(f, "[{}]", element),
+            Type::Dictionary(key, value) => write!(f, "[{} : {}]", key, value),
+            Type::Set(element) => write!(f, "Set<{}>", element),
+            Type::Tuple(elements) => {
+                write!(f, "(")?;
+                for (index, element) in elements.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    if let Some(label) = &element.label {
+                        write!(f, "{}: ", label)?;
+                    }
+                    write!(f, "{}", element.element_type)?;
+                }
+                write!(f, ")")
+            }
+            Type::Function { parameters, result, throws } => {
+                write!(f, "(")?;
+                for (index, parameter) in parameters.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", parameter)?;
+                }
+                write!(f, ") ")?;
+                if *throws {
+                    write!(f, "throws ")?;
+                }
+                write!(f, "-> {}", result)
+            }
+        }
+    }
+}
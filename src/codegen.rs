@@ -1,29 +1,182 @@
-use crate::ast::{AstNode, Statement, Expression, BinaryOperator};
-use std::collections::HashMap;
+use crate::ast::{
+    AstNode, Statement, Expression, BinaryOperator, UnaryOperator, Argument, Type, EnumDecl,
+    SwitchCase, Pattern, Condition, FunctionDecl, TupleElement, InterpolationPart, Fixity, ForPattern,
+    ProtocolDecl, Requirement, ExtensionDecl, Parameter, CatchClause, PropertyDecl, StoredPropertyDecl,
+    ClosureParameter, is_higher_order_method,
+};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone)]
+struct Variable {
+    reg: String,
+    ty: Type,
+    // functions reading and writing the variable instead of `reg`, named `@"global.x.get"` and the like
+    getter: Option<String>,
+    setter: Option<String>,
+}
+
+/// the copy of a variable read through its getter, which is mutated in place
+/// and passed to the setter when the mutation is done
+struct WriteBack {
+    slot: String,
+    ty: Type,
+    setter: String,
+    // the address of the value a property setter is called on
+    receiver: Option<String>,
+}
+
+/// where a `return` inside an inlined closure stores its value and jumps to
+struct ClosureExit {
+    label: String,
+    // the number of deferred bodies outside the closure
+    deferred: usize,
+    // the result type given by the context or the closure's annotation
+    result_type: Option<Type>,
+    // the slot of the returned value, allocated by the first `return`
+    result: Option<(String, Type)>,
+    // set for the closure of `compactMap`, whose first `return` makes the result optional
+    optional_result: bool,
+}
+
+/// the body of a `defer` statement waiting for its scope to be left
+#[derive(Clone)]
+struct Deferred {
+    body: Vec<Statement>,
+    // the number of scopes visible to the body, later ones may shadow its variables
+    scope_depth: usize,
+}
+
+/// the block a `break` or `continue` jumps to, leaving the bodies deferred after it was entered
+struct JumpTarget {
+    label: String,
+    deferred: usize,
+}
+
+/// where a thrown error goes, innermost last
+#[derive(Clone)]
+enum ErrorHandler {
+    /// out of a throwing function, through the error slot it was passed as `%error`
+    Return,
+    /// the error slot of a `do` statement and the block matching its catch clauses
+    Catch { slot: String, label: String, deferred: usize },
+    /// the block producing the nil result of a `try?`
+    Optional { label: String, deferred: usize },
+    /// a runtime error with this message, for `try!` and at the top level
+    Fatal(&'static str),
+    /// out of a closure emitted as a function of its own, through the error
+    /// slot it was passed as `%error` to the block returning from it
+    Closure { label: String },
+}
 
 pub struct LLVMCodeGenerator {
     output: String,
+    // allocas of the current function, hoisted into its entry block
+    allocas: String,
+    // named types, constants and helper functions emitted before `main`
+    type_definitions: String,
+    globals: String,
+    functions: String,
     indent_label: usize,
     next_register: i32,
-    variables: HashMap<String, String>
+    next_label: i32,
+    scopes: Vec<HashMap<String, Variable>>,
+    // static properties of enums by `Enum.name`
+    statics: HashMap<String, Variable>,
+    // copies of variables with accessors waiting to be written back, innermost last
+    write_backs: Vec<WriteBack>,
+    enums: HashMap<String, EnumDecl>,
+    protocols: HashMap<String, ProtocolDecl>,
+    // extensions of protocols and standard library types, those of enums are merged into the enum
+    extensions: Vec<ExtensionDecl>,
+    function_decls: HashMap<String, Vec<FunctionDecl>>,
+    // return type of the function being generated, None inside main
+    return_type: Option<Type>,
+    break_targets: Vec<JumpTarget>,
+    continue_targets: Vec<JumpTarget>,
+    // `defer` bodies of the scopes being generated, run in reverse order when they are left
+    deferred: Vec<Deferred>,
+    string_constants: HashMap<String, String>,
+    emitted_helpers: HashSet<String>,
+    // external functions used by the module besides printf
+    declarations: Vec<String>,
+    // set once the hash table of the support library is declared
+    uses_support_library: bool,
+    // where a nil link of the innermost optional chain jumps to
+    optional_chain_targets: Vec<String>,
+    // closures being inlined, innermost last
+    closure_exits: Vec<ClosureExit>,
+    // set for the closure of `compactMap` until its body is generated
+    optional_result: bool,
+    // names of the variables used by closures in the function being generated,
+    // whose local variables of that name live on the heap
+    captured_names: HashSet<String>,
+    // addresses closures share instead of copying, in the function being
+    // generated: those local variables and the caller's variables passed `inout`
+    heap_variables: HashSet<String>,
+    // set once an error is thrown out of the closure function being generated
+    closure_throws: bool,
+    // set by `visit_arguments` when an argument is a throwing function value,
+    // so that a call of a `rethrows` function knows whether it throws
+    arguments_throw: bool,
+    error_handlers: Vec<ErrorHandler>,
+    // `%StringBuilder*` register that writes are appended to instead of
+    // being printed, set while lowering string interpolation
+    builder: Option<String>,
 }
 
 impl LLVMCodeGenerator {
     pub fn new() -> Self {
         LLVMCodeGenerator {
             output: String::new(),
+            allocas: String::new(),
+            type_definitions: String::new(),
+            globals: String::new(),
+            functions: String::new(),
             indent_label: 0,
             next_register: 1,
-            variables: HashMap::new(),
+            next_label: 1,
+            scopes: vec![HashMap::new()],
+            statics: HashMap::new(),
+            write_backs: Vec::new(),
+            enums: HashMap::new(),
+            protocols: HashMap::new(),
+            extensions: Vec::new(),
+            function_decls: HashMap::new(),
+            return_type: None,
+            break_targets: Vec::new(),
+            continue_targets: Vec::new(),
+            deferred: Vec::new(),
+            string_constants: HashMap::new(),
+            emitted_helpers: HashSet::new(),
+            declarations: Vec::new(),
+            uses_support_library: false,
+            optional_chain_targets: Vec::new(),
+            closure_exits: Vec::new(),
+            optional_result: false,
+            captured_names: HashSet::new(),
+            heap_variables: HashSet::new(),
+            closure_throws: false,
+            arguments_throw: false,
+            error_handlers: vec![ErrorHandler::Fatal("Error raised at top level")],
+            builder: None,
         }
     }
 
+    // registers are named (%t1, %t2, ...) so that values can be emitted
+    // in any order across basic blocks and helper functions
     fn alloc_register(&mut self) -> String {
-        let reg = format!("%{}", self.next_register);
+        let reg = format!("%t{}", self.next_register);
         self.next_register += 1;
         reg
     }
 
+    fn alloc_label(&mut self, name: &str) -> String {
+        let label = format!("{}{}", name, self.next_label);
+        self.next_label += 1;
+        label
+    }
+
     fn emit_indent(&mut self) {
         for _ in 0..self.indent_label {
             self.output.push_str("  ");
@@ -40,140 +193,5477 @@ impl LLVMCodeGenerator {
         self.output.push_str(code);
     }
 
+    fn emit_label(&mut self, label: &str) {
+        self.emit(&format!("{}:\n", label));
+    }
+
+    fn emit_branch(&mut self, label: &str) {
+        self.emit_line(&format!("br label %{}", label));
+    }
+
+    /// reserve stack space in the entry block of the current function
+    fn emit_alloca(&mut self, llvm_type: &str) -> String {
+        let reg = self.alloc_register();
+        self.allocas.push_str(&format!("  {} = alloca {}, align 8\n", reg, llvm_type));
+        reg
+    }
+
+    fn declare(&mut self, declaration: &str) {
+        if !self.declarations.iter().any(|existing| existing == declaration) {
+            self.declarations.push(declaration.to_string());
+        }
+    }
+
     fn generate(&mut self, ast: &AstNode) -> String {
         self.output.clear();
         self.next_register = 1;
 
-        // LLVM IR header
-        self.emit_line("; ModuleID = 'swift_module'");
-        self.emit_line("source_filename = \"swift_source\"");
-        self.emit_line("");
-
-        // Declare external printf function
-        self.emit_line("declare i32 @printf(i8*, ...)");
-        self.emit_line("");
-
-        // Format string for printf(global costant)
-        self.emit_line("@.str = private unnamed_addr constant [4 x i8] c\"%d\\0A\\00\", align 1");
-        self.emit_line("");
+        let AstNode::Program(statements) = ast;
+        self.protocols.insert("Error".to_string(), ProtocolDecl::error());
+        for statement in statements {
+            match statement {
+                Statement::EnumDecl(decl) => {
+                    self.enums.insert(decl.name.clone(), decl.clone());
+                    for function in &decl.static_functions {
+                        self.function_decls.entry(function.name.clone()).or_default().push(function.clone());
+                    }
+                }
+                Statement::FunctionDecl(decl) => {
+                    self.function_decls.entry(decl.name.clone()).or_default().push(decl.clone());
+                }
+                Statement::ProtocolDecl(decl) => {
+                    self.protocols.insert(decl.name.clone(), decl.clone());
+                }
+                Statement::Extension(decl) => {
+                    for function in &decl.static_functions {
+                        self.function_decls.entry(function.name.clone()).or_default().push(function.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+        for statement in statements {
+            if let Statement::Extension(decl) = statement {
+                match self.enums.get_mut(&decl.extended_type) {
+                    Some(enum_decl) => {
+                        enum_decl.conformances.extend(decl.conformances.iter().cloned());
+                        enum_decl.methods.extend(decl.methods.iter().cloned());
+                        enum_decl.properties.extend(decl.properties.iter().cloned());
+                    }
+                    None => self.extensions.push(decl.clone()),
+                }
+            }
+        }
+        self.emit_enum_types(statements);
+        for statement in statements {
+            match statement {
+                Statement::EnumDecl(decl) => {
+                    self.declare_static_properties(&decl.name, &decl.static_properties, &decl.static_stored_properties);
+                }
+                Statement::Extension(decl) if self.enums.contains_key(&decl.extended_type) => {
+                    self.declare_static_properties(&decl.extended_type, &decl.static_properties, &decl.static_stored_properties);
+                }
+                _ => {}
+            }
+        }
 
         // Main function
-        self.emit_line("define i32 @main() {");
-        self.emit_line("entry:");
         self.indent_label = 1;
-
+        self.captured_names = Self::closure_variable_names(statements);
         self.visit_node(ast);
 
         // Return from main
         self.emit_line("ret i32 0");
         self.indent_label = 0;
-        self.emit_line("}");
 
-        self.output.clone()
+        let mut module = String::new();
+        // LLVM IR header
+        module.push_str("; ModuleID = 'swift_module'\n");
+        module.push_str("source_filename = \"swift_source\"\n\n");
+        module.push_str(&self.type_definitions);
+        // refcount, count, capacity and the elements of an array buffer
+        module.push_str("%Array = type { i64, i64, i64, i8* }\n");
+        // Declare external printf function
+        module.push_str("declare i32 @printf(i8*, ...)\n");
+        for declaration in &self.declarations {
+            module.push_str(declaration);
+            module.push('\n');
+        }
+        module.push('\n');
+        module.push_str(&self.globals);
+        module.push('\n');
+        module.push_str(&self.functions);
+        module.push_str("define i32 @main() {\nentry:\n");
+        module.push_str(&self.allocas);
+        module.push_str(&self.output);
+        module.push_str("}\n");
+        module
+    }
+
+    /// Emit a helper function. `header` is the `define` line without the
+    /// opening brace and `body` generates its instructions.
+    fn emit_function<F: FnOnce(&mut Self)>(&mut self, header: &str, body: F) {
+        self.emit_function_with_header(|generator| {
+            body(generator);
+            header.to_string()
+        });
+    }
+
+    /// Emit a function whose `define` line is returned by `body`, as it is
+    /// only known once the body is generated for closures inferring their result type.
+    fn emit_function_with_header<F: FnOnce(&mut Self) -> String>(&mut self, body: F) {
+        let saved_output = std::mem::take(&mut self.output);
+        let saved_allocas = std::mem::take(&mut self.allocas);
+        let saved_indent = self.indent_label;
+        let saved_builder = self.builder.take();
+        self.indent_label = 1;
+
+        let header = body(self);
+
+        let output = std::mem::replace(&mut self.output, saved_output);
+        let allocas = std::mem::replace(&mut self.allocas, saved_allocas);
+        self.indent_label = saved_indent;
+        self.builder = saved_builder;
+
+        self.functions.push_str(&format!("{} {{\nentry:\n{}{}}}\n\n", header, allocas, output));
     }
 
     fn visit_node(&mut self, node: &AstNode) {
         match node {
-            AstNode::Program(statements) => {
-                for statement in statements {
-                    self.visit_statement(statement);
+            AstNode::Program(statements) => self.visit_statements(statements),
+        }
+    }
+
+    // MARK - types
+
+    fn llvm_type(&self, ty: &Type) -> String {
+        match ty {
+            Type::Int | Type::Int64 | Type::UInt | Type::UInt64 => "i64".to_string(),
+            Type::Int8 | Type::UInt8 => "i8".to_string(),
+            Type::Int16 | Type::UInt16 => "i16".to_string(),
+            Type::Int32 | Type::UInt32 => "i32".to_string(),
+            Type::Double => "double".to_string(),
+            Type::Float => "float".to_string(),
+            Type::Bool => "i1".to_string(),
+            // pointer to UTF-8 bytes and their count
+            Type::String => "{ i8*, i64 }".to_string(),
+            Type::Void | Type::Never => "{}".to_string(),
+            Type::Named(name, arguments) if arguments.is_empty() => format!("%\"enum.{}\"", name),
+            // specializations of a generic enum have the same layout as a literal struct
+            Type::Named(..) => format!("{{ i32, [{} x i64] }}", self.payload_words(&self.enum_decl(ty))),
+            // the boxed value and the witness table of its conformance
+            Type::Existential(_) => "{ i8*, i8** }".to_string(),
+            Type::Generic(name) => unreachable!("type parameter '{}' of a function that is not specialized", name),
+            Type::Opaque(name) => unreachable!("'some {}' outside of a function signature", name),
+            Type::Optional(wrapped) => format!("{{ i1, {} }}", self.llvm_type(wrapped)),
+            Type::Array(_) => "%Array*".to_string(),
+            // an opaque hash table of the support library
+            Type::Dictionary(..) | Type::Set(_) => "i8*".to_string(),
+            // tuples are anonymous structs, labels only exist at compile time
+            Type::Tuple(elements) => {
+                let fields = elements.iter()
+                    .map(|element| self.llvm_type(&element.element_type))
+                    .collect::<Vec<_>>();
+                format!("{{ {} }}", fields.join(", "))
+            }
+            // the code, taking the context as its first argument, and the captured variables
+            Type::Function { .. } => "{ i8*, i8* }".to_string(),
+        }
+    }
+
+    /// upper bound of the size of a value in bytes, always a multiple of 8
+    fn storage_size(&self, ty: &Type) -> usize {
+        match ty {
+            Type::Int | Type::Int8 | Type::Int16 | Type::Int32 | Type::Int64
+            | Type::UInt | Type::UInt8 | Type::UInt16 | Type::UInt32 | Type::UInt64
+            | Type::Double | Type::Float | Type::Bool | Type::Void | Type::Never
+            | Type::Array(_) | Type::Dictionary(..) | Type::Set(_) => 8,
+            Type::String | Type::Existential(_) | Type::Function { .. } => 16,
+            Type::Named(..) => 8 + 8 * self.payload_words(&self.enum_decl(ty)),
+            Type::Generic(name) => unreachable!("type parameter '{}' of a function that is not specialized", name),
+            Type::Opaque(name) => unreachable!("'some {}' outside of a function signature", name),
+            Type::Optional(wrapped) => 8 + self.storage_size(wrapped),
+            Type::Tuple(elements) => elements.iter()
+                .map(|element| self.storage_size(&element.element_type))
+                .sum(),
+        }
+    }
+
+    /// number of 8 byte words needed to hold the largest payload of an enum
+    fn payload_words(&self, decl: &EnumDecl) -> usize {
+        decl.cases.iter()
+            .map(|case| {
+                case.associated_values.iter()
+                    .map(|value| self.storage_size(&value.value_type))
+                    .sum::<usize>() / 8
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn payload_type(&self, decl: &EnumDecl, case_index: usize) -> String {
+        let fields = decl.cases[case_index].associated_values.iter()
+            .map(|value| self.llvm_type(&value.value_type))
+            .collect::<Vec<_>>();
+        format!("{{ {} }}", fields.join(", "))
+    }
+
+    /// enums are lowered to tagged unions: { i32 tag, [N x i64] payload },
+    /// the layout of a generic enum is only known for its specializations
+    fn emit_enum_types(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            if let Statement::EnumDecl(decl) = statement
+                && decl.generic_parameters.is_empty()
+            {
+                let words = self.payload_words(decl);
+                self.type_definitions.push_str(&Self::doc_comment_lines(&decl.doc_comment));
+                self.type_definitions.push_str(&format!(
+                    "%\"enum.{}\" = type {{ i32, [{} x i64] }}\n",
+                    decl.name, words
+                ));
+                if decl.raw_type.is_some() {
+                    let values = decl.raw_values().expect("checked by the semantic pass").iter()
+                        .map(|value| format!("i64 {}", value))
+                        .collect::<Vec<_>>();
+                    self.globals.push_str(&format!(
+                        "@\"enum.{}.rawValues\" = private unnamed_addr constant [{} x i64] [{}]\n",
+                        decl.name,
+                        values.len(),
+                        values.join(", ")
+                    ));
                 }
             }
         }
+        if !self.type_definitions.is_empty() {
+            self.type_definitions.push('\n');
+        }
+    }
+
+    // MARK - scopes
+
+    fn lookup(&self, name: &str) -> Option<&Variable> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn declare_variable(&mut self, name: &str, reg: String, ty: Type) {
+        self.scopes.last_mut()
+            .expect("scope stack is never empty")
+            .insert(name.to_string(), Variable { reg, ty, getter: None, setter: None });
+    }
+
+    /// the declaration of an enum type, specialized for its type arguments
+    fn enum_decl(&self, ty: &Type) -> Cow<'_, EnumDecl> {
+        let Type::Named(name, arguments) = ty else {
+            unreachable!("'{}' is not an enum", ty);
+        };
+        match arguments.is_empty() {
+            true => Cow::Borrowed(&self.enums[name]),
+            false => Cow::Owned(self.enums[name].specialize(arguments)),
+        }
+    }
+
+    fn enum_named(&self, expr: &Expression) -> Option<&EnumDecl> {
+        match expr {
+            Expression::Variable(name) if self.lookup(name).is_none() => self.enums.get(name),
+            Expression::SpecializedType(Type::Named(name, _)) => self.enums.get(name),
+            _ => None,
+        }
+    }
+
+    /// `self.name`, what a bare member name stands for inside a method
+    fn self_member(name: &str) -> Expression {
+        Expression::Member {
+            base: Box::new(Expression::Variable("self".to_string())),
+            name: name.to_string(),
+        }
     }
 
+    // MARK - statements
+
     fn visit_statement(&mut self, statement: &Statement) {
         match statement {
             Statement::Print(expr) => {
-                let result_reg = self.visit_expression(expr);
+                let (value_reg, ty) = self.visit_expression(expr, None);
+                self.emit_write(&value_reg, &ty, false);
+                self.emit_write_literal("\n");
+            }
+            Statement::VarDecl { name, type_annotation, value, is_mutable: _ } => {
+                // Evaluate the value expression
+                let (value_reg, ty) = match type_annotation {
+                    Some(ty) => (self.visit_expression_as(value, ty), ty.clone()),
+                    None => self.visit_expression(value, None),
+                };
+                self.emit_variable(name, &value_reg, ty);
+            }
+            Statement::Destructure { names, type_annotation, value, is_mutable: _ } => {
+                let (value_reg, ty) = match type_annotation {
+                    Some(ty) => (self.visit_expression_as(value, ty), ty.clone()),
+                    None => self.visit_expression(value, None),
+                };
+                self.emit_destructure(names, &value_reg, &ty);
+            }
+            Statement::ComputedVar(property) => {
+                let prefix = format!("global.{}", property.name);
+                // declared first, so that the accessors can use it
+                let variable = Self::computed_variable(&prefix, property);
+                self.scopes[0].insert(property.name.clone(), variable);
+                self.emit_computed_property(property, &prefix);
+            }
+            Statement::StoredVar(decl) => {
+                let prefix = format!("global.{}", decl.name);
+                let ty = if decl.is_lazy {
+                    // the value is computed by the getter on first access
+                    decl.type_annotation.clone().unwrap_or_else(|| self.peek_type(&decl.value))
+                } else {
+                    // the value is stored right away, without calling the observers
+                    let (value, ty) = match &decl.type_annotation {
+                        Some(ty) => (self.visit_expression_as(&decl.value, ty), ty.clone()),
+                        None => self.visit_expression(&decl.value, None),
+                    };
+                    self.emit_variable(&decl.name, &value, ty.clone());
+                    ty
+                };
+                // the observers see the plain variable, so assigning it inside them doesn't call them again
+                let variable = self.emit_stored_property(decl, &prefix, &ty, decl.is_lazy);
+                self.scopes[0].insert(decl.name.clone(), variable);
+            }
+            // a plain assignment through a setter doesn't read the current value
+            Statement::Assignment { target, operator: None, value } if self.has_setter(target) => {
+                self.emit_setter_assignment(target, value);
+            }
+            Statement::Assignment { target, operator, value } => {
+                let pending = self.write_backs.len();
+                // the address is computed once, also for compound assignments
+                let (ptr, ty) = match target {
+                    Expression::Subscript { base, index } => {
+                        let (base_ptr, base_type) = self.emit_lvalue(base);
+                        if let Type::Dictionary(key_type, value_type) = base_type {
+                            self.visit_dictionary_assignment(&base_ptr, &key_type, &value_type, index, value);
+                            self.emit_write_backs(pending);
+                            return;
+                        }
+                        self.emit_subscript_lvalue(&base_ptr, base_type, index)
+                    }
+                    _ => self.emit_lvalue(target),
+                };
+                let llvm_type = self.llvm_type(&ty);
+                let value_reg = match operator {
+                    None => self.visit_expression_as(value, &ty),
+                    Some(operator) => {
+                        let current = self.alloc_register();
+                        self.emit_line(&format!("{} = load {}, {}* {}", current, llvm_type, llvm_type, ptr));
+                        if operator.is_shift() {
+                            let (amount, amount_type) = self.visit_expression(value, None);
+                            self.emit_shift(&current, &ty, &amount, &amount_type, operator)
+                        } else {
+                            let (right, _) = self.visit_expression(value, Some(&ty));
+                            self.emit_arithmetic(&current, &ty, operator, &right).0
+                        }
+                    }
+                };
+                self.emit_retain(&value_reg, &ty);
+                self.emit_line(&format!("store {} {}, {}* {}", llvm_type, value_reg, llvm_type, ptr));
+                self.emit_write_backs(pending);
+            }
+            Statement::EnumDecl(decl) => {
+                // lowered to a type definition up front, only its functions remain
+                for function in &decl.static_functions {
+                    self.visit_function(function);
+                }
+                // members of generic enums are emitted for each specialization
+                if decl.generic_parameters.is_empty() {
+                    let enum_type = decl.declared_type();
+                    for method in &decl.methods {
+                        self.emit_function_definition(method, &Self::method_name(&decl.name, method), Some(&enum_type));
+                    }
+                    for property in &decl.properties {
+                        self.emit_property_accessors(property, &decl.name, &enum_type);
+                    }
+                }
+                self.emit_static_properties(&decl.name, &decl.static_properties, &decl.static_stored_properties);
+            }
+            // requirements only exist at compile time, witness tables are emitted on first use
+            Statement::ProtocolDecl(_) => {}
+            Statement::Extension(decl) => {
+                // members of standard library types are emitted on first use, for each type
+                let self_type = if let Some(enum_decl) = self.enums.get(&decl.extended_type) {
+                    if !enum_decl.generic_parameters.is_empty() {
+                        // merged into the generic enum, emitted for each specialization
+                        return;
+                    }
+                    for function in &decl.static_functions {
+                        self.visit_function(function);
+                    }
+                    self.emit_static_properties(&decl.extended_type, &decl.static_properties, &decl.static_stored_properties);
+                    Type::Named(decl.extended_type.clone(), Vec::new())
+                } else if self.protocols.contains_key(&decl.extended_type) {
+                    Type::Existential(decl.extended_type.clone())
+                } else {
+                    return;
+                };
+                for method in &decl.methods {
+                    self.emit_function_definition(method, &Self::method_name(&decl.extended_type, method), Some(&self_type));
+                }
+                for property in &decl.properties {
+                    self.emit_property_accessors(property, &decl.extended_type, &self_type);
+                }
+            }
+            Statement::Switch { subject, cases } => {
+                self.visit_switch(subject, cases);
+            }
+            Statement::If { conditions, then_branch, else_branch } => {
+                let end_label = self.alloc_label("if.end");
+                let else_label = match else_branch {
+                    Some(_) => self.alloc_label("if.else"),
+                    None => end_label.clone(),
+                };
 
-                let call_reg = self.alloc_register();
-                self.emit_indent();
-                self.emit(&format!(
-                    "{} = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([4 x i8], [4 x i8]* @.str, i32 0, i32 0), i32 {})\n",
-                    call_reg, result_reg
-                ));
+                self.scopes.push(HashMap::new());
+                self.emit_conditions(conditions, &else_label);
+                self.visit_block(then_branch);
+                self.scopes.pop();
+                self.emit_branch(&end_label);
+
+                if let Some(else_branch) = else_branch {
+                    self.emit_label(&else_label);
+                    self.visit_block(else_branch);
+                    self.emit_branch(&end_label);
+                }
+                self.emit_label(&end_label);
             }
-            Statement::VarDecl { name, value, is_mutable: _ } => {
-                // Allocate stack space for the variable
-                let var_reg = self.alloc_register();
-                self.emit_indent();
-                self.emit(&format!("{} = alloca i32, align 4\n", var_reg));
+            Statement::While { conditions, body } => {
+                let condition_label = self.alloc_label("while.cond");
+                let end_label = self.alloc_label("while.end");
 
-                // Store the variable register
-                self.variables.insert(name.clone(), var_reg.clone());
+                self.emit_branch(&condition_label);
+                self.emit_label(&condition_label);
+                self.scopes.push(HashMap::new());
+                self.emit_conditions(conditions, &end_label);
+                self.break_targets.push(self.jump_target(&end_label));
+                self.continue_targets.push(self.jump_target(&condition_label));
+                self.visit_block(body);
+                self.break_targets.pop();
+                self.continue_targets.pop();
+                self.scopes.pop();
+                self.emit_branch(&condition_label);
+                self.emit_label(&end_label);
+            }
+            Statement::ForIn { pattern, sequence, body } => {
+                self.visit_for_in(pattern, sequence, body);
+            }
+            Statement::Guard { conditions, else_branch } => {
+                let else_label = self.alloc_label("guard.else");
+                let continue_label = self.alloc_label("guard.continue");
 
-                // Evaluate the value expression
-                let value_reg = self.visit_expression(value);
+                // the bindings only become visible after the else branch
+                self.scopes.push(HashMap::new());
+                self.emit_conditions(conditions, &else_label);
+                let bindings = self.scopes.pop().unwrap_or_default();
+                self.emit_branch(&continue_label);
+
+                self.emit_label(&else_label);
+                self.visit_block(else_branch);
+                // the semantic pass guarantees the else branch exits
+                self.emit_line("unreachable");
+
+                self.emit_label(&continue_label);
+                self.scopes.last_mut()
+                    .expect("scope stack is never empty")
+                    .extend(bindings);
+            }
+            // generic functions are emitted once for each list of type arguments they are called with
+            Statement::FunctionDecl(decl) if !decl.generic_parameters.is_empty() => {}
+            Statement::FunctionDecl(decl) => {
+                self.visit_function(decl);
+            }
+            Statement::Return(value) if !self.closure_exits.is_empty() => {
+                self.emit_closure_return(value.as_ref());
+            }
+            Statement::Return(value) => {
+                let return_type = self.return_type.clone().expect("return inside a function");
+                let value = match value {
+                    Some(value) => self.visit_expression_as(value, &return_type),
+                    None => "zeroinitializer".to_string(),
+                };
+                // the value is computed before the deferred bodies run
+                self.emit_deferred(0);
+                let llvm_type = self.llvm_type(&return_type);
+                self.emit_line(&format!("ret {} {}", llvm_type, value));
+                self.emit_dead_block();
+            }
+            Statement::Throw(value) => {
+                let error = self.visit_expression_as(value, &Type::error());
+                self.emit_throw(&error);
+                self.emit_dead_block();
+            }
+            Statement::Do { body, catches } if catches.is_empty() => {
+                self.visit_block(body);
+            }
+            Statement::Do { body, catches } => {
+                self.visit_do(body, catches);
+            }
+            Statement::Defer(body) => {
+                self.deferred.push(Deferred { body: body.clone(), scope_depth: self.scopes.len() });
+            }
+            Statement::Break => {
+                let target = self.break_targets.last().expect("break inside a loop or switch");
+                let (label, deferred) = (target.label.clone(), target.deferred);
+                self.emit_deferred(deferred);
+                self.emit_branch(&label);
+                self.emit_dead_block();
+            }
+            Statement::Continue => {
+                let target = self.continue_targets.last().expect("continue inside a loop");
+                let (label, deferred) = (target.label.clone(), target.deferred);
+                self.emit_deferred(deferred);
+                self.emit_branch(&label);
+                self.emit_dead_block();
+            }
+            Statement::Expression(expr) => {
+                self.visit_expression(expr, None);
+            }
+        }
+    }
+
+    /// Allocate storage for a new variable and initialize it with `value`.
+    /// Top level variables are globals so that functions can use them,
+    /// those closures may capture live on the heap and everything else on the stack.
+    fn emit_variable(&mut self, name: &str, value: &str, ty: Type) {
+        let llvm_type = self.llvm_type(&ty);
+        let var_reg = if self.scopes.len() == 1 {
+            let global = format!("@\"global.{}\"", name);
+            self.globals.push_str(&format!("{} = internal global {} zeroinitializer\n", global, llvm_type));
+            global
+        } else if self.captured_names.contains(name) {
+            self.emit_heap_variable(&ty)
+        } else {
+            self.emit_alloca(&llvm_type)
+        };
+        self.emit_retain(value, &ty);
+        self.emit_line(&format!("store {} {}, {}* {}", llvm_type, value, llvm_type, var_reg));
+        self.declare_variable(name, var_reg, ty);
+    }
+
+    /// storage for a variable outliving the function, shared by the closures capturing it
+    fn emit_heap_variable(&mut self, ty: &Type) -> String {
+        self.declare("declare i8* @malloc(i64)");
+        let llvm_type = self.llvm_type(ty);
+        let size = self.element_size(ty);
+        let cell = self.alloc_register();
+        self.emit_line(&format!("{} = call i8* @malloc(i64 {})", cell, size));
+        let reg = self.alloc_register();
+        self.emit_line(&format!("{} = bitcast i8* {} to {}*", reg, cell, llvm_type));
+        self.heap_variables.insert(reg.clone());
+        reg
+    }
 
-                // Store the value in the allocated space
-                self.emit_indent();
-                self.emit(&format!("store i32 {}, i32* {}, align 4\n", value_reg, var_reg));
+    /// a variable for every named element of the tuple `value`
+    fn emit_destructure(&mut self, names: &[Option<String>], value: &str, ty: &Type) {
+        let llvm_type = self.llvm_type(ty);
+        let Type::Tuple(elements) = ty else {
+            unreachable!("destructuring a non-tuple value");
+        };
+        for (index, (name, element)) in names.iter().zip(elements).enumerate() {
+            if let Some(name) = name {
+                let element_reg = self.alloc_register();
+                self.emit_line(&format!("{} = extractvalue {} {}, {}", element_reg, llvm_type, value, index));
+                self.emit_variable(name, &element_reg, element.element_type.clone());
             }
-            Statement::Assignment { name, value } => {
-                // Look up the variable's allocated register
-                if let Some(var_reg) = self.variables.get(name) {
-                    let var_reg = var_reg.clone();
-                    let value_reg = self.visit_expression(value);
+        }
+    }
+
+    /// start a new block for the code following a terminator (return, break, ...)
+    fn emit_dead_block(&mut self) {
+        let label = self.alloc_label("dead");
+        self.emit_label(&label);
+    }
+
+    /// LLVM name of a user function, mangled with its argument labels
+    fn function_name(decl: &FunctionDecl) -> String {
+        format!("@\"{}\"", decl.signature())
+    }
+
+    /// `@"Shape.scaled(by:)"`, the first parameter of a method is `self`
+    fn method_name(enum_name: &str, decl: &FunctionDecl) -> String {
+        format!("@\"{}.{}\"", enum_name, decl.signature())
+    }
+
+    fn getter_name(enum_name: &str, property: &str) -> String {
+        format!("@\"{}.{}.get\"", enum_name, property)
+    }
+
+    fn setter_name(enum_name: &str, property: &str) -> String {
+        format!("@\"{}.{}.set\"", enum_name, property)
+    }
+
+    /// a declaration's doc comment as IR comment lines
+    fn doc_comment_lines(doc_comment: &Option<String>) -> String {
+        doc_comment.iter()
+            .flat_map(|text| text.lines())
+            .map(|line| format!("; {}\n", line))
+            .collect()
+    }
+
+    /// the LLVM type a parameter is passed as, a pointer for `inout`
+    fn parameter_type(&self, parameter: &Parameter) -> String {
+        let llvm_type = self.llvm_type(&parameter.param_type);
+        if parameter.is_inout {
+            format!("{}*", llvm_type)
+        } else {
+            llvm_type
+        }
+    }
+
+    fn visit_function(&mut self, decl: &FunctionDecl) {
+        self.emit_function_definition(decl, &Self::function_name(decl), None);
+    }
+
+    /// a function named `name`, which takes the value it is called on
+    /// as an extra first parameter `self` when `self_type` is set
+    fn emit_function_definition(&mut self, decl: &FunctionDecl, name: &str, self_type: Option<&Type>) {
+        let mut parameters = decl.parameters.iter()
+            .map(|parameter| format!("{} %\"arg.{}\"", self.parameter_type(parameter), parameter.name))
+            .collect::<Vec<_>>();
+        // a setter gets the address of the value it changes
+        if let Some(self_type) = self_type {
+            let pointer = if decl.is_mutating { "*" } else { "" };
+            parameters.insert(0, format!("{}{} %self", self.llvm_type(self_type), pointer));
+        }
+        if decl.throws {
+            parameters.push(format!("{}* %error", self.llvm_type(&Type::error())));
+        }
+        let return_llvm_type = self.llvm_type(&decl.return_type);
+        let header = format!(
+            "{}define private {} {}({})",
+            Self::doc_comment_lines(&decl.doc_comment),
+            return_llvm_type,
+            name,
+            parameters.join(", ")
+        );
 
-                    self.emit_indent();
-                    self.emit(&format!("store i32 {}, i32* {}, align 4\n", value_reg, var_reg));
+        let decl = decl.clone();
+        let self_type = self_type.cloned();
+        self.emit_function(&header, |generator| {
+            let saved_return_type = generator.return_type.replace(decl.return_type.clone());
+            let saved_breaks = std::mem::take(&mut generator.break_targets);
+            let saved_continues = std::mem::take(&mut generator.continue_targets);
+            // members of extensions can be emitted in the middle of another function
+            let saved_locals = generator.scopes.split_off(1);
+            let saved_closures = std::mem::take(&mut generator.closure_exits);
+            let saved_chains = std::mem::take(&mut generator.optional_chain_targets);
+            let saved_deferred = std::mem::take(&mut generator.deferred);
+            let handlers = if decl.throws { vec![ErrorHandler::Return] } else { Vec::new() };
+            let saved_handlers = std::mem::replace(&mut generator.error_handlers, handlers);
+            let saved_captured = std::mem::replace(&mut generator.captured_names, Self::closure_variable_names(&decl.body));
+            let saved_heap = std::mem::take(&mut generator.heap_variables);
+
+            // parameters are copied to the stack like any other variable
+            generator.scopes.push(HashMap::new());
+            if let Some(self_type) = self_type.clone().filter(|_| decl.is_mutating) {
+                generator.heap_variables.insert("%self".to_string());
+                generator.declare_variable("self", "%self".to_string(), self_type);
+            } else if let Some(self_type) = self_type {
+                let llvm_type = generator.llvm_type(&self_type);
+                let slot = generator.emit_alloca(&llvm_type);
+                generator.emit_line(&format!("store {} %self, {}* {}", llvm_type, llvm_type, slot));
+                generator.declare_variable("self", slot, self_type);
+            }
+            for parameter in &decl.parameters {
+                // an inout parameter is the address of the caller's variable
+                if parameter.is_inout {
+                    let address = format!("%\"arg.{}\"", parameter.name);
+                    generator.heap_variables.insert(address.clone());
+                    generator.declare_variable(&parameter.name, address, parameter.param_type.clone());
+                    continue;
+                }
+                let llvm_type = generator.llvm_type(&parameter.param_type);
+                let argument = format!("%\"arg.{}\"", parameter.name);
+                let slot = if generator.captured_names.contains(&parameter.name) {
+                    // the closure may outlive the caller's value, which the caller may change in place
+                    generator.emit_retain(&argument, &parameter.param_type);
+                    generator.emit_heap_variable(&parameter.param_type)
                 } else {
-                    eprintln!("Error: Variable '{}' not found for assignment", name);
+                    generator.emit_alloca(&llvm_type)
+                };
+                generator.emit_line(&format!("store {} {}, {}* {}", llvm_type, argument, llvm_type, slot));
+                generator.declare_variable(&parameter.name, slot, parameter.param_type.clone());
+            }
+            generator.visit_block(&decl.body);
+            generator.scopes.pop();
+
+            // falling off the end is only possible in functions returning Void
+            match decl.return_type {
+                Type::Void => generator.emit_line("ret {} zeroinitializer"),
+                _ => generator.emit_line("unreachable"),
+            }
+
+            generator.return_type = saved_return_type;
+            generator.break_targets = saved_breaks;
+            generator.continue_targets = saved_continues;
+            generator.scopes.extend(saved_locals);
+            generator.closure_exits = saved_closures;
+            generator.optional_chain_targets = saved_chains;
+            generator.deferred = saved_deferred;
+            generator.error_handlers = saved_handlers;
+            generator.captured_names = saved_captured;
+            generator.heap_variables = saved_heap;
+        });
+    }
+
+    /// The body jumps to the catch clauses with the error stored in a slot.
+    /// They are tried in order, an error none of them matches is thrown on.
+    fn visit_do(&mut self, body: &[Statement], catches: &[CatchClause]) {
+        let error_type = Type::error();
+        let error_llvm_type = self.llvm_type(&error_type);
+        let slot = self.emit_alloca(&error_llvm_type);
+        let catch_label = self.alloc_label("do.catch");
+        let end_label = self.alloc_label("do.end");
+
+        self.error_handlers.push(ErrorHandler::Catch { slot: slot.clone(), label: catch_label.clone(), deferred: self.deferred.len() });
+        self.visit_block(body);
+        self.error_handlers.pop();
+        self.emit_branch(&end_label);
+
+        self.emit_label(&catch_label);
+        for clause in catches {
+            let next_label = self.alloc_label("catch.next");
+            // the pattern is matched against the error or the enum value in its box
+            let (ptr, ty) = match &clause.error_type {
+                Some(ty @ Type::Named(..)) => {
+                    let error = self.alloc_register();
+                    self.emit_line(&format!("{} = load {}, {}* {}", error, error_llvm_type, error_llvm_type, slot));
+                    let (boxed, table) = self.emit_open_existential(&error);
+                    let expected = self.witness_table(ty, "Error");
+                    let matches = self.alloc_register();
+                    self.emit_line(&format!("{} = icmp eq i8** {}, {}", matches, table, expected));
+                    self.emit_match_branch(&matches, &next_label);
+                    let ptr = self.alloc_register();
+                    self.emit_line(&format!("{} = bitcast i8* {} to {}*", ptr, boxed, self.llvm_type(ty)));
+                    (ptr, ty.clone())
                 }
+                _ => (slot.clone(), error_type.clone()),
+            };
+            let mut bindings = Vec::new();
+            self.pattern_bindings(&clause.pattern, &ty, &mut bindings);
+            let mut slots = HashMap::new();
+            for (name, ty) in bindings {
+                let slot_type = self.llvm_type(&ty);
+                slots.insert(name, (self.emit_alloca(&slot_type), ty));
             }
-            Statement::Expression(expr) => {
-                // TODO
+            self.emit_pattern_test(&ptr, &ty, &clause.pattern, &next_label);
+            self.emit_pattern_bindings(&ptr, &ty, &clause.pattern, &slots);
+
+            self.scopes.push(HashMap::new());
+            for (name, (slot, ty)) in slots {
+                self.declare_variable(&name, slot, ty);
             }
+            self.visit_block(&clause.body);
+            self.scopes.pop();
+            self.emit_branch(&end_label);
+            self.emit_label(&next_label);
         }
+        if catches.iter().any(CatchClause::is_exhaustive) {
+            self.emit_line("unreachable");
+        } else {
+            let error = self.alloc_register();
+            self.emit_line(&format!("{} = load {}, {}* {}", error, error_llvm_type, error_llvm_type, slot));
+            self.emit_throw(&error);
+        }
+        self.emit_label(&end_label);
     }
 
-    fn visit_expression(&mut self, expr: &Expression) -> String {
-        match expr {
-            Expression::Number(n) => {
-                n.to_string()
+    fn visit_block(&mut self, statements: &[Statement]) {
+        self.scopes.push(HashMap::new());
+        self.visit_statements(statements);
+        self.scopes.pop();
+    }
+
+    /// the statements of a scope, followed by the bodies they deferred
+    fn visit_statements(&mut self, statements: &[Statement]) {
+        let deferred = self.deferred.len();
+        for statement in statements {
+            self.visit_statement(statement);
+        }
+        self.emit_deferred(deferred);
+        self.deferred.truncate(deferred);
+    }
+
+    /// Run the deferred bodies above `depth`, last deferred first, on the way
+    /// out of their scopes. Each body only sees the scopes and deferred bodies
+    /// that existed where it was written.
+    fn emit_deferred(&mut self, depth: usize) {
+        for index in (depth..self.deferred.len()).rev() {
+            let Deferred { body, scope_depth } = self.deferred[index].clone();
+            let saved_scopes = self.scopes.split_off(scope_depth);
+            let saved_deferred = self.deferred.split_off(index);
+            self.visit_block(&body);
+            self.deferred.extend(saved_deferred);
+            self.scopes.extend(saved_scopes);
+        }
+    }
+
+    fn jump_target(&self, label: &str) -> JumpTarget {
+        JumpTarget { label: label.to_string(), deferred: self.deferred.len() }
+    }
+
+    /// Evaluate the conditions in order, jumping to `fail_label` as soon as one
+    /// fails. Optional bindings are declared in the current scope.
+    fn emit_conditions(&mut self, conditions: &[Condition], fail_label: &str) {
+        for condition in conditions {
+            match condition {
+                Condition::Expression(expr) => {
+                    let (value, _) = self.visit_expression(expr, Some(&Type::Bool));
+                    self.emit_match_branch(&value, fail_label);
+                }
+                Condition::OptionalBinding { name, is_mutable: _, type_annotation, value } => {
+                    let expected = type_annotation.as_ref().map(|ty| Type::Optional(Box::new(ty.clone())));
+                    let (optional, ty) = self.visit_expression(value, expected.as_ref());
+                    let Type::Optional(wrapped) = &ty else {
+                        unreachable!("optional binding of non-optional value");
+                    };
+                    let llvm_type = self.llvm_type(&ty);
+                    let has_value = self.alloc_register();
+                    self.emit_line(&format!("{} = extractvalue {} {}, 0", has_value, llvm_type, optional));
+                    self.emit_match_branch(&has_value, fail_label);
+
+                    let wrapped_type = self.llvm_type(wrapped);
+                    let unwrapped = self.alloc_register();
+                    self.emit_line(&format!("{} = extractvalue {} {}, 1", unwrapped, llvm_type, optional));
+                    let slot = self.emit_alloca(&wrapped_type);
+                    self.emit_retain(&unwrapped, wrapped);
+                    self.emit_line(&format!("store {} {}, {}* {}", wrapped_type, unwrapped, wrapped_type, slot));
+                    self.declare_variable(name, slot, (**wrapped).clone());
+                }
             }
-            Expression::Variable(name) => {
-                // Look up the variable's register
-                if let Some(var_reg) = self.variables.get(name) {
-                    let var_reg = var_reg.clone();  // Clone to avoid borrow issues
-                    // Load the value from the variable's address
-                    let load_reg = self.alloc_register();
-                    self.emit_indent();
-                    self.emit(&format!("{} = load i32, i32* {}, align 4\n", load_reg, var_reg));
-                    load_reg
+        }
+    }
+
+    /// Switch statements test each pattern in source order against the
+    /// subject stored in memory and jump to the first matching case body.
+    fn visit_switch(&mut self, subject: &Expression, cases: &[SwitchCase]) {
+        let (subject_reg, subject_type) = self.visit_expression(subject, None);
+        let llvm_type = self.llvm_type(&subject_type);
+        let subject_ptr = self.emit_alloca(&llvm_type);
+        self.emit_line(&format!("store {} {}, {}* {}", llvm_type, subject_reg, llvm_type, subject_ptr));
+
+        let end_label = self.alloc_label("switch.end");
+        let unmatched_label = self.alloc_label("switch.unmatched");
+        let case_labels = cases.iter()
+            .map(|_| self.alloc_label("switch.case"))
+            .collect::<Vec<_>>();
+        self.emit_branch(&case_labels[0]);
+
+        for (index, case) in cases.iter().enumerate() {
+            let next_case = case_labels.get(index + 1).unwrap_or(&unmatched_label).clone();
+            let body_label = self.alloc_label("switch.body");
+
+            // every pattern of a case binds the same names, so the slots are shared
+            let mut bindings = Vec::new();
+            self.pattern_bindings(&case.patterns[0], &subject_type, &mut bindings);
+            let mut slots = HashMap::new();
+            for (name, ty) in bindings {
+                let slot_type = self.llvm_type(&ty);
+                let slot = self.emit_alloca(&slot_type);
+                slots.insert(name, (slot, ty));
+            }
+
+            self.emit_label(&case_labels[index]);
+            for (pattern_index, pattern) in case.patterns.iter().enumerate() {
+                let fail_label = if pattern_index + 1 < case.patterns.len() {
+                    self.alloc_label("switch.pattern")
                 } else {
-                    // Variable not found - return a placeholder
-                    eprintln!("Error: Variable '{}' not found", name);
-                    "0".to_string()
+                    next_case.clone()
+                };
+                self.emit_pattern_test(&subject_ptr, &subject_type, pattern, &fail_label);
+                self.emit_pattern_bindings(&subject_ptr, &subject_type, pattern, &slots);
+                self.emit_branch(&body_label);
+                if fail_label != next_case {
+                    self.emit_label(&fail_label);
                 }
             }
-            Expression::Binary { left, operator, right } => {
-                let left_reg = self.visit_expression(left);
-                let right_reg = self.visit_expression(right);
 
-                let result_reg = self.alloc_register();
+            self.emit_label(&body_label);
+            self.scopes.push(HashMap::new());
+            for (name, (slot, ty)) in slots {
+                self.declare_variable(&name, slot, ty);
+            }
+            // `break` inside a case leaves the switch
+            self.break_targets.push(self.jump_target(&end_label));
+            self.visit_statements(&case.body);
+            self.break_targets.pop();
+            self.scopes.pop();
+            self.emit_branch(&end_label);
+        }
 
-                let op_instruction = match operator {
-                    BinaryOperator::Add => "add",
-                    BinaryOperator::Subtract => "sub",
-                    BinaryOperator::Multiply => "mul",
-                    BinaryOperator::Divide => "sdiv",  // 符号付き除算
-                };
+        // the semantic pass guarantees exhaustiveness
+        self.emit_label(&unmatched_label);
+        self.emit_line("unreachable");
+        self.emit_label(&end_label);
+    }
+
+    fn pattern_bindings(&self, pattern: &Pattern, ty: &Type, out: &mut Vec<(String, Type)>) {
+        match pattern {
+            Pattern::Binding(name) => out.push((name.clone(), ty.clone())),
+            Pattern::EnumCase { case_name, subpatterns: Some(subpatterns), .. } => {
+                for (subpattern, field_type) in subpatterns.iter().zip(self.case_payload(ty, case_name)) {
+                    self.pattern_bindings(subpattern, &field_type, out);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn case_payload(&self, ty: &Type, case_name: &str) -> Vec<Type> {
+        match ty {
+            Type::Named(..) => {
+                let decl = self.enum_decl(ty);
+                let (_, case) = decl.find_case(case_name).expect("enum case exists");
+                case.associated_values.iter().map(|value| value.value_type.clone()).collect()
+            }
+            Type::Optional(wrapped) if case_name == "some" => vec![(**wrapped).clone()],
+            _ => Vec::new(),
+        }
+    }
+
+    /// pointer to the `field`th associated value of `case_name` stored at `ptr`
+    fn emit_payload_field_ptr(&mut self, ptr: &str, ty: &Type, case_name: &str, field: usize) -> String {
+        let llvm_type = self.llvm_type(ty);
+        match ty {
+            Type::Named(..) => {
+                let decl = self.enum_decl(ty).into_owned();
+                let (case_index, _) = decl.find_case(case_name).expect("enum case exists");
+                let words = self.payload_words(&decl);
+                let payload_type = self.payload_type(&decl, case_index);
+
+                let payload_ptr = self.alloc_register();
+                self.emit_line(&format!(
+                    "{} = getelementptr {}, {}* {}, i32 0, i32 1",
+                    payload_ptr, llvm_type, llvm_type, ptr
+                ));
+                let cast_ptr = self.alloc_register();
+                self.emit_line(&format!(
+                    "{} = bitcast [{} x i64]* {} to {}*",
+                    cast_ptr, words, payload_ptr, payload_type
+                ));
+                let field_ptr = self.alloc_register();
+                self.emit_line(&format!(
+                    "{} = getelementptr {}, {}* {}, i32 0, i32 {}",
+                    field_ptr, payload_type, payload_type, cast_ptr, field
+                ));
+                field_ptr
+            }
+            _ => {
+                // Optional: { i1, T }
+                let field_ptr = self.alloc_register();
+                self.emit_line(&format!(
+                    "{} = getelementptr {}, {}* {}, i32 0, i32 1",
+                    field_ptr, llvm_type, llvm_type, ptr
+                ));
+                field_ptr
+            }
+        }
+    }
 
-                self.emit_indent();
-                self.emit(&format!(
-                    "{} = {} i32 {}, {}\n",
-                    result_reg, op_instruction, left_reg, right_reg
+    /// branch to `fail_label` unless the value at `ptr` matches `pattern`,
+    /// otherwise continue in a fresh basic block
+    fn emit_pattern_test(&mut self, ptr: &str, ty: &Type, pattern: &Pattern, fail_label: &str) {
+        match pattern {
+            Pattern::Wildcard | Pattern::Binding(_) => {}
+            Pattern::Number(value) => {
+                let llvm_type = self.llvm_type(ty);
+                let loaded = self.alloc_register();
+                self.emit_line(&format!("{} = load {}, {}* {}", loaded, llvm_type, llvm_type, ptr));
+                let matches = self.alloc_register();
+                self.emit_line(&format!("{} = icmp eq {} {}, {}", matches, llvm_type, loaded, value));
+                self.emit_match_branch(&matches, fail_label);
+            }
+            Pattern::EnumCase { case_name, subpatterns, .. } => {
+                let llvm_type = self.llvm_type(ty);
+                let tag_ptr = self.alloc_register();
+                self.emit_line(&format!(
+                    "{} = getelementptr {}, {}* {}, i32 0, i32 0",
+                    tag_ptr, llvm_type, llvm_type, ptr
                 ));
+                let tag = self.alloc_register();
+                let matches = self.alloc_register();
+                match ty {
+                    Type::Named(name, _) => {
+                        let (case_index, _) = self.enums[name].find_case(case_name).expect("enum case exists");
+                        self.emit_line(&format!("{} = load i32, i32* {}", tag, tag_ptr));
+                        self.emit_line(&format!("{} = icmp eq i32 {}, {}", matches, tag, case_index));
+                    }
+                    _ => {
+                        let has_value = if case_name == "some" { 1 } else { 0 };
+                        self.emit_line(&format!("{} = load i1, i1* {}", tag, tag_ptr));
+                        self.emit_line(&format!("{} = icmp eq i1 {}, {}", matches, tag, has_value));
+                    }
+                }
+                self.emit_match_branch(&matches, fail_label);
 
-                result_reg
+                if let Some(subpatterns) = subpatterns {
+                    let payload = self.case_payload(ty, case_name);
+                    for (field, (subpattern, field_type)) in subpatterns.iter().zip(&payload).enumerate() {
+                        if matches!(subpattern, Pattern::Wildcard | Pattern::Binding(_)) {
+                            continue;
+                        }
+                        let field_ptr = self.emit_payload_field_ptr(ptr, ty, case_name, field);
+                        self.emit_pattern_test(&field_ptr, field_type, subpattern, fail_label);
+                    }
+                }
             }
         }
     }
-}
 
-pub fn generate_llvm(ast: &AstNode) -> String {
-    let mut generator = LLVMCodeGenerator::new();
-    generator.generate(ast)
-}
\ No newline at end of file
+    fn emit_match_branch(&mut self, condition: &str, fail_label: &str) {
+        let matched_label = self.alloc_label("pattern.matched");
+        self.emit_line(&format!(
+            "br i1 {}, label %{}, label %{}",
+            condition, matched_label, fail_label
+        ));
+        self.emit_label(&matched_label);
+    }
+
+    fn emit_pattern_bindings(&mut self, ptr: &str, ty: &Type, pattern: &Pattern, slots: &HashMap<String, (String, Type)>) {
+        match pattern {
+            Pattern::Binding(name) => {
+                let llvm_type = self.llvm_type(ty);
+                let loaded = self.alloc_register();
+                self.emit_line(&format!("{} = load {}, {}* {}", loaded, llvm_type, llvm_type, ptr));
+                self.emit_retain(&loaded, ty);
+                let (slot, _) = &slots[name];
+                self.emit_line(&format!("store {} {}, {}* {}", llvm_type, loaded, llvm_type, slot));
+            }
+            Pattern::EnumCase { case_name, subpatterns: Some(subpatterns), .. } => {
+                let payload = self.case_payload(ty, case_name);
+                for (field, (subpattern, field_type)) in subpatterns.iter().zip(&payload).enumerate() {
+                    if matches!(subpattern, Pattern::Wildcard | Pattern::Number(_)) {
+                        continue;
+                    }
+                    let field_ptr = self.emit_payload_field_ptr(ptr, ty, case_name, field);
+                    self.emit_pattern_bindings(&field_ptr, field_type, subpattern, slots);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // MARK - printing
+
+    /// intern a NUL terminated string constant and return an `i8*` to it
+    fn string_constant(&mut self, text: &str) -> String {
+        let length = text.len() + 1;
+        let name = match self.string_constants.get(text) {
+            Some(name) => name.clone(),
+            None => {
+                let name = format!("@.str.{}", self.string_constants.len());
+                let mut escaped = String::new();
+                for byte in text.bytes() {
+                    if byte.is_ascii_graphic() && byte != b'"' && byte != b'\\' || byte == b' ' {
+                        escaped.push(byte as char);
+                    } else {
+                        escaped.push_str(&format!("\\{:02X}", byte));
+                    }
+                }
+                self.globals.push_str(&format!(
+                    "{} = private unnamed_addr constant [{} x i8] c\"{}\\00\", align 1\n",
+                    name, length, escaped
+                ));
+                self.string_constants.insert(text.to_string(), name.clone());
+                name
+            }
+        };
+        format!(
+            "i8* getelementptr inbounds ([{} x i8], [{} x i8]* {}, i64 0, i64 0)",
+            length, length, name
+        )
+    }
+
+    fn emit_printf(&mut self, format: &str, arguments: &[String]) {
+        let format_ptr = self.string_constant(format);
+        if let Some(builder) = self.builder.clone() {
+            self.emit_append_formatted(&builder, &format_ptr, arguments);
+            return;
+        }
+        let call_reg = self.alloc_register();
+        let mut operands = vec![format_ptr];
+        operands.extend_from_slice(arguments);
+        self.emit_line(&format!(
+            "{} = call i32 (i8*, ...) @printf({})",
+            call_reg,
+            operands.join(", ")
+        ));
+    }
+
+    fn emit_write_literal(&mut self, text: &str) {
+        self.emit_printf(&text.replace('%', "%%"), &[]);
+    }
+
+    /// Write the textual representation of a value without a trailing newline.
+    /// `debug` selects the representation used inside containers, which
+    /// qualifies enum cases with the module and type name like Swift does.
+    fn emit_write(&mut self, value: &str, ty: &Type, debug: bool) {
+        match ty {
+            Type::Int | Type::Int8 | Type::Int16 | Type::Int32 | Type::Int64
+            | Type::UInt | Type::UInt8 | Type::UInt16 | Type::UInt32 | Type::UInt64 => {
+                // narrower integers are widened to the 64 bit printf argument
+                let (extend, format) = if ty.is_unsigned_integer() { ("zext", "%llu") } else { ("sext", "%lld") };
+                let llvm_type = self.llvm_type(ty);
+                let value = if llvm_type == "i64" {
+                    value.to_string()
+                } else {
+                    let wide = self.alloc_register();
+                    self.emit_line(&format!("{} = {} {} {} to i64", wide, extend, llvm_type, value));
+                    wide
+                };
+                self.emit_printf(format, &[format!("i64 {}", value)]);
+            }
+            Type::String if !debug => {
+                let (bytes, count) = self.emit_string_parts(value);
+                let count32 = self.alloc_register();
+                self.emit_line(&format!("{} = trunc i64 {} to i32", count32, count));
+                self.emit_printf("%.*s", &[format!("i32 {}", count32), format!("i8* {}", bytes)]);
+            }
+            Type::Void => {
+                self.emit_write_literal("()");
+            }
+            Type::Function { .. } => {
+                self.emit_write_literal("(Function)");
+            }
+            Type::Never => {
+                self.emit_line("unreachable");
+            }
+            Type::Bool => {
+                let true_ptr = self.string_constant("true");
+                let false_ptr = self.string_constant("false");
+                let text = self.alloc_register();
+                self.emit_line(&format!("{} = select i1 {}, {}, {}", text, value, true_ptr, false_ptr));
+                self.emit_printf("%s", &[format!("i8* {}", text)]);
+            }
+            Type::Double | Type::Float | Type::String | Type::Named(..) | Type::Optional(_) | Type::Tuple(_)
+            | Type::Array(_) | Type::Dictionary(..) | Type::Set(_) | Type::Existential(_) => {
+                let helper = self.write_helper(ty, debug);
+                let llvm_type = self.llvm_type(ty);
+                match self.builder.clone() {
+                    Some(builder) => self.emit_line(&format!(
+                        "call void {}({} {}, %StringBuilder* {})",
+                        helper, llvm_type, value, builder
+                    )),
+                    None => self.emit_line(&format!("call void {}({} {})", helper, llvm_type, value)),
+                }
+            }
+            Type::Generic(name) => unreachable!("type parameter '{}' of a function that is not specialized", name),
+            Type::Opaque(name) => unreachable!("'some {}' outside of a function signature", name),
+        }
+    }
+
+    /// Name of the function writing values of `ty`, generated on first use.
+    /// While a string builder is active the helper appends to the builder
+    /// it takes as an extra argument.
+    fn write_helper(&mut self, ty: &Type, debug: bool) -> String {
+        let action = if self.builder.is_some() { "append" } else { "write" };
+        let name = format!("@\"{}{}.{}\"", action, if debug { ".debug" } else { "" }, ty);
+        if !self.emitted_helpers.insert(name.clone()) {
+            return name;
+        }
+
+        let llvm_type = self.llvm_type(ty);
+        let appends = self.builder.is_some();
+        let header = if appends {
+            format!("define private void {}({} %value, %StringBuilder* %builder)", name, llvm_type)
+        } else {
+            format!("define private void {}({} %value)", name, llvm_type)
+        };
+        let ty = ty.clone();
+        self.emit_function(&header, |generator| {
+            if appends {
+                generator.builder = Some("%builder".to_string());
+            }
+            let ptr = generator.emit_alloca(&llvm_type);
+            generator.emit_line(&format!("store {} %value, {}* {}", llvm_type, llvm_type, ptr));
+            match &ty {
+                Type::Named(..) => generator.emit_write_enum(&ptr, &ty, debug),
+                Type::Optional(wrapped) => generator.emit_write_optional(&ptr, &ty, wrapped),
+                Type::Tuple(elements) => generator.emit_write_tuple("%value", &ty, elements),
+                Type::Array(element_type) => generator.emit_write_array("%value", element_type),
+                Type::Dictionary(key_type, value_type) => generator.emit_write_dictionary("%value", key_type, value_type),
+                Type::Set(element_type) => generator.emit_write_set("%value", element_type),
+                Type::Existential(_) => generator.emit_write_existential("%value", debug),
+                Type::String => generator.emit_write_string_literal("%value"),
+                Type::Double | Type::Float => generator.emit_write_floating_point("%value", &ty),
+                _ => unreachable!("scalars are written inline"),
+            }
+        });
+        name
+    }
+
+    fn emit_write_enum(&mut self, ptr: &str, ty: &Type, debug: bool) {
+        let decl = self.enum_decl(ty).into_owned();
+        let enum_name = &ty.to_string();
+        let llvm_type = self.llvm_type(ty);
+
+        // `description` replaces the case name, also inside containers
+        if decl.conformances.iter().any(|protocol| protocol == "CustomStringConvertible") {
+            let value = self.alloc_register();
+            self.emit_line(&format!("{} = load {}, {}* {}", value, llvm_type, llvm_type, ptr));
+            let description = self.alloc_register();
+            self.emit_line(&format!(
+                "{} = call {} {}({} {})",
+                description, self.llvm_type(&Type::String), Self::getter_name(enum_name, "description"), llvm_type, value
+            ));
+            self.emit_write(&description, &Type::String, false);
+            self.emit_line("ret void");
+            return;
+        }
+
+        let tag_ptr = self.alloc_register();
+        self.emit_line(&format!("{} = getelementptr {}, {}* {}, i32 0, i32 0", tag_ptr, llvm_type, llvm_type, ptr));
+        let tag = self.alloc_register();
+        self.emit_line(&format!("{} = load i32, i32* {}", tag, tag_ptr));
+
+        let labels = decl.cases.iter()
+            .map(|_| self.alloc_label("write.case"))
+            .collect::<Vec<_>>();
+        let unknown_label = self.alloc_label("write.unknown");
+        let targets = labels.iter().enumerate()
+            .map(|(index, label)| format!("i32 {}, label %{}", index, label))
+            .collect::<Vec<_>>();
+        self.emit_line(&format!("switch i32 {}, label %{} [ {} ]", tag, unknown_label, targets.join(" ")));
+
+        for (index, case) in decl.cases.iter().enumerate() {
+            self.emit_label(&labels[index]);
+            let prefix = if debug { format!("{}.", Self::qualified_name(ty)) } else { String::new() };
+            self.emit_write_literal(&format!("{}{}", prefix, case.name));
+
+            if !case.associated_values.is_empty() {
+                self.emit_write_literal("(");
+                for (field, associated) in case.associated_values.iter().enumerate() {
+                    if field > 0 {
+                        self.emit_write_literal(", ");
+                    }
+                    if let Some(label) = &associated.label {
+                        self.emit_write_literal(&format!("{}: ", label));
+                    }
+                    let field_ptr = self.emit_payload_field_ptr(ptr, ty, &case.name, field);
+                    let field_type = self.llvm_type(&associated.value_type);
+                    let field_value = self.alloc_register();
+                    self.emit_line(&format!("{} = load {}, {}* {}", field_value, field_type, field_type, field_ptr));
+                    self.emit_write(&field_value, &associated.value_type, true);
+                }
+                self.emit_write_literal(")");
+            }
+            self.emit_line("ret void");
+        }
+
+        self.emit_label(&unknown_label);
+        self.emit_line("unreachable");
+    }
+
+    /// the name `String(reflecting:)` gives a type: `main.Box<Swift.Int>`
+    fn qualified_name(ty: &Type) -> String {
+        let arguments = |arguments: &[&Type]| {
+            arguments.iter().map(|argument| Self::qualified_name(argument)).collect::<Vec<_>>().join(", ")
+        };
+        match ty {
+            Type::Named(name, generic_arguments) if generic_arguments.is_empty() => format!("main.{}", name),
+            Type::Named(name, generic_arguments) => {
+                format!("main.{}<{}>", name, arguments(&generic_arguments.iter().collect::<Vec<_>>()))
+            }
+            Type::Optional(wrapped) => format!("Swift.Optional<{}>", arguments(&[wrapped])),
+            Type::Array(element_type) => format!("Swift.Array<{}>", arguments(&[element_type])),
+            Type::Set(element_type) => format!("Swift.Set<{}>", arguments(&[element_type])),
+            Type::Dictionary(key_type, value_type) => {
+                format!("Swift.Dictionary<{}>", arguments(&[key_type, value_type]))
+            }
+            Type::Existential(protocol) => format!("any main.{}", protocol),
+            Type::Tuple(_) | Type::Function { .. } => ty.to_string(),
+            _ => format!("Swift.{}", ty),
+        }
+    }
+
+    /// the first entry of a witness table writes the boxed value
+    fn emit_write_existential(&mut self, value: &str, debug: bool) {
+        self.declare_string_builder();
+        let (boxed, table) = self.emit_open_existential(value);
+        let write = self.emit_witness(&table, 0, "void (i8*, i1, %StringBuilder*)");
+        let builder = self.builder.clone().unwrap_or_else(|| "null".to_string());
+        self.emit_line(&format!(
+            "call void {}(i8* {}, i1 {}, %StringBuilder* {})",
+            write, boxed, debug, builder
+        ));
+        self.emit_line("ret void");
+    }
+
+    fn emit_write_optional(&mut self, ptr: &str, ty: &Type, wrapped: &Type) {
+        let llvm_type = self.llvm_type(ty);
+        let tag_ptr = self.alloc_register();
+        self.emit_line(&format!("{} = getelementptr {}, {}* {}, i32 0, i32 0", tag_ptr, llvm_type, llvm_type, ptr));
+        let tag = self.alloc_register();
+        self.emit_line(&format!("{} = load i1, i1* {}", tag, tag_ptr));
+        let some_label = self.alloc_label("write.some");
+        let none_label = self.alloc_label("write.none");
+        self.emit_line(&format!("br i1 {}, label %{}, label %{}", tag, some_label, none_label));
+
+        self.emit_label(&some_label);
+        self.emit_write_literal("Optional(");
+        let value_ptr = self.emit_payload_field_ptr(ptr, ty, "some", 0);
+        let wrapped_type = self.llvm_type(wrapped);
+        let value = self.alloc_register();
+        self.emit_line(&format!("{} = load {}, {}* {}", value, wrapped_type, wrapped_type, value_ptr));
+        self.emit_write(&value, wrapped, true);
+        self.emit_write_literal(")");
+        self.emit_line("ret void");
+
+        self.emit_label(&none_label);
+        self.emit_write_literal("nil");
+        self.emit_line("ret void");
+    }
+
+    /// `(1, 2)` or `(x: 1, y: 2)`, elements use their debug representation
+    fn emit_write_tuple(&mut self, value: &str, ty: &Type, elements: &[TupleElement]) {
+        let llvm_type = self.llvm_type(ty);
+        self.emit_write_literal("(");
+        for (index, element) in elements.iter().enumerate() {
+            if index > 0 {
+                self.emit_write_literal(", ");
+            }
+            if let Some(label) = &element.label {
+                self.emit_write_literal(&format!("{}: ", label));
+            }
+            let element_value = self.alloc_register();
+            self.emit_line(&format!("{} = extractvalue {} {}, {}", element_value, llvm_type, value, index));
+            self.emit_write(&element_value, &element.element_type, true);
+        }
+        self.emit_write_literal(")");
+        self.emit_line("ret void");
+    }
+
+    /// `"text"` with quotes and escapes, the way Swift shows strings inside containers
+    fn emit_write_string_literal(&mut self, value: &str) {
+        let (bytes, count) = self.emit_string_parts(value);
+        self.emit_write_literal("\"");
+        let index_ptr = self.emit_alloca("i64");
+        self.emit_line(&format!("store i64 0, i64* {}", index_ptr));
+        let condition_label = self.alloc_label("write.char.condition");
+        let body_label = self.alloc_label("write.char");
+        let next_label = self.alloc_label("write.char.next");
+        let end_label = self.alloc_label("write.end");
+        self.emit_branch(&condition_label);
+
+        self.emit_label(&condition_label);
+        let index = self.alloc_register();
+        self.emit_line(&format!("{} = load i64, i64* {}", index, index_ptr));
+        let in_range = self.alloc_register();
+        self.emit_line(&format!("{} = icmp slt i64 {}, {}", in_range, index, count));
+        self.emit_line(&format!("br i1 {}, label %{}, label %{}", in_range, body_label, end_label));
+
+        self.emit_label(&body_label);
+        let byte_ptr = self.alloc_register();
+        self.emit_line(&format!("{} = getelementptr i8, i8* {}, i64 {}", byte_ptr, bytes, index));
+        let byte = self.alloc_register();
+        self.emit_line(&format!("{} = load i8, i8* {}", byte, byte_ptr));
+        let escapes = [(0u8, "\\0"), (b'\t', "\\t"), (b'\n', "\\n"), (b'\r', "\\r"), (b'"', "\\\""), (b'\\', "\\\\")];
+        let escape_labels = escapes.iter()
+            .map(|_| self.alloc_label("write.escape"))
+            .collect::<Vec<_>>();
+        let plain_label = self.alloc_label("write.plain");
+        let targets = escapes.iter().zip(&escape_labels)
+            .map(|((byte, _), label)| format!("i8 {}, label %{}", byte, label))
+            .collect::<Vec<_>>();
+        self.emit_line(&format!("switch i8 {}, label %{} [ {} ]", byte, plain_label, targets.join(" ")));
+        for ((_, escaped), label) in escapes.iter().zip(&escape_labels) {
+            self.emit_label(label);
+            self.emit_write_literal(escaped);
+            self.emit_branch(&next_label);
+        }
+        self.emit_label(&plain_label);
+        let character = self.alloc_register();
+        self.emit_line(&format!("{} = zext i8 {} to i32", character, byte));
+        self.emit_printf("%c", &[format!("i32 {}", character)]);
+        self.emit_branch(&next_label);
+
+        self.emit_label(&next_label);
+        let next_index = self.alloc_register();
+        self.emit_line(&format!("{} = add i64 {}, 1", next_index, index));
+        self.emit_line(&format!("store i64 {}, i64* {}", next_index, index_ptr));
+        self.emit_branch(&condition_label);
+
+        self.emit_label(&end_label);
+        self.emit_write_literal("\"");
+        self.emit_line("ret void");
+    }
+
+    /// Floating point numbers are written like Swift: the shortest digits that
+    /// read back as the same value, in decimal notation with at least one
+    /// fractional digit (`1.0`) unless the value is very small or very large.
+    fn emit_write_floating_point(&mut self, value: &str, ty: &Type) {
+        self.declare("declare double @strtod(i8*, i8**)");
+        self.declare("declare i8* @strchr(i8*, i32)");
+        self.declare("declare i32 @atoi(i8*)");
+        self.declare("declare i32 @snprintf(i8*, i64, i8*, ...)");
+        // digits needed to round-trip any value, and where exponents start
+        let (max_digits, exponential_above) = match ty {
+            Type::Float => (9, "0x4180000000000000"),  // 2^25
+            _ => (17, "0x4350000000000000"),  // 2^54
+        };
+        let llvm_type = self.llvm_type(ty);
+        let number = if *ty == Type::Float {
+            let extended = self.alloc_register();
+            self.emit_line(&format!("{} = fpext float {} to double", extended, value));
+            extended
+        } else {
+            value.to_string()
+        };
+
+        let nan_label = self.alloc_label("write.nan");
+        let finite_label = self.alloc_label("write.finite");
+        let infinite_label = self.alloc_label("write.infinite");
+        let search_label = self.alloc_label("write.digits");
+        let format_label = self.alloc_label("write.format");
+        let exponential_label = self.alloc_label("write.exponential");
+        let decimal_label = self.alloc_label("write.decimal");
+
+        let is_nan = self.alloc_register();
+        self.emit_line(&format!("{} = fcmp uno double {}, {}", is_nan, number, number));
+        self.emit_line(&format!("br i1 {}, label %{}, label %{}", is_nan, nan_label, finite_label));
+        self.emit_label(&nan_label);
+        self.emit_write_literal("nan");
+        self.emit_line("ret void");
+
+        self.emit_label(&finite_label);
+        let negated = self.alloc_register();
+        self.emit_line(&format!("{} = fneg double {}", negated, number));
+        let is_negative = self.alloc_register();
+        self.emit_line(&format!("{} = fcmp olt double {}, 0.0", is_negative, number));
+        let magnitude = self.alloc_register();
+        self.emit_line(&format!("{} = select i1 {}, double {}, double {}", magnitude, is_negative, negated, number));
+        let is_infinite = self.alloc_register();
+        self.emit_line(&format!("{} = fcmp oeq double {}, 0x7FF0000000000000", is_infinite, magnitude));
+        let digits_ptr = self.emit_alloca("i32");
+        self.emit_line(&format!("store i32 1, i32* {}", digits_ptr));
+        let buffer = self.emit_alloca("[32 x i8]");
+        let text = self.alloc_register();
+        self.emit_line(&format!("{} = getelementptr [32 x i8], [32 x i8]* {}, i64 0, i64 0", text, buffer));
+        self.emit_line(&format!("br i1 {}, label %{}, label %{}", is_infinite, infinite_label, search_label));
+
+        self.emit_label(&infinite_label);
+        let minus_infinity = self.string_constant("-inf");
+        let infinity = self.string_constant("inf");
+        let sign_text = self.alloc_register();
+        self.emit_line(&format!("{} = select i1 {}, {}, {}", sign_text, is_negative, minus_infinity, infinity));
+        self.emit_printf("%s", &[format!("i8* {}", sign_text)]);
+        self.emit_line("ret void");
+
+        // try 1, 2, ... significant digits until the text reads back as the value
+        self.emit_label(&search_label);
+        let digits = self.alloc_register();
+        self.emit_line(&format!("{} = load i32, i32* {}", digits, digits_ptr));
+        let precision = self.alloc_register();
+        self.emit_line(&format!("{} = sub i32 {}, 1", precision, digits));
+        let exponent_format = self.string_constant("%.*e");
+        let written = self.alloc_register();
+        self.emit_line(&format!(
+            "{} = call i32 (i8*, i64, i8*, ...) @snprintf(i8* {}, i64 32, {}, i32 {}, double {})",
+            written, text, exponent_format, precision, number
+        ));
+        let parsed = self.alloc_register();
+        self.emit_line(&format!("{} = call double @strtod(i8* {}, i8** null)", parsed, text));
+        let parsed = if *ty == Type::Float {
+            let truncated = self.alloc_register();
+            self.emit_line(&format!("{} = fptrunc double {} to float", truncated, parsed));
+            truncated
+        } else {
+            parsed
+        };
+        let round_trips = self.alloc_register();
+        self.emit_line(&format!("{} = fcmp oeq {} {}, {}", round_trips, llvm_type, parsed, value));
+        let exhausted = self.alloc_register();
+        self.emit_line(&format!("{} = icmp sge i32 {}, {}", exhausted, digits, max_digits));
+        let found = self.alloc_register();
+        self.emit_line(&format!("{} = or i1 {}, {}", found, round_trips, exhausted));
+        let next_digits = self.alloc_register();
+        self.emit_line(&format!("{} = add i32 {}, 1", next_digits, digits));
+        self.emit_line(&format!("store i32 {}, i32* {}", next_digits, digits_ptr));
+        self.emit_line(&format!("br i1 {}, label %{}, label %{}", found, format_label, search_label));
+
+        self.emit_label(&format_label);
+        let exponent_mark = self.alloc_register();
+        self.emit_line(&format!("{} = call i8* @strchr(i8* {}, i32 101)", exponent_mark, text));
+        let exponent_text = self.alloc_register();
+        self.emit_line(&format!("{} = getelementptr i8, i8* {}, i64 1", exponent_text, exponent_mark));
+        let exponent = self.alloc_register();
+        self.emit_line(&format!("{} = call i32 @atoi(i8* {})", exponent, exponent_text));
+        let is_small = self.alloc_register();
+        self.emit_line(&format!("{} = icmp slt i32 {}, -4", is_small, exponent));
+        let is_large = self.alloc_register();
+        self.emit_line(&format!("{} = fcmp ogt double {}, {}", is_large, magnitude, exponential_above));
+        let is_exponential = self.alloc_register();
+        self.emit_line(&format!("{} = or i1 {}, {}", is_exponential, is_small, is_large));
+        self.emit_line(&format!("br i1 {}, label %{}, label %{}", is_exponential, exponential_label, decimal_label));
+
+        // `1e-05` and `1.5e+20` match C's %e output
+        self.emit_label(&exponential_label);
+        self.emit_printf("%s", &[format!("i8* {}", text)]);
+        self.emit_line("ret void");
+
+        // the same digits in decimal notation: 0.001, 100.0, 3.14
+        self.emit_label(&decimal_label);
+        let fraction_digits = self.alloc_register();
+        self.emit_line(&format!("{} = sub i32 {}, {}", fraction_digits, precision, exponent));
+        let has_fraction = self.alloc_register();
+        self.emit_line(&format!("{} = icmp sgt i32 {}, 0", has_fraction, fraction_digits));
+        let fraction_digits_or_one = self.alloc_register();
+        self.emit_line(&format!(
+            "{} = select i1 {}, i32 {}, i32 1",
+            fraction_digits_or_one, has_fraction, fraction_digits
+        ));
+        self.emit_printf("%.*f", &[format!("i32 {}", fraction_digits_or_one), format!("double {}", number)]);
+        self.emit_line("ret void");
+    }
+
+    // MARK - strings
+
+    /// constant String value for a literal
+    fn string_literal(&mut self, text: &str) -> String {
+        let bytes = self.string_constant(text);
+        format!("{{ {}, i64 {} }}", bytes, text.len())
+    }
+
+    /// registers holding the byte pointer and the byte count of a String
+    fn emit_string_parts(&mut self, value: &str) -> (String, String) {
+        let bytes = self.alloc_register();
+        self.emit_line(&format!("{} = extractvalue {{ i8*, i64 }} {}, 0", bytes, value));
+        let count = self.alloc_register();
+        self.emit_line(&format!("{} = extractvalue {{ i8*, i64 }} {}, 1", count, value));
+        (bytes, count)
+    }
+
+    /// `left + right` for strings, the result is a new heap buffer
+    fn emit_string_concat(&mut self, left: &str, right: &str) -> String {
+        let helper = "@\"String.+\"";
+        if self.emitted_helpers.insert(helper.to_string()) {
+            self.declare("declare i8* @malloc(i64)");
+            self.declare("declare void @llvm.memcpy.p0i8.p0i8.i64(i8*, i8*, i64, i1)");
+            let header = format!("define private {{ i8*, i64 }} {}({{ i8*, i64 }} %left, {{ i8*, i64 }} %right)", helper);
+            self.emit_function(&header, |generator| {
+                let (left_bytes, left_count) = generator.emit_string_parts("%left");
+                let (right_bytes, right_count) = generator.emit_string_parts("%right");
+                let count = generator.alloc_register();
+                generator.emit_line(&format!("{} = add i64 {}, {}", count, left_count, right_count));
+                let buffer = generator.alloc_register();
+                generator.emit_line(&format!("{} = call i8* @malloc(i64 {})", buffer, count));
+                generator.emit_line(&format!(
+                    "call void @llvm.memcpy.p0i8.p0i8.i64(i8* {}, i8* {}, i64 {}, i1 false)",
+                    buffer, left_bytes, left_count
+                ));
+                let tail = generator.alloc_register();
+                generator.emit_line(&format!("{} = getelementptr i8, i8* {}, i64 {}", tail, buffer, left_count));
+                generator.emit_line(&format!(
+                    "call void @llvm.memcpy.p0i8.p0i8.i64(i8* {}, i8* {}, i64 {}, i1 false)",
+                    tail, right_bytes, right_count
+                ));
+                let with_bytes = generator.alloc_register();
+                generator.emit_line(&format!("{} = insertvalue {{ i8*, i64 }} undef, i8* {}, 0", with_bytes, buffer));
+                let result = generator.alloc_register();
+                generator.emit_line(&format!("{} = insertvalue {{ i8*, i64 }} {}, i64 {}, 1", result, with_bytes, count));
+                generator.emit_line(&format!("ret {{ i8*, i64 }} {}", result));
+            });
+        }
+
+        let result = self.alloc_register();
+        self.emit_line(&format!(
+            "{} = call {{ i8*, i64 }} {}({{ i8*, i64 }} {}, {{ i8*, i64 }} {})",
+            result, helper, left, right
+        ));
+        result
+    }
+
+    /// strings are equal when they hold the same bytes
+    fn emit_string_equal(&mut self, left: &str, right: &str) -> String {
+        let helper = "@\"String.==\"";
+        if self.emitted_helpers.insert(helper.to_string()) {
+            self.declare("declare i32 @memcmp(i8*, i8*, i64)");
+            let header = format!("define private i1 {}({{ i8*, i64 }} %left, {{ i8*, i64 }} %right)", helper);
+            self.emit_function(&header, |generator| {
+                let (left_bytes, left_count) = generator.emit_string_parts("%left");
+                let (right_bytes, right_count) = generator.emit_string_parts("%right");
+                let same_count = generator.alloc_register();
+                generator.emit_line(&format!("{} = icmp eq i64 {}, {}", same_count, left_count, right_count));
+                let compare_label = generator.alloc_label("compare");
+                let different_label = generator.alloc_label("different");
+                generator.emit_line(&format!("br i1 {}, label %{}, label %{}", same_count, compare_label, different_label));
+
+                generator.emit_label(&compare_label);
+                let order = generator.alloc_register();
+                generator.emit_line(&format!(
+                    "{} = call i32 @memcmp(i8* {}, i8* {}, i64 {})",
+                    order, left_bytes, right_bytes, left_count
+                ));
+                let equal = generator.alloc_register();
+                generator.emit_line(&format!("{} = icmp eq i32 {}, 0", equal, order));
+                generator.emit_line(&format!("ret i1 {}", equal));
+
+                generator.emit_label(&different_label);
+                generator.emit_line("ret i1 false");
+            });
+        }
+
+        let result = self.alloc_register();
+        self.emit_line(&format!(
+            "{} = call i1 {}({{ i8*, i64 }} {}, {{ i8*, i64 }} {})",
+            result, helper, left, right
+        ));
+        result
+    }
+
+    /// Lexicographic order of the bytes of two strings: negative, zero or
+    /// positive like `memcmp`, where a prefix comes before the longer string.
+    fn emit_string_compare(&mut self, left: &str, right: &str) -> String {
+        let helper = "@\"String.compare\"";
+        if self.emitted_helpers.insert(helper.to_string()) {
+            self.declare("declare i32 @memcmp(i8*, i8*, i64)");
+            let header = format!("define private i32 {}({{ i8*, i64 }} %left, {{ i8*, i64 }} %right)", helper);
+            self.emit_function(&header, |generator| {
+                let (left_bytes, left_count) = generator.emit_string_parts("%left");
+                let (right_bytes, right_count) = generator.emit_string_parts("%right");
+                let left_shorter = generator.alloc_register();
+                generator.emit_line(&format!("{} = icmp ult i64 {}, {}", left_shorter, left_count, right_count));
+                let common = generator.alloc_register();
+                generator.emit_line(&format!(
+                    "{} = select i1 {}, i64 {}, i64 {}",
+                    common, left_shorter, left_count, right_count
+                ));
+                let order = generator.alloc_register();
+                generator.emit_line(&format!(
+                    "{} = call i32 @memcmp(i8* {}, i8* {}, i64 {})",
+                    order, left_bytes, right_bytes, common
+                ));
+                let same_prefix = generator.alloc_register();
+                generator.emit_line(&format!("{} = icmp eq i32 {}, 0", same_prefix, order));
+                let compare_counts_label = generator.alloc_label("compare.counts");
+                let different_label = generator.alloc_label("different");
+                generator.emit_line(&format!(
+                    "br i1 {}, label %{}, label %{}",
+                    same_prefix, compare_counts_label, different_label
+                ));
+
+                generator.emit_label(&compare_counts_label);
+                let right_shorter = generator.alloc_register();
+                generator.emit_line(&format!("{} = icmp ugt i64 {}, {}", right_shorter, left_count, right_count));
+                let longer = generator.alloc_register();
+                generator.emit_line(&format!("{} = select i1 {}, i32 1, i32 0", longer, right_shorter));
+                let count_order = generator.alloc_register();
+                generator.emit_line(&format!("{} = select i1 {}, i32 -1, i32 {}", count_order, left_shorter, longer));
+                generator.emit_line(&format!("ret i32 {}", count_order));
+
+                generator.emit_label(&different_label);
+                generator.emit_line(&format!("ret i32 {}", order));
+            });
+        }
+
+        let result = self.alloc_register();
+        self.emit_line(&format!(
+            "{} = call i32 {}({{ i8*, i64 }} {}, {{ i8*, i64 }} {})",
+            result, helper, left, right
+        ));
+        result
+    }
+
+    // MARK - string builder
+
+    /// Declare the growable buffer `{ bytes, count, capacity }` used to build
+    /// interpolated strings, along with its reserve helper.
+    fn declare_string_builder(&mut self) {
+        if !self.emitted_helpers.insert("%StringBuilder".to_string()) {
+            return;
+        }
+        self.type_definitions.push_str("%StringBuilder = type { i8*, i64, i64 }\n\n");
+        self.declare("declare i8* @realloc(i8*, i64)");
+        self.declare("declare i32 @snprintf(i8*, i64, i8*, ...)");
+
+        // returns a pointer to at least `size` free bytes at the end of the buffer
+        let header = "define private i8* @\"StringBuilder.reserve\"(%StringBuilder* %builder, i64 %size)";
+        self.emit_function(header, |generator| {
+            let [bytes_ptr, count_ptr, capacity_ptr] = [0, 1, 2].map(|index| {
+                let ptr = generator.alloc_register();
+                generator.emit_line(&format!(
+                    "{} = getelementptr %StringBuilder, %StringBuilder* %builder, i32 0, i32 {}",
+                    ptr, index
+                ));
+                ptr
+            });
+            let count = generator.alloc_register();
+            generator.emit_line(&format!("{} = load i64, i64* {}", count, count_ptr));
+            let capacity = generator.alloc_register();
+            generator.emit_line(&format!("{} = load i64, i64* {}", capacity, capacity_ptr));
+            let needed = generator.alloc_register();
+            generator.emit_line(&format!("{} = add i64 {}, %size", needed, count));
+            let fits = generator.alloc_register();
+            generator.emit_line(&format!("{} = icmp ule i64 {}, {}", fits, needed, capacity));
+            let grow_label = generator.alloc_label("grow");
+            let done_label = generator.alloc_label("done");
+            generator.emit_line(&format!("br i1 {}, label %{}, label %{}", fits, done_label, grow_label));
+
+            // grow to twice the needed size to keep appends amortized O(1)
+            generator.emit_label(&grow_label);
+            let new_capacity = generator.alloc_register();
+            generator.emit_line(&format!("{} = mul i64 {}, 2", new_capacity, needed));
+            let old_bytes = generator.alloc_register();
+            generator.emit_line(&format!("{} = load i8*, i8** {}", old_bytes, bytes_ptr));
+            let new_bytes = generator.alloc_register();
+            generator.emit_line(&format!("{} = call i8* @realloc(i8* {}, i64 {})", new_bytes, old_bytes, new_capacity));
+            generator.emit_line(&format!("store i8* {}, i8** {}", new_bytes, bytes_ptr));
+            generator.emit_line(&format!("store i64 {}, i64* {}", new_capacity, capacity_ptr));
+            generator.emit_branch(&done_label);
+
+            generator.emit_label(&done_label);
+            let bytes = generator.alloc_register();
+            generator.emit_line(&format!("{} = load i8*, i8** {}", bytes, bytes_ptr));
+            let end = generator.alloc_register();
+            generator.emit_line(&format!("{} = getelementptr i8, i8* {}, i64 {}", end, bytes, count));
+            generator.emit_line(&format!("ret i8* {}", end));
+        });
+    }
+
+    /// the `emit_printf` equivalent while a string builder is active
+    fn emit_append_formatted(&mut self, builder: &str, format_ptr: &str, arguments: &[String]) {
+        self.declare_string_builder();
+        let mut operands = vec![format_ptr.to_string()];
+        operands.extend_from_slice(arguments);
+        let operands = operands.join(", ");
+
+        // measure first, then format into the reserved space
+        let length = self.alloc_register();
+        self.emit_line(&format!("{} = call i32 (i8*, i64, i8*, ...) @snprintf(i8* null, i64 0, {})", length, operands));
+        let length64 = self.alloc_register();
+        self.emit_line(&format!("{} = sext i32 {} to i64", length64, length));
+        let size = self.alloc_register();
+        self.emit_line(&format!("{} = add i64 {}, 1", size, length64));
+        let destination = self.alloc_register();
+        self.emit_line(&format!(
+            "{} = call i8* @\"StringBuilder.reserve\"(%StringBuilder* {}, i64 {})",
+            destination, builder, size
+        ));
+        let written = self.alloc_register();
+        self.emit_line(&format!(
+            "{} = call i32 (i8*, i64, i8*, ...) @snprintf(i8* {}, i64 {}, {})",
+            written, destination, size, operands
+        ));
+
+        let count_ptr = self.alloc_register();
+        self.emit_line(&format!("{} = getelementptr %StringBuilder, %StringBuilder* {}, i32 0, i32 1", count_ptr, builder));
+        let count = self.alloc_register();
+        self.emit_line(&format!("{} = load i64, i64* {}", count, count_ptr));
+        let new_count = self.alloc_register();
+        self.emit_line(&format!("{} = add i64 {}, {}", new_count, count, length64));
+        self.emit_line(&format!("store i64 {}, i64* {}", new_count, count_ptr));
+    }
+
+    /// `"a\(x)b"`: every part is written into a fresh builder whose buffer becomes the String
+    fn visit_interpolation(&mut self, parts: &[InterpolationPart]) -> (String, Type) {
+        self.declare_string_builder();
+        let builder = self.emit_alloca("%StringBuilder");
+        self.emit_line(&format!("store %StringBuilder zeroinitializer, %StringBuilder* {}", builder));
+
+        for part in parts {
+            match part {
+                InterpolationPart::Literal(text) => {
+                    let saved_builder = self.builder.replace(builder.clone());
+                    self.emit_write_literal(text);
+                    self.builder = saved_builder;
+                }
+                InterpolationPart::Expression(expr) => {
+                    let (value, ty) = self.visit_expression(expr, None);
+                    let saved_builder = self.builder.replace(builder.clone());
+                    self.emit_write(&value, &ty, false);
+                    self.builder = saved_builder;
+                }
+            }
+        }
+
+        let contents = self.alloc_register();
+        self.emit_line(&format!("{} = load %StringBuilder, %StringBuilder* {}", contents, builder));
+        let bytes = self.alloc_register();
+        self.emit_line(&format!("{} = extractvalue %StringBuilder {}, 0", bytes, contents));
+        let count = self.alloc_register();
+        self.emit_line(&format!("{} = extractvalue %StringBuilder {}, 1", count, contents));
+        let with_bytes = self.alloc_register();
+        self.emit_line(&format!("{} = insertvalue {{ i8*, i64 }} undef, i8* {}, 0", with_bytes, bytes));
+        let result = self.alloc_register();
+        self.emit_line(&format!("{} = insertvalue {{ i8*, i64 }} {}, i64 {}, 1", result, with_bytes, count));
+        (result, Type::String)
+    }
+
+    // MARK - arrays
+
+    /// Arrays are pointers to a heap header `{ refcount, count, capacity, elements }`.
+    /// Copies share the header and every stored copy counts as a reference,
+    /// so a mutation only has to copy the buffer while it is shared.
+    /// Dictionaries and sets share their hash tables the same way.
+    fn contains_shared_buffer(ty: &Type) -> bool {
+        match ty {
+            Type::Array(_) | Type::Dictionary(..) | Type::Set(_) => true,
+            Type::Tuple(elements) => elements.iter().any(|element| Self::contains_shared_buffer(&element.element_type)),
+            Type::Optional(wrapped) => Self::contains_shared_buffer(wrapped),
+            _ => false,
+        }
+    }
+
+    /// count one more reference to every array stored in `value`
+    fn emit_retain(&mut self, value: &str, ty: &Type) {
+        if !Self::contains_shared_buffer(ty) {
+            return;
+        }
+        match ty {
+            Type::Array(_) => {
+                let refcount_ptr = self.emit_array_field_ptr(value, 0);
+                let refcount = self.alloc_register();
+                self.emit_line(&format!("{} = load i64, i64* {}", refcount, refcount_ptr));
+                let incremented = self.alloc_register();
+                self.emit_line(&format!("{} = add i64 {}, 1", incremented, refcount));
+                self.emit_line(&format!("store i64 {}, i64* {}", incremented, refcount_ptr));
+            }
+            Type::Dictionary(..) | Type::Set(_) => {
+                self.declare_hash_table();
+                self.emit_line(&format!("call void @swift_hashtable_retain(i8* {})", value));
+            }
+            Type::Tuple(elements) => {
+                let llvm_type = self.llvm_type(ty);
+                for (index, element) in elements.iter().enumerate() {
+                    if Self::contains_shared_buffer(&element.element_type) {
+                        let element_value = self.alloc_register();
+                        self.emit_line(&format!("{} = extractvalue {} {}, {}", element_value, llvm_type, value, index));
+                        self.emit_retain(&element_value, &element.element_type);
+                    }
+                }
+            }
+            Type::Optional(wrapped) => {
+                let llvm_type = self.llvm_type(ty);
+                let has_value = self.alloc_register();
+                self.emit_line(&format!("{} = extractvalue {} {}, 0", has_value, llvm_type, value));
+                let some_label = self.alloc_label("retain.some");
+                let end_label = self.alloc_label("retain.end");
+                self.emit_line(&format!("br i1 {}, label %{}, label %{}", has_value, some_label, end_label));
+
+                self.emit_label(&some_label);
+                let wrapped_value = self.alloc_register();
+                self.emit_line(&format!("{} = extractvalue {} {}, 1", wrapped_value, llvm_type, value));
+                self.emit_retain(&wrapped_value, wrapped);
+                self.emit_branch(&end_label);
+                self.emit_label(&end_label);
+            }
+            _ => unreachable!("'{}' contains no shared buffers", ty),
+        }
+    }
+
+    fn emit_array_field_ptr(&mut self, array: &str, field: usize) -> String {
+        let ptr = self.alloc_register();
+        self.emit_line(&format!("{} = getelementptr %Array, %Array* {}, i32 0, i32 {}", ptr, array, field));
+        ptr
+    }
+
+    fn emit_array_count(&mut self, array: &str) -> String {
+        let count_ptr = self.emit_array_field_ptr(array, 1);
+        let count = self.alloc_register();
+        self.emit_line(&format!("{} = load i64, i64* {}", count, count_ptr));
+        count
+    }
+
+    fn emit_set_array_count(&mut self, array: &str, count: &str) {
+        let count_ptr = self.emit_array_field_ptr(array, 1);
+        self.emit_line(&format!("store i64 {}, i64* {}", count, count_ptr));
+    }
+
+    /// the size of one element as a constant expression
+    fn element_size(&self, element_type: &Type) -> String {
+        let llvm_type = self.llvm_type(element_type);
+        format!("ptrtoint ({}* getelementptr ({}, {}* null, i32 1) to i64)", llvm_type, llvm_type, llvm_type)
+    }
+
+    fn emit_element_ptr(&mut self, array: &str, element_type: &Type, index: &str) -> String {
+        let llvm_type = self.llvm_type(element_type);
+        let elements_ptr = self.emit_array_field_ptr(array, 3);
+        let elements = self.alloc_register();
+        self.emit_line(&format!("{} = load i8*, i8** {}", elements, elements_ptr));
+        let typed = self.alloc_register();
+        self.emit_line(&format!("{} = bitcast i8* {} to {}*", typed, elements, llvm_type));
+        let ptr = self.alloc_register();
+        self.emit_line(&format!("{} = getelementptr {}, {}* {}, i64 {}", ptr, llvm_type, llvm_type, typed, index));
+        ptr
+    }
+
+    /// trap with `message` unless `index comparison count` holds
+    fn emit_index_check(&mut self, array: &str, index: &str, comparison: &str, message: &str) {
+        let count = self.emit_array_count(array);
+        let in_range = self.alloc_register();
+        self.emit_line(&format!("{} = {} i64 {}, {}", in_range, comparison, index, count));
+        let ok_label = self.alloc_label("index.ok");
+        let fail_label = self.alloc_label("index.fail");
+        self.emit_line(&format!("br i1 {}, label %{}, label %{}", in_range, ok_label, fail_label));
+
+        self.emit_label(&fail_label);
+        self.emit_fatal_error(message);
+        self.emit_label(&ok_label);
+    }
+
+    /// `for index in 0..<count`; the body gets the index and the labels that
+    /// continue with the next index or leave the loop
+    fn emit_index_loop<F: FnOnce(&mut Self, &str, &str, &str)>(&mut self, count: &str, name: &str, body: F) {
+        let index_ptr = self.emit_alloca("i64");
+        self.emit_line(&format!("store i64 0, i64* {}", index_ptr));
+        let condition_label = self.alloc_label(&format!("{}.cond", name));
+        let body_label = self.alloc_label(&format!("{}.body", name));
+        let next_label = self.alloc_label(&format!("{}.next", name));
+        let end_label = self.alloc_label(&format!("{}.end", name));
+        self.emit_branch(&condition_label);
+
+        self.emit_label(&condition_label);
+        let index = self.alloc_register();
+        self.emit_line(&format!("{} = load i64, i64* {}", index, index_ptr));
+        let in_range = self.alloc_register();
+        self.emit_line(&format!("{} = icmp slt i64 {}, {}", in_range, index, count));
+        self.emit_line(&format!("br i1 {}, label %{}, label %{}", in_range, body_label, end_label));
+
+        self.emit_label(&body_label);
+        body(self, &index, &next_label, &end_label);
+        self.emit_branch(&next_label);
+
+        self.emit_label(&next_label);
+        let next_index = self.alloc_register();
+        self.emit_line(&format!("{} = add i64 {}, 1", next_index, index));
+        self.emit_line(&format!("store i64 {}, i64* {}", next_index, index_ptr));
+        self.emit_branch(&condition_label);
+
+        self.emit_label(&end_label);
+    }
+
+    /// `@"Array.allocate"(count, capacity, elementSize)` returns an unreferenced array
+    fn array_allocate_helper(&mut self) -> &'static str {
+        let helper = "@\"Array.allocate\"";
+        if self.emitted_helpers.insert(helper.to_string()) {
+            self.declare("declare i8* @malloc(i64)");
+            let header = format!("define private %Array* {}(i64 %count, i64 %capacity, i64 %elementSize)", helper);
+            self.emit_function(&header, |generator| {
+                let raw = generator.alloc_register();
+                generator.emit_line(&format!(
+                    "{} = call i8* @malloc(i64 ptrtoint (%Array* getelementptr (%Array, %Array* null, i32 1) to i64))",
+                    raw
+                ));
+                let array = generator.alloc_register();
+                generator.emit_line(&format!("{} = bitcast i8* {} to %Array*", array, raw));
+                let bytes = generator.alloc_register();
+                generator.emit_line(&format!("{} = mul i64 %capacity, %elementSize", bytes));
+                let elements = generator.alloc_register();
+                generator.emit_line(&format!("{} = call i8* @malloc(i64 {})", elements, bytes));
+                for (field, value) in ["i64 0", "i64 %count", "i64 %capacity", &format!("i8* {}", elements)].iter().enumerate() {
+                    let ptr = generator.emit_array_field_ptr(&array, field);
+                    let field_type = value.split(' ').next().unwrap_or_default();
+                    generator.emit_line(&format!("store {}, {}* {}", value, field_type, ptr));
+                }
+                generator.emit_line(&format!("ret %Array* {}", array));
+            });
+        }
+        helper
+    }
+
+    /// make room for at least `minimum` elements, doubling the capacity to keep appends amortized O(1)
+    fn emit_reserve(&mut self, array: &str, minimum: &str, element_type: &Type) {
+        let helper = "@\"Array.reserve\"";
+        if self.emitted_helpers.insert(helper.to_string()) {
+            self.declare("declare i8* @realloc(i8*, i64)");
+            let header = format!("define private void {}(%Array* %array, i64 %minimum, i64 %elementSize)", helper);
+            self.emit_function(&header, |generator| {
+                let capacity_ptr = generator.emit_array_field_ptr("%array", 2);
+                let capacity = generator.alloc_register();
+                generator.emit_line(&format!("{} = load i64, i64* {}", capacity, capacity_ptr));
+                let fits = generator.alloc_register();
+                generator.emit_line(&format!("{} = icmp sge i64 {}, %minimum", fits, capacity));
+                let grow_label = generator.alloc_label("grow");
+                let done_label = generator.alloc_label("done");
+                generator.emit_line(&format!("br i1 {}, label %{}, label %{}", fits, done_label, grow_label));
+
+                generator.emit_label(&grow_label);
+                let doubled = generator.alloc_register();
+                generator.emit_line(&format!("{} = mul i64 {}, 2", doubled, capacity));
+                let enough = generator.alloc_register();
+                generator.emit_line(&format!("{} = icmp sgt i64 {}, %minimum", enough, doubled));
+                let new_capacity = generator.alloc_register();
+                generator.emit_line(&format!("{} = select i1 {}, i64 {}, i64 %minimum", new_capacity, enough, doubled));
+                generator.emit_line(&format!("store i64 {}, i64* {}", new_capacity, capacity_ptr));
+                let elements_ptr = generator.emit_array_field_ptr("%array", 3);
+                let old_elements = generator.alloc_register();
+                generator.emit_line(&format!("{} = load i8*, i8** {}", old_elements, elements_ptr));
+                let bytes = generator.alloc_register();
+                generator.emit_line(&format!("{} = mul i64 {}, %elementSize", bytes, new_capacity));
+                let new_elements = generator.alloc_register();
+                generator.emit_line(&format!("{} = call i8* @realloc(i8* {}, i64 {})", new_elements, old_elements, bytes));
+                generator.emit_line(&format!("store i8* {}, i8** {}", new_elements, elements_ptr));
+                generator.emit_branch(&done_label);
+
+                generator.emit_label(&done_label);
+                generator.emit_line("ret void");
+            });
+        }
+        let size = self.element_size(element_type);
+        self.emit_line(&format!("call void {}(%Array* {}, i64 {}, i64 {})", helper, array, minimum, size));
+    }
+
+    /// Give the array stored at `slot` a buffer of its own before it is
+    /// mutated, copying the elements while other references share it.
+    fn emit_make_unique(&mut self, slot: &str, element_type: &Type) -> String {
+        let array_type = Type::Array(Box::new(element_type.clone()));
+        let helper = format!("@\"Array.makeUnique.{}\"", array_type);
+        if self.emitted_helpers.insert(helper.clone()) {
+            self.declare("declare void @llvm.memcpy.p0i8.p0i8.i64(i8*, i8*, i64, i1)");
+            let allocate = self.array_allocate_helper();
+            let size = self.element_size(element_type);
+            let header = format!("define private %Array* {}(%Array** %slot)", helper);
+            let element_type = element_type.clone();
+            self.emit_function(&header, |generator| {
+                let array = generator.alloc_register();
+                generator.emit_line(&format!("{} = load %Array*, %Array** %slot", array));
+                let refcount_ptr = generator.emit_array_field_ptr(&array, 0);
+                let refcount = generator.alloc_register();
+                generator.emit_line(&format!("{} = load i64, i64* {}", refcount, refcount_ptr));
+                let shared = generator.alloc_register();
+                generator.emit_line(&format!("{} = icmp sgt i64 {}, 1", shared, refcount));
+                let copy_label = generator.alloc_label("copy");
+                let unique_label = generator.alloc_label("unique");
+                generator.emit_line(&format!("br i1 {}, label %{}, label %{}", shared, copy_label, unique_label));
+
+                generator.emit_label(&unique_label);
+                generator.emit_line(&format!("ret %Array* {}", array));
+
+                generator.emit_label(&copy_label);
+                let count = generator.emit_array_count(&array);
+                let copy = generator.alloc_register();
+                generator.emit_line(&format!(
+                    "{} = call %Array* {}(i64 {}, i64 {}, i64 {})",
+                    copy, allocate, count, count, size
+                ));
+                let [source, destination] = [&array, &copy].map(|array| {
+                    let elements_ptr = generator.emit_array_field_ptr(array, 3);
+                    let elements = generator.alloc_register();
+                    generator.emit_line(&format!("{} = load i8*, i8** {}", elements, elements_ptr));
+                    elements
+                });
+                let bytes = generator.alloc_register();
+                generator.emit_line(&format!("{} = mul i64 {}, {}", bytes, count, size));
+                generator.emit_line(&format!(
+                    "call void @llvm.memcpy.p0i8.p0i8.i64(i8* {}, i8* {}, i64 {}, i1 false)",
+                    destination, source, bytes
+                ));
+                let copy_refcount_ptr = generator.emit_array_field_ptr(&copy, 0);
+                generator.emit_line(&format!("store i64 1, i64* {}", copy_refcount_ptr));
+                let released = generator.alloc_register();
+                generator.emit_line(&format!("{} = sub i64 {}, 1", released, refcount));
+                generator.emit_line(&format!("store i64 {}, i64* {}", released, refcount_ptr));
+
+                // the copied elements are now referenced from both buffers
+                if Self::contains_shared_buffer(&element_type) {
+                    let llvm_type = generator.llvm_type(&element_type);
+                    generator.emit_index_loop(&count, "retain", |generator, index, _, _| {
+                        let element_ptr = generator.emit_element_ptr(&copy, &element_type, index);
+                        let element = generator.alloc_register();
+                        generator.emit_line(&format!("{} = load {}, {}* {}", element, llvm_type, llvm_type, element_ptr));
+                        generator.emit_retain(&element, &element_type);
+                    });
+                }
+                generator.emit_line(&format!("store %Array* {}, %Array** %slot", copy));
+                generator.emit_line(&format!("ret %Array* {}", copy));
+            });
+        }
+        let array = self.alloc_register();
+        self.emit_line(&format!("{} = call %Array* {}(%Array** {})", array, helper, slot));
+        array
+    }
+
+    /// `memmove` `count` elements starting at index `from` to index `to`
+    fn emit_move_elements(&mut self, array: &str, element_type: &Type, from: &str, to: &str, count: &str) {
+        self.declare("declare void @llvm.memmove.p0i8.p0i8.i64(i8*, i8*, i64, i1)");
+        let llvm_type = self.llvm_type(element_type);
+        let [source, destination] = [from, to].map(|index| {
+            let element_ptr = self.emit_element_ptr(array, element_type, index);
+            let bytes = self.alloc_register();
+            self.emit_line(&format!("{} = bitcast {}* {} to i8*", bytes, llvm_type, element_ptr));
+            bytes
+        });
+        let size = self.element_size(element_type);
+        let bytes = self.alloc_register();
+        self.emit_line(&format!("{} = mul i64 {}, {}", bytes, count, size));
+        self.emit_line(&format!(
+            "call void @llvm.memmove.p0i8.p0i8.i64(i8* {}, i8* {}, i64 {}, i1 false)",
+            destination, source, bytes
+        ));
+    }
+
+    /// Generate the elements of a collection literal converted to a common type,
+    /// which is inferred the same way as in the semantic pass unless given.
+    fn visit_elements(&mut self, elements: &[&Expression], element_type: Option<&Type>) -> (Vec<String>, Type) {
+        let (element_type, first) = match element_type {
+            Some(element_type) => (element_type.clone(), None),
+            None => match elements.iter().position(|element| !element.is_number_literal()) {
+                Some(position) => {
+                    let (value, ty) = self.visit_expression(elements[position], None);
+                    (ty, Some((position, value)))
+                }
+                None if elements.iter().any(|element| element.is_float_literal()) => (Type::Double, None),
+                None => (Type::Int, None),
+            },
+        };
+        let values = elements.iter().enumerate()
+            .map(|(index, element)| match &first {
+                Some((position, value)) if *position == index => value.clone(),
+                _ => self.visit_expression_as(element, &element_type),
+            })
+            .collect::<Vec<_>>();
+        (values, element_type)
+    }
+
+    /// `[a, b, c]`, which builds a set when the context expects one
+    fn visit_array_literal(&mut self, elements: &[Expression], expected: Option<&Type>) -> (String, Type) {
+        let elements = elements.iter().collect::<Vec<_>>();
+        let (values, element_type) = match expected.map(Type::innermost_wrapped) {
+            Some(Type::Array(element_type)) => self.visit_elements(&elements, Some(element_type)),
+            Some(Type::Set(element_type)) => {
+                let (values, element_type) = self.visit_elements(&elements, Some(element_type));
+                return self.emit_set_literal(&values, element_type);
+            }
+            _ => self.visit_elements(&elements, None),
+        };
+
+        let allocate = self.array_allocate_helper();
+        let size = self.element_size(&element_type);
+        let array = self.alloc_register();
+        self.emit_line(&format!(
+            "{} = call %Array* {}(i64 {}, i64 {}, i64 {})",
+            array, allocate, values.len(), values.len(), size
+        ));
+        let llvm_type = self.llvm_type(&element_type);
+        for (index, value) in values.iter().enumerate() {
+            let element_ptr = self.emit_element_ptr(&array, &element_type, &index.to_string());
+            self.emit_retain(value, &element_type);
+            self.emit_line(&format!("store {} {}, {}* {}", llvm_type, value, llvm_type, element_ptr));
+        }
+        (array, Type::Array(Box::new(element_type)))
+    }
+
+    /// Methods of arrays, dictionaries and sets. The mutating ones work on
+    /// the variable holding the collection, which is evaluated first.
+    fn visit_collection_method(&mut self, base: &Expression, name: &str, arguments: &[Argument]) -> (String, Type) {
+        if name == "contains" {
+            let (set, ty) = self.visit_expression(base, None);
+            let Type::Set(element_type) = ty else {
+                unreachable!("contains(_:) on '{}'", ty);
+            };
+            let element = self.visit_expression_as(&arguments[0].value, &element_type);
+            let entry = self.emit_hash_table_lookup("find", &set, &element, &element_type);
+            let found = self.alloc_register();
+            self.emit_line(&format!("{} = icmp sge i64 {}, 0", found, entry));
+            return (found, Type::Bool);
+        }
+
+        let (slot, ty) = self.emit_lvalue(base);
+        match ty {
+            Type::Array(element_type) => self.visit_array_method(&slot, *element_type, name, arguments),
+            Type::Dictionary(key_type, value_type) => {
+                // removeValue(forKey:)
+                let key = self.visit_expression_as(&arguments[0].value, &key_type);
+                let table = self.emit_hash_table_make_unique(&slot, Some(&value_type));
+                let entry = self.emit_hash_table_lookup("find", &table, &key, &key_type);
+                self.emit_optional_entry(&table, &entry, "value", &value_type, true)
+            }
+            Type::Set(element_type) => self.visit_set_method(&slot, *element_type, name, arguments),
+            _ => unreachable!("method call on '{}'", ty),
+        }
+    }
+
+    /// `append(_:)`, `insert(_:at:)` and `remove(at:)` on the array stored at `slot`
+    fn visit_array_method(&mut self, slot: &str, element_type: Type, name: &str, arguments: &[Argument]) -> (String, Type) {
+        let parameter_types = match name {
+            "append" => vec![element_type.clone()],
+            "insert" => vec![element_type.clone(), Type::Int],
+            _ => vec![Type::Int],
+        };
+        let values = arguments.iter().zip(&parameter_types)
+            .map(|(argument, ty)| self.visit_expression_as(&argument.value, ty))
+            .collect::<Vec<_>>();
+
+        let array = self.emit_make_unique(slot, &element_type);
+        let count = self.emit_array_count(&array);
+        let new_count = self.alloc_register();
+        let llvm_type = self.llvm_type(&element_type);
+        match name {
+            "append" | "insert" => {
+                let index = if name == "append" {
+                    count.clone()
+                } else {
+                    self.emit_index_check(&array, &values[1], "icmp ule", "Array index is out of range");
+                    values[1].clone()
+                };
+                self.emit_line(&format!("{} = add i64 {}, 1", new_count, count));
+                self.emit_reserve(&array, &new_count, &element_type);
+                if name == "insert" {
+                    let moved = self.alloc_register();
+                    self.emit_line(&format!("{} = sub i64 {}, {}", moved, count, index));
+                    let destination = self.alloc_register();
+                    self.emit_line(&format!("{} = add i64 {}, 1", destination, index));
+                    self.emit_move_elements(&array, &element_type, &index, &destination, &moved);
+                }
+                let element_ptr = self.emit_element_ptr(&array, &element_type, &index);
+                self.emit_retain(&values[0], &element_type);
+                self.emit_line(&format!("store {} {}, {}* {}", llvm_type, values[0], llvm_type, element_ptr));
+                self.emit_set_array_count(&array, &new_count);
+                ("zeroinitializer".to_string(), Type::Void)
+            }
+            _ => {
+                let index = &values[0];
+                self.emit_index_check(&array, index, "icmp ult", "Index out of range");
+                let element_ptr = self.emit_element_ptr(&array, &element_type, index);
+                let element = self.alloc_register();
+                self.emit_line(&format!("{} = load {}, {}* {}", element, llvm_type, llvm_type, element_ptr));
+                self.emit_line(&format!("{} = sub i64 {}, 1", new_count, count));
+                let moved = self.alloc_register();
+                self.emit_line(&format!("{} = sub i64 {}, {}", moved, new_count, index));
+                let source = self.alloc_register();
+                self.emit_line(&format!("{} = add i64 {}, 1", source, index));
+                self.emit_move_elements(&array, &element_type, &source, index, &moved);
+                self.emit_set_array_count(&array, &new_count);
+                (element, element_type)
+            }
+        }
+    }
+
+    /// `for name in collection { ... }` iterates over the collection as it was when
+    /// the loop started; dictionaries produce `(key:value:)` tuples
+    fn visit_for_in(&mut self, pattern: &ForPattern, sequence: &Expression, body: &[Statement]) {
+        let (collection, ty) = self.visit_expression(sequence, None);
+        // the loop's reference makes mutations in the body copy the buffer
+        self.emit_retain(&collection, &ty);
+        let count = self.emit_sequence_count(&collection, &ty);
+        self.emit_element_loop(&collection, &ty, &count, "for", |generator, element, element_type, next_label, end_label| {
+            generator.scopes.push(HashMap::new());
+            match pattern {
+                ForPattern::Name(Some(name)) => generator.emit_variable(name, element, element_type.clone()),
+                ForPattern::Name(None) => {}
+                ForPattern::Tuple(names) => generator.emit_destructure(names, element, element_type),
+            }
+            generator.break_targets.push(generator.jump_target(end_label));
+            generator.continue_targets.push(generator.jump_target(next_label));
+            generator.visit_block(body);
+            generator.break_targets.pop();
+            generator.continue_targets.pop();
+            generator.scopes.pop();
+        });
+    }
+
+    fn emit_sequence_count(&mut self, collection: &str, ty: &Type) -> String {
+        match ty {
+            Type::Array(_) => self.emit_array_count(collection),
+            _ => self.emit_hash_table_count(collection),
+        }
+    }
+
+    /// `emit_index_loop` over the first `count` elements of an array, a set or
+    /// a dictionary; the body gets each element and its type
+    fn emit_element_loop<F>(&mut self, collection: &str, ty: &Type, count: &str, name: &str, body: F)
+    where
+        F: FnOnce(&mut Self, &str, &Type, &str, &str),
+    {
+        let element_type = ty.sequence_element().expect("a collection");
+        self.emit_index_loop(count, name, |generator, index, next_label, end_label| {
+            let element = match ty {
+                Type::Array(_) => {
+                    let llvm_type = generator.llvm_type(&element_type);
+                    let element_ptr = generator.emit_element_ptr(collection, &element_type, index);
+                    let element = generator.alloc_register();
+                    generator.emit_line(&format!("{} = load {}, {}* {}", element, llvm_type, llvm_type, element_ptr));
+                    element
+                }
+                Type::Set(_) => generator.emit_entry_load(collection, index, "key", &element_type),
+                _ => generator.emit_dictionary_element(collection, index, &element_type),
+            };
+            body(generator, &element, &element_type, next_label, end_label);
+        });
+    }
+
+    /// store `value` after the last element of `array`, which has enough capacity
+    fn emit_append_unchecked(&mut self, array: &str, value: &str, element_type: &Type) {
+        let count = self.emit_array_count(array);
+        let element_ptr = self.emit_element_ptr(array, element_type, &count);
+        let llvm_type = self.llvm_type(element_type);
+        self.emit_retain(value, element_type);
+        self.emit_line(&format!("store {} {}, {}* {}", llvm_type, value, llvm_type, element_ptr));
+        let new_count = self.alloc_register();
+        self.emit_line(&format!("{} = add i64 {}, 1", new_count, count));
+        self.emit_set_array_count(array, &new_count);
+    }
+
+    /// `[1, 2, 3]`, elements use their debug representation
+    fn emit_write_array(&mut self, value: &str, element_type: &Type) {
+        let llvm_type = self.llvm_type(element_type);
+        self.emit_write_literal("[");
+        let count = self.emit_array_count(value);
+        self.emit_index_loop(&count, "write.element", |generator, index, _, _| {
+            generator.emit_write_separator(index);
+            let element_ptr = generator.emit_element_ptr(value, element_type, index);
+            let element = generator.alloc_register();
+            generator.emit_line(&format!("{} = load {}, {}* {}", element, llvm_type, llvm_type, element_ptr));
+            generator.emit_write(&element, element_type, true);
+        });
+        self.emit_write_literal("]");
+        self.emit_line("ret void");
+    }
+
+    /// `, ` before every element but the first
+    fn emit_write_separator(&mut self, index: &str) {
+        let separator_label = self.alloc_label("write.separator");
+        let element_label = self.alloc_label("write.element");
+        let is_first = self.alloc_register();
+        self.emit_line(&format!("{} = icmp eq i64 {}, 0", is_first, index));
+        self.emit_line(&format!("br i1 {}, label %{}, label %{}", is_first, element_label, separator_label));
+        self.emit_label(&separator_label);
+        self.emit_write_literal(", ");
+        self.emit_branch(&element_label);
+        self.emit_label(&element_label);
+    }
+
+    // MARK - dictionaries and sets
+
+    /// Dictionaries and sets are hash tables of the support library that
+    /// llvm_backend links into the program. Keys are passed by address along
+    /// with their hash and a function comparing two keys of the key type.
+    fn declare_hash_table(&mut self) {
+        self.uses_support_library = true;
+        for declaration in [
+            "declare i8* @swift_hashtable_create(i64, i64, i64)",
+            "declare void @swift_hashtable_retain(i8*)",
+            "declare i64 @swift_hashtable_count(i8*)",
+            "declare i8* @swift_hashtable_key(i8*, i64)",
+            "declare i8* @swift_hashtable_value(i8*, i64)",
+            "declare i64 @swift_hashtable_find(i8*, i64, i8*, i1 (i8*, i8*)*)",
+            "declare i64 @swift_hashtable_insert(i8*, i64, i8*, i1 (i8*, i8*)*)",
+            "declare void @swift_hashtable_remove(i8*, i64)",
+            "declare i8* @swift_hashtable_make_unique(i8**, void (i8*)*)",
+            "declare i64 @swift_hash_bytes(i8*, i64)",
+        ] {
+            self.declare(declaration);
+        }
+    }
+
+    /// hash of a key; equal keys have equal hashes
+    fn emit_hash(&mut self, value: &str, ty: &Type) -> String {
+        match ty {
+            Type::Int | Type::Int8 | Type::Int16 | Type::Int32 | Type::Int64
+            | Type::UInt | Type::UInt8 | Type::UInt16 | Type::UInt32 | Type::UInt64 | Type::Bool => {
+                let llvm_type = self.llvm_type(ty);
+                if llvm_type == "i64" {
+                    return value.to_string();
+                }
+                let extend = if ty.is_integer() && !ty.is_unsigned_integer() { "sext" } else { "zext" };
+                let hash = self.alloc_register();
+                self.emit_line(&format!("{} = {} {} {} to i64", hash, extend, llvm_type, value));
+                hash
+            }
+            Type::Double | Type::Float => {
+                // adding zero turns -0.0 into 0.0, which compares equal
+                let llvm_type = self.llvm_type(ty);
+                let normalized = self.alloc_register();
+                self.emit_line(&format!("{} = fadd {} {}, 0.0", normalized, llvm_type, value));
+                let bits = self.alloc_register();
+                if *ty == Type::Double {
+                    self.emit_line(&format!("{} = bitcast double {} to i64", bits, normalized));
+                    return bits;
+                }
+                self.emit_line(&format!("{} = bitcast float {} to i32", bits, normalized));
+                let hash = self.alloc_register();
+                self.emit_line(&format!("{} = zext i32 {} to i64", hash, bits));
+                hash
+            }
+            Type::String => {
+                self.declare_hash_table();
+                let (bytes, count) = self.emit_string_parts(value);
+                let hash = self.alloc_register();
+                self.emit_line(&format!("{} = call i64 @swift_hash_bytes(i8* {}, i64 {})", hash, bytes, count));
+                hash
+            }
+            Type::Named(..) => {
+                let llvm_type = self.llvm_type(ty);
+                let tag = self.alloc_register();
+                self.emit_line(&format!("{} = extractvalue {} {}, 0", tag, llvm_type, value));
+                let hash = self.alloc_register();
+                self.emit_line(&format!("{} = zext i32 {} to i64", hash, tag));
+                hash
+            }
+            Type::Optional(wrapped) => {
+                let has_value = self.emit_has_value(value, ty);
+                let unwrapped = self.emit_unwrapped(value, ty);
+                let wrapped_hash = self.emit_hash(&unwrapped, wrapped);
+                let hash = self.alloc_register();
+                self.emit_line(&format!("{} = select i1 {}, i64 {}, i64 0", hash, has_value, wrapped_hash));
+                hash
+            }
+            _ => unreachable!("the semantic pass rejects '{}' as a key", ty),
+        }
+    }
+
+    /// `@"equal.T"(i8*, i8*)` comparing two keys in memory
+    fn equal_helper(&mut self, ty: &Type) -> String {
+        let name = format!("@\"equal.{}\"", ty);
+        if self.emitted_helpers.insert(name.clone()) {
+            let header = format!("define private i1 {}(i8* %lhs, i8* %rhs)", name);
+            let ty = ty.clone();
+            self.emit_function(&header, |generator| {
+                let llvm_type = generator.llvm_type(&ty);
+                let [lhs, rhs] = ["%lhs", "%rhs"].map(|ptr| {
+                    let typed = generator.alloc_register();
+                    generator.emit_line(&format!("{} = bitcast i8* {} to {}*", typed, ptr, llvm_type));
+                    let value = generator.alloc_register();
+                    generator.emit_line(&format!("{} = load {}, {}* {}", value, llvm_type, llvm_type, typed));
+                    value
+                });
+                let equal = generator.emit_equal(&lhs, &rhs, &ty);
+                generator.emit_line(&format!("ret i1 {}", equal));
+            });
+        }
+        name
+    }
+
+    /// `@"retain.T"(i8*)` retaining a value in memory, `null` when values of `ty` need no retaining
+    fn retain_helper(&mut self, ty: &Type) -> String {
+        if !Self::contains_shared_buffer(ty) {
+            return "null".to_string();
+        }
+        let name = format!("@\"retain.{}\"", ty);
+        if self.emitted_helpers.insert(name.clone()) {
+            let header = format!("define private void {}(i8* %value)", name);
+            let ty = ty.clone();
+            self.emit_function(&header, |generator| {
+                let llvm_type = generator.llvm_type(&ty);
+                let typed = generator.alloc_register();
+                generator.emit_line(&format!("{} = bitcast i8* %value to {}*", typed, llvm_type));
+                let value = generator.alloc_register();
+                generator.emit_line(&format!("{} = load {}, {}* {}", value, llvm_type, llvm_type, typed));
+                generator.emit_retain(&value, &ty);
+                generator.emit_line("ret void");
+            });
+        }
+        name
+    }
+
+    /// address of a stack copy of `value`, for passing keys to the support library
+    fn emit_spill(&mut self, value: &str, ty: &Type) -> String {
+        let llvm_type = self.llvm_type(ty);
+        let slot = self.emit_alloca(&llvm_type);
+        self.emit_line(&format!("store {} {}, {}* {}", llvm_type, value, llvm_type, slot));
+        let ptr = self.alloc_register();
+        self.emit_line(&format!("{} = bitcast {}* {} to i8*", ptr, llvm_type, slot));
+        ptr
+    }
+
+    /// a new unreferenced table; sets have no values
+    fn emit_hash_table_create(&mut self, key_type: &Type, value_type: Option<&Type>, capacity: usize) -> String {
+        self.declare_hash_table();
+        let key_size = self.element_size(key_type);
+        let value_size = value_type.map_or("0".to_string(), |ty| self.element_size(ty));
+        let table = self.alloc_register();
+        self.emit_line(&format!(
+            "{} = call i8* @swift_hashtable_create(i64 {}, i64 {}, i64 {})",
+            table, key_size, value_size, capacity
+        ));
+        table
+    }
+
+    /// `find` returns the entry of `key` or -1, `insert` adds a missing key first
+    fn emit_hash_table_lookup(&mut self, function: &str, table: &str, key: &str, key_type: &Type) -> String {
+        self.declare_hash_table();
+        let hash = self.emit_hash(key, key_type);
+        let key_ptr = self.emit_spill(key, key_type);
+        let equal = self.equal_helper(key_type);
+        let entry = self.alloc_register();
+        self.emit_line(&format!(
+            "{} = call i64 @swift_hashtable_{}(i8* {}, i64 {}, i8* {}, i1 (i8*, i8*)* {})",
+            entry, function, table, hash, key_ptr, equal
+        ));
+        entry
+    }
+
+    fn emit_hash_table_count(&mut self, table: &str) -> String {
+        self.declare_hash_table();
+        let count = self.alloc_register();
+        self.emit_line(&format!("{} = call i64 @swift_hashtable_count(i8* {})", count, table));
+        count
+    }
+
+    /// the table stored at `slot`, copied first while it is shared
+    fn emit_hash_table_make_unique(&mut self, slot: &str, value_type: Option<&Type>) -> String {
+        self.declare_hash_table();
+        let retain = value_type.map_or("null".to_string(), |ty| self.retain_helper(ty));
+        let table = self.alloc_register();
+        self.emit_line(&format!(
+            "{} = call i8* @swift_hashtable_make_unique(i8** {}, void (i8*)* {})",
+            table, slot, retain
+        ));
+        table
+    }
+
+    /// address of the `key` or `value` of an entry
+    fn emit_entry_ptr(&mut self, table: &str, entry: &str, part: &str, ty: &Type) -> String {
+        let raw = self.alloc_register();
+        self.emit_line(&format!("{} = call i8* @swift_hashtable_{}(i8* {}, i64 {})", raw, part, table, entry));
+        let llvm_type = self.llvm_type(ty);
+        let ptr = self.alloc_register();
+        self.emit_line(&format!("{} = bitcast i8* {} to {}*", ptr, raw, llvm_type));
+        ptr
+    }
+
+    fn emit_entry_load(&mut self, table: &str, entry: &str, part: &str, ty: &Type) -> String {
+        let ptr = self.emit_entry_ptr(table, entry, part, ty);
+        let llvm_type = self.llvm_type(ty);
+        let value = self.alloc_register();
+        self.emit_line(&format!("{} = load {}, {}* {}", value, llvm_type, llvm_type, ptr));
+        value
+    }
+
+    /// the `key` or `value` of an entry as an optional, nil when `entry` is -1;
+    /// `remove` also removes a found entry from the table
+    fn emit_optional_entry(&mut self, table: &str, entry: &str, part: &str, ty: &Type, remove: bool) -> (String, Type) {
+        let optional_type = Type::Optional(Box::new(ty.clone()));
+        let llvm_type = self.llvm_type(&optional_type);
+        let result_ptr = self.emit_alloca(&llvm_type);
+        self.emit_line(&format!("store {} zeroinitializer, {}* {}", llvm_type, llvm_type, result_ptr));
+        let found = self.alloc_register();
+        self.emit_line(&format!("{} = icmp sge i64 {}, 0", found, entry));
+        let found_label = self.alloc_label("entry.found");
+        let end_label = self.alloc_label("entry.end");
+        self.emit_line(&format!("br i1 {}, label %{}, label %{}", found, found_label, end_label));
+
+        self.emit_label(&found_label);
+        let value = self.emit_entry_load(table, entry, part, ty);
+        let wrapped = self.emit_wrap_some(&value, &optional_type);
+        self.emit_line(&format!("store {} {}, {}* {}", llvm_type, wrapped, llvm_type, result_ptr));
+        if remove {
+            self.emit_line(&format!("call void @swift_hashtable_remove(i8* {}, i64 {})", table, entry));
+        }
+        self.emit_branch(&end_label);
+
+        self.emit_label(&end_label);
+        let result = self.alloc_register();
+        self.emit_line(&format!("{} = load {}, {}* {}", result, llvm_type, llvm_type, result_ptr));
+        (result, optional_type)
+    }
+
+    /// the `(key: Key, value: Value)` tuple of an entry
+    fn emit_dictionary_element(&mut self, table: &str, entry: &str, element_type: &Type) -> String {
+        let Type::Tuple(elements) = element_type else {
+            unreachable!("dictionary element '{}'", element_type);
+        };
+        let llvm_type = self.llvm_type(element_type);
+        let mut tuple = "undef".to_string();
+        for (index, (part, element)) in ["key", "value"].iter().zip(elements).enumerate() {
+            let value = self.emit_entry_load(table, entry, part, &element.element_type);
+            let inserted = self.alloc_register();
+            self.emit_line(&format!(
+                "{} = insertvalue {} {}, {} {}, {}",
+                inserted, llvm_type, tuple, self.llvm_type(&element.element_type), value, index
+            ));
+            tuple = inserted;
+        }
+        tuple
+    }
+
+    /// `dictionary.keys` and `dictionary.values` as arrays
+    fn emit_entries_array(&mut self, table: &str, part: &str, ty: &Type) -> (String, Type) {
+        let count = self.emit_hash_table_count(table);
+        let allocate = self.array_allocate_helper();
+        let size = self.element_size(ty);
+        let array = self.alloc_register();
+        self.emit_line(&format!("{} = call %Array* {}(i64 {}, i64 {}, i64 {})", array, allocate, count, count, size));
+        let llvm_type = self.llvm_type(ty);
+        self.emit_index_loop(&count, "entries", |generator, index, _, _| {
+            let value = generator.emit_entry_load(table, index, part, ty);
+            let element_ptr = generator.emit_element_ptr(&array, ty, index);
+            generator.emit_retain(&value, ty);
+            generator.emit_line(&format!("store {} {}, {}* {}", llvm_type, value, llvm_type, element_ptr));
+        });
+        (array, Type::Array(Box::new(ty.clone())))
+    }
+
+    /// `["a": 1, "b": 2]`; keys and values are inferred like array elements
+    fn visit_dictionary_literal(&mut self, entries: &[(Expression, Expression)], expected: Option<&Type>) -> (String, Type) {
+        let (key_type, value_type) = match expected.map(Type::innermost_wrapped) {
+            Some(Type::Dictionary(key_type, value_type)) => (Some(&**key_type), Some(&**value_type)),
+            _ => (None, None),
+        };
+        let keys = entries.iter().map(|(key, _)| key).collect::<Vec<_>>();
+        let values = entries.iter().map(|(_, value)| value).collect::<Vec<_>>();
+        let (keys, key_type) = self.visit_elements(&keys, key_type);
+        let (values, value_type) = self.visit_elements(&values, value_type);
+
+        let table = self.emit_hash_table_create(&key_type, Some(&value_type), entries.len());
+        let llvm_type = self.llvm_type(&value_type);
+        for (key, value) in keys.iter().zip(&values) {
+            let entry = self.emit_hash_table_lookup("insert", &table, key, &key_type);
+            let value_ptr = self.emit_entry_ptr(&table, &entry, "value", &value_type);
+            self.emit_retain(value, &value_type);
+            self.emit_line(&format!("store {} {}, {}* {}", llvm_type, value, llvm_type, value_ptr));
+        }
+        // a key inserted twice replaces its entry instead of adding one
+        if entries.len() > 1 {
+            let count = self.emit_hash_table_count(&table);
+            let unique = self.alloc_register();
+            self.emit_line(&format!("{} = icmp eq i64 {}, {}", unique, count, entries.len()));
+            let duplicate_label = self.alloc_label("dictionary.duplicate");
+            let unique_label = self.alloc_label("dictionary.unique");
+            self.emit_line(&format!("br i1 {}, label %{}, label %{}", unique, unique_label, duplicate_label));
+            self.emit_label(&duplicate_label);
+            self.emit_fatal_error("Dictionary literal contains duplicate keys");
+            self.emit_label(&unique_label);
+        }
+        (table, Type::Dictionary(Box::new(key_type), Box::new(value_type)))
+    }
+
+    fn emit_set_literal(&mut self, values: &[String], element_type: Type) -> (String, Type) {
+        let table = self.emit_hash_table_create(&element_type, None, values.len());
+        for value in values {
+            self.emit_hash_table_lookup("insert", &table, value, &element_type);
+        }
+        (table, Type::Set(Box::new(element_type)))
+    }
+
+    /// `dictionary[key] = value` adds or replaces the entry, assigning nil removes it
+    fn visit_dictionary_assignment(&mut self, slot: &str, key_type: &Type, value_type: &Type, key: &Expression, value: &Expression) {
+        let key = self.visit_expression_as(key, key_type);
+        let optional_type = Type::Optional(Box::new(value_type.clone()));
+        let value = self.visit_expression_as(value, &optional_type);
+        let table = self.emit_hash_table_make_unique(slot, Some(value_type));
+
+        let has_value = self.emit_has_value(&value, &optional_type);
+        let store_label = self.alloc_label("dictionary.store");
+        let remove_label = self.alloc_label("dictionary.remove");
+        let end_label = self.alloc_label("dictionary.end");
+        self.emit_line(&format!("br i1 {}, label %{}, label %{}", has_value, store_label, remove_label));
+
+        self.emit_label(&store_label);
+        let entry = self.emit_hash_table_lookup("insert", &table, &key, key_type);
+        let value_ptr = self.emit_entry_ptr(&table, &entry, "value", value_type);
+        let unwrapped = self.emit_unwrapped(&value, &optional_type);
+        self.emit_retain(&unwrapped, value_type);
+        let llvm_type = self.llvm_type(value_type);
+        self.emit_line(&format!("store {} {}, {}* {}", llvm_type, unwrapped, llvm_type, value_ptr));
+        self.emit_branch(&end_label);
+
+        self.emit_label(&remove_label);
+        let entry = self.emit_hash_table_lookup("find", &table, &key, key_type);
+        self.emit_optional_entry(&table, &entry, "value", value_type, true);
+        self.emit_branch(&end_label);
+
+        self.emit_label(&end_label);
+    }
+
+    /// `insert(_:)` and `remove(_:)` on the set stored at `slot`
+    fn visit_set_method(&mut self, slot: &str, element_type: Type, name: &str, arguments: &[Argument]) -> (String, Type) {
+        let element = self.visit_expression_as(&arguments[0].value, &element_type);
+        let table = self.emit_hash_table_make_unique(slot, None);
+        if name == "remove" {
+            let entry = self.emit_hash_table_lookup("find", &table, &element, &element_type);
+            return self.emit_optional_entry(&table, &entry, "key", &element_type, true);
+        }
+
+        // (inserted: Bool, memberAfterInsert: Element)
+        let old_count = self.emit_hash_table_count(&table);
+        let entry = self.emit_hash_table_lookup("insert", &table, &element, &element_type);
+        let new_count = self.emit_hash_table_count(&table);
+        let inserted = self.alloc_register();
+        self.emit_line(&format!("{} = icmp ugt i64 {}, {}", inserted, new_count, old_count));
+        let member = self.emit_entry_load(&table, &entry, "key", &element_type);
+        let result_type = Type::Tuple(vec![
+            TupleElement { label: Some("inserted".to_string()), element_type: Type::Bool },
+            TupleElement { label: Some("memberAfterInsert".to_string()), element_type: element_type.clone() },
+        ]);
+        let llvm_type = self.llvm_type(&result_type);
+        let with_inserted = self.alloc_register();
+        self.emit_line(&format!("{} = insertvalue {} undef, i1 {}, 0", with_inserted, llvm_type, inserted));
+        let result = self.alloc_register();
+        self.emit_line(&format!(
+            "{} = insertvalue {} {}, {} {}, 1",
+            result, llvm_type, with_inserted, self.llvm_type(&element_type), member
+        ));
+        (result, result_type)
+    }
+
+    /// `["a": 1, "b": 2]`, or `[:]` when empty
+    fn emit_write_dictionary(&mut self, value: &str, key_type: &Type, value_type: &Type) {
+        let count = self.emit_hash_table_count(value);
+        let empty = self.alloc_register();
+        self.emit_line(&format!("{} = icmp eq i64 {}, 0", empty, count));
+        let empty_label = self.alloc_label("write.empty");
+        let entries_label = self.alloc_label("write.entries");
+        self.emit_line(&format!("br i1 {}, label %{}, label %{}", empty, empty_label, entries_label));
+        self.emit_label(&empty_label);
+        self.emit_write_literal("[:]");
+        self.emit_line("ret void");
+
+        self.emit_label(&entries_label);
+        self.emit_write_literal("[");
+        self.emit_index_loop(&count, "write.entry", |generator, index, _, _| {
+            generator.emit_write_separator(index);
+            let key = generator.emit_entry_load(value, index, "key", key_type);
+            generator.emit_write(&key, key_type, true);
+            generator.emit_write_literal(": ");
+            let entry_value = generator.emit_entry_load(value, index, "value", value_type);
+            generator.emit_write(&entry_value, value_type, true);
+        });
+        self.emit_write_literal("]");
+        self.emit_line("ret void");
+    }
+
+    /// `[1, 2, 3]` like an array
+    fn emit_write_set(&mut self, value: &str, element_type: &Type) {
+        self.emit_write_literal("[");
+        let count = self.emit_hash_table_count(value);
+        self.emit_index_loop(&count, "write.element", |generator, index, _, _| {
+            generator.emit_write_separator(index);
+            let element = generator.emit_entry_load(value, index, "key", element_type);
+            generator.emit_write(&element, element_type, true);
+        });
+        self.emit_write_literal("]");
+        self.emit_line("ret void");
+    }
+
+    // MARK - closures and higher-order methods
+
+    /// Closures are inlined into the method they are passed to, so every call
+    /// site gets code specialized for its element types and nothing is boxed.
+    /// The body runs in a scope holding the parameters and leaves through
+    /// `closure.exit`, which is where its `return` statements jump to.
+    fn emit_closure_call(&mut self, closure: &Expression, arguments: &[(String, Type)], result_type: Option<&Type>) -> (String, Type) {
+        let Expression::Closure { parameters, return_type, body } = closure else {
+            unreachable!("closure argument {:?}", closure);
+        };
+        // the value of a single expression is discarded where nothing is returned
+        let body = match (result_type, body.as_slice()) {
+            (Some(Type::Void), [Statement::Return(Some(value))]) => vec![Statement::Expression(value.clone())],
+            _ => body.clone(),
+        };
+
+        self.scopes.push(HashMap::new());
+        for (parameter, (value, ty)) in parameters.iter().zip(arguments) {
+            if parameter.name != "_" {
+                self.emit_variable(&parameter.name, value, ty.clone());
+            }
+        }
+        let label = self.alloc_label("closure.exit");
+        self.closure_exits.push(ClosureExit {
+            label: label.clone(),
+            deferred: self.deferred.len(),
+            result_type: return_type.clone().or(result_type.cloned()),
+            result: None,
+            optional_result: std::mem::take(&mut self.optional_result),
+        });
+        let saved_breaks = std::mem::take(&mut self.break_targets);
+        let saved_continues = std::mem::take(&mut self.continue_targets);
+        self.visit_block(&body);
+        self.break_targets = saved_breaks;
+        self.continue_targets = saved_continues;
+        self.emit_branch(&label);
+        self.emit_label(&label);
+        let exit = self.closure_exits.pop().expect("closure exit");
+        self.scopes.pop();
+
+        match exit.result {
+            Some((slot, ty)) => {
+                let llvm_type = self.llvm_type(&ty);
+                let value = self.alloc_register();
+                self.emit_line(&format!("{} = load {}, {}* {}", value, llvm_type, llvm_type, slot));
+                (value, ty)
+            }
+            None => ("zeroinitializer".to_string(), Type::Void),
+        }
+    }
+
+    /// `return` inside an inlined closure; without a result type from the
+    /// context the first `return` decides it, as in the semantic pass
+    fn emit_closure_return(&mut self, value: Option<&Expression>) {
+        let exit = self.closure_exits.last().expect("return inside a closure");
+        let (label, deferred) = (exit.label.clone(), exit.deferred);
+        let result_type = exit.result.as_ref().map(|(_, ty)| ty.clone()).or(exit.result_type.clone());
+        let optional_result = exit.optional_result;
+        let (value, ty) = match (value, result_type) {
+            (Some(value), Some(ty)) => (self.visit_expression_as(value, &ty), ty),
+            (Some(value), None) => match self.visit_expression(value, None) {
+                (value, ty @ Type::Optional(_)) => (value, ty),
+                (value, ty) if optional_result => {
+                    let optional_type = Type::Optional(Box::new(ty));
+                    (self.emit_wrap_some(&value, &optional_type), optional_type)
+                }
+                generated => generated,
+            },
+            (None, _) => ("zeroinitializer".to_string(), Type::Void),
+        };
+        let llvm_type = self.llvm_type(&ty);
+        let slot = match &self.closure_exits.last().expect("return inside a closure").result {
+            Some((slot, _)) => slot.clone(),
+            None => {
+                let slot = self.emit_alloca(&llvm_type);
+                self.closure_exits.last_mut().expect("return inside a closure").result = Some((slot.clone(), ty));
+                slot
+            }
+        };
+        self.emit_line(&format!("store {} {}, {}* {}", llvm_type, value, llvm_type, slot));
+        self.emit_deferred(deferred);
+        self.emit_branch(&label);
+        self.emit_dead_block();
+    }
+
+    // MARK - function values
+
+    /// names of the variables used in `statements`, closures inside them included
+    fn used_variable_names(statements: &[Statement]) -> HashSet<String> {
+        let mut names = HashSet::new();
+        for statement in statements {
+            statement.walk_expressions(&mut |expr| {
+                if let Expression::Variable(name) = expr {
+                    names.insert(name.clone());
+                }
+            });
+        }
+        names
+    }
+
+    /// names of the variables used by the closures in `statements`
+    fn closure_variable_names(statements: &[Statement]) -> HashSet<String> {
+        let mut names = HashSet::new();
+        for statement in statements {
+            statement.walk_expressions(&mut |expr| {
+                if let Expression::Closure { body, .. } = expr {
+                    names.extend(Self::used_variable_names(body));
+                }
+            });
+        }
+        names
+    }
+
+    /// `i64 (i8*, i64, { i8*, i8** }*)*`, the code of a function value, which
+    /// takes the context first and an error slot last, null unless it throws
+    fn function_code_type(&self, parameters: &[Type], result: &Type) -> String {
+        let mut types = vec!["i8*".to_string()];
+        types.extend(parameters.iter().map(|parameter| self.llvm_type(parameter)));
+        types.push(format!("{}*", self.llvm_type(&Type::error())));
+        format!("{} ({})*", self.llvm_type(result), types.join(", "))
+    }
+
+    /// the function value made of `code` and the `context` it is called with
+    fn emit_function_value(&mut self, code: &str, code_type: &str, context: &str) -> String {
+        let code_ptr = self.alloc_register();
+        self.emit_line(&format!("{} = bitcast {} {} to i8*", code_ptr, code_type, code));
+        let with_code = self.alloc_register();
+        self.emit_line(&format!("{} = insertvalue {{ i8*, i8* }} undef, i8* {}, 0", with_code, code_ptr));
+        let value = self.alloc_register();
+        self.emit_line(&format!("{} = insertvalue {{ i8*, i8* }} {}, i8* {}, 1", value, with_code, context));
+        value
+    }
+
+    /// A function used as a value is called through a thunk, which ignores
+    /// the context and passes the error slot on when the function throws.
+    fn emit_function_reference(&mut self, decl: &FunctionDecl) -> (String, Type) {
+        let function_type = decl.function_type();
+        let parameter_types = decl.parameters.iter().map(|parameter| parameter.param_type.clone()).collect::<Vec<_>>();
+        let code_type = self.function_code_type(&parameter_types, &decl.return_type);
+        let name = format!("@\"thunk.{}\"", decl.signature());
+        if self.emitted_helpers.insert(name.clone()) {
+            let error_type = format!("{}*", self.llvm_type(&Type::error()));
+            let return_type = self.llvm_type(&decl.return_type);
+            let mut arguments = parameter_types.iter().enumerate()
+                .map(|(index, ty)| format!("{} %arg{}", self.llvm_type(ty), index))
+                .collect::<Vec<_>>();
+            let header = format!(
+                "define private {} {}(i8* %context, {})",
+                return_type, name,
+                arguments.iter().cloned().chain(std::iter::once(format!("{} %error", error_type))).collect::<Vec<_>>().join(", ")
+            );
+            if decl.throws {
+                arguments.push(format!("{} %error", error_type));
+            }
+            let decl = decl.clone();
+            self.emit_function(&header, |generator| {
+                let (result, _) = generator.emit_call(&Self::function_name(&decl), &decl.return_type, &arguments);
+                generator.emit_line(&format!("ret {} {}", return_type, result));
+            });
+        }
+        (self.emit_function_value(&name, &code_type, "null"), function_type)
+    }
+
+    /// A closure used as a value is emitted as a function of its own. Its
+    /// context is an array of the addresses of the local variables it
+    /// captures: those on the heap are shared with the closure, any other,
+    /// such as a parameter, is copied to the heap when the closure is created.
+    fn emit_closure_value(&mut self, closure: &Expression, expected: Option<&Type>) -> (String, Type) {
+        let Expression::Closure { parameters, body, .. } = closure else {
+            unreachable!("closure expression");
+        };
+        let (parameter_types, result_type) = match expected.map(Type::innermost_wrapped) {
+            Some(Type::Function { parameters, result, .. }) => {
+                (parameters.clone(), Some((**result).clone()).filter(|result| *result != Type::inferred()))
+            }
+            _ => {
+                let parameter_types = parameters.iter()
+                    .map(|parameter| parameter.param_type.clone().expect("parameter types inferred by the semantic pass"))
+                    .collect::<Vec<_>>();
+                (parameter_types, None)
+            }
+        };
+
+        let mut names = Self::used_variable_names(body).into_iter().collect::<Vec<_>>();
+        names.push("self".to_string());
+        names.sort();
+        let captures = names.into_iter()
+            .filter_map(|name| {
+                let variable = self.scopes[1..].iter().rev().find_map(|scope| scope.get(&name))?;
+                Some((name, variable.reg.clone(), variable.ty.clone()))
+            })
+            .collect::<Vec<_>>();
+        let context = if captures.is_empty() {
+            "null".to_string()
+        } else {
+            self.declare("declare i8* @malloc(i64)");
+            let context = self.alloc_register();
+            self.emit_line(&format!("{} = call i8* @malloc(i64 {})", context, 8 * captures.len()));
+            let cells = self.alloc_register();
+            self.emit_line(&format!("{} = bitcast i8* {} to i8**", cells, context));
+            for (index, (_, reg, ty)) in captures.iter().enumerate() {
+                let llvm_type = self.llvm_type(ty);
+                let cell = if self.heap_variables.contains(reg) {
+                    reg.clone()
+                } else {
+                    let value = self.alloc_register();
+                    self.emit_line(&format!("{} = load {}, {}* {}", value, llvm_type, llvm_type, reg));
+                    self.emit_retain(&value, ty);
+                    let cell = self.emit_heap_variable(ty);
+                    self.emit_line(&format!("store {} {}, {}* {}", llvm_type, value, llvm_type, cell));
+                    cell
+                };
+                let address = self.alloc_register();
+                self.emit_line(&format!("{} = bitcast {}* {} to i8*", address, llvm_type, cell));
+                let slot = self.alloc_register();
+                self.emit_line(&format!("{} = getelementptr i8*, i8** {}, i64 {}", slot, cells, index));
+                self.emit_line(&format!("store i8* {}, i8** {}", address, slot));
+            }
+            context
+        };
+
+        let name = format!("@\"{}\"", self.alloc_label("closure."));
+        let closure = closure.clone();
+        let mut signature = None;
+        self.emit_function_with_header(|generator| {
+            let saved_return_type = generator.return_type.take();
+            let saved_breaks = std::mem::take(&mut generator.break_targets);
+            let saved_continues = std::mem::take(&mut generator.continue_targets);
+            let saved_locals = generator.scopes.split_off(1);
+            let saved_closures = std::mem::take(&mut generator.closure_exits);
+            let saved_chains = std::mem::take(&mut generator.optional_chain_targets);
+            let saved_deferred = std::mem::take(&mut generator.deferred);
+            let throw_label = generator.alloc_label("closure.throw");
+            let handlers = vec![ErrorHandler::Closure { label: throw_label.clone() }];
+            let saved_handlers = std::mem::replace(&mut generator.error_handlers, handlers);
+            let Expression::Closure { body, .. } = &closure else {
+                unreachable!("closure expression");
+            };
+            let saved_captured = std::mem::replace(&mut generator.captured_names, Self::closure_variable_names(body));
+            let saved_heap = std::mem::take(&mut generator.heap_variables);
+            let saved_throws = std::mem::take(&mut generator.closure_throws);
+
+            generator.scopes.push(HashMap::new());
+            if !captures.is_empty() {
+                let cells = generator.alloc_register();
+                generator.emit_line(&format!("{} = bitcast i8* %context to i8**", cells));
+                for (index, (name, _, ty)) in captures.iter().enumerate() {
+                    let slot = generator.alloc_register();
+                    generator.emit_line(&format!("{} = getelementptr i8*, i8** {}, i64 {}", slot, cells, index));
+                    let address = generator.alloc_register();
+                    generator.emit_line(&format!("{} = load i8*, i8** {}", address, slot));
+                    let reg = generator.alloc_register();
+                    generator.emit_line(&format!("{} = bitcast i8* {} to {}*", reg, address, generator.llvm_type(ty)));
+                    generator.heap_variables.insert(reg.clone());
+                    generator.declare_variable(name, reg, ty.clone());
+                }
+            }
+            let arguments = parameter_types.iter().enumerate()
+                .map(|(index, ty)| (format!("%arg{}", index), ty.clone()))
+                .collect::<Vec<_>>();
+            let (result, result_type) = generator.emit_closure_call(&closure, &arguments, result_type.as_ref());
+            generator.scopes.pop();
+            let llvm_type = generator.llvm_type(&result_type);
+            generator.emit_line(&format!("ret {} {}", llvm_type, result));
+            generator.emit_label(&throw_label);
+            generator.emit_line(&format!("ret {} zeroinitializer", llvm_type));
+            signature = Some((result_type, generator.closure_throws));
+
+            generator.return_type = saved_return_type;
+            generator.break_targets = saved_breaks;
+            generator.continue_targets = saved_continues;
+            generator.scopes.extend(saved_locals);
+            generator.closure_exits = saved_closures;
+            generator.optional_chain_targets = saved_chains;
+            generator.deferred = saved_deferred;
+            generator.error_handlers = saved_handlers;
+            generator.captured_names = saved_captured;
+            generator.heap_variables = saved_heap;
+            generator.closure_throws = saved_throws;
+
+            let parameters = arguments.iter()
+                .map(|(argument, ty)| format!("{} {}", generator.llvm_type(ty), argument))
+                .collect::<Vec<_>>();
+            format!(
+                "define private {} {}(i8* %context, {}{}* %error)",
+                llvm_type, name,
+                parameters.iter().map(|parameter| format!("{}, ", parameter)).collect::<String>(),
+                generator.llvm_type(&Type::error())
+            )
+        });
+
+        let (result_type, throws) = signature.expect("closure generated");
+        let code_type = self.function_code_type(&parameter_types, &result_type);
+        let value = self.emit_function_value(&name, &code_type, &context);
+        (value, Type::Function { parameters: parameter_types, result: Box::new(result_type), throws })
+    }
+
+    /// call a closure or function value through its code, passing its context first
+    fn emit_function_value_call(&mut self, callee: &Expression, arguments: &[Argument]) -> (String, Type) {
+        let (function, ty) = self.visit_expression(callee, None);
+        let Type::Function { parameters, result, throws } = ty else {
+            unreachable!("call of a value of type '{}'", ty);
+        };
+        let code_ptr = self.alloc_register();
+        self.emit_line(&format!("{} = extractvalue {{ i8*, i8* }} {}, 0", code_ptr, function));
+        let context = self.alloc_register();
+        self.emit_line(&format!("{} = extractvalue {{ i8*, i8* }} {}, 1", context, function));
+        let code_type = self.function_code_type(&parameters, &result);
+        let code = self.alloc_register();
+        self.emit_line(&format!("{} = bitcast i8* {} to {}", code, code_ptr, code_type));
+
+        let mut values = vec![format!("i8* {}", context)];
+        for (argument, parameter) in arguments.iter().zip(&parameters) {
+            let value = self.visit_expression_as(&argument.value, parameter);
+            values.push(format!("{} {}", self.llvm_type(parameter), value));
+        }
+        if throws {
+            return self.emit_throwing_call(&code, &result, &values);
+        }
+        values.push(format!("{}* null", self.llvm_type(&Type::error())));
+        self.emit_call(&code, &result, &values)
+    }
+
+    /// `{ $0 in function($0) }` for a function value passed to a higher-order
+    /// method, which is generated once into a hidden variable
+    fn function_value_closure(&mut self, value: &Expression) -> Expression {
+        let (function, ty) = self.visit_expression(value, None);
+        let Type::Function { parameters, .. } = &ty else {
+            unreachable!("function value of type '{}'", ty);
+        };
+        let name = self.alloc_label("$function");
+        let llvm_type = self.llvm_type(&ty);
+        let slot = self.emit_alloca(&llvm_type);
+        self.emit_line(&format!("store {} {}, {}* {}", llvm_type, function, llvm_type, slot));
+        let parameters = (0..parameters.len())
+            .map(|index| ClosureParameter { name: format!("${}", index), param_type: None })
+            .collect::<Vec<_>>();
+        let arguments = parameters.iter()
+            .map(|parameter| Argument { label: None, value: Expression::Variable(parameter.name.clone()) })
+            .collect();
+        self.declare_variable(&name, slot, ty);
+        Expression::Closure {
+            parameters,
+            return_type: None,
+            body: vec![Statement::Return(Some(Expression::Call {
+                callee: Box::new(Expression::Variable(name)),
+                arguments,
+            }))],
+        }
+    }
+
+    /// `map`, `filter`, `reduce` and the other methods taking a closure. The
+    /// collection is retained first like the sequence of a for-in loop.
+    fn visit_higher_order_method(&mut self, base: &Expression, name: &str, arguments: &[Argument], expected: Option<&Type>) -> (String, Type) {
+        let (collection, ty) = self.visit_expression(base, None);
+        self.emit_retain(&collection, &ty);
+        let element_type = ty.sequence_element().expect("a collection");
+        let count = self.emit_sequence_count(&collection, &ty);
+        // sorted() compares with `<`
+        let default_order = Expression::operator_closure(|left, right| Expression::Binary {
+            left: Box::new(left),
+            operator: BinaryOperator::Less,
+            right: Box::new(right),
+        });
+        let closure = match arguments.last() {
+            Some(argument) if matches!(argument.value, Expression::Closure { .. }) => argument.value.clone(),
+            Some(argument) => self.function_value_closure(&argument.value),
+            None => default_order,
+        };
+        let closure = &closure;
+
+        match name {
+            "map" | "compactMap" => {
+                // the element type of the result is known once the closure is
+                // generated, so the allocation is inserted before the loop afterwards
+                let allocate = self.array_allocate_helper();
+                let array = self.alloc_register();
+                let loop_start = self.output.len();
+                let mut result_type = None;
+                self.emit_element_loop(&collection, &ty, &count, name, |generator, element, element_type, next_label, _| {
+                    generator.optional_result = name == "compactMap";
+                    let (value, value_type) = generator.emit_closure_call(closure, &[(element.to_string(), element_type.clone())], None);
+                    match value_type {
+                        Type::Optional(wrapped) if name == "compactMap" => {
+                            let optional_type = Type::Optional(wrapped.clone());
+                            let has_value = generator.emit_has_value(&value, &optional_type);
+                            let append_label = generator.alloc_label("compactMap.append");
+                            generator.emit_line(&format!("br i1 {}, label %{}, label %{}", has_value, append_label, next_label));
+                            generator.emit_label(&append_label);
+                            let unwrapped = generator.emit_unwrapped(&value, &optional_type);
+                            generator.emit_append_unchecked(&array, &unwrapped, &wrapped);
+                            result_type = Some(*wrapped);
+                        }
+                        value_type => {
+                            generator.emit_append_unchecked(&array, &value, &value_type);
+                            result_type = Some(value_type);
+                        }
+                    }
+                });
+                let result_type = result_type.expect("closure result type");
+                let loop_code = self.output.split_off(loop_start);
+                let size = self.element_size(&result_type);
+                self.emit_line(&format!("{} = call %Array* {}(i64 0, i64 {}, i64 {})", array, allocate, count, size));
+                self.output.push_str(&loop_code);
+                (array, Type::Array(Box::new(result_type)))
+            }
+            "filter" => {
+                let result = match &ty {
+                    Type::Array(_) => {
+                        let allocate = self.array_allocate_helper();
+                        let size = self.element_size(&element_type);
+                        let array = self.alloc_register();
+                        self.emit_line(&format!("{} = call %Array* {}(i64 0, i64 {}, i64 {})", array, allocate, count, size));
+                        array
+                    }
+                    Type::Set(_) => self.emit_hash_table_create(&element_type, None, 0),
+                    Type::Dictionary(key_type, value_type) => {
+                        let (key_type, value_type) = ((**key_type).clone(), (**value_type).clone());
+                        self.emit_hash_table_create(&key_type, Some(&value_type), 0)
+                    }
+                    _ => unreachable!("filter on '{}'", ty),
+                };
+                self.emit_element_loop(&collection, &ty, &count, "filter", |generator, element, element_type, next_label, _| {
+                    let (included, _) = generator.emit_closure_call(closure, &[(element.to_string(), element_type.clone())], Some(&Type::Bool));
+                    let keep_label = generator.alloc_label("filter.keep");
+                    generator.emit_line(&format!("br i1 {}, label %{}, label %{}", included, keep_label, next_label));
+                    generator.emit_label(&keep_label);
+                    match &ty {
+                        Type::Array(_) => generator.emit_append_unchecked(&result, element, element_type),
+                        Type::Set(_) => {
+                            generator.emit_hash_table_lookup("insert", &result, element, element_type);
+                        }
+                        Type::Dictionary(key_type, value_type) => {
+                            let llvm_type = generator.llvm_type(element_type);
+                            let [key, value] = [0, 1].map(|index| {
+                                let part = generator.alloc_register();
+                                generator.emit_line(&format!("{} = extractvalue {} {}, {}", part, llvm_type, element, index));
+                                part
+                            });
+                            let entry = generator.emit_hash_table_lookup("insert", &result, &key, key_type);
+                            let value_ptr = generator.emit_entry_ptr(&result, &entry, "value", value_type);
+                            generator.emit_retain(&value, value_type);
+                            let value_llvm_type = generator.llvm_type(value_type);
+                            generator.emit_line(&format!("store {} {}, {}* {}", value_llvm_type, value, value_llvm_type, value_ptr));
+                        }
+                        _ => unreachable!("filter on '{}'", ty),
+                    }
+                });
+                (result, ty.clone())
+            }
+            "forEach" => {
+                self.emit_element_loop(&collection, &ty, &count, "forEach", |generator, element, element_type, _, _| {
+                    generator.emit_closure_call(closure, &[(element.to_string(), element_type.clone())], Some(&Type::Void));
+                });
+                ("zeroinitializer".to_string(), Type::Void)
+            }
+            "reduce" => {
+                // `reduce(0, +)` over Doubles starts from `0.0`
+                let initial = &arguments[0].value;
+                let hint = if initial.is_number_literal() && element_type.is_numeric() {
+                    Some(&element_type)
+                } else {
+                    expected
+                };
+                let (value, result_type) = self.visit_expression(initial, hint);
+                let llvm_type = self.llvm_type(&result_type);
+                let accumulator = self.emit_alloca(&llvm_type);
+                self.emit_line(&format!("store {} {}, {}* {}", llvm_type, value, llvm_type, accumulator));
+                self.emit_element_loop(&collection, &ty, &count, "reduce", |generator, element, element_type, _, _| {
+                    let current = generator.alloc_register();
+                    generator.emit_line(&format!("{} = load {}, {}* {}", current, llvm_type, llvm_type, accumulator));
+                    let arguments = [(current, result_type.clone()), (element.to_string(), element_type.clone())];
+                    let (next, _) = generator.emit_closure_call(closure, &arguments, Some(&result_type));
+                    generator.emit_line(&format!("store {} {}, {}* {}", llvm_type, next, llvm_type, accumulator));
+                });
+                let result = self.alloc_register();
+                self.emit_line(&format!("{} = load {}, {}* {}", result, llvm_type, llvm_type, accumulator));
+                (result, result_type)
+            }
+            "sorted" => {
+                let allocate = self.array_allocate_helper();
+                let size = self.element_size(&element_type);
+                let array = self.alloc_register();
+                self.emit_line(&format!("{} = call %Array* {}(i64 0, i64 {}, i64 {})", array, allocate, count, size));
+                self.emit_element_loop(&collection, &ty, &count, "sorted.copy", |generator, element, element_type, _, _| {
+                    generator.emit_append_unchecked(&array, element, element_type);
+                });
+                self.emit_insertion_sort(&array, &count, &element_type, closure);
+                (array, Type::Array(Box::new(element_type)))
+            }
+            _ => {
+                // contains(where:) and first(where:) stop at the first match
+                let result_type = match name {
+                    "contains" => Type::Bool,
+                    _ => Type::Optional(Box::new(element_type.clone())),
+                };
+                let llvm_type = self.llvm_type(&result_type);
+                let result_ptr = self.emit_alloca(&llvm_type);
+                self.emit_line(&format!("store {} zeroinitializer, {}* {}", llvm_type, llvm_type, result_ptr));
+                self.emit_element_loop(&collection, &ty, &count, name, |generator, element, element_type, next_label, end_label| {
+                    let (matches, _) = generator.emit_closure_call(closure, &[(element.to_string(), element_type.clone())], Some(&Type::Bool));
+                    let found_label = generator.alloc_label(&format!("{}.found", name));
+                    generator.emit_line(&format!("br i1 {}, label %{}, label %{}", matches, found_label, next_label));
+                    generator.emit_label(&found_label);
+                    let found = match &result_type {
+                        Type::Bool => "true".to_string(),
+                        _ => generator.emit_wrap_some(element, &result_type),
+                    };
+                    generator.emit_line(&format!("store {} {}, {}* {}", llvm_type, found, llvm_type, result_ptr));
+                    generator.emit_branch(end_label);
+                    generator.emit_dead_block();
+                });
+                let result = self.alloc_register();
+                self.emit_line(&format!("{} = load {}, {}* {}", result, llvm_type, llvm_type, result_ptr));
+                (result, result_type)
+            }
+        }
+    }
+
+    /// Stable binary insertion sort of `array` in place: each element is moved
+    /// behind the sorted elements that `are_in_increasing_order` does not put after it.
+    fn emit_insertion_sort(&mut self, array: &str, count: &str, element_type: &Type, are_in_increasing_order: &Expression) {
+        let llvm_type = self.llvm_type(element_type);
+        let unsorted = self.alloc_register();
+        self.emit_line(&format!("{} = sub i64 {}, 1", unsorted, count));
+        self.emit_index_loop(&unsorted, "sort", |generator, index, _, _| {
+            let position = generator.alloc_register();
+            generator.emit_line(&format!("{} = add i64 {}, 1", position, index));
+            let element_ptr = generator.emit_element_ptr(array, element_type, &position);
+            let element = generator.alloc_register();
+            generator.emit_line(&format!("{} = load {}, {}* {}", element, llvm_type, llvm_type, element_ptr));
+
+            // binary search for the first sorted element that goes after `element`
+            let low_ptr = generator.emit_alloca("i64");
+            let high_ptr = generator.emit_alloca("i64");
+            generator.emit_line(&format!("store i64 0, i64* {}", low_ptr));
+            generator.emit_line(&format!("store i64 {}, i64* {}", position, high_ptr));
+            let condition_label = generator.alloc_label("search.cond");
+            let body_label = generator.alloc_label("search.body");
+            let end_label = generator.alloc_label("search.end");
+            generator.emit_branch(&condition_label);
+
+            generator.emit_label(&condition_label);
+            let low = generator.alloc_register();
+            generator.emit_line(&format!("{} = load i64, i64* {}", low, low_ptr));
+            let high = generator.alloc_register();
+            generator.emit_line(&format!("{} = load i64, i64* {}", high, high_ptr));
+            let searching = generator.alloc_register();
+            generator.emit_line(&format!("{} = icmp slt i64 {}, {}", searching, low, high));
+            generator.emit_line(&format!("br i1 {}, label %{}, label %{}", searching, body_label, end_label));
+
+            generator.emit_label(&body_label);
+            let sum = generator.alloc_register();
+            generator.emit_line(&format!("{} = add i64 {}, {}", sum, low, high));
+            let middle = generator.alloc_register();
+            generator.emit_line(&format!("{} = lshr i64 {}, 1", middle, sum));
+            let middle_ptr = generator.emit_element_ptr(array, element_type, &middle);
+            let middle_element = generator.alloc_register();
+            generator.emit_line(&format!("{} = load {}, {}* {}", middle_element, llvm_type, llvm_type, middle_ptr));
+            let arguments = [(element.clone(), element_type.clone()), (middle_element, element_type.clone())];
+            let (goes_before, _) = generator.emit_closure_call(are_in_increasing_order, &arguments, Some(&Type::Bool));
+            let after_middle = generator.alloc_register();
+            generator.emit_line(&format!("{} = add i64 {}, 1", after_middle, middle));
+            let new_low = generator.alloc_register();
+            generator.emit_line(&format!("{} = select i1 {}, i64 {}, i64 {}", new_low, goes_before, low, after_middle));
+            let new_high = generator.alloc_register();
+            generator.emit_line(&format!("{} = select i1 {}, i64 {}, i64 {}", new_high, goes_before, middle, high));
+            generator.emit_line(&format!("store i64 {}, i64* {}", new_low, low_ptr));
+            generator.emit_line(&format!("store i64 {}, i64* {}", new_high, high_ptr));
+            generator.emit_branch(&condition_label);
+
+            generator.emit_label(&end_label);
+            let moved = generator.alloc_register();
+            generator.emit_line(&format!("{} = sub i64 {}, {}", moved, position, low));
+            let destination = generator.alloc_register();
+            generator.emit_line(&format!("{} = add i64 {}, 1", destination, low));
+            generator.emit_move_elements(array, element_type, &low, &destination, &moved);
+            let insertion_ptr = generator.emit_element_ptr(array, element_type, &low);
+            generator.emit_line(&format!("store {} {}, {}* {}", llvm_type, element, llvm_type, insertion_ptr));
+        });
+    }
+
+    // MARK - protocols
+
+    /// Box `value` on the heap next to the witness table of its conformance to `protocol`.
+    /// Enums never change in place, so copies of an existential share the box.
+    fn emit_existential(&mut self, value: &str, ty: &Type, protocol: &str) -> String {
+        self.declare("declare i8* @malloc(i64)");
+        let llvm_type = self.llvm_type(ty);
+        let boxed = self.alloc_register();
+        self.emit_line(&format!("{} = call i8* @malloc(i64 {})", boxed, self.element_size(ty)));
+        let ptr = self.alloc_register();
+        self.emit_line(&format!("{} = bitcast i8* {} to {}*", ptr, boxed, llvm_type));
+        self.emit_line(&format!("store {} {}, {}* {}", llvm_type, value, llvm_type, ptr));
+
+        let table = self.witness_table(ty, protocol);
+        let existential_type = self.llvm_type(&Type::Existential(protocol.to_string()));
+        let with_box = self.alloc_register();
+        self.emit_line(&format!("{} = insertvalue {} undef, i8* {}, 0", with_box, existential_type, boxed));
+        let result = self.alloc_register();
+        self.emit_line(&format!("{} = insertvalue {} {}, i8** {}, 1", result, existential_type, with_box, table));
+        result
+    }
+
+    /// the box and the witness table of an existential
+    fn emit_open_existential(&mut self, value: &str) -> (String, String) {
+        let [boxed, table] = [0, 1].map(|index| {
+            let reg = self.alloc_register();
+            self.emit_line(&format!("{} = extractvalue {{ i8*, i8** }} {}, {}", reg, value, index));
+            reg
+        });
+        (boxed, table)
+    }
+
+    /// load entry `slot` of a witness table as a pointer to a function of `function_type`
+    fn emit_witness(&mut self, table: &str, slot: usize, function_type: &str) -> String {
+        let slot_ptr = self.alloc_register();
+        self.emit_line(&format!("{} = getelementptr inbounds i8*, i8** {}, i64 {}", slot_ptr, table, slot));
+        let entry = self.alloc_register();
+        self.emit_line(&format!("{} = load i8*, i8** {}", entry, slot_ptr));
+        let function = self.alloc_register();
+        self.emit_line(&format!("{} = bitcast i8* {} to {}*", function, entry, function_type));
+        function
+    }
+
+    /// Constant pointer to the witness table of the enum type `ty` for `protocol`, generated on first use.
+    /// Entry 0 writes the boxed value, followed by one thunk per requirement
+    /// that unboxes `self` and calls the enum's method or getter.
+    fn witness_table(&mut self, ty: &Type, protocol: &str) -> String {
+        let enum_name = &ty.to_string();
+        let name = format!("@\"witness.{}.{}\"", enum_name, protocol);
+        let requirements = self.protocols[protocol].requirements.clone();
+        let table = format!(
+            "getelementptr inbounds ([{} x i8*], [{} x i8*]* {}, i32 0, i32 0)",
+            requirements.len() + 1, requirements.len() + 1, name
+        );
+        if !self.emitted_helpers.insert(name.clone()) {
+            return table;
+        }
+        self.emit_enum_specialization(ty);
+
+        let ty = ty.clone();
+        let llvm_type = self.llvm_type(&ty);
+        let unbox = |generator: &mut Self| {
+            let ptr = generator.alloc_register();
+            generator.emit_line(&format!("{} = bitcast i8* %box to {}*", ptr, llvm_type));
+            let value = generator.alloc_register();
+            generator.emit_line(&format!("{} = load {}, {}* {}", value, llvm_type, llvm_type, ptr));
+            value
+        };
+
+        self.declare_string_builder();
+        let write = format!("@\"witness.{}.{}.write\"", enum_name, protocol);
+        let header = format!("define private void {}(i8* %box, i1 %debug, %StringBuilder* %builder)", write);
+        self.emit_function(&header, |generator| {
+            let value = unbox(generator);
+            let has_builder = generator.alloc_register();
+            generator.emit_line(&format!("{} = icmp ne %StringBuilder* %builder, null", has_builder));
+            let [append, print] = ["witness.append", "witness.print"].map(|label| generator.alloc_label(label));
+            generator.emit_line(&format!("br i1 {}, label %{}, label %{}", has_builder, append, print));
+            for (label, builder) in [(append, Some("%builder".to_string())), (print, None)] {
+                generator.emit_label(&label);
+                generator.builder = builder;
+                let [debug_label, plain_label] = ["witness.debug", "witness.plain"].map(|label| generator.alloc_label(label));
+                generator.emit_line(&format!("br i1 %debug, label %{}, label %{}", debug_label, plain_label));
+                for (label, debug) in [(debug_label, true), (plain_label, false)] {
+                    generator.emit_label(&label);
+                    generator.emit_write(&value, &ty, debug);
+                    generator.emit_line("ret void");
+                }
+            }
+        });
+        let mut entries = vec![format!("i8* bitcast (void (i8*, i1, %StringBuilder*)* {} to i8*)", write)];
+
+        let decl = self.enum_decl(&ty).into_owned();
+        for requirement in &requirements {
+            let (witness, target, parameters, return_type) = match requirement {
+                Requirement::Method(method) => match decl.find_method(&method.name, &Self::labels_of(method)) {
+                    Some(method) => (method.signature(), Self::method_name(enum_name, method), method.parameters.clone(), method.return_type.clone()),
+                    // the default implementation takes the existential itself
+                    None => (method.signature(), Self::method_name(protocol, method), method.parameters.clone(), method.return_type.clone()),
+                },
+                Requirement::Property { name, property_type, .. } => match decl.find_property(name) {
+                    Some(_) => (format!("{}.get", name), Self::getter_name(enum_name, name), Vec::new(), property_type.clone()),
+                    None => (format!("{}.get", name), Self::getter_name(protocol, name), Vec::new(), property_type.clone()),
+                },
+            };
+            let is_default = match requirement {
+                Requirement::Method(method) => decl.find_method(&method.name, &Self::labels_of(method)).is_none(),
+                Requirement::Property { name, .. } => decl.find_property(name).is_none(),
+            };
+            // a method that does not throw can meet a requirement that does
+            let (throws, target_throws) = match requirement {
+                Requirement::Method(method) => {
+                    let implementation = decl.find_method(&method.name, &Self::labels_of(method)).cloned().or_else(|| {
+                        self.extensions.iter()
+                            .filter(|extension| extension.extended_type == protocol)
+                            .flat_map(|extension| &extension.methods)
+                            .find(|candidate| candidate.signature() == method.signature())
+                            .cloned()
+                    });
+                    (method.throws, implementation.is_some_and(|implementation| implementation.throws))
+                }
+                Requirement::Property { .. } => (false, false),
+            };
+            let thunk = format!("@\"witness.{}.{}.{}\"", enum_name, protocol, witness);
+            let parameter_types = parameters.iter()
+                .map(|parameter| self.llvm_type(&parameter.param_type))
+                .collect::<Vec<_>>();
+            let return_llvm_type = self.llvm_type(&return_type);
+            let arguments = parameter_types.iter().enumerate()
+                .map(|(index, llvm_type)| format!("{} %arg{}", llvm_type, index))
+                .collect::<Vec<_>>();
+            let error_parameter = format!("{}* %error", self.llvm_type(&Type::error()));
+            let header = format!(
+                "define private {} {}({})",
+                return_llvm_type,
+                thunk,
+                std::iter::once("i8* %box".to_string())
+                    .chain(arguments.iter().cloned())
+                    .chain(throws.then(|| error_parameter.clone()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            self.emit_function(&header, |generator| {
+                let receiver = if is_default {
+                    let existential_type = generator.llvm_type(&Type::Existential(protocol.to_string()));
+                    let with_box = generator.alloc_register();
+                    generator.emit_line(&format!("{} = insertvalue {} undef, i8* %box, 0", with_box, existential_type));
+                    let existential = generator.alloc_register();
+                    generator.emit_line(&format!("{} = insertvalue {} {}, i8** {}, 1", existential, existential_type, with_box, table));
+                    format!("{} {}", existential_type, existential)
+                } else {
+                    format!("{} {}", llvm_type, unbox(generator))
+                };
+                // the error slot is passed on, an error is returned the same way
+                let arguments = std::iter::once(receiver)
+                    .chain(arguments)
+                    .chain(target_throws.then_some(error_parameter))
+                    .collect::<Vec<_>>();
+                let (result, _) = generator.emit_call(&target, &return_type, &arguments);
+                if return_type != Type::Never {
+                    generator.emit_line(&format!("ret {} {}", return_llvm_type, result));
+                }
+            });
+            let parameter_types = parameters.iter().map(|parameter| parameter.param_type.clone()).collect::<Vec<_>>();
+            let function_type = self.witness_type(&return_type, &parameter_types, throws);
+            entries.push(format!("i8* bitcast ({}* {} to i8*)", function_type, thunk));
+        }
+
+        self.globals.push_str(&format!(
+            "{} = private unnamed_addr constant [{} x i8*] [{}]\n",
+            name, entries.len(), entries.join(", ")
+        ));
+        table
+    }
+
+    /// call arguments with a method's labels, used to look the method up by signature
+    fn labels_of(decl: &FunctionDecl) -> Vec<Argument> {
+        decl.parameters.iter()
+            .map(|parameter| Argument { label: parameter.label.clone(), value: Expression::Nil })
+            .collect()
+    }
+
+    /// Extensions that add members to values of `ty`, with the generic
+    /// arguments they are specialized with: those of the protocols an enum
+    /// conforms to, of the protocol of an existential, or of the standard
+    /// library type.
+    fn applicable_extensions(&self, ty: &Type) -> Vec<(ExtensionDecl, Vec<(String, Type)>)> {
+        self.extensions.iter()
+            .filter_map(|decl| {
+                let applies = match ty {
+                    Type::Named(enum_name, _) => self.enums[enum_name].conformances.contains(&decl.extended_type),
+                    Type::Existential(protocol) => decl.extended_type == *protocol,
+                    _ => return decl.bindings(ty).map(|bindings| (decl.clone(), bindings)),
+                };
+                applies.then(|| (decl.clone(), Vec::new()))
+            })
+            .collect()
+    }
+
+    /// a method an extension adds to `ty`, specialized for it
+    fn extension_method(&self, ty: &Type, name: &str, arguments: &[Argument]) -> Option<(ExtensionDecl, FunctionDecl)> {
+        self.applicable_extensions(ty).into_iter().find_map(|(decl, bindings)| {
+            let method = decl.methods.iter().find(|method| method.name == name && method.matches_labels(arguments))?;
+            let method = method.substitute(&bindings);
+            Some((decl, method))
+        })
+    }
+
+    /// the getter of a computed property an extension adds to `ty`, specialized for it
+    fn extension_getter(&self, ty: &Type, name: &str) -> Option<(ExtensionDecl, FunctionDecl)> {
+        self.applicable_extensions(ty).into_iter().find_map(|(decl, bindings)| {
+            let property = decl.properties.iter().find(|property| property.name == name)?;
+            let getter = FunctionDecl { doc_comment: property.doc_comment.clone(), ..property.getter_function() };
+            let getter = getter.substitute(&bindings);
+            Some((decl, getter))
+        })
+    }
+
+    fn has_method(&self, ty: &Type, name: &str, arguments: &[Argument]) -> bool {
+        match ty {
+            Type::Named(..) | Type::Existential(_) => true,
+            _ => self.extension_method(ty, name, arguments).is_some(),
+        }
+    }
+
+    /// whether a bare call of `name` in the function being generated calls a method of `self`
+    fn is_self_method(&self, name: &str, arguments: &[Argument]) -> bool {
+        let Some(variable) = self.lookup("self") else {
+            return false;
+        };
+        let own = match &variable.ty {
+            Type::Named(enum_name, _) => self.enums[enum_name].find_method(name, arguments).is_some(),
+            Type::Existential(protocol) => self.protocols[protocol].find_requirement(name, Some(arguments)).is_some(),
+            _ => false,
+        };
+        own || self.extension_method(&variable.ty, name, arguments).is_some()
+    }
+
+    fn is_self_property(&self, name: &str) -> bool {
+        self.lookup("self").is_some_and(|variable| self.has_property(&variable.ty, name))
+    }
+
+    fn has_property(&self, ty: &Type, name: &str) -> bool {
+        let own = match ty {
+            Type::Named(enum_name, _) => self.enums[enum_name].find_property(name).is_some(),
+            Type::Existential(protocol) => self.protocols[protocol].find_requirement(name, None).is_some(),
+            _ => false,
+        };
+        own || self.extension_getter(ty, name).is_some()
+    }
+
+    /// Call a member of an extension: the members of protocol extensions take
+    /// the existential, members of standard library types are emitted for
+    /// each type they are used with.
+    fn emit_extension_call(
+        &mut self,
+        receiver: &str,
+        receiver_type: &Type,
+        extension: &ExtensionDecl,
+        decl: &FunctionDecl,
+        function: String,
+        arguments: Vec<String>,
+    ) -> (String, Type) {
+        let receiver = match receiver_type {
+            Type::Named(..) => self.emit_existential(receiver, receiver_type, &extension.extended_type),
+            _ => receiver.to_string(),
+        };
+        let self_type = match receiver_type {
+            Type::Named(..) | Type::Existential(_) => Type::Existential(extension.extended_type.clone()),
+            _ => {
+                if self.emitted_helpers.insert(function.clone()) {
+                    self.emit_function_definition(decl, &function, Some(receiver_type));
+                }
+                receiver_type.clone()
+            }
+        };
+        let values = std::iter::once(format!("{} {}", self.llvm_type(&self_type), receiver))
+            .chain(arguments)
+            .collect::<Vec<_>>();
+        self.emit_decl_call(&function, decl, &values)
+    }
+
+    /// the name extension members of `ty` are emitted under
+    fn extension_prefix(ty: &Type, extension: &ExtensionDecl) -> String {
+        match ty {
+            Type::Named(..) | Type::Existential(_) => extension.extended_type.clone(),
+            _ => ty.to_string(),
+        }
+    }
+
+    /// the type of `expr`, found by generating it into a discarded buffer
+    fn peek_type(&mut self, expr: &Expression) -> Type {
+        self.peek_type_as(expr, None)
+    }
+
+    /// the type of `expr` generated for the `expected` type
+    fn peek_type_as(&mut self, expr: &Expression, expected: Option<&Type>) -> Type {
+        let saved_output = std::mem::take(&mut self.output);
+        let saved_allocas = std::mem::take(&mut self.allocas);
+        let (_, ty) = self.visit_expression(expr, expected);
+        self.output = saved_output;
+        self.allocas = saved_allocas;
+        ty
+    }
+
+    /// call the method `name` of an enum value, of the value boxed in an existential,
+    /// or one added by an extension
+    fn emit_method_call(&mut self, receiver: &str, receiver_type: &Type, name: &str, arguments: &[Argument]) -> (String, Type) {
+        match receiver_type {
+            Type::Named(enum_name, _) if self.enums[enum_name].find_method(name, arguments).is_some() => {
+                let decl = self.enum_decl(receiver_type).find_method(name, arguments).cloned().expect("method resolved by the semantic pass");
+                let mut values = vec![format!("{} {}", self.llvm_type(receiver_type), receiver)];
+                values.extend(self.visit_arguments(arguments, &decl));
+                self.emit_decl_call(&Self::method_name(&receiver_type.to_string(), &decl), &decl, &values)
+            }
+            Type::Existential(protocol) if self.protocols[protocol].find_requirement(name, Some(arguments)).is_some() => {
+                let Some((index, Requirement::Method(decl))) =
+                    self.protocols[protocol].find_requirement(name, Some(arguments)).map(|(index, requirement)| (index, requirement.clone()))
+                else {
+                    unreachable!("method resolved by the semantic pass");
+                };
+                let (boxed, table) = self.emit_open_existential(receiver);
+                let mut values = vec![format!("i8* {}", boxed)];
+                values.extend(self.visit_arguments(arguments, &decl));
+                let function_type = self.witness_type(&decl.return_type, &decl.parameters.iter().map(|parameter| parameter.param_type.clone()).collect::<Vec<_>>(), decl.throws);
+                let function = self.emit_witness(&table, index + 1, &function_type);
+                self.emit_decl_call(&function, &decl, &values)
+            }
+            _ => {
+                let (extension, decl) = self.extension_method(receiver_type, name, arguments)
+                    .expect("method resolved by the semantic pass");
+                let function = Self::method_name(&Self::extension_prefix(receiver_type, &extension), &decl);
+                let values = self.visit_arguments(arguments, &decl);
+                self.emit_extension_call(receiver, receiver_type, &extension, &decl, function, values)
+            }
+        }
+    }
+
+    /// read a computed property of an enum value, an existential or one added by an extension
+    fn emit_property(&mut self, receiver: &str, receiver_type: &Type, name: &str) -> (String, Type) {
+        match receiver_type {
+            Type::Named(enum_name, _) if self.enums[enum_name].find_property(name).is_some() => {
+                let property_type = self.enum_decl(receiver_type).find_property(name).expect("a computed property").property_type.clone();
+                let receiver = format!("{} {}", self.llvm_type(receiver_type), receiver);
+                self.emit_call(&Self::getter_name(&receiver_type.to_string(), name), &property_type, &[receiver])
+            }
+            Type::Existential(protocol) if self.protocols[protocol].find_requirement(name, None).is_some() => {
+                let Some((index, Requirement::Property { property_type, .. })) =
+                    self.protocols[protocol].find_requirement(name, None).map(|(index, requirement)| (index, requirement.clone()))
+                else {
+                    unreachable!("property resolved by the semantic pass");
+                };
+                let (boxed, table) = self.emit_open_existential(receiver);
+                let function_type = self.witness_type(&property_type, &[], false);
+                let function = self.emit_witness(&table, index + 1, &function_type);
+                self.emit_call(&function, &property_type, &[format!("i8* {}", boxed)])
+            }
+            _ => {
+                let (extension, getter) = self.extension_getter(receiver_type, name).expect("property resolved by the semantic pass");
+                let function = Self::getter_name(&Self::extension_prefix(receiver_type, &extension), name);
+                self.emit_extension_call(receiver, receiver_type, &extension, &getter, function, Vec::new())
+            }
+        }
+    }
+
+    /// the function type of a witness thunk, which takes the box before the
+    /// parameters and the error slot after them when it throws
+    fn witness_type(&self, return_type: &Type, parameter_types: &[Type], throws: bool) -> String {
+        let parameters = std::iter::once("i8*".to_string())
+            .chain(parameter_types.iter().map(|ty| self.llvm_type(ty)))
+            .chain(throws.then(|| format!("{}*", self.llvm_type(&Type::error()))))
+            .collect::<Vec<_>>();
+        format!("{} ({})", self.llvm_type(return_type), parameters.join(", "))
+    }
+
+    // MARK - runtime errors
+
+    /// print Swift's "Fatal error: ..." message to stderr and trap
+    fn emit_fatal_error(&mut self, message: &str) {
+        let helper = self.fatal_error_helper();
+        let message = self.string_constant(message);
+        self.emit_line(&format!("call void {}({})", helper, message));
+        self.emit_line("unreachable");
+    }
+
+    /// trap with `message` when `failed` is true, otherwise continue in a fresh basic block
+    fn emit_runtime_check(&mut self, failed: &str, message: &str) {
+        let ok_label = self.alloc_label("check.ok");
+        let fail_label = self.alloc_label("check.fail");
+        self.emit_line(&format!("br i1 {}, label %{}, label %{}", failed, fail_label, ok_label));
+
+        self.emit_label(&fail_label);
+        self.emit_fatal_error(message);
+        self.emit_label(&ok_label);
+    }
+
+    /// the function printing a NUL terminated message as a fatal error
+    fn fatal_error_helper(&mut self) -> &'static str {
+        let helper = "@\"fatalError\"";
+        if self.emitted_helpers.insert(helper.to_string()) {
+            self.declare("declare i32 @fflush(i8*)");
+            self.declare("declare i32 @dprintf(i32, i8*, ...)");
+            self.declare("declare void @llvm.trap() noreturn nounwind");
+            let header = format!("define private void {}(i8* %message) noreturn", helper);
+            self.emit_function(&header, |generator| {
+                // flush what print wrote so far before the message
+                let flushed = generator.alloc_register();
+                generator.emit_line(&format!("{} = call i32 @fflush(i8* null)", flushed));
+                let format = generator.string_constant("Fatal error: %s\n");
+                let written = generator.alloc_register();
+                generator.emit_line(&format!(
+                    "{} = call i32 (i32, i8*, ...) @dprintf(i32 2, {}, i8* %message)",
+                    written, format
+                ));
+                generator.emit_line("call void @llvm.trap()");
+                generator.emit_line("unreachable");
+            });
+        }
+        helper
+    }
+
+    /// the fatal error for an error nothing catches, which shows the error like inside a container
+    fn emit_uncaught_error(&mut self, message: &str, error: &str) {
+        self.emit_written_fatal_error(|generator| {
+            generator.emit_write_literal(&format!("{}: ", message));
+            generator.emit_write(error, &Type::error(), true);
+        });
+    }
+
+    /// a fatal error whose message `write` appends to a string builder at runtime
+    fn emit_written_fatal_error(&mut self, write: impl FnOnce(&mut Self)) {
+        self.declare_string_builder();
+        let builder = self.emit_alloca("%StringBuilder");
+        self.emit_line(&format!("store %StringBuilder zeroinitializer, %StringBuilder* {}", builder));
+        let saved_builder = self.builder.replace(builder.clone());
+        write(self);
+        self.builder = saved_builder;
+
+        // the builder's buffer stays NUL terminated after every append
+        let bytes_ptr = self.alloc_register();
+        self.emit_line(&format!("{} = getelementptr %StringBuilder, %StringBuilder* {}, i32 0, i32 0", bytes_ptr, builder));
+        let bytes = self.alloc_register();
+        self.emit_line(&format!("{} = load i8*, i8** {}", bytes, bytes_ptr));
+        let helper = self.fatal_error_helper();
+        self.emit_line(&format!("call void {}(i8* {})", helper, bytes));
+        self.emit_line("unreachable");
+    }
+
+    // MARK - properties
+
+    /// a computed variable or static property, whose accessors are named after `prefix`
+    fn computed_variable(prefix: &str, property: &PropertyDecl) -> Variable {
+        Variable {
+            reg: String::new(),
+            ty: property.property_type.clone(),
+            getter: Some(format!("@\"{}.get\"", prefix)),
+            setter: property.setter.as_ref().map(|_| format!("@\"{}.set\"", prefix)),
+        }
+    }
+
+    /// Static properties can be used before their declaration. Stored ones
+    /// are initialized by their getter on first access, like lazy variables.
+    fn declare_static_properties(&mut self, type_name: &str, properties: &[PropertyDecl], stored: &[StoredPropertyDecl]) {
+        for property in properties {
+            let variable = Self::computed_variable(&format!("static.{}.{}", type_name, property.name), property);
+            self.statics.insert(format!("{}.{}", type_name, property.name), variable);
+        }
+        for property in stored {
+            let prefix = format!("static.{}.{}", type_name, property.name);
+            let ty = property.type_annotation.clone().unwrap_or_else(|| self.peek_type(&property.value));
+            self.statics.insert(format!("{}.{}", type_name, property.name), Variable {
+                reg: format!("@\"{}\"", prefix),
+                ty,
+                getter: Some(format!("@\"{}.get\"", prefix)),
+                setter: property.is_mutable.then(|| format!("@\"{}.set\"", prefix)),
+            });
+        }
+    }
+
+    fn emit_static_properties(&mut self, type_name: &str, properties: &[PropertyDecl], stored: &[StoredPropertyDecl]) {
+        for property in properties {
+            self.emit_computed_property(property, &format!("static.{}.{}", type_name, property.name));
+        }
+        for property in stored {
+            let key = format!("{}.{}", type_name, property.name);
+            let variable = self.statics[&key].clone();
+            // the observers store to the property directly, so assigning it inside them doesn't call them again
+            self.statics.insert(key.clone(), Variable { setter: None, ..variable.clone() });
+            self.emit_stored_property(property, &format!("static.{}", key), &variable.ty, true);
+            self.statics.insert(key, variable);
+        }
+    }
+
+    /// the getter and setter of a computed property of `self_type`
+    fn emit_property_accessors(&mut self, property: &PropertyDecl, type_name: &str, self_type: &Type) {
+        let getter = FunctionDecl { doc_comment: property.doc_comment.clone(), ..property.getter_function() };
+        self.emit_function_definition(&getter, &Self::getter_name(type_name, &property.name), Some(self_type));
+        if let Some(setter) = property.setter_function() {
+            self.emit_function_definition(&setter, &Self::setter_name(type_name, &property.name), Some(self_type));
+        }
+    }
+
+    /// the accessors of a computed variable or static property, named after `prefix`
+    fn emit_computed_property(&mut self, property: &PropertyDecl, prefix: &str) {
+        let getter = FunctionDecl { doc_comment: property.doc_comment.clone(), ..property.getter_function() };
+        self.emit_function_definition(&getter, &format!("@\"{}.get\"", prefix), None);
+        if let Some(setter) = property.setter_function() {
+            self.emit_function_definition(&setter, &format!("@\"{}.set\"", prefix), None);
+        }
+    }
+
+    /// Emit the accessors of a stored property kept in the global `@"<prefix>"`:
+    /// a getter computing a lazy value on first access, the observers, and
+    /// a setter calling them around the store. The storage of a variable that
+    /// is not lazy has been emitted with its initial value.
+    fn emit_stored_property(&mut self, decl: &StoredPropertyDecl, prefix: &str, ty: &Type, is_lazy: bool) -> Variable {
+        let storage = format!("@\"{}\"", prefix);
+        let flag = format!("@\"{}.initialized\"", prefix);
+        let llvm_type = self.llvm_type(ty);
+        let getter = is_lazy.then(|| format!("@\"{}.get\"", prefix));
+        if let Some(getter) = &getter {
+            self.globals.push_str(&format!("{} = internal global {} zeroinitializer\n", storage, llvm_type));
+            self.globals.push_str(&format!("{} = internal global i1 false\n", flag));
+            let header = format!("{}define private {} {}()", Self::doc_comment_lines(&decl.doc_comment), llvm_type, getter);
+            self.emit_function(&header, |generator| {
+                let initialized = generator.alloc_register();
+                generator.emit_line(&format!("{} = load i1, i1* {}", initialized, flag));
+                let init_label = generator.alloc_label("lazy.init");
+                let done_label = generator.alloc_label("lazy.done");
+                generator.emit_line(&format!("br i1 {}, label %{}, label %{}", initialized, done_label, init_label));
+                generator.emit_label(&init_label);
+                let value = generator.visit_expression_as(&decl.value, ty);
+                generator.emit_retain(&value, ty);
+                generator.emit_line(&format!("store {} {}, {}* {}", llvm_type, value, llvm_type, storage));
+                generator.emit_line(&format!("store i1 true, i1* {}", flag));
+                generator.emit_branch(&done_label);
+                generator.emit_label(&done_label);
+                let result = generator.alloc_register();
+                generator.emit_line(&format!("{} = load {}, {}* {}", result, llvm_type, llvm_type, storage));
+                generator.emit_line(&format!("ret {} {}", llvm_type, result));
+            });
+        }
+
+        let mut observers = [None, None];
+        for (index, (observer, kind)) in [(&decl.will_set, "willSet"), (&decl.did_set, "didSet")].into_iter().enumerate() {
+            if let Some(observer) = observer {
+                let function = format!("@\"{}.{}\"", prefix, kind);
+                self.emit_function_definition(&observer.function(&decl.name, ty), &function, None);
+                observers[index] = Some(function);
+            }
+        }
+        let [will_set, did_set] = observers;
+
+        let setter = decl.is_mutable.then(|| format!("@\"{}.set\"", prefix));
+        if let Some(setter) = &setter {
+            let header = format!("define private {{}} {}({} %value)", setter, llvm_type);
+            self.emit_function(&header, |generator| {
+                let value = format!("{} %value", llvm_type);
+                if let Some(will_set) = &will_set {
+                    generator.emit_call(will_set, &Type::Void, std::slice::from_ref(&value));
+                }
+                let old = did_set.as_ref().map(|_| generator.emit_read(&Variable {
+                    reg: storage.clone(),
+                    ty: ty.clone(),
+                    getter: getter.clone(),
+                    setter: None,
+                }));
+                generator.emit_retain("%value", ty);
+                generator.emit_line(&format!("store {}, {}* {}", value, llvm_type, storage));
+                if is_lazy {
+                    generator.emit_line(&format!("store i1 true, i1* {}", flag));
+                }
+                if let (Some(did_set), Some(old)) = (&did_set, old) {
+                    generator.emit_call(did_set, &Type::Void, &[format!("{} {}", llvm_type, old)]);
+                }
+                generator.emit_line("ret {} zeroinitializer");
+            });
+        }
+        Variable { reg: storage, ty: ty.clone(), getter, setter }
+    }
+
+    /// the value of a variable, read through its getter if it has one
+    fn emit_read(&mut self, variable: &Variable) -> String {
+        if let Some(getter) = &variable.getter {
+            return self.emit_call(getter, &variable.ty, &[]).0;
+        }
+        let llvm_type = self.llvm_type(&variable.ty);
+        let value = self.alloc_register();
+        self.emit_line(&format!("{} = load {}, {}* {}", value, llvm_type, llvm_type, variable.reg));
+        value
+    }
+
+    /// the variable or static property `target` names, when it has accessors
+    fn accessor_variable(&self, target: &Expression) -> Option<Variable> {
+        let variable = match target {
+            Expression::Variable(name) => self.lookup(name)?,
+            Expression::Member { base, name } => {
+                let decl = self.enum_named(base)?;
+                self.statics.get(&format!("{}.{}", decl.name, name))?
+            }
+            _ => return None,
+        };
+        (variable.getter.is_some() || variable.setter.is_some()).then(|| variable.clone())
+    }
+
+    /// whether assigning to `target` calls a setter
+    fn has_setter(&mut self, target: &Expression) -> bool {
+        match target {
+            Expression::Variable(name) if self.lookup(name).is_none() => self.has_setter(&Self::self_member(name)),
+            Expression::Member { base, name } if self.enum_named(base).is_none() => {
+                matches!(self.peek_type(base), Type::Named(enum_name, _) if self.enums[&enum_name].find_property(name).is_some())
+            }
+            _ => self.accessor_variable(target).is_some_and(|variable| variable.setter.is_some()),
+        }
+    }
+
+    /// `target = value` through a setter, which is called without reading the current value
+    fn emit_setter_assignment(&mut self, target: &Expression, value: &Expression) {
+        let pending = self.write_backs.len();
+        let (setter, receiver, ty) = match target {
+            Expression::Variable(name) if self.lookup(name).is_none() => {
+                return self.emit_setter_assignment(&Self::self_member(name), value);
+            }
+            Expression::Member { base, name } if self.enum_named(base).is_none() => {
+                let (base_ptr, base_type) = self.emit_lvalue(base);
+                let ty = self.enum_decl(&base_type).find_property(name).expect("a settable property").property_type.clone();
+                let receiver = format!("{}* {}", self.llvm_type(&base_type), base_ptr);
+                (Self::setter_name(&base_type.to_string(), name), Some(receiver), ty)
+            }
+            _ => {
+                let variable = self.accessor_variable(target).expect("a variable with a setter");
+                (variable.setter.expect("checked by has_setter"), None, variable.ty)
+            }
+        };
+        let value = self.visit_expression_as(value, &ty);
+        let arguments = receiver.into_iter()
+            .chain(std::iter::once(format!("{} {}", self.llvm_type(&ty), value)))
+            .collect::<Vec<_>>();
+        self.emit_call(&setter, &Type::Void, &arguments);
+        self.emit_write_backs(pending);
+    }
+
+    /// a copy of `value` that is mutated in place and passed to `setter` by `emit_write_backs`
+    fn emit_write_back_slot(&mut self, value: &str, ty: Type, setter: String, receiver: Option<String>) -> (String, Type) {
+        let llvm_type = self.llvm_type(&ty);
+        let slot = self.emit_alloca(&llvm_type);
+        // the copy's reference makes mutations copy a shared buffer
+        self.emit_retain(value, &ty);
+        self.emit_line(&format!("store {} {}, {}* {}", llvm_type, value, llvm_type, slot));
+        self.write_backs.push(WriteBack { slot: slot.clone(), ty: ty.clone(), setter, receiver });
+        (slot, ty)
+    }
+
+    /// call the setters of the copies made since there were `pending` of them, innermost first
+    fn emit_write_backs(&mut self, pending: usize) {
+        while self.write_backs.len() > pending {
+            let write_back = self.write_backs.pop().expect("a pending write-back");
+            let llvm_type = self.llvm_type(&write_back.ty);
+            let value = self.alloc_register();
+            self.emit_line(&format!("{} = load {}, {}* {}", value, llvm_type, llvm_type, write_back.slot));
+            let arguments = write_back.receiver.into_iter()
+                .chain(std::iter::once(format!("{} {}", llvm_type, value)))
+                .collect::<Vec<_>>();
+            self.emit_call(&write_back.setter, &Type::Void, &arguments);
+        }
+    }
+
+    // MARK - throwing and catching
+
+    /// Pass `error` to the innermost handler, running the bodies deferred
+    /// since it was entered. The block ends here, the caller starts the next one.
+    fn emit_throw(&mut self, error: &str) {
+        let error_llvm_type = self.llvm_type(&Type::error());
+        match self.error_handlers.last().cloned().expect("errors are handled, checked by the semantic pass") {
+            ErrorHandler::Return => {
+                self.emit_deferred(0);
+                self.emit_line(&format!("store {} {}, {}* %error", error_llvm_type, error, error_llvm_type));
+                let return_type = self.return_type.clone().expect("a throwing function");
+                // the result is never looked at when an error is thrown
+                self.emit_line(&format!("ret {} zeroinitializer", self.llvm_type(&return_type)));
+            }
+            ErrorHandler::Catch { slot, label, deferred } => {
+                self.emit_deferred(deferred);
+                self.emit_line(&format!("store {} {}, {}* {}", error_llvm_type, error, error_llvm_type, slot));
+                self.emit_branch(&label);
+            }
+            ErrorHandler::Optional { label, deferred } => {
+                self.emit_deferred(deferred);
+                self.emit_branch(&label);
+            }
+            ErrorHandler::Fatal(message) => self.emit_uncaught_error(message, error),
+            ErrorHandler::Closure { label } => {
+                self.closure_throws = true;
+                self.emit_deferred(0);
+                self.emit_line(&format!("store {} {}, {}* %error", error_llvm_type, error, error_llvm_type));
+                self.emit_branch(&label);
+            }
+        }
+    }
+
+    /// Call a throwing function, which takes the address of an error slot
+    /// after its other arguments. Instead of unwinding, the callee stores a
+    /// thrown error in the slot and returns, so the caller checks the slot
+    /// for a box and passes the error on to its handler.
+    fn emit_throwing_call(&mut self, function: &str, return_type: &Type, arguments: &[String]) -> (String, Type) {
+        let error_llvm_type = self.llvm_type(&Type::error());
+        let slot = self.emit_alloca(&error_llvm_type);
+        self.emit_line(&format!("store {} zeroinitializer, {}* {}", error_llvm_type, error_llvm_type, slot));
+        let arguments = arguments.iter().cloned()
+            .chain(std::iter::once(format!("{}* {}", error_llvm_type, slot)))
+            .collect::<Vec<_>>();
+        let result = self.alloc_register();
+        self.emit_line(&format!(
+            "{} = call {} {}({})",
+            result,
+            self.llvm_type(return_type),
+            function,
+            arguments.join(", ")
+        ));
+
+        let error = self.alloc_register();
+        self.emit_line(&format!("{} = load {}, {}* {}", error, error_llvm_type, error_llvm_type, slot));
+        let (boxed, _) = self.emit_open_existential(&error);
+        let thrown = self.alloc_register();
+        self.emit_line(&format!("{} = icmp ne i8* {}, null", thrown, boxed));
+        let throw_label = self.alloc_label("call.throw");
+        let continue_label = self.alloc_label("call.continue");
+        self.emit_line(&format!("br i1 {}, label %{}, label %{}", thrown, throw_label, continue_label));
+        self.emit_label(&throw_label);
+        self.emit_throw(&error);
+        self.emit_label(&continue_label);
+        if *return_type == Type::Never {
+            self.emit_line("unreachable");
+            self.emit_dead_block();
+        }
+        (result, return_type.clone())
+    }
+
+    /// Call `function`, the code emitted for `decl`, with its error slot when
+    /// it throws. A `rethrows` function called with arguments that do not
+    /// throw gets a null slot, which it never stores to.
+    fn emit_decl_call(&mut self, function: &str, decl: &FunctionDecl, arguments: &[String]) -> (String, Type) {
+        let arguments_throw = std::mem::take(&mut self.arguments_throw);
+        if decl.throws && (arguments_throw || !decl.rethrows) {
+            self.emit_throwing_call(function, &decl.return_type, arguments)
+        } else if decl.rethrows {
+            let arguments = arguments.iter().cloned()
+                .chain(std::iter::once(format!("{}* null", self.llvm_type(&Type::error()))))
+                .collect::<Vec<_>>();
+            self.emit_call(function, &decl.return_type, &arguments)
+        } else {
+            self.emit_call(function, &decl.return_type, arguments)
+        }
+    }
+
+    // MARK - expressions
+
+    /// calls of functions, methods, numeric conversions and enum case constructors
+    fn visit_call_expression(&mut self, callee: &Expression, arguments: &[Argument], expected: Option<&Type>) -> (String, Type) {
+            if let Expression::Variable(name) = callee
+                && self.lookup(name).is_some()
+            {
+                return self.emit_function_value_call(callee, arguments);
+            }
+            if let Expression::Variable(name) = callee
+                && self.lookup(name).is_none()
+                && self.is_self_method(name, arguments)
+            {
+                let (receiver, self_type) = self.visit_expression(&Expression::Variable("self".to_string()), None);
+                return self.emit_method_call(&receiver, &self_type, name, arguments);
+            }
+            if let Expression::Variable(name) = callee
+                && self.lookup(name).is_none()
+                && (name == "fatalError" || self.function_decls.contains_key(name))
+            {
+                return self.visit_call(name, arguments);
+            }
+            // members of values, rather than of enum types, are methods of
+            // enums and protocols or collection methods
+            if let Expression::Member { base, name } = callee
+                && self.enum_named(base).is_none()
+            {
+                let base_type = self.peek_type(base);
+                if self.has_method(&base_type, name, arguments) {
+                    let (receiver, receiver_type) = self.visit_expression(base, None);
+                    return self.emit_method_call(&receiver, &receiver_type, name, arguments);
+                }
+                if is_higher_order_method(name, arguments) {
+                    return self.visit_higher_order_method(base, name, arguments, expected);
+                }
+                return self.visit_collection_method(base, name, arguments);
+            }
+            if let Expression::Variable(name) = callee
+                && self.lookup(name).is_none()
+                && let Some(target) = Type::builtin(name).filter(Type::is_numeric)
+            {
+                // literals take the target type: `UInt8(255)`
+                let (value, ty) = self.visit_expression(&arguments[0].value, Some(&target));
+                return (self.emit_numeric_conversion(&value, &ty, &target), target);
+            }
+            if let Some(decl) = self.enum_named(callee) {
+                let enum_name = decl.name.clone();
+                return self.emit_enum_from_raw_value(&enum_name, &arguments[0].value);
+            }
+            let (ty, case_name) = match callee {
+                Expression::Member { base, name } => {
+                    let decl = self.enum_named(base).expect("enum case constructor").clone();
+                    let expected = Self::specialized_type(base, expected);
+                    (self.enum_case_type(&decl, name, arguments, expected), name)
+                }
+                Expression::ImplicitMember(name) => (Self::contextual_base(expected, name), name),
+                _ => return self.emit_function_value_call(callee, arguments),
+            };
+            self.emit_enum_case(&ty, case_name, arguments)
+    }
+
+    /// generate `expr` and convert it to `ty`, wrapping values into optionals where needed
+    fn visit_expression_as(&mut self, expr: &Expression, ty: &Type) -> String {
+        let (value, actual) = self.visit_expression(expr, Some(ty));
+        self.emit_coerce(&value, &actual, ty)
+    }
+
+    /// mirrors the implicit conversions accepted by the semantic pass
+    fn is_convertible(from: &Type, to: &Type) -> bool {
+        if from == to {
+            return true;
+        }
+        match (from, to) {
+            (Type::Tuple(from_elements), Type::Tuple(to_elements)) => {
+                from_elements.len() == to_elements.len()
+                    && from_elements.iter().zip(to_elements)
+                        .all(|(from, to)| Self::is_convertible(&from.element_type, &to.element_type))
+            }
+            (_, Type::Optional(wrapped)) => Self::is_convertible(from, wrapped),
+            (Type::Named(..), Type::Existential(_)) => true,
+            // a function value that does not throw is called the same way
+            (Type::Function { .. }, Type::Function { .. }) => true,
+            _ => false,
+        }
+    }
+
+    fn emit_coerce(&mut self, value: &str, from: &Type, to: &Type) -> String {
+        if from == to {
+            return value.to_string();
+        }
+        match (from, to) {
+            (Type::Tuple(from_elements), Type::Tuple(to_elements)) => {
+                let from_type = self.llvm_type(from);
+                let to_type = self.llvm_type(to);
+                let mut result = "undef".to_string();
+                for (index, (from_element, to_element)) in from_elements.iter().zip(to_elements).enumerate() {
+                    let element = self.alloc_register();
+                    self.emit_line(&format!("{} = extractvalue {} {}, {}", element, from_type, value, index));
+                    let element = self.emit_coerce(&element, &from_element.element_type, &to_element.element_type);
+                    let inserted = self.alloc_register();
+                    self.emit_line(&format!(
+                        "{} = insertvalue {} {}, {} {}, {}",
+                        inserted, to_type, result, self.llvm_type(&to_element.element_type), element, index
+                    ));
+                    result = inserted;
+                }
+                result
+            }
+            (_, Type::Optional(wrapped)) => {
+                let inner = self.emit_coerce(value, from, wrapped);
+                self.emit_wrap_some(&inner, to)
+            }
+            (Type::Named(..), Type::Existential(protocol)) => {
+                self.emit_existential(value, from, protocol)
+            }
+            (Type::Function { .. }, Type::Function { .. }) => value.to_string(),
+            _ => unreachable!("cannot convert '{}' to '{}'", from, to),
+        }
+    }
+
+    fn emit_wrap_some(&mut self, value: &str, optional_type: &Type) -> String {
+        let Type::Optional(wrapped) = optional_type else {
+            unreachable!("wrapping into non-optional type");
+        };
+        let llvm_type = self.llvm_type(optional_type);
+        let wrapped_type = self.llvm_type(wrapped);
+        let tagged = self.alloc_register();
+        self.emit_line(&format!("{} = insertvalue {} undef, i1 1, 0", tagged, llvm_type));
+        let result = self.alloc_register();
+        self.emit_line(&format!(
+            "{} = insertvalue {} {}, {} {}, 1",
+            result, llvm_type, tagged, wrapped_type, value
+        ));
+        result
+    }
+
+    /// `try? value`, whose errors jump to a block storing nil as the result
+    fn visit_optional_try(&mut self, value: &Expression, expected: Option<&Type>) -> (String, Type) {
+        let nil_label = self.alloc_label("try.nil");
+        let end_label = self.alloc_label("try.end");
+        let expected = match expected {
+            Some(Type::Optional(wrapped)) => Some(wrapped.as_ref()),
+            expected => expected,
+        };
+        self.error_handlers.push(ErrorHandler::Optional { label: nil_label.clone(), deferred: self.deferred.len() });
+        let (value, ty) = self.visit_expression(value, expected);
+        self.error_handlers.pop();
+
+        let (value, result_type) = match ty {
+            Type::Optional(_) => (value, ty),
+            _ => {
+                let result_type = Type::Optional(Box::new(ty));
+                (self.emit_wrap_some(&value, &result_type), result_type)
+            }
+        };
+        let llvm_type = self.llvm_type(&result_type);
+        let slot = self.emit_alloca(&llvm_type);
+        self.emit_line(&format!("store {} {}, {}* {}", llvm_type, value, llvm_type, slot));
+        self.emit_branch(&end_label);
+        self.emit_label(&nil_label);
+        self.emit_line(&format!("store {} zeroinitializer, {}* {}", llvm_type, llvm_type, slot));
+        self.emit_branch(&end_label);
+        self.emit_label(&end_label);
+        let result = self.alloc_register();
+        self.emit_line(&format!("{} = load {}, {}* {}", result, llvm_type, llvm_type, slot));
+        (result, result_type)
+    }
+
+    /// i1 register holding whether an optional value is non-nil
+    fn emit_has_value(&mut self, value: &str, optional_type: &Type) -> String {
+        let llvm_type = self.llvm_type(optional_type);
+        let has_value = self.alloc_register();
+        self.emit_line(&format!("{} = extractvalue {} {}, 0", has_value, llvm_type, value));
+        has_value
+    }
+
+    /// trap like a force unwrap of nil unless `has_value`
+    fn emit_nil_check(&mut self, has_value: &str) {
+        let nil_label = self.alloc_label("unwrap.nil");
+        self.emit_match_branch(has_value, &nil_label);
+        let continue_label = self.alloc_label("unwrap.ok");
+        self.emit_branch(&continue_label);
+
+        self.emit_label(&nil_label);
+        self.emit_fatal_error("Unexpectedly found nil while unwrapping an Optional value");
+        self.emit_label(&continue_label);
+    }
+
+    fn emit_unwrapped(&mut self, value: &str, optional_type: &Type) -> String {
+        let llvm_type = self.llvm_type(optional_type);
+        let unwrapped = self.alloc_register();
+        self.emit_line(&format!("{} = extractvalue {} {}, 1", unwrapped, llvm_type, value));
+        unwrapped
+    }
+
+    /// `==` for numbers, Bool, String, payload-free enums and optionals or tuples of those
+    fn emit_equal(&mut self, left: &str, right: &str, ty: &Type) -> String {
+        if *ty == Type::String {
+            return self.emit_string_equal(left, right);
+        }
+        let result = self.alloc_register();
+        match ty {
+            Type::Int | Type::Int8 | Type::Int16 | Type::Int32 | Type::Int64
+            | Type::UInt | Type::UInt8 | Type::UInt16 | Type::UInt32 | Type::UInt64
+            | Type::Bool => {
+                let llvm_type = self.llvm_type(ty);
+                self.emit_line(&format!("{} = icmp eq {} {}, {}", result, llvm_type, left, right));
+            }
+            Type::Double | Type::Float => {
+                let llvm_type = self.llvm_type(ty);
+                self.emit_line(&format!("{} = fcmp oeq {} {}, {}", result, llvm_type, left, right));
+            }
+            Type::Named(..) => {
+                let llvm_type = self.llvm_type(ty);
+                let left_tag = self.alloc_register();
+                self.emit_line(&format!("{} = extractvalue {} {}, 0", left_tag, llvm_type, left));
+                let right_tag = self.alloc_register();
+                self.emit_line(&format!("{} = extractvalue {} {}, 0", right_tag, llvm_type, right));
+                self.emit_line(&format!("{} = icmp eq i32 {}, {}", result, left_tag, right_tag));
+            }
+            Type::Optional(wrapped) => {
+                // equal when both are nil, or both hold equal values
+                let left_has_value = self.emit_has_value(left, ty);
+                let right_has_value = self.emit_has_value(right, ty);
+                let left_value = self.emit_unwrapped(left, ty);
+                let right_value = self.emit_unwrapped(right, ty);
+                let values_equal = self.emit_equal(&left_value, &right_value, wrapped);
+                let tags_equal = self.alloc_register();
+                self.emit_line(&format!("{} = icmp eq i1 {}, {}", tags_equal, left_has_value, right_has_value));
+                let both = self.alloc_register();
+                self.emit_line(&format!("{} = and i1 {}, {}", both, left_has_value, right_has_value));
+                self.emit_line(&format!(
+                    "{} = select i1 {}, i1 {}, i1 {}",
+                    result, both, values_equal, tags_equal
+                ));
+            }
+            Type::Tuple(elements) => {
+                // equal when every element is equal
+                let llvm_type = self.llvm_type(ty);
+                let mut all_equal = "true".to_string();
+                for (index, element) in elements.iter().enumerate() {
+                    let left_element = self.alloc_register();
+                    self.emit_line(&format!("{} = extractvalue {} {}, {}", left_element, llvm_type, left, index));
+                    let right_element = self.alloc_register();
+                    self.emit_line(&format!("{} = extractvalue {} {}, {}", right_element, llvm_type, right, index));
+                    let element_equal = self.emit_equal(&left_element, &right_element, &element.element_type);
+                    let combined = self.alloc_register();
+                    self.emit_line(&format!("{} = and i1 {}, {}", combined, all_equal, element_equal));
+                    all_equal = combined;
+                }
+                self.emit_line(&format!("{} = and i1 {}, true", result, all_equal));
+            }
+            Type::String => unreachable!("handled above"),
+            Type::Void | Type::Never | Type::Array(_) | Type::Dictionary(..) | Type::Set(_) | Type::Existential(_)
+            | Type::Function { .. } | Type::Generic(_) | Type::Opaque(_) => {
+                unreachable!("the semantic pass rejects == on {}", ty)
+            }
+        }
+        result
+    }
+
+    fn visit_binary(
+        &mut self,
+        left: &Expression,
+        operator: &BinaryOperator,
+        right: &Expression,
+        expected: Option<&Type>,
+    ) -> (String, Type) {
+        match operator {
+            BinaryOperator::And | BinaryOperator::Or => {
+                // short circuit: the right side only runs when it decides the result
+                let result_ptr = self.emit_alloca("i1");
+                let (left_reg, _) = self.visit_expression(left, Some(&Type::Bool));
+                self.emit_line(&format!("store i1 {}, i1* {}", left_reg, result_ptr));
+                let right_label = self.alloc_label("logical.rhs");
+                let end_label = self.alloc_label("logical.end");
+                let (on_true, on_false) = if *operator == BinaryOperator::And {
+                    (&right_label, &end_label)
+                } else {
+                    (&end_label, &right_label)
+                };
+                self.emit_line(&format!("br i1 {}, label %{}, label %{}", left_reg, on_true, on_false));
+
+                self.emit_label(&right_label);
+                let (right_reg, _) = self.visit_expression(right, Some(&Type::Bool));
+                self.emit_line(&format!("store i1 {}, i1* {}", right_reg, result_ptr));
+                self.emit_branch(&end_label);
+
+                self.emit_label(&end_label);
+                let result = self.alloc_register();
+                self.emit_line(&format!("{} = load i1, i1* {}", result, result_ptr));
+                (result, Type::Bool)
+            }
+            BinaryOperator::NilCoalescing => self.visit_nil_coalescing(left, right),
+            BinaryOperator::Equal | BinaryOperator::NotEqual
+                if *left == Expression::Nil || *right == Expression::Nil =>
+            {
+                let other = if *left == Expression::Nil { right } else { left };
+                let (value, ty) = self.visit_expression(other, None);
+                let has_value = self.emit_has_value(&value, &ty);
+                let result = self.alloc_register();
+                let expected = if *operator == BinaryOperator::Equal { 0 } else { 1 };
+                self.emit_line(&format!("{} = icmp eq i1 {}, {}", result, has_value, expected));
+                (result, Type::Bool)
+            }
+            BinaryOperator::Equal | BinaryOperator::NotEqual => {
+                let (left_reg, left_type) = self.visit_expression(left, None);
+                let (right_reg, right_type) = self.visit_expression(right, Some(&left_type));
+                let ty = if Self::is_convertible(&right_type, &left_type) { left_type.clone() } else { right_type.clone() };
+                let left_reg = self.emit_coerce(&left_reg, &left_type, &ty);
+                let right_reg = self.emit_coerce(&right_reg, &right_type, &ty);
+                let equal = self.emit_equal(&left_reg, &right_reg, &ty);
+                if *operator == BinaryOperator::Equal {
+                    return (equal, Type::Bool);
+                }
+                let result = self.alloc_register();
+                self.emit_line(&format!("{} = xor i1 {}, true", result, equal));
+                (result, Type::Bool)
+            }
+            _ if operator.is_shift() => {
+                let expected = expected.map(Type::innermost_wrapped).filter(|ty| ty.is_integer());
+                let (value, ty) = self.visit_expression(left, expected);
+                let (amount, amount_type) = self.visit_expression(right, None);
+                (self.emit_shift(&value, &ty, &amount, &amount_type, operator), ty)
+            }
+            _ => {
+                // operand types are inferred like the semantic pass does
+                let expected = expected
+                    .map(Type::innermost_wrapped)
+                    .filter(|ty| ty.is_numeric() && !operator.is_comparison());
+                let (mut left_reg, mut left_type) = self.visit_expression(left, expected);
+                let (right_reg, right_type) = self.visit_expression(right, Some(&left_type));
+                if left.is_number_literal() && right_type.is_numeric() && left_type != right_type {
+                    // literals are constants, so evaluating again emits no code
+                    (left_reg, left_type) = self.visit_expression(left, Some(&right_type));
+                }
+                self.emit_arithmetic(&left_reg, &left_type, operator, &right_reg)
+            }
+        }
+    }
+
+    /// Swift traps on a zero divisor and on `min / -1`, whose result doesn't fit
+    /// in the type, where LLVM's `sdiv` and `srem` would be undefined.
+    fn emit_division_check(&mut self, left_reg: &str, ty: &Type, operator: &BinaryOperator, right_reg: &str) {
+        let suffix = if *operator == BinaryOperator::Remainder { " in remainder operation" } else { "" };
+        let llvm_type = self.llvm_type(ty);
+        let is_zero = self.alloc_register();
+        self.emit_line(&format!("{} = icmp eq {} {}, 0", is_zero, llvm_type, right_reg));
+        self.emit_runtime_check(&is_zero, &format!("Division by zero{}", suffix));
+        if ty.is_unsigned_integer() {
+            return;
+        }
+        let (min, _) = ty.integer_range().expect("integer division");
+        let is_min = self.alloc_register();
+        self.emit_line(&format!("{} = icmp eq {} {}, {}", is_min, llvm_type, left_reg, min));
+        let is_minus_one = self.alloc_register();
+        self.emit_line(&format!("{} = icmp eq {} {}, -1", is_minus_one, llvm_type, right_reg));
+        let overflows = self.alloc_register();
+        self.emit_line(&format!("{} = and i1 {}, {}", overflows, is_min, is_minus_one));
+        self.emit_runtime_check(&overflows, &format!("Division results in an overflow{}", suffix));
+    }
+
+    /// Integer `+`, `-` and `*` trap when the result overflows the type, through
+    /// LLVM's `with.overflow` intrinsics. Like Swift, the trap prints no message.
+    fn emit_checked_arithmetic(&mut self, left_reg: &str, ty: &Type, operator: &BinaryOperator, right_reg: &str) -> String {
+        let llvm_type = self.llvm_type(ty);
+        let sign = if ty.is_unsigned_integer() { "u" } else { "s" };
+        let operation = match operator {
+            BinaryOperator::Add => "add",
+            BinaryOperator::Subtract => "sub",
+            _ => "mul",
+        };
+        let intrinsic = format!("@llvm.{}{}.with.overflow.{}", sign, operation, llvm_type);
+        self.declare(&format!(
+            "declare {{ {}, i1 }} {}({}, {})",
+            llvm_type, intrinsic, llvm_type, llvm_type
+        ));
+        let pair = self.alloc_register();
+        self.emit_line(&format!(
+            "{} = call {{ {}, i1 }} {}({} {}, {} {})",
+            pair, llvm_type, intrinsic, llvm_type, left_reg, llvm_type, right_reg
+        ));
+        let overflowed = self.alloc_register();
+        self.emit_line(&format!("{} = extractvalue {{ {}, i1 }} {}, 1", overflowed, llvm_type, pair));
+        let ok_label = self.alloc_label("overflow.ok");
+        let trap_label = self.alloc_label("overflow.trap");
+        self.emit_line(&format!("br i1 {}, label %{}, label %{}", overflowed, trap_label, ok_label));
+
+        self.emit_label(&trap_label);
+        self.declare("declare void @llvm.trap() noreturn nounwind");
+        self.emit_line("call void @llvm.trap()");
+        self.emit_line("unreachable");
+
+        self.emit_label(&ok_label);
+        let result = self.alloc_register();
+        self.emit_line(&format!("{} = extractvalue {{ {}, i1 }} {}, 0", result, llvm_type, pair));
+        result
+    }
+
+    /// `left <operator> right` for two numbers of type `ty`, or `+` and the comparisons for strings
+    fn emit_arithmetic(&mut self, left_reg: &str, ty: &Type, operator: &BinaryOperator, right_reg: &str) -> (String, Type) {
+        if *ty == Type::String && operator.is_comparison() {
+            let order = self.emit_string_compare(left_reg, right_reg);
+            let condition = match operator {
+                BinaryOperator::Less => "slt",
+                BinaryOperator::LessEqual => "sle",
+                BinaryOperator::Greater => "sgt",
+                BinaryOperator::GreaterEqual => "sge",
+                _ => unreachable!("strings are equal through emit_equal"),
+            };
+            let result = self.alloc_register();
+            self.emit_line(&format!("{} = icmp {} i32 {}, 0", result, condition, order));
+            return (result, Type::Bool);
+        }
+        if *ty == Type::String {
+            return (self.emit_string_concat(left_reg, right_reg), Type::String);
+        }
+
+        if matches!(operator, BinaryOperator::Add | BinaryOperator::Subtract | BinaryOperator::Multiply) && ty.is_integer() {
+            return (self.emit_checked_arithmetic(left_reg, ty, operator, right_reg), ty.clone());
+        }
+
+        let result_reg = self.alloc_register();
+        let is_float = ty.is_floating_point();
+        let is_unsigned = ty.is_unsigned_integer();
+        if matches!(operator, BinaryOperator::Divide | BinaryOperator::Remainder) && !is_float {
+            self.emit_division_check(left_reg, ty, operator, right_reg);
+        }
+
+        let (op_instruction, result_type) = match operator {
+            BinaryOperator::Add => (if is_float { "fadd" } else { "add" }, ty.clone()),
+            BinaryOperator::Subtract => (if is_float { "fsub" } else { "sub" }, ty.clone()),
+            BinaryOperator::Multiply => (if is_float { "fmul" } else { "mul" }, ty.clone()),
+            BinaryOperator::Divide => (if is_float { "fdiv" } else if is_unsigned { "udiv" } else { "sdiv" }, ty.clone()),
+            BinaryOperator::Remainder => (if is_unsigned { "urem" } else { "srem" }, ty.clone()),
+            BinaryOperator::BitwiseAnd => ("and", ty.clone()),
+            BinaryOperator::BitwiseOr => ("or", ty.clone()),
+            BinaryOperator::BitwiseXor => ("xor", ty.clone()),
+            BinaryOperator::Less => (if is_float { "fcmp olt" } else if is_unsigned { "icmp ult" } else { "icmp slt" }, Type::Bool),
+            BinaryOperator::LessEqual => (if is_float { "fcmp ole" } else if is_unsigned { "icmp ule" } else { "icmp sle" }, Type::Bool),
+            BinaryOperator::Greater => (if is_float { "fcmp ogt" } else if is_unsigned { "icmp ugt" } else { "icmp sgt" }, Type::Bool),
+            BinaryOperator::GreaterEqual => (if is_float { "fcmp oge" } else if is_unsigned { "icmp uge" } else { "icmp sge" }, Type::Bool),
+            _ => unreachable!("'{}' is not an arithmetic operator", operator.symbol()),
+        };
+
+        let llvm_type = self.llvm_type(ty);
+        self.emit_line(&format!(
+            "{} = {} {} {}, {}",
+            result_reg, op_instruction, llvm_type, left_reg, right_reg
+        ));
+
+        (result_reg, result_type)
+    }
+
+    /// Swift's shifts: `&<<` and `&>>` mask the amount to the bit width, while
+    /// `<<` and `>>` shift the other way for negative amounts and produce 0
+    /// (or -1 when shifting a negative value right) once all bits are gone
+    fn emit_shift(&mut self, value: &str, ty: &Type, amount: &str, amount_type: &Type, operator: &BinaryOperator) -> String {
+        let llvm_type = self.llvm_type(ty);
+        let bits = ty.integer_bits().expect("integer shift");
+        let right_shift = if ty.is_unsigned_integer() { "lshr" } else { "ashr" };
+        let extend = if amount_type.is_unsigned_integer() { "zext" } else { "sext" };
+
+        if matches!(operator, BinaryOperator::MaskingShiftLeft | BinaryOperator::MaskingShiftRight) {
+            let amount_bits = amount_type.integer_bits().expect("integer shift amount");
+            let amount_llvm_type = self.llvm_type(amount_type);
+            let count = if amount_bits == bits {
+                amount.to_string()
+            } else {
+                let count = self.alloc_register();
+                let conversion = if amount_bits > bits { "trunc" } else { extend };
+                self.emit_line(&format!("{} = {} {} {} to {}", count, conversion, amount_llvm_type, amount, llvm_type));
+                count
+            };
+            let masked = self.alloc_register();
+            self.emit_line(&format!("{} = and {} {}, {}", masked, llvm_type, count, bits - 1));
+            let instruction = if *operator == BinaryOperator::MaskingShiftLeft { "shl" } else { right_shift };
+            let result = self.alloc_register();
+            self.emit_line(&format!("{} = {} {} {}, {}", result, instruction, llvm_type, value, masked));
+            return result;
+        }
+
+        // the amount is widened so that every integer type can be negated
+        let mut wide = self.alloc_register();
+        self.emit_line(&format!("{} = {} {} {} to i128", wide, extend, self.llvm_type(amount_type), amount));
+        if *operator == BinaryOperator::ShiftRight {
+            let negated = self.alloc_register();
+            self.emit_line(&format!("{} = sub i128 0, {}", negated, wide));
+            wide = negated;
+        }
+        let is_right = self.alloc_register();
+        self.emit_line(&format!("{} = icmp slt i128 {}, 0", is_right, wide));
+        let negated = self.alloc_register();
+        self.emit_line(&format!("{} = sub i128 0, {}", negated, wide));
+        let magnitude = self.alloc_register();
+        self.emit_line(&format!("{} = select i1 {}, i128 {}, i128 {}", magnitude, is_right, negated, wide));
+        let overshift = self.alloc_register();
+        self.emit_line(&format!("{} = icmp uge i128 {}, {}", overshift, magnitude, bits));
+        // shifting right by `bits - 1` already fills every bit with the sign
+        let clamped = self.alloc_register();
+        self.emit_line(&format!("{} = select i1 {}, i128 {}, i128 {}", clamped, overshift, bits - 1, magnitude));
+        let count = self.alloc_register();
+        self.emit_line(&format!("{} = trunc i128 {} to {}", count, clamped, llvm_type));
+
+        let shifted_left = self.alloc_register();
+        self.emit_line(&format!("{} = shl {} {}, {}", shifted_left, llvm_type, value, count));
+        let left_result = self.alloc_register();
+        self.emit_line(&format!("{} = select i1 {}, {} 0, {} {}", left_result, overshift, llvm_type, llvm_type, shifted_left));
+        let shifted_right = self.alloc_register();
+        self.emit_line(&format!("{} = {} {} {}, {}", shifted_right, right_shift, llvm_type, value, count));
+        let right_result = if ty.is_unsigned_integer() {
+            let result = self.alloc_register();
+            self.emit_line(&format!("{} = select i1 {}, {} 0, {} {}", result, overshift, llvm_type, llvm_type, shifted_right));
+            result
+        } else {
+            shifted_right
+        };
+        let result = self.alloc_register();
+        self.emit_line(&format!(
+            "{} = select i1 {}, {} {}, {} {}",
+            result, is_right, llvm_type, right_result, llvm_type, left_result
+        ));
+        result
+    }
+
+    /// address of an assignment target: a variable or a tuple element inside one
+    fn emit_lvalue(&mut self, target: &Expression) -> (String, Type) {
+        // a variable with accessors is mutated in a copy that is written back later
+        if let Some(variable) = self.accessor_variable(target) {
+            let value = self.emit_read(&variable);
+            let setter = variable.setter.expect("assignable variables with accessors have a setter");
+            return self.emit_write_back_slot(&value, variable.ty, setter, None);
+        }
+        match target {
+            Expression::Variable(name) => match self.lookup(name) {
+                Some(variable) => (variable.reg.clone(), variable.ty.clone()),
+                // a property of `self`
+                None => self.emit_lvalue(&Self::self_member(name)),
+            },
+            Expression::Member { base, name } => {
+                let (base_ptr, base_type) = self.emit_lvalue(base);
+                // a computed property, mutated in a copy that is passed to its setter
+                if let Type::Named(..) = &base_type {
+                    let llvm_type = self.llvm_type(&base_type);
+                    let base_value = self.alloc_register();
+                    self.emit_line(&format!("{} = load {}, {}* {}", base_value, llvm_type, llvm_type, base_ptr));
+                    let (value, ty) = self.emit_property(&base_value, &base_type, name);
+                    let receiver = format!("{}* {}", llvm_type, base_ptr);
+                    return self.emit_write_back_slot(&value, ty, Self::setter_name(&base_type.to_string(), name), Some(receiver));
+                }
+                let Some((index, element_type)) = base_type.tuple_element(name) else {
+                    unreachable!("assignment to member '{}' of '{}'", name, base_type);
+                };
+                let llvm_type = self.llvm_type(&base_type);
+                let ptr = self.alloc_register();
+                self.emit_line(&format!(
+                    "{} = getelementptr inbounds {}, {}* {}, i32 0, i32 {}",
+                    ptr, llvm_type, llvm_type, base_ptr, index
+                ));
+                (ptr, element_type.clone())
+            }
+            Expression::Subscript { base, index } => {
+                let (base_ptr, base_type) = self.emit_lvalue(base);
+                self.emit_subscript_lvalue(&base_ptr, base_type, index)
+            }
+            // `dictionary[key]!` is the value stored in the table, `optional!` the payload
+            Expression::ForceUnwrap(operand) => {
+                let (ptr, ty) = match operand.as_ref() {
+                    Expression::Subscript { base, index } => {
+                        let (base_ptr, base_type) = self.emit_lvalue(base);
+                        if let Type::Dictionary(key_type, value_type) = base_type {
+                            let key = self.visit_expression_as(index, &key_type);
+                            let table = self.emit_hash_table_make_unique(&base_ptr, Some(&value_type));
+                            let entry = self.emit_hash_table_lookup("find", &table, &key, &key_type);
+                            let found = self.alloc_register();
+                            self.emit_line(&format!("{} = icmp sge i64 {}, 0", found, entry));
+                            self.emit_nil_check(&found);
+                            return (self.emit_entry_ptr(&table, &entry, "value", &value_type), *value_type);
+                        }
+                        self.emit_subscript_lvalue(&base_ptr, base_type, index)
+                    }
+                    _ => self.emit_lvalue(operand),
+                };
+                let Type::Optional(wrapped) = &ty else {
+                    unreachable!("force unwrap of non-optional value");
+                };
+                let llvm_type = self.llvm_type(&ty);
+                let value = self.alloc_register();
+                self.emit_line(&format!("{} = load {}, {}* {}", value, llvm_type, llvm_type, ptr));
+                let has_value = self.emit_has_value(&value, &ty);
+                self.emit_nil_check(&has_value);
+                let payload = self.alloc_register();
+                self.emit_line(&format!(
+                    "{} = getelementptr inbounds {}, {}* {}, i32 0, i32 1",
+                    payload, llvm_type, llvm_type, ptr
+                ));
+                (payload, (**wrapped).clone())
+            }
+            _ => unreachable!("checked by the semantic pass"),
+        }
+    }
+
+    /// address of an array element, giving the array stored at `base_ptr` a buffer of its own
+    fn emit_subscript_lvalue(&mut self, base_ptr: &str, base_type: Type, index: &Expression) -> (String, Type) {
+        let Type::Array(element_type) = base_type else {
+            unreachable!("subscript of '{}'", base_type);
+        };
+        let index = self.visit_expression_as(index, &Type::Int);
+        let array = self.emit_make_unique(base_ptr, &element_type);
+        self.emit_index_check(&array, &index, "icmp ult", "Index out of range");
+        (self.emit_element_ptr(&array, &element_type, &index), *element_type)
+    }
+
+    /// `condition ? then_value : else_value`; the result type is only known once
+    /// both branches are generated, so each branch converts its value in a
+    /// block of its own that is emitted afterwards
+    fn visit_ternary(
+        &mut self,
+        condition: &Expression,
+        then_value: &Expression,
+        else_value: &Expression,
+        expected: Option<&Type>,
+    ) -> (String, Type) {
+        // `flag ? value : nil` is an optional of the type of the value
+        if expected.is_none()
+            && let Some(value) = Expression::non_nil_branch(then_value, else_value)
+        {
+            let ty = Type::Optional(Box::new(self.peek_type(value)));
+            return self.visit_ternary(condition, then_value, else_value, Some(&ty));
+        }
+        let (condition_reg, _) = self.visit_expression(condition, Some(&Type::Bool));
+        let then_label = self.alloc_label("ternary.then");
+        let else_label = self.alloc_label("ternary.else");
+        let then_end_label = self.alloc_label("ternary.then.end");
+        let else_end_label = self.alloc_label("ternary.else.end");
+        let end_label = self.alloc_label("ternary.end");
+        self.emit_line(&format!("br i1 {}, label %{}, label %{}", condition_reg, then_label, else_label));
+
+        self.emit_label(&then_label);
+        let (mut then_reg, mut then_type) = self.visit_expression(then_value, expected);
+        self.emit_branch(&then_end_label);
+        self.emit_label(&else_label);
+        let (else_reg, else_type) = self.visit_expression(else_value, expected.or(Some(&then_type)));
+        self.emit_branch(&else_end_label);
+
+        if then_value.is_number_literal() && else_type.is_numeric() && then_type != else_type {
+            // literals are constants, so evaluating again emits no code
+            (then_reg, then_type) = self.visit_expression(then_value, Some(&else_type));
+        }
+        let result_type = if Self::is_convertible(&else_type, &then_type) { then_type.clone() } else { else_type.clone() };
+        let llvm_type = self.llvm_type(&result_type);
+        let result_ptr = self.emit_alloca(&llvm_type);
+        for (label, value, ty) in [(&then_end_label, &then_reg, &then_type), (&else_end_label, &else_reg, &else_type)] {
+            self.emit_label(label);
+            let value = self.emit_coerce(value, ty, &result_type);
+            self.emit_line(&format!("store {} {}, {}* {}", llvm_type, value, llvm_type, result_ptr));
+            self.emit_branch(&end_label);
+        }
+
+        self.emit_label(&end_label);
+        let result = self.alloc_register();
+        self.emit_line(&format!("{} = load {}, {}* {}", result, llvm_type, llvm_type, result_ptr));
+        (result, result_type)
+    }
+
+    /// `optional ?? fallback`, the fallback is only evaluated when the optional is nil
+    fn visit_nil_coalescing(&mut self, left: &Expression, right: &Expression) -> (String, Type) {
+        let (optional, optional_type) = self.visit_expression(left, None);
+        let Type::Optional(wrapped) = &optional_type else {
+            unreachable!("left side of ?? is optional");
+        };
+        let has_value = self.emit_has_value(&optional, &optional_type);
+        let some_label = self.alloc_label("coalesce.some");
+        let none_label = self.alloc_label("coalesce.none");
+        let end_label = self.alloc_label("coalesce.end");
+        self.emit_line(&format!("br i1 {}, label %{}, label %{}", has_value, some_label, none_label));
+
+        // the fallback decides whether the result is `T` or `T?`
+        self.emit_label(&none_label);
+        let (fallback, fallback_type) = self.visit_expression(right, Some(wrapped));
+        let result_type = if fallback_type == optional_type {
+            optional_type.clone()
+        } else {
+            (**wrapped).clone()
+        };
+        let llvm_type = self.llvm_type(&result_type);
+        let result_ptr = self.emit_alloca(&llvm_type);
+        let fallback = self.emit_coerce(&fallback, &fallback_type, &result_type);
+        self.emit_line(&format!("store {} {}, {}* {}", llvm_type, fallback, llvm_type, result_ptr));
+        self.emit_branch(&end_label);
+
+        self.emit_label(&some_label);
+        let value = if result_type == optional_type {
+            optional.clone()
+        } else {
+            self.emit_unwrapped(&optional, &optional_type)
+        };
+        self.emit_line(&format!("store {} {}, {}* {}", llvm_type, value, llvm_type, result_ptr));
+        self.emit_branch(&end_label);
+
+        self.emit_label(&end_label);
+        let result = self.alloc_register();
+        self.emit_line(&format!("{} = load {}, {}* {}", result, llvm_type, llvm_type, result_ptr));
+        (result, result_type)
+    }
+
+    /// Generate code for `expr` and return the register (or constant) holding
+    /// its value together with its type. `expected` is the contextual type
+    /// used to resolve implicit member expressions like `.north`.
+    fn visit_expression(&mut self, expr: &Expression, expected: Option<&Type>) -> (String, Type) {
+        match expr {
+            Expression::Number(n) => {
+                match Self::literal_type(expected, Type::Int) {
+                    ty if ty.is_floating_point() => (Self::float_constant(*n as f64, &ty), ty),
+                    ty => (n.to_string(), ty),
+                }
+            }
+            Expression::FloatLiteral(value) => {
+                let ty = Self::literal_type(expected, Type::Double);
+                (Self::float_constant(*value, &ty), ty)
+            }
+            Expression::Bool(value) => {
+                (value.to_string(), Type::Bool)
+            }
+            Expression::StringLiteral(text) => {
+                (self.string_literal(text), Type::String)
+            }
+            Expression::Interpolation(parts) => self.visit_interpolation(parts),
+            Expression::Nil => {
+                let ty = expected.expect("nil has a contextual type").clone();
+                ("zeroinitializer".to_string(), ty)
+            }
+            Expression::Variable(name) => {
+                // Look up the variable's register
+                let variable = match self.lookup(name) {
+                    Some(variable) => variable.clone(),
+                    None if !self.is_self_property(name) && self.function_decls.contains_key(name) => {
+                        let decl = FunctionDecl::referenced(&self.function_decls[name], expected)[0].clone();
+                        return self.emit_function_reference(&decl);
+                    }
+                    // a member of `self`
+                    None => return self.visit_expression(&Self::self_member(name), expected),
+                };
+                // Load the value from the variable's address
+                (self.emit_read(&variable), variable.ty)
+            }
+            Expression::SpecializedType(ty) => unreachable!("type '{}' used as a value", ty),
+            Expression::Binary { left, operator, right } => self.visit_binary(left, operator, right, expected),
+            Expression::Unary { operator: UnaryOperator::Negate, operand } if operand.is_number_literal() => {
+                // fold negative literals into constants
+                let negated = match operand.as_ref() {
+                    Expression::Number(n) => Expression::Number(n.wrapping_neg()),
+                    Expression::FloatLiteral(value) => Expression::FloatLiteral(-value),
+                    Expression::Unary { operand, .. } => (**operand).clone(),
+                    _ => unreachable!("number literal"),
+                };
+                self.visit_expression(&negated, expected)
+            }
+            Expression::Unary { operator, operand } => {
+                let operand_expected = match operator {
+                    UnaryOperator::Negate | UnaryOperator::BitwiseNot => expected,
+                    UnaryOperator::Not => Some(&Type::Bool),
+                };
+                let (value, ty) = self.visit_expression(operand, operand_expected);
+                let llvm_type = self.llvm_type(&ty);
+                let result = self.alloc_register();
+                match operator {
+                    UnaryOperator::Negate if ty.is_floating_point() => {
+                        self.emit_line(&format!("{} = fneg {} {}", result, llvm_type, value));
+                    }
+                    UnaryOperator::Negate => self.emit_line(&format!("{} = sub {} 0, {}", result, llvm_type, value)),
+                    UnaryOperator::Not => self.emit_line(&format!("{} = xor i1 {}, true", result, value)),
+                    UnaryOperator::BitwiseNot => self.emit_line(&format!("{} = xor {} {}, -1", result, llvm_type, value)),
+                }
+                (result, ty)
+            }
+            Expression::ForceUnwrap(operand) => {
+                let (value, ty) = self.visit_expression(operand, None);
+                let Type::Optional(wrapped) = &ty else {
+                    unreachable!("force unwrap of non-optional value");
+                };
+                let has_value = self.emit_has_value(&value, &ty);
+                self.emit_nil_check(&has_value);
+                (self.emit_unwrapped(&value, &ty), (**wrapped).clone())
+            }
+            Expression::BindOptional(operand) => {
+                let (value, ty) = self.visit_expression(operand, None);
+                let Type::Optional(wrapped) = &ty else {
+                    unreachable!("optional chaining on non-optional value");
+                };
+                let nil_label = self.optional_chain_targets.last()
+                    .expect("optional chain is inside an optional evaluation")
+                    .clone();
+                let has_value = self.emit_has_value(&value, &ty);
+                self.emit_match_branch(&has_value, &nil_label);
+                (self.emit_unwrapped(&value, &ty), (**wrapped).clone())
+            }
+            Expression::Closure { .. } => self.emit_closure_value(expr, expected),
+            Expression::InOut(_) => unreachable!("'&' only marks an inout argument"),
+            // the calls inside pass errors to the enclosing handler
+            Expression::Try(value) => self.visit_expression(value, expected),
+            Expression::OptionalTry(value) => self.visit_optional_try(value, expected),
+            Expression::ForceTry(value) => {
+                self.error_handlers.push(ErrorHandler::Fatal("'try!' expression unexpectedly raised an error"));
+                let result = self.visit_expression(value, expected);
+                self.error_handlers.pop();
+                result
+            }
+            Expression::OptionalEvaluation(chain) => {
+                let nil_label = self.alloc_label("chain.nil");
+                let end_label = self.alloc_label("chain.end");
+                self.optional_chain_targets.push(nil_label.clone());
+                let (value, ty) = self.visit_expression(chain, None);
+                self.optional_chain_targets.pop();
+
+                let result_type = match &ty {
+                    Type::Optional(_) => ty.clone(),
+                    _ => Type::Optional(Box::new(ty.clone())),
+                };
+                let llvm_type = self.llvm_type(&result_type);
+                let result_ptr = self.emit_alloca(&llvm_type);
+                let wrapped = self.emit_coerce(&value, &ty, &result_type);
+                self.emit_line(&format!("store {} {}, {}* {}", llvm_type, wrapped, llvm_type, result_ptr));
+                self.emit_branch(&end_label);
+
+                self.emit_label(&nil_label);
+                self.emit_line(&format!("store {} zeroinitializer, {}* {}", llvm_type, llvm_type, result_ptr));
+                self.emit_branch(&end_label);
+
+                self.emit_label(&end_label);
+                let result = self.alloc_register();
+                self.emit_line(&format!("{} = load {}, {}* {}", result, llvm_type, llvm_type, result_ptr));
+                (result, result_type)
+            }
+            Expression::Member { base, name } => {
+                if let Some(variable) = self.accessor_variable(expr) {
+                    return (self.emit_read(&variable), variable.ty);
+                }
+                if let Some(decl) = self.enum_named(base) {
+                    let decl = decl.clone();
+                    let expected = Self::specialized_type(base, expected);
+                    let ty = self.enum_case_type(&decl, name, &[], expected);
+                    return self.emit_enum_case(&ty, name, &[]);
+                }
+
+                let (value, ty) = self.visit_expression(base, None);
+                if self.has_property(&ty, name) {
+                    return self.emit_property(&value, &ty, name);
+                }
+                if let Type::Dictionary(key_type, value_type) = &ty
+                    && (name == "keys" || name == "values")
+                {
+                    return match name.as_str() {
+                        "keys" => self.emit_entries_array(&value, "key", key_type),
+                        _ => self.emit_entries_array(&value, "value", value_type),
+                    };
+                }
+                if let Type::Array(_) | Type::Dictionary(..) | Type::Set(_) = ty {
+                    let count = self.emit_sequence_count(&value, &ty);
+                    if name == "count" {
+                        return (count, Type::Int);
+                    }
+                    let is_empty = self.alloc_register();
+                    self.emit_line(&format!("{} = icmp eq i64 {}, 0", is_empty, count));
+                    return (is_empty, Type::Bool);
+                }
+                if let Some((index, element_type)) = ty.tuple_element(name) {
+                    let llvm_type = self.llvm_type(&ty);
+                    let element = self.alloc_register();
+                    self.emit_line(&format!("{} = extractvalue {} {}, {}", element, llvm_type, value, index));
+                    return (element, element_type.clone());
+                }
+
+                // otherwise the member is an enum's rawValue
+                let Type::Named(enum_name, _) = &ty else {
+                    unreachable!("member access on non-enum value");
+                };
+                let count = self.enums[enum_name].cases.len();
+                let llvm_type = self.llvm_type(&ty);
+                let tag = self.alloc_register();
+                self.emit_line(&format!("{} = extractvalue {} {}, 0", tag, llvm_type, value));
+                let raw_ptr = self.alloc_register();
+                self.emit_line(&format!(
+                    "{} = getelementptr [{} x i64], [{} x i64]* @\"enum.{}.rawValues\", i32 0, i32 {}",
+                    raw_ptr, count, count, enum_name, tag
+                ));
+                let raw_value = self.alloc_register();
+                self.emit_line(&format!("{} = load i64, i64* {}", raw_value, raw_ptr));
+                (raw_value, Type::Int)
+            }
+            Expression::ImplicitMember(name) => {
+                let ty = Self::contextual_base(expected, name);
+                self.emit_enum_case(&ty, name, &[])
+            }
+            Expression::OperatorCall { symbol, fixity, operands } => {
+                self.visit_operator_call(symbol, *fixity, operands)
+            }
+            Expression::ArrayLiteral(elements) => self.visit_array_literal(elements, expected),
+            Expression::DictionaryLiteral(entries) => self.visit_dictionary_literal(entries, expected),
+            Expression::Subscript { base, index } => {
+                let (array, ty) = self.visit_expression(base, None);
+                if let Type::Dictionary(key_type, value_type) = &ty {
+                    let key = self.visit_expression_as(index, key_type);
+                    let entry = self.emit_hash_table_lookup("find", &array, &key, key_type);
+                    return self.emit_optional_entry(&array, &entry, "value", value_type, false);
+                }
+                let Type::Array(element_type) = ty else {
+                    unreachable!("subscript of '{}'", ty);
+                };
+                let index = self.visit_expression_as(index, &Type::Int);
+                self.emit_index_check(&array, &index, "icmp ult", "Index out of range");
+                let element_ptr = self.emit_element_ptr(&array, &element_type, &index);
+                let llvm_type = self.llvm_type(&element_type);
+                let element = self.alloc_register();
+                self.emit_line(&format!("{} = load {}, {}* {}", element, llvm_type, llvm_type, element_ptr));
+                (element, *element_type)
+            }
+            Expression::Call { callee, arguments } => {
+                // variables with accessors mutated by the call are written back after it
+                let pending = self.write_backs.len();
+                let result = self.visit_call_expression(callee, arguments, expected);
+                self.emit_write_backs(pending);
+                result
+            }
+            Expression::Ternary { condition, then_value, else_value } => {
+                self.visit_ternary(condition, then_value, else_value, expected)
+            }
+            Expression::Tuple(elements) => {
+                let mut expected_elements = expected;
+                while let Some(Type::Optional(wrapped)) = expected_elements {
+                    expected_elements = Some(wrapped);
+                }
+                let expected_elements = match expected_elements {
+                    Some(Type::Tuple(expected_elements)) if expected_elements.len() == elements.len() => {
+                        Some(expected_elements.clone())
+                    }
+                    _ => None,
+                };
+
+                let mut values = Vec::new();
+                let mut element_types = Vec::new();
+                for (index, element) in elements.iter().enumerate() {
+                    let expected_element = expected_elements.as_ref().map(|elements| &elements[index].element_type);
+                    let (value, element_type) = self.visit_expression(&element.value, expected_element);
+                    values.push(value);
+                    element_types.push(TupleElement {
+                        label: element.label.clone(),
+                        element_type,
+                    });
+                }
+
+                let field_types = element_types.iter()
+                    .map(|element| self.llvm_type(&element.element_type))
+                    .collect::<Vec<_>>();
+                let ty = Type::Tuple(element_types);
+                let llvm_type = self.llvm_type(&ty);
+                let mut result = "undef".to_string();
+                for (index, (value, element_type)) in values.iter().zip(&field_types).enumerate() {
+                    let inserted = self.alloc_register();
+                    self.emit_line(&format!(
+                        "{} = insertvalue {} {}, {} {}, {}",
+                        inserted, llvm_type, result, element_type, value, index
+                    ));
+                    result = inserted;
+                }
+                (result, ty)
+            }
+        }
+    }
+
+    /// mirrors the semantic pass: `Int` or `Double` unless the context asks
+    /// for another numeric type
+    fn literal_type(expected: Option<&Type>, default: Type) -> Type {
+        match expected.map(Type::innermost_wrapped) {
+            Some(ty) if ty.is_floating_point() || (ty.is_integer() && default.is_integer()) => ty.clone(),
+            _ => default,
+        }
+    }
+
+    /// floating point constants are written in hexadecimal to be exact;
+    /// LLVM spells float constants as the equivalent double
+    fn float_constant(value: f64, ty: &Type) -> String {
+        let value = if *ty == Type::Float { value as f32 as f64 } else { value };
+        format!("0x{:016X}", value.to_bits())
+    }
+
+    /// `Int(x)`, `UInt8(x)`, `Double(x)` and the other numeric conversions
+    fn emit_numeric_conversion(&mut self, value: &str, from: &Type, to: &Type) -> String {
+        let from_type = self.llvm_type(from);
+        let to_type = self.llvm_type(to);
+        let instruction = match (from, to) {
+            _ if from_type == to_type && from.is_integer() == to.is_integer() => {
+                self.emit_integer_conversion_check(value, from, to);
+                return value.to_string();
+            }
+            _ if from.is_integer() && to.is_integer() => {
+                self.emit_integer_conversion_check(value, from, to);
+                if from.integer_bits() > to.integer_bits() {
+                    "trunc"
+                } else if from.is_unsigned_integer() {
+                    "zext"
+                } else {
+                    "sext"
+                }
+            }
+            _ if from.is_unsigned_integer() => "uitofp",
+            _ if from.is_integer() => "sitofp",
+            (Type::Double, Type::Float) => "fptrunc",
+            (Type::Float, Type::Double) => "fpext",
+            _ => {
+                self.emit_float_conversion_check(value, from, to);
+                if to.is_unsigned_integer() { "fptoui" } else { "fptosi" }
+            }
+        };
+        let result = self.alloc_register();
+        self.emit_line(&format!("{} = {} {} {} to {}", result, instruction, from_type, value, to_type));
+        result
+    }
+
+    /// Swift traps instead of truncating integers that do not fit, the value
+    /// is compared as i128 so that every integer type can be represented
+    fn emit_integer_conversion_check(&mut self, value: &str, from: &Type, to: &Type) {
+        let (Some((from_min, from_max)), Some((to_min, to_max))) = (from.integer_range(), to.integer_range()) else {
+            return;
+        };
+        let mut checks = Vec::new();
+        if from_min < to_min {
+            let reason = if to.is_unsigned_integer() {
+                "Negative value is not representable"
+            } else {
+                "Not enough bits to represent the passed value"
+            };
+            checks.push(("icmp slt", to_min.to_string(), reason.to_string()));
+        }
+        if from_max > to_max {
+            checks.push(("icmp sgt", to_max.to_string(), "Not enough bits to represent the passed value".to_string()));
+        }
+        if checks.is_empty() {
+            return;
+        }
+
+        let extend = if from.is_unsigned_integer() { "zext" } else { "sext" };
+        let wide = self.alloc_register();
+        self.emit_line(&format!("{} = {} {} {} to i128", wide, extend, self.llvm_type(from), value));
+        self.emit_conversion_checks(&wide, "i128", &checks);
+    }
+
+    /// Swift traps instead of producing garbage for values an integer type cannot hold
+    fn emit_float_conversion_check(&mut self, value: &str, from: &Type, to: &Type) {
+        let (min, max) = to.integer_range().expect("conversion to an integer type");
+        let nan_or_infinite = format!("{} value cannot be converted to {} because it is either infinite or NaN", from, to);
+        // values are truncated, so anything above `min - 1` still fits; when
+        // `min - 1` cannot be represented the next value below `min` is far lower
+        let below_min = (min - 1) as f64;
+        let is_exact = if *from == Type::Float { below_min as f32 as i128 == min - 1 } else { below_min as i128 == min - 1 };
+        let lower = if is_exact {
+            ("fcmp ole", Self::float_constant(below_min, from))
+        } else {
+            ("fcmp olt", Self::float_constant(min as f64, from))
+        };
+        let checks = [
+            ("fcmp uno", "0.0".to_string(), nan_or_infinite.clone()),
+            ("fcmp oeq", "0x7FF0000000000000".to_string(), nan_or_infinite.clone()),
+            ("fcmp oeq", "0xFFF0000000000000".to_string(), nan_or_infinite),
+            // `max + 1` is a power of two, which is always exact
+            (
+                "fcmp oge",
+                Self::float_constant((max + 1) as f64, from),
+                format!("{} value cannot be converted to {} because the result would be greater than {}.max", from, to, to),
+            ),
+            (
+                lower.0,
+                lower.1,
+                format!("{} value cannot be converted to {} because the result would be less than {}.min", from, to, to),
+            ),
+        ];
+        let llvm_type = self.llvm_type(from);
+        self.emit_conversion_checks(value, &llvm_type, &checks);
+    }
+
+    /// trap with the message of the first `value <comparison> bound` that holds
+    fn emit_conversion_checks(&mut self, value: &str, llvm_type: &str, checks: &[(&str, String, String)]) {
+        for (comparison, bound, message) in checks {
+            let fails = self.alloc_register();
+            self.emit_line(&format!("{} = {} {} {}, {}", fails, comparison, llvm_type, value, bound));
+            self.emit_runtime_check(&fails, message);
+        }
+    }
+
+    fn visit_call(&mut self, name: &str, arguments: &[Argument]) -> (String, Type) {
+        let Some(decl) = self.function_decls.get(name)
+            .and_then(|overloads| overloads.iter().find(|decl| decl.matches_labels(arguments)))
+            .cloned()
+        else {
+            // the only builtin function is `fatalError(_:)`, whose message is optional
+            match arguments.first() {
+                Some(argument) => {
+                    let message = self.visit_expression_as(&argument.value, &Type::String);
+                    self.emit_written_fatal_error(|generator| generator.emit_write(&message, &Type::String, false));
+                }
+                None => self.emit_fatal_error(""),
+            }
+            self.emit_dead_block();
+            return ("zeroinitializer".to_string(), Type::Never);
+        };
+
+        if decl.generic_parameters.is_empty() {
+            let values = self.visit_arguments(arguments, &decl);
+            return self.emit_decl_call(&Self::function_name(&decl), &decl, &values);
+        }
+        let (name, decl) = self.specialize(&decl, arguments);
+        let values = self.visit_arguments(arguments, &decl);
+        self.emit_decl_call(&name, &decl, &values)
+    }
+
+    /// the arguments of a call to `decl`, each typed for the call instruction;
+    /// an `inout` argument is passed as the address of the variable
+    fn visit_arguments(&mut self, arguments: &[Argument], decl: &FunctionDecl) -> Vec<String> {
+        let mut arguments_throw = false;
+        let values = arguments.iter().zip(&decl.parameters)
+            .map(|(argument, parameter)| {
+                let value = match &argument.value {
+                    Expression::InOut(target) => self.emit_lvalue(target).0,
+                    value => {
+                        let (value, ty) = self.visit_expression(value, Some(&parameter.param_type));
+                        arguments_throw |= matches!(ty, Type::Function { throws: true, .. });
+                        self.emit_coerce(&value, &ty, &parameter.param_type)
+                    }
+                };
+                format!("{} {}", self.parameter_type(parameter), value)
+            })
+            .collect();
+        self.arguments_throw = arguments_throw;
+        values
+    }
+
+    /// The specialization of the generic function `decl` for the types of
+    /// `arguments`, emitted on first use under a name listing the type
+    /// arguments in declaration order: `@"swapValues<Int>(_:_:)"`.
+    fn specialize(&mut self, decl: &FunctionDecl, arguments: &[Argument]) -> (String, FunctionDecl) {
+        let inferred = decl.infer_generic_arguments(arguments, |value, hint| Ok::<_, ()>(self.peek_type_as(value, hint)))
+            .expect("peeking at a type never fails");
+        let bindings = decl.generic_parameters.iter()
+            .map(|generic| {
+                inferred.iter()
+                    .find(|(name, _)| *name == generic.name)
+                    .cloned()
+                    .expect("type parameters inferred by the semantic pass")
+            })
+            .collect::<Vec<_>>();
+        let type_arguments = bindings.iter().map(|(_, ty)| ty.to_string()).collect::<Vec<_>>();
+        let signature = decl.signature();
+        let name = format!(
+            "@\"{}<{}>{}\"",
+            decl.name,
+            type_arguments.join(", "),
+            &signature[decl.name.len()..]
+        );
+        let specialized = decl.substitute(&bindings);
+        if self.emitted_helpers.insert(name.clone()) {
+            self.emit_function_definition(&specialized, &name, None);
+        }
+        (name, specialized)
+    }
+
+    /// Mirrors the overload resolution of the semantic pass. Operands that are
+    /// not number literals are generated first, since their types pick the
+    /// implementation; literals are generated once the parameter type is known.
+    fn visit_operator_call(&mut self, symbol: &str, fixity: Fixity, operands: &[Expression]) -> (String, Type) {
+        let candidates = self.function_decls.get(symbol).into_iter().flatten()
+            .filter(|decl| decl.operator == Some(fixity))
+            .cloned()
+            .collect::<Vec<_>>();
+        if let [decl] = candidates.as_slice() {
+            let values = operands.iter().zip(&decl.parameters)
+                .map(|(operand, parameter)| self.visit_expression_as(operand, &parameter.param_type))
+                .collect::<Vec<_>>();
+            return self.emit_function_call(decl, &values);
+        }
+
+        let generated = operands.iter()
+            .map(|operand| (!operand.is_number_literal()).then(|| self.visit_expression(operand, None)))
+            .collect::<Vec<_>>();
+        let types = operands.iter().zip(&generated)
+            .map(|(operand, generated)| match generated {
+                Some((_, ty)) => ty.clone(),
+                None if operand.is_float_literal() => Type::Double,
+                None => Type::Int,
+            })
+            .collect::<Vec<_>>();
+        let exact = candidates.iter().find(|decl| {
+            decl.parameters.iter().zip(&types).all(|(parameter, ty)| parameter.param_type == *ty)
+        });
+        let convertible = || candidates.iter().find(|decl| {
+            decl.parameters.iter().zip(operands).zip(&types).all(|((parameter, operand), ty)| {
+                let expected = &parameter.param_type;
+                Self::is_convertible(ty, expected)
+                    || (operand.is_number_literal()
+                        && Self::literal_type(Some(expected), ty.clone()) == *expected.innermost_wrapped())
+            })
+        });
+        let decl = exact.or_else(convertible).cloned().expect("operator resolved by the semantic pass");
+
+        let values = operands.iter().zip(generated).zip(&decl.parameters)
+            .map(|((operand, generated), parameter)| match generated {
+                Some((value, ty)) => self.emit_coerce(&value, &ty, &parameter.param_type),
+                None => self.visit_expression_as(operand, &parameter.param_type),
+            })
+            .collect::<Vec<_>>();
+        self.emit_function_call(&decl, &values)
+    }
+
+    fn emit_function_call(&mut self, decl: &FunctionDecl, values: &[String]) -> (String, Type) {
+        let values = values.iter().zip(&decl.parameters)
+            .map(|(value, parameter)| format!("{} {}", self.llvm_type(&parameter.param_type), value))
+            .collect::<Vec<_>>();
+        self.emit_call(&Self::function_name(decl), &decl.return_type, &values)
+    }
+
+    /// call `function` with arguments that are already typed, e.g. `i64 %t1`
+    fn emit_call(&mut self, function: &str, return_type: &Type, arguments: &[String]) -> (String, Type) {
+        let result = self.alloc_register();
+        self.emit_line(&format!(
+            "{} = call {} {}({})",
+            result,
+            self.llvm_type(return_type),
+            function,
+            arguments.join(", ")
+        ));
+        if *return_type == Type::Never {
+            self.emit_line("unreachable");
+            self.emit_dead_block();
+        }
+        (result, return_type.clone())
+    }
+
+    /// the type an implicit member expression like `.north` refers to
+    fn contextual_base(expected: Option<&Type>, name: &str) -> Type {
+        match expected {
+            Some(Type::Optional(wrapped)) if name != "some" && name != "none" => {
+                Self::contextual_base(Some(wrapped), name)
+            }
+            Some(ty) => ty.clone(),
+            None => unreachable!("implicit member has a contextual type"),
+        }
+    }
+
+    /// the type arguments written after a generic enum take the place of the contextual type
+    fn specialized_type<'a>(base: &'a Expression, expected: Option<&'a Type>) -> Option<&'a Type> {
+        match base {
+            Expression::SpecializedType(ty) => Some(ty),
+            _ => expected,
+        }
+    }
+
+    /// Mirrors the semantic pass: the type arguments of a generic enum come
+    /// from the contextual type, otherwise from the associated values.
+    fn enum_case_type(&mut self, decl: &EnumDecl, case_name: &str, arguments: &[Argument], expected: Option<&Type>) -> Type {
+        if decl.generic_parameters.is_empty() {
+            return decl.declared_type();
+        }
+        if let Some(ty @ Type::Named(name, _)) = expected.map(Type::innermost_wrapped)
+            && *name == decl.name
+        {
+            return ty.clone();
+        }
+        let (_, case) = decl.find_case(case_name).expect("enum case exists");
+        let bindings = decl.case_constructor(case)
+            .infer_generic_arguments(arguments, |value, hint| Ok::<_, ()>(self.peek_type_as(value, hint)))
+            .expect("peeking at a type never fails");
+        let type_arguments = decl.generic_parameters.iter()
+            .map(|parameter| {
+                bindings.iter()
+                    .find(|(name, _)| *name == parameter.name)
+                    .map(|(_, ty)| ty.clone())
+                    .expect("type parameters inferred by the semantic pass")
+            })
+            .collect();
+        Type::Named(decl.name.clone(), type_arguments)
+    }
+
+    /// Emit the methods and computed properties of a specialization of a
+    /// generic enum, once for each list of type arguments.
+    fn emit_enum_specialization(&mut self, ty: &Type) {
+        let Type::Named(_, type_arguments) = ty else {
+            return;
+        };
+        let enum_name = ty.to_string();
+        if type_arguments.is_empty() || !self.emitted_helpers.insert(format!("enum.{}", enum_name)) {
+            return;
+        }
+        let decl = self.enum_decl(ty).into_owned();
+        for method in &decl.methods {
+            self.emit_function_definition(method, &Self::method_name(&enum_name, method), Some(ty));
+        }
+        for property in &decl.properties {
+            self.emit_property_accessors(property, &enum_name, ty);
+        }
+    }
+
+    /// construct the enum (or optional) case `case_name` of `ty` with its associated values
+    fn emit_enum_case(&mut self, ty: &Type, case_name: &str, arguments: &[Argument]) -> (String, Type) {
+        self.emit_enum_specialization(ty);
+        let llvm_type = self.llvm_type(ty);
+        let payload = self.case_payload(ty, case_name);
+        let values = arguments.iter().zip(&payload)
+            .map(|(argument, field_type)| self.visit_expression_as(&argument.value, field_type))
+            .collect::<Vec<_>>();
+
+        let Type::Named(enum_name, _) = ty else {
+            // Optional: { i1, T }
+            if case_name == "none" {
+                return ("zeroinitializer".to_string(), ty.clone());
+            }
+            let tagged = self.alloc_register();
+            self.emit_line(&format!("{} = insertvalue {} undef, i1 1, 0", tagged, llvm_type));
+            let result = self.alloc_register();
+            self.emit_line(&format!(
+                "{} = insertvalue {} {}, {} {}, 1",
+                result, llvm_type, tagged, self.llvm_type(&payload[0]), values[0]
+            ));
+            return (result, ty.clone());
+        };
+
+        let (case_index, _) = self.enums[enum_name].find_case(case_name).expect("enum case exists");
+        if values.is_empty() {
+            let result = self.alloc_register();
+            self.emit_line(&format!("{} = insertvalue {} undef, i32 {}, 0", result, llvm_type, case_index));
+            return (result, ty.clone());
+        }
+
+        let ptr = self.emit_alloca(&llvm_type);
+        let tag_ptr = self.alloc_register();
+        self.emit_line(&format!("{} = getelementptr {}, {}* {}, i32 0, i32 0", tag_ptr, llvm_type, llvm_type, ptr));
+        self.emit_line(&format!("store i32 {}, i32* {}", case_index, tag_ptr));
+        for (field, (value, field_type)) in values.iter().zip(&payload).enumerate() {
+            let field_ptr = self.emit_payload_field_ptr(&ptr, ty, case_name, field);
+            let field_llvm_type = self.llvm_type(field_type);
+            self.emit_retain(value, field_type);
+            self.emit_line(&format!("store {} {}, {}* {}", field_llvm_type, value, field_llvm_type, field_ptr));
+        }
+        let result = self.alloc_register();
+        self.emit_line(&format!("{} = load {}, {}* {}", result, llvm_type, llvm_type, ptr));
+        (result, ty.clone())
+    }
+
+    /// `init?(rawValue:)`: look the raw value up among the cases, nil if none matches
+    fn emit_enum_from_raw_value(&mut self, enum_name: &str, raw_value: &Expression) -> (String, Type) {
+        let enum_type = Type::Named(enum_name.to_string(), Vec::new());
+        let result_type = Type::Optional(Box::new(enum_type.clone()));
+        let enum_llvm_type = self.llvm_type(&enum_type);
+        let llvm_type = self.llvm_type(&result_type);
+
+        let raw_reg = self.visit_expression_as(raw_value, &Type::Int);
+        let result_ptr = self.emit_alloca(&llvm_type);
+        self.emit_line(&format!("store {} zeroinitializer, {}* {}", llvm_type, llvm_type, result_ptr));
+
+        let done_label = self.alloc_label("rawValue.done");
+        let raw_values = self.enums[enum_name].raw_values().expect("checked by the semantic pass");
+        let case_labels = raw_values.iter()
+            .map(|_| self.alloc_label("rawValue.case"))
+            .collect::<Vec<_>>();
+        let targets = raw_values.iter().zip(&case_labels)
+            .map(|(value, label)| format!("i64 {}, label %{}", value, label))
+            .collect::<Vec<_>>();
+        self.emit_line(&format!("switch i64 {}, label %{} [ {} ]", raw_reg, done_label, targets.join(" ")));
+
+        for (index, label) in case_labels.iter().enumerate() {
+            self.emit_label(label);
+            let case_value = self.alloc_register();
+            self.emit_line(&format!("{} = insertvalue {} undef, i32 {}, 0", case_value, enum_llvm_type, index));
+            let tagged = self.alloc_register();
+            self.emit_line(&format!("{} = insertvalue {} undef, i1 1, 0", tagged, llvm_type));
+            let wrapped = self.alloc_register();
+            self.emit_line(&format!(
+                "{} = insertvalue {} {}, {} {}, 1",
+                wrapped, llvm_type, tagged, enum_llvm_type, case_value
+            ));
+            self.emit_line(&format!("store {} {}, {}* {}", llvm_type, wrapped, llvm_type, result_ptr));
+            self.emit_branch(&done_label);
+        }
+
+        self.emit_label(&done_label);
+        let result = self.alloc_register();
+        self.emit_line(&format!("{} = load {}, {}* {}", result, llvm_type, llvm_type, result_ptr));
+        (result, result_type)
+    }
+}
+
+/// the IR of a program, which must be linked with the support library when it uses it
+pub struct LlvmModule {
+    pub ir: String,
+    pub uses_support_library: bool,
+}
+
+pub fn generate_llvm(ast: &AstNode) -> LlvmModule {
+    let mut generator = LLVMCodeGenerator::new();
+    let ir = generator.generate(ast);
+    LlvmModule { ir, uses_support_library: generator.uses_support_library }
+}
@@ -1,6 +1,7 @@
 use crate::codegen;
 use crate::lexer;
 use crate::parser;
+use crate::semantic;
 use crate::llvm_backend::LLVMCompiler;
 use crate::options::CompilerOption;
 
@@ -13,14 +14,14 @@ impl Compiler {
     pub fn new(options: Vec<CompilerOption>) -> Self {
         Compiler {
             backend: LLVMCompiler::new(options.clone()),
-            options: options,
+            options,
         }
     }
 
     pub fn compile(&self, source: &str) -> Result<(), String> {
         // frontend
         // token
-        let tokens = match lexer::tokenize(&source) {
+        let tokens = match lexer::tokenize(source) {
             Ok(tokens) => {
                 if self.options.contains(&CompilerOption::Verbose) {
                     println!("Tokens:");
@@ -49,15 +50,20 @@ impl Compiler {
                 return Err(format!("AST error: {}", error));
             }
         };
-        let llvm_ir = codegen::generate_llvm(&ast);
+        // semantic analysis
+        if let Err(error) = semantic::analyze(&ast) {
+            eprintln!("Semantic error: {}", error);
+            return Err(format!("Semantic error: {}", error));
+        }
+        let module = codegen::generate_llvm(&ast);
         if self.options.contains(&CompilerOption::Verbose) {
             println!("=== LLVM IR ===");
-            println!("{}", llvm_ir);
+            println!("{}", module.ir);
         }
         
         // backend
         // llvm compiler
-        self.backend.compile_to_executable(&llvm_ir, "output")
+        self.backend.compile_to_executable(&module, "output")
             .map_err(|e| e.to_string())
     }
 }
\ No newline at end of file
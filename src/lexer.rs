@@ -4,6 +4,19 @@ pub struct Lexer {
     input: Vec<char>,
     position: usize,
     current_char: Option<char>,
+    // each `\(...)` being lexed, innermost last: the parentheses opened inside
+    // it and the delimiter of the string literal to continue afterwards
+    interpolations: Vec<(usize, StringDelimiter)>,
+    // lines of the doc comments since the last token
+    doc_comment: Vec<String>,
+}
+
+/// how a string literal is delimited
+struct StringDelimiter {
+    // number of `#` around a raw string such as `#"...\n"#`
+    hashes: usize,
+    // indentation of the closing `"""` of a multi-line literal
+    indentation: Option<String>,
 }
 
 impl Lexer {
@@ -20,6 +33,8 @@ impl Lexer {
             input: chars,
             position: 0,
             current_char: current,
+            interpolations: Vec::new(),
+            doc_comment: Vec::new(),
         }
     }
 
@@ -33,89 +48,613 @@ impl Lexer {
         }
     }
 
-    fn skip_whitespace(&mut self) {
-        while let Some(ch) = self.current_char {
-            if ch.is_whitespace() {
-                self.advance();
-            } else {
+    fn advance_by(&mut self, count: usize) {
+        for _ in 0..count {
+            self.advance();
+        }
+    }
+
+    // look at the character after the current one
+    fn peek(&self) -> Option<char> {
+        self.input.get(self.position + 1).copied()
+    }
+
+    /// whether the input continues with `text` from the current character
+    fn starts_with(&self, text: &str) -> bool {
+        text.chars().enumerate()
+            .all(|(offset, ch)| self.input.get(self.position + offset) == Some(&ch))
+    }
+
+    /// 1-based line number of the current character, for diagnostics
+    fn line(&self) -> usize {
+        self.input[..self.position.min(self.input.len())].iter()
+            .filter(|ch| **ch == '\n')
+            .count() + 1
+    }
+
+    /// Operators are the longest run of operator characters, so `**` or `<>`
+    /// are single tokens. Known operators get their own token type.
+    fn read_operator(&mut self) -> Token {
+        let mut operator = String::new();
+        while let Some(ch) = self.current_char.filter(|ch| Self::is_operator_character(*ch)) {
+            // `//` and `/*` start a comment even in the middle of an operator
+            if !operator.is_empty() && (self.starts_with("//") || self.starts_with("/*")) {
                 break;
             }
+            operator.push(ch);
+            self.advance();
         }
+        Token::operator(&operator)
+    }
+
+    /// whether the previous character binds an operator to the left operand
+    fn is_left_bound(&self) -> bool {
+        self.position > 0
+            && !matches!(self.input[self.position - 1], '(' | '[' | '{' | ',' | ';' | ':')
+            && !self.input[self.position - 1].is_whitespace()
+    }
+
+    /// Swift's operator-head characters
+    fn is_operator_head(ch: char) -> bool {
+        matches!(
+            ch,
+            '/' | '=' | '-' | '+' | '!' | '*' | '%' | '<' | '>' | '&' | '|' | '^' | '~' | '?'
+                | '\u{A1}'..='\u{A7}' | '\u{A9}' | '\u{AB}' | '\u{AC}' | '\u{AE}'
+                | '\u{B0}'..='\u{B1}' | '\u{B6}' | '\u{BB}' | '\u{BF}' | '\u{D7}' | '\u{F7}'
+                | '\u{2016}'..='\u{2017}' | '\u{2020}'..='\u{2027}' | '\u{2030}'..='\u{203E}'
+                | '\u{2041}'..='\u{2053}' | '\u{2055}'..='\u{205E}' | '\u{2190}'..='\u{23FF}'
+                | '\u{2500}'..='\u{2775}' | '\u{2794}'..='\u{2BFF}' | '\u{2E00}'..='\u{2E7F}'
+                | '\u{3001}'..='\u{3003}' | '\u{3008}'..='\u{3020}' | '\u{3030}'
+        )
+    }
+
+    /// Swift's operator-character: a head or a combining mark
+    fn is_operator_character(ch: char) -> bool {
+        Self::is_operator_head(ch)
+            || matches!(
+                ch,
+                '\u{300}'..='\u{36F}' | '\u{1DC0}'..='\u{1DFF}' | '\u{20D0}'..='\u{20FF}'
+                    | '\u{FE00}'..='\u{FE0F}' | '\u{FE20}'..='\u{FE2F}' | '\u{E0100}'..='\u{E01EF}'
+            )
     }
 
-    fn read_number(&mut self) -> String {
+    /// Skips whitespace and comments. Doc comments (`///` and `/** */`) are
+    /// kept for the next token, a plain comment in between discards them.
+    fn skip_trivia(&mut self) -> Result<(), String> {
+        loop {
+            match self.current_char {
+                Some(ch) if ch.is_whitespace() => self.advance(),
+                Some('/') if self.starts_with("//") => {
+                    let is_doc = self.starts_with("///") && !self.starts_with("////");
+                    let text = self.read_line_comment();
+                    if is_doc {
+                        self.doc_comment.push(text[3..].trim().to_string());
+                    } else {
+                        self.doc_comment.clear();
+                    }
+                }
+                Some('/') if self.starts_with("/*") => {
+                    let is_doc = self.starts_with("/**") && !self.starts_with("/**/") && !self.starts_with("/***");
+                    let text = self.read_block_comment()?;
+                    self.doc_comment.clear();
+                    if is_doc {
+                        let body = &text[3..text.len() - 2];
+                        self.doc_comment.extend(body.lines()
+                            .map(|line| line.trim().trim_start_matches('*').trim().to_string())
+                            .filter(|line| !line.is_empty()));
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// `// ...` up to, but not including, the end of the line
+    fn read_line_comment(&mut self) -> String {
+        let mut text = String::new();
+        while let Some(ch) = self.current_char.filter(|ch| *ch != '\n') {
+            text.push(ch);
+            self.advance();
+        }
+        text
+    }
+
+    /// `/* ... */`, where block comments nest: `/* a /* b */ c */`
+    fn read_block_comment(&mut self) -> Result<String, String> {
+        let line = self.line();
+        let mut text = String::new();
+        let mut depth = 0;
+        loop {
+            if self.starts_with("/*") {
+                depth += 1;
+            } else if self.starts_with("*/") {
+                depth -= 1;
+            } else {
+                match self.current_char {
+                    Some(ch) => text.push(ch),
+                    None => return Err(format!("line {}: unterminated '/*' comment", line)),
+                }
+                self.advance();
+                continue;
+            }
+            text.push_str(&self.input[self.position..self.position + 2].iter().collect::<String>());
+            self.advance_by(2);
+            if depth == 0 {
+                return Ok(text);
+            }
+        }
+    }
+
+    /// the doc comment lines collected before the token just read
+    fn take_doc_comment(&mut self) -> Option<String> {
+        if self.doc_comment.is_empty() {
+            None
+        } else {
+            Some(std::mem::take(&mut self.doc_comment).join("\n"))
+        }
+    }
+
+    fn read_number(&mut self) -> Result<Token, String> {
+        // `pair.0.1` accesses tuple elements, it is not the number 0.1
+        let is_tuple_index = self.position > 0 && self.input[self.position - 1] == '.';
+        let radix = match self.peek() {
+            Some('x') if self.current_char == Some('0') => 16,
+            Some('o') if self.current_char == Some('0') => 8,
+            Some('b') if self.current_char == Some('0') => 2,
+            _ => 10,
+        };
+        let is_hex = radix == 16;
         let mut number = String::new();
+        if radix != 10 {
+            number.extend(self.current_char.into_iter().chain(self.peek()));
+            self.advance_by(2);
+            if !self.current_char.is_some_and(|ch| ch.is_digit(radix)) {
+                return Err(format!("line {}: expected a digit after integer literal prefix", self.line()));
+            }
+        }
+        let is_digit = |ch: char| ch.is_digit(radix);
 
-        while let Some(ch) = self.current_char {
-            if ch.is_numeric() {
-                number.push(ch);
+        self.read_digits(&mut number, is_digit);
+        if is_tuple_index {
+            return Ok(Token::new(TokenType::Number, number));
+        }
+
+        let mut is_float = false;
+        if (radix == 10 || is_hex) && self.current_char == Some('.') && self.peek().is_some_and(is_digit) {
+            is_float = true;
+            number.push('.');
+            self.advance();
+            self.read_digits(&mut number, is_digit);
+        }
+
+        // decimal exponents are written with `e`, hexadecimal ones with `p`
+        let exponent = if is_hex { ['p', 'P'] } else { ['e', 'E'] };
+        if (radix == 10 || is_hex) && self.current_char.is_some_and(|ch| exponent.contains(&ch)) {
+            let sign = matches!(self.peek(), Some('+') | Some('-'));
+            let digit = self.input.get(self.position + if sign { 2 } else { 1 }).copied();
+            if digit.is_some_and(|ch| ch.is_ascii_digit()) {
+                is_float = true;
+                number.push(self.current_char.unwrap_or_default());
                 self.advance();
-            } else {
-                break;
+                if sign {
+                    number.push(self.current_char.unwrap_or_default());
+                    self.advance();
+                }
+                self.read_digits(&mut number, |ch| ch.is_ascii_digit());
             }
         }
-        number
+
+        if let Some(ch) = self.current_char.filter(|ch| ch.is_alphanumeric()) {
+            return Err(match radix {
+                2 => format!("line {}: '{}' is not a valid binary digit (0 or 1) in integer literal", self.line(), ch),
+                8 => format!("line {}: '{}' is not a valid octal digit (0-7) in integer literal", self.line(), ch),
+                16 => format!("line {}: '{}' is not a valid hexadecimal digit (0-9, A-F) in integer literal", self.line(), ch),
+                _ if is_float => format!("line {}: '{}' is not a valid digit in floating point exponent", self.line(), ch),
+                _ => format!("line {}: '{}' is not a valid digit in integer literal", self.line(), ch),
+            });
+        }
+        if is_float {
+            Ok(Token::new(TokenType::FloatLiteral, number))
+        } else {
+            Ok(Token::new(TokenType::Number, number))
+        }
     }
 
-    fn read_identifier(&mut self) -> String {
-        let mut identifier = String::new();
+    /// digits may be separated by underscores (`1_000_000`), which are kept
+    /// in the lexeme and ignored when the parser reads the value
+    fn read_digits(&mut self, number: &mut String, is_digit: impl Fn(char) -> bool) {
+        while let Some(ch) = self.current_char.filter(|ch| is_digit(*ch) || (*ch == '_' && !number.is_empty())) {
+            number.push(ch);
+            self.advance();
+        }
+    }
+
+    /// read a string literal from its opening quote or `#`
+    fn read_string(&mut self) -> Result<Token, String> {
+        let mut hashes = 0;
+        while self.current_char == Some('#') {
+            hashes += 1;
+            self.advance();
+        }
+        if self.current_char != Some('"') {
+            return Err("expected '\"' after '#' to start a raw string literal".to_string());
+        }
 
-        while let Some(ch) = self.current_char {
-            if ch.is_alphanumeric() || ch == '_' {
-                identifier.push(ch);
+        let delimiter = if self.starts_with("\"\"\"") {
+            self.advance_by(3);
+            while matches!(self.current_char, Some(' ') | Some('\t')) {
                 self.advance();
-            } else {
-                break;
             }
+            if self.current_char != Some('\n') {
+                return Err(format!(
+                    "line {}: multi-line string literal content must begin on a new line",
+                    self.line()
+                ));
+            }
+            self.advance();
+            let indentation = self.closing_indentation(hashes)?;
+            StringDelimiter { hashes, indentation: Some(indentation) }
+        } else {
+            self.advance();
+            StringDelimiter { hashes, indentation: None }
+        };
+
+        // the first line of a multi-line literal may already be the closing one
+        if delimiter.indentation.is_some() && self.begin_line(&delimiter)? {
+            return Ok(Token::new(TokenType::StringLiteral, String::new()));
+        }
+
+        let (text, interpolates) = self.read_string_segment(&delimiter)?;
+        if interpolates {
+            self.interpolations.push((0, delimiter));
+            Ok(Token::new(TokenType::InterpolationStart, text))
+        } else {
+            Ok(Token::new(TokenType::StringLiteral, text))
+        }
+    }
+
+    /// continue a string literal after the `)` closing an interpolation
+    fn read_interpolation_continuation(&mut self) -> Result<Token, String> {
+        self.advance(); // consume ')'
+        let (_, delimiter) = self.interpolations.pop().expect("inside an interpolation");
+        let (text, interpolates) = self.read_string_segment(&delimiter)?;
+        if interpolates {
+            self.interpolations.push((0, delimiter));
+            Ok(Token::new(TokenType::InterpolationMiddle, text))
+        } else {
+            Ok(Token::new(TokenType::InterpolationEnd, text))
+        }
+    }
+
+    /// Read string contents up to the closing delimiter or the start of an
+    /// interpolation `\(`, resolving escape sequences. Returns the text and
+    /// whether an interpolation follows.
+    fn read_string_segment(&mut self, delimiter: &StringDelimiter) -> Result<(String, bool), String> {
+        let mut text = String::new();
+        let closing_hashes = "#".repeat(delimiter.hashes);
+
+        loop {
+            match self.current_char {
+                None => return Err("unterminated string literal".to_string()),
+                Some('\n') if delimiter.indentation.is_none() => {
+                    return Err("unterminated string literal".to_string());
+                }
+                Some('\n') => {
+                    self.advance();
+                    // the newline before the closing delimiter is not part of the string
+                    if self.begin_line(delimiter)? {
+                        return Ok((text, false));
+                    }
+                    text.push('\n');
+                }
+                Some('"') if delimiter.indentation.is_none() && self.starts_with(&format!("\"{}", closing_hashes)) => {
+                    self.advance_by(1 + delimiter.hashes);
+                    return Ok((text, false));
+                }
+                Some('"') if self.starts_with(&format!("\"\"\"{}", closing_hashes)) => {
+                    return Err(format!(
+                        "line {}: multi-line string literal closing delimiter must begin on a new line",
+                        self.line()
+                    ));
+                }
+                // in raw strings only `\#` starts an escape sequence
+                Some('\\') if self.starts_with(&format!("\\{}", closing_hashes)) => {
+                    self.advance_by(1 + delimiter.hashes);
+                    match self.current_char {
+                        Some('(') => {
+                            self.advance();
+                            return Ok((text, true));
+                        }
+                        // a backslash at the end of a line joins it with the next one
+                        Some('\n') if delimiter.indentation.is_some() => {
+                            self.advance();
+                            if self.begin_line(delimiter)? {
+                                return Ok((text, false));
+                            }
+                        }
+                        _ => text.push(self.read_escape()?),
+                    }
+                }
+                Some(ch) => {
+                    text.push(ch);
+                    self.advance();
+                }
+            }
+        }
+    }
+
+    /// Indentation of the closing `"""` of the multi-line literal starting at
+    /// the current position; it is stripped from every line of the literal.
+    fn closing_indentation(&self, hashes: usize) -> Result<String, String> {
+        let closing = format!("\"\"\"{}", "#".repeat(hashes));
+        let closing_chars = closing.chars().collect::<Vec<_>>();
+        let mut line_start = self.position;
+        while line_start < self.input.len() {
+            let indentation = self.input[line_start..].iter()
+                .take_while(|ch| **ch == ' ' || **ch == '\t')
+                .collect::<String>();
+            let content_start = line_start + indentation.len();
+            if self.input[content_start..].starts_with(&closing_chars) {
+                return Ok(indentation);
+            }
+            match self.input[line_start..].iter().position(|ch| *ch == '\n') {
+                Some(offset) => line_start += offset + 1,
+                None => break,
+            }
+        }
+
+        // a closing delimiter after other content on its line
+        match self.input[self.position..].windows(closing_chars.len()).position(|window| window == closing_chars) {
+            Some(offset) => {
+                let line = self.line() + self.input[self.position..self.position + offset].iter()
+                    .filter(|ch| **ch == '\n')
+                    .count();
+                Err(format!(
+                    "line {}: multi-line string literal closing delimiter must begin on a new line",
+                    line
+                ))
+            }
+            None => Err("unterminated string literal".to_string()),
+        }
+    }
+
+    /// At the start of a line of a multi-line literal: consume the closing
+    /// delimiter and return true, or strip the indentation and return false.
+    fn begin_line(&mut self, delimiter: &StringDelimiter) -> Result<bool, String> {
+        let indentation = delimiter.indentation.as_deref().unwrap_or_default();
+        let leading = self.input[self.position..].iter()
+            .take_while(|ch| **ch == ' ' || **ch == '\t')
+            .collect::<String>();
+        let closing = format!("\"\"\"{}", "#".repeat(delimiter.hashes));
+
+        if self.starts_with(&format!("{}{}", leading, closing)) {
+            self.advance_by(leading.len() + closing.len());
+            return Ok(true);
+        }
+        if leading.starts_with(indentation) {
+            self.advance_by(indentation.len());
+            return Ok(false);
+        }
+        // lines of only whitespace may be indented less
+        let after_leading = self.input.get(self.position + leading.len()).copied();
+        if after_leading == Some('\n') || after_leading.is_none() {
+            self.advance_by(leading.len());
+            return Ok(false);
+        }
+
+        if indentation.starts_with(&leading) {
+            return Err(format!(
+                "line {}: insufficient indentation of line in multi-line string literal, \
+                 change the indentation of this line to match the closing delimiter",
+                self.line()
+            ));
+        }
+        let mismatch = leading.chars().zip(indentation.chars())
+            .find(|(actual, expected)| actual != expected)
+            .map(|(actual, _)| actual)
+            .unwrap_or(' ');
+        Err(format!(
+            "line {}: unexpected {} in indentation of line in multi-line string literal",
+            self.line(),
+            if mismatch == '\t' { "tab" } else { "space" }
+        ))
+    }
+
+    /// the character of an escape sequence, after its backslash
+    fn read_escape(&mut self) -> Result<char, String> {
+        let escaped = match self.current_char {
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('t') => '\t',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('"') => '"',
+            Some('\'') => '\'',
+            Some('u') => {
+                self.advance();
+                if self.current_char != Some('{') {
+                    return Err("expected '{' in \\u{...} escape sequence".to_string());
+                }
+                self.advance();
+                let mut digits = String::new();
+                while let Some(ch) = self.current_char.filter(|ch| ch.is_ascii_hexdigit()) {
+                    digits.push(ch);
+                    self.advance();
+                }
+                if self.current_char != Some('}') {
+                    return Err("expected '}' in \\u{...} escape sequence".to_string());
+                }
+                if digits.is_empty() || digits.len() > 8 {
+                    return Err("\\u{...} escape sequence expects between 1 and 8 hex digits".to_string());
+                }
+                u32::from_str_radix(&digits, 16).ok()
+                    .and_then(char::from_u32)
+                    .ok_or("invalid unicode scalar".to_string())?
+            }
+            _ => return Err("invalid escape sequence in literal".to_string()),
+        };
+        self.advance();
+        Ok(escaped)
+    }
+
+    /// Swift's identifier-head characters
+    fn is_identifier_head(ch: char) -> bool {
+        let code = ch as u32;
+        matches!(
+            ch,
+            'a'..='z' | 'A'..='Z' | '_'
+                | '\u{A8}' | '\u{AA}' | '\u{AD}' | '\u{AF}' | '\u{B2}'..='\u{B5}' | '\u{B7}'..='\u{BA}'
+                | '\u{BC}'..='\u{BE}' | '\u{C0}'..='\u{D6}' | '\u{D8}'..='\u{F6}' | '\u{F8}'..='\u{FF}'
+                | '\u{100}'..='\u{2FF}' | '\u{370}'..='\u{167F}' | '\u{1681}'..='\u{180D}' | '\u{180F}'..='\u{1DBF}'
+                | '\u{1E00}'..='\u{1FFF}'
+                | '\u{200B}'..='\u{200D}' | '\u{202A}'..='\u{202E}' | '\u{203F}'..='\u{2040}' | '\u{2054}'
+                | '\u{2060}'..='\u{206F}'
+                | '\u{2070}'..='\u{20CF}' | '\u{2100}'..='\u{218F}' | '\u{2460}'..='\u{24FF}' | '\u{2776}'..='\u{2793}'
+                | '\u{2C00}'..='\u{2DFF}' | '\u{2E80}'..='\u{2FFF}'
+                | '\u{3004}'..='\u{3007}' | '\u{3021}'..='\u{302F}' | '\u{3031}'..='\u{303F}' | '\u{3040}'..='\u{D7FF}'
+                | '\u{F900}'..='\u{FD3D}' | '\u{FD40}'..='\u{FDCF}' | '\u{FDF0}'..='\u{FE1F}' | '\u{FE30}'..='\u{FE44}'
+                | '\u{FE47}'..='\u{FFFD}'
+        ) || (0x10000..=0xEFFFD).contains(&code) && code & 0xFFFF <= 0xFFFD
+    }
+
+    /// Swift's identifier-character: a head, a digit or a combining mark
+    fn is_identifier_character(ch: char) -> bool {
+        Self::is_identifier_head(ch)
+            || matches!(
+                ch,
+                '0'..='9' | '\u{300}'..='\u{36F}' | '\u{1DC0}'..='\u{1DFF}' | '\u{20D0}'..='\u{20FF}'
+                    | '\u{FE20}'..='\u{FE2F}'
+            )
+    }
+
+    fn read_identifier(&mut self) -> String {
+        let mut identifier = String::new();
+
+        while let Some(ch) = self.current_char.filter(|ch| Self::is_identifier_character(*ch)) {
+            identifier.push(ch);
+            self.advance();
         }
 
         identifier
     }
 
+    /// `` `let` `` is the identifier `let`, so keywords can be used as names
+    fn read_escaped_identifier(&mut self) -> Result<Token, String> {
+        self.advance(); // consume '`'
+        if !self.current_char.is_some_and(Self::is_identifier_head) {
+            return Err(format!("line {}: expected an identifier between backticks", self.line()));
+        }
+        let identifier = self.read_identifier();
+        if self.current_char != Some('`') {
+            return Err(format!("line {}: expected '`' to close the escaped identifier '{}'", self.line(), identifier));
+        }
+        self.advance();
+        Ok(Token { is_escaped: true, ..Token::new(TokenType::Identifier, identifier) })
+    }
+
     pub fn next_token(&mut self) -> Result<Token, String> {
-        self.skip_whitespace();
+        let start = self.position;
+        self.skip_trivia()?;
+        let doc_comment = self.take_doc_comment();
+        let space_before = self.position > start;
+        let newline_before = self.input[start..self.position].contains(&'\n');
+        let mut token = self.read_token()?;
+        token.doc_comment = doc_comment;
+        token.space_before = space_before;
+        token.newline_before = newline_before;
+        Ok(token)
+    }
+
+    fn read_token(&mut self) -> Result<Token, String> {
 
         match self.current_char {
-            None => Ok(Token::new(TokenType::EOF, String::new())),
+            None => Ok(Token::new(TokenType::Eof, String::new())),
 
             Some(ch) => match ch {
                 '(' => {
+                    if let Some((depth, _)) = self.interpolations.last_mut() {
+                        *depth += 1;
+                    }
                     self.advance();
                     Ok(Token::new(TokenType::LeftParen, "(".to_string()))
                 }
+                ')' if matches!(self.interpolations.last(), Some((0, _))) => {
+                    self.read_interpolation_continuation()
+                }
                 ')' => {
+                    if let Some((depth, _)) = self.interpolations.last_mut() {
+                        *depth -= 1;
+                    }
                     self.advance();
                     Ok(Token::new(TokenType::RightParen, ")".to_string()))
                 }
-                '+' => {
+                '{' => {
                     self.advance();
-                    Ok(Token::new(TokenType::Plus, "+".to_string()))
+                    Ok(Token::new(TokenType::LeftBrace, "{".to_string()))
                 }
-                '-' => {
+                '}' => {
                     self.advance();
-                    Ok(Token::new(TokenType::Minus, "-".to_string()))
+                    Ok(Token::new(TokenType::RightBrace, "}".to_string()))
                 }
-                '*' => {
+                '[' => {
                     self.advance();
-                    Ok(Token::new(TokenType::Star, "*".to_string()))
+                    Ok(Token::new(TokenType::LeftBracket, "[".to_string()))
                 }
-                '/' => {
+                ']' => {
                     self.advance();
-                    Ok(Token::new(TokenType::Slash, "/".to_string()))
+                    Ok(Token::new(TokenType::RightBracket, "]".to_string()))
                 }
                 ':' => {
                     self.advance();
                     Ok(Token::new(TokenType::Colon, ":".to_string()))
                 }
-                '=' => {
+                ';' => {
+                    self.advance();
+                    Ok(Token::new(TokenType::Semicolon, ";".to_string()))
+                }
+                '*' if self.starts_with("*/") => {
+                    Err(format!("line {}: unexpected end of block comment", self.line()))
+                }
+                // `value!` and `value?` when nothing separates them from the operand
+                '!' | '?' if self.is_left_bound() => {
+                    self.advance();
+                    Ok(Token::operator(&ch.to_string()))
+                }
+                ch if Self::is_operator_head(ch) => {
+                    Ok(self.read_operator())
+                }
+                ',' => {
+                    self.advance();
+                    Ok(Token::new(TokenType::Comma, ",".to_string()))
+                }
+                '.' => {
                     self.advance();
-                    Ok(Token::new(TokenType::Assign, "=".to_string()))
+                    Ok(Token::new(TokenType::Dot, ".".to_string()))
+                }
+                '_' if !self.peek().is_some_and(Self::is_identifier_character) => {
+                    self.advance();
+                    Ok(Token::new(TokenType::Underscore, "_".to_string()))
+                }
+                '`' => {
+                    self.read_escaped_identifier()
+                }
+                // anonymous closure parameters: `$0`, `$1`, ...
+                '$' if self.peek().is_some_and(|ch| ch.is_ascii_digit()) => {
+                    self.advance();
+                    let mut identifier = "$".to_string();
+                    while let Some(ch) = self.current_char.filter(char::is_ascii_digit) {
+                        identifier.push(ch);
+                        self.advance();
+                    }
+                    Ok(Token::new(TokenType::Identifier, identifier))
+                }
+                '"' | '#' => {
+                    self.read_string()
                 }
                 '0'..='9' => {
-                    let number = self.read_number();
-                    Ok(Token::new(TokenType::Number, number))
+                    self.read_number()
                 }
-                'a'..='z' | 'A' ..= 'Z' => {
+                ch if Self::is_identifier_head(ch) => {
                     let identifier = self.read_identifier();
 
                     match identifier.as_str() {
@@ -128,6 +667,96 @@ impl Lexer {
                         "var" => {
                             Ok(Token::new(TokenType::Var, identifier))
                         }
+                        "enum" => {
+                            Ok(Token::new(TokenType::Enum, identifier))
+                        }
+                        "case" => {
+                            Ok(Token::new(TokenType::Case, identifier))
+                        }
+                        "switch" => {
+                            Ok(Token::new(TokenType::Switch, identifier))
+                        }
+                        "default" => {
+                            Ok(Token::new(TokenType::Default, identifier))
+                        }
+                        "if" => {
+                            Ok(Token::new(TokenType::If, identifier))
+                        }
+                        "else" => {
+                            Ok(Token::new(TokenType::Else, identifier))
+                        }
+                        "while" => {
+                            Ok(Token::new(TokenType::While, identifier))
+                        }
+                        "for" => {
+                            Ok(Token::new(TokenType::For, identifier))
+                        }
+                        "in" => {
+                            Ok(Token::new(TokenType::In, identifier))
+                        }
+                        "guard" => {
+                            Ok(Token::new(TokenType::Guard, identifier))
+                        }
+                        "func" => {
+                            Ok(Token::new(TokenType::Func, identifier))
+                        }
+                        "return" => {
+                            Ok(Token::new(TokenType::Return, identifier))
+                        }
+                        "break" => {
+                            Ok(Token::new(TokenType::Break, identifier))
+                        }
+                        "continue" => {
+                            Ok(Token::new(TokenType::Continue, identifier))
+                        }
+                        "throw" => {
+                            Ok(Token::new(TokenType::Throw, identifier))
+                        }
+                        "throws" => {
+                            Ok(Token::new(TokenType::Throws, identifier))
+                        }
+                        "rethrows" => {
+                            Ok(Token::new(TokenType::Rethrows, identifier))
+                        }
+                        "try" => {
+                            Ok(Token::new(TokenType::Try, identifier))
+                        }
+                        "do" => {
+                            Ok(Token::new(TokenType::Do, identifier))
+                        }
+                        "catch" => {
+                            Ok(Token::new(TokenType::Catch, identifier))
+                        }
+                        "is" => {
+                            Ok(Token::new(TokenType::Is, identifier))
+                        }
+                        "as" => {
+                            Ok(Token::new(TokenType::As, identifier))
+                        }
+                        "inout" => {
+                            Ok(Token::new(TokenType::Inout, identifier))
+                        }
+                        "defer" => {
+                            Ok(Token::new(TokenType::Defer, identifier))
+                        }
+                        "protocol" => {
+                            Ok(Token::new(TokenType::Protocol, identifier))
+                        }
+                        "extension" => {
+                            Ok(Token::new(TokenType::Extension, identifier))
+                        }
+                        "static" => {
+                            Ok(Token::new(TokenType::Static, identifier))
+                        }
+                        "true" => {
+                            Ok(Token::new(TokenType::True, identifier))
+                        }
+                        "false" => {
+                            Ok(Token::new(TokenType::False, identifier))
+                        }
+                        "nil" => {
+                            Ok(Token::new(TokenType::Nil, identifier))
+                        }
                         _ => {
                             Ok(Token::new(TokenType::Identifier, identifier))
                         }
@@ -145,7 +774,7 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>, String> {
 
     loop {
         let token = lexer.next_token()?;
-        let is_eof = token.token_type == TokenType::EOF;
+        let is_eof = token.token_type == TokenType::Eof;
         tokens.push(token);
 
         if is_eof {
@@ -1,7 +1,12 @@
 use std::fs;
 use std::process::Command;
+use crate::codegen::LlvmModule;
 use crate::options::CompilerOption;
 
+/// runtime functions used by the generated code, such as the hash table of
+/// Dictionary and Set, linked into every program
+const SUPPORT_LIBRARY: &str = include_str!("../runtime/support.ll");
+
 pub struct LLVMCompiler {
     _output_dir: String,
     options: Vec<CompilerOption>,
@@ -11,29 +16,34 @@ impl LLVMCompiler {
   pub fn new(options:Vec<CompilerOption>) -> Self {
     LLVMCompiler {
       _output_dir: "target/llvm".to_string(),
-      options: options,
+      options,
     }
   }
 
-  pub fn compile_to_executable(&self, llvm_ir: &str, _output: &str) -> Result<(), &str> {
+  pub fn compile_to_executable(&self, module: &LlvmModule, _output: &str) -> Result<(), &str> {
     // Create output directory
     let output_dir = "target/llvm";
     fs::create_dir_all(output_dir)
         .expect("Failed to create output directory");
     
     let ll_file = format!("{}/output.ll", output_dir);
+    let program_file = format!("{}/program.ll", output_dir);
+    let support_file = format!("{}/support.ll", output_dir);
     let s_file = format!("{}/output.s", output_dir);
     let exec_file = format!("{}/output", output_dir);
     
-    fs::write(&ll_file, &llvm_ir)
+    fs::write(&program_file, &module.ir)
         .expect("Failed to write output file");
+    fs::write(&support_file, SUPPORT_LIBRARY)
+        .expect("Failed to write support library");
+    Self::link_support_library(module.uses_support_library, &program_file, &support_file, &ll_file)?;
     
     if self.options.contains(&CompilerOption::Verbose) {
         println!("=== LLVM IR saved to {} ===\n", ll_file);
     }
     
     // Execute with LLVM toolchain (if installed)
-    if let Ok(_) = Command::new("lli").output() {
+    if Command::new("lli").output().is_ok() {
         Self::compile_and_run_llvm(self, &ll_file, &s_file, &exec_file);
         Ok(())
     } else {
@@ -48,6 +58,27 @@ impl LLVMCompiler {
     }
   }
 
+  /// Link the program with the support library into `ll_file`, so that it runs on its own.
+  /// Without llvm-link the program is written as is, which only works when it
+  /// does not use the support library.
+  fn link_support_library(uses_support_library: bool, program_file: &str, support_file: &str, ll_file: &str) -> Result<(), &'static str> {
+    match Command::new("llvm-link").args(["-S", program_file, support_file, "-o", ll_file]).output() {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => {
+            eprintln!("{}", String::from_utf8_lossy(&output.stderr));
+            Err("Failed to link the support library")
+        }
+        Err(_) if uses_support_library => {
+            Err("llvm-link is required to link the support library used by Dictionary and Set, please install the LLVM toolchain")
+        }
+        Err(_) => {
+            fs::copy(program_file, ll_file)
+                .expect("Failed to write output file");
+            Ok(())
+        }
+    }
+  }
+
   fn compile_and_run_llvm(&self, ll_file: &str, s_file: &str, exec_file: &str) {
     println!("=== LLVM Execution ===");
     
@@ -73,18 +104,20 @@ impl LLVMCompiler {
     }
     
     // LLVM IR → Assembly
-    if let Ok(_) = Command::new("llc")
-        .args(&[ll_file, "-o", s_file])
-        .output() 
+    if Command::new("llc")
+        .args([ll_file, "-o", s_file])
+        .output()
+        .is_ok()
     {
         if self.options.contains(&CompilerOption::Verbose) {
             println!("Generated assembly file: {}", s_file);
         }
         
         // Assembly → Executable
-        if let Ok(_) = Command::new("clang")
-            .args(&[s_file, "-o", exec_file])
+        if Command::new("clang")
+            .args([s_file, "-o", exec_file])
             .output()
+            .is_ok()
         {
             if self.options.contains(&CompilerOption::Verbose) {
                 println!("Generated executable: {}", exec_file);
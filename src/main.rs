@@ -3,6 +3,7 @@ mod lexer;
 mod token;
 mod ast;
 mod parser;
+mod semantic;
 mod codegen;
 mod llvm_backend;
 mod options;
@@ -27,7 +28,7 @@ fn main() {
     for arg in args.iter().skip(1) {
         if arg.starts_with('-') {
             // verbose option on
-            if let Some(option) = CompilerOption::from_literal(&arg) {
+            if let Some(option) = CompilerOption::from_literal(arg) {
                 options.push(option);
             }
         } else {
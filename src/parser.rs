@@ -1,98 +1,1625 @@
+use std::collections::{HashMap, HashSet};
+
 use crate::token::{Token, TokenType};
-use crate::ast::{AstNode, Statement, Expression, BinaryOperator};
+use crate::ast::{
+    AstNode, Statement, Expression, BinaryOperator, UnaryOperator, Argument, Type, EnumDecl,
+    EnumCase, AssociatedValue, SwitchCase, Pattern, Condition, FunctionDecl, Parameter,
+    TupleElement, InterpolationPart, Fixity, ForPattern, ClosureParameter, ProtocolDecl, Requirement,
+    PropertyDecl, ExtensionDecl, GenericParameter, CatchClause, Accessor, StoredPropertyDecl,
+};
+
+/// a named level of binding strength, higher levels bind tighter
+#[derive(Debug, Clone)]
+struct PrecedenceGroup {
+    name: String,
+    level: u32,
+    associativity: Associativity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Associativity {
+    Left,
+    Right,
+    None,
+}
+
+/// Swift's standard precedence groups, from the loosest to the tightest binding
+const STANDARD_PRECEDENCE_GROUPS: [(&str, Associativity); 12] = [
+    ("AssignmentPrecedence", Associativity::Right),
+    ("TernaryPrecedence", Associativity::Right),
+    ("DefaultPrecedence", Associativity::None),
+    ("LogicalDisjunctionPrecedence", Associativity::Left),
+    ("LogicalConjunctionPrecedence", Associativity::Left),
+    ("ComparisonPrecedence", Associativity::None),
+    ("NilCoalescingPrecedence", Associativity::Right),
+    ("CastingPrecedence", Associativity::None),
+    ("RangeFormationPrecedence", Associativity::None),
+    ("AdditionPrecedence", Associativity::Left),
+    ("MultiplicationPrecedence", Associativity::Left),
+    ("BitwiseShiftPrecedence", Associativity::None),
+];
+
+/// standard groups are this far apart, leaving room for user-defined ones
+const PRECEDENCE_LEVEL_STEP: u32 = 1 << 16;
+
+/// `infix operator ** : MultiplicationPrecedence` or a `precedencegroup` body
+enum OperatorDeclaration {
+    Operator {
+        symbol: String,
+        fixity: Fixity,
+        group: Option<String>,
+    },
+    PrecedenceGroup {
+        name: String,
+        higher_than: Vec<String>,
+        lower_than: Vec<String>,
+        associativity: Associativity,
+    },
+}
+
+/// a member of an enum or extension body other than `case`
+enum TypeMember {
+    /// `static func ==`
+    Operator(FunctionDecl),
+    Method(FunctionDecl),
+    Property(PropertyDecl),
+    /// `static var` with accessors
+    StaticProperty(PropertyDecl),
+    /// `static let` or `static var` with a stored value
+    StaticStoredProperty(StoredPropertyDecl),
+}
+
+pub struct Parser {
+    tokens: Vec<Token>,
+    current: usize,
+    precedence_groups: HashMap<String, PrecedenceGroup>,
+    /// user-defined infix operators and the name of their precedence group
+    infix_operators: HashMap<String, String>,
+    prefix_operators: HashSet<String>,
+    postfix_operators: HashSet<String>,
+    /// cleared where a `{` starts the body of a statement: `if list.isEmpty {`
+    trailing_closures: bool,
+    /// for each closure being parsed, how many of `$0`, `$1`, ... its body uses
+    anonymous_parameters: Vec<usize>,
+    /// names declared with `protocol`, which stand for `any P` when used as a type
+    protocols: HashSet<String>,
+    /// names of generic enums, whose `<` starts type arguments in an expression
+    generic_enums: HashSet<String>,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        let precedence_groups = STANDARD_PRECEDENCE_GROUPS.iter().enumerate()
+            .map(|(index, (name, associativity))| {
+                let group = PrecedenceGroup {
+                    name: name.to_string(),
+                    level: (index as u32 + 1) * PRECEDENCE_LEVEL_STEP,
+                    associativity: *associativity,
+                };
+                (name.to_string(), group)
+            })
+            .collect();
+        Parser {
+            tokens,
+            current: 0,
+            precedence_groups,
+            infix_operators: HashMap::new(),
+            prefix_operators: HashSet::new(),
+            postfix_operators: HashSet::new(),
+            trailing_closures: true,
+            anonymous_parameters: Vec::new(),
+            protocols: HashSet::new(),
+            generic_enums: HashSet::new(),
+        }
+    }
+
+    /// get current token
+    fn peak(&self) -> &Token {
+        &self.tokens[self.current]
+    }
+
+    /// check if current token is specified type
+    fn check(&self, token_type: TokenType) -> bool {
+        if self.is_at_end() {
+            false
+        } else {
+            self.peak().token_type == token_type
+        }
+    }
+
+    /// whether the current token is the contextual keyword `keyword`
+    fn check_keyword(&self, keyword: &str) -> bool {
+        !self.is_at_end() && self.peak().is_keyword(keyword)
+    }
+
+    /// move to next token
+    fn advance(&mut self) -> Token {
+        if !self.is_at_end() {
+            self.current += 1
+        }
+        self.tokens[self.current - 1].clone()
+    }
+
+    /// look at the token after the current one
+    fn peak_next(&self) -> &Token {
+        let index = (self.current + 1).min(self.tokens.len() - 1);
+        &self.tokens[index]
+    }
+
+    /// check if moved to EOF
+    fn is_at_end(&self) -> bool {
+        self.peak().token_type == TokenType::Eof
+    }
+
+    fn consume(&mut self, token_type: TokenType, message: &str) -> Result<Token, String> {
+        if self.check(token_type) {
+            Ok(self.advance())
+        } else {
+            Err(format!("{} at token {:?}", message, self.peak()))
+        }
+    }
+
+    pub fn parse(&mut self) -> Result<AstNode, String> {
+        self.collect_operator_declarations()?;
+        self.collect_type_names();
+        let mut statements = Vec::new();
+
+        while !self.is_at_end() {
+            statements.push(self.parse_statement()?);
+            self.end_statement()?;
+        }
+
+        // the members an extension adds to a generic enum see its type parameters
+        let generic_enums = statements.iter()
+            .filter_map(|statement| match statement {
+                Statement::EnumDecl(decl) if !decl.generic_parameters.is_empty() => {
+                    Some((decl.name.clone(), decl.generic_bindings()))
+                }
+                _ => None,
+            })
+            .collect::<HashMap<_, _>>();
+        for statement in &mut statements {
+            if let Statement::Extension(decl) = statement
+                && let Some(bindings) = generic_enums.get(&decl.extended_type)
+            {
+                decl.substitute_types(bindings);
+            }
+        }
+
+        Ok(AstNode::Program(statements))
+    }
+
+    /// Statements end at a `;`, a line break or the end of their block or
+    /// switch case, so `print(1) print(2)` is an error.
+    fn end_statement(&mut self) -> Result<(), String> {
+        if self.check(TokenType::Case) || self.check(TokenType::Default) {
+            return Ok(());
+        }
+        self.end_line_item("statements")
+    }
+
+    /// members of a type body: `case north; case south`
+    fn end_declaration(&mut self) -> Result<(), String> {
+        self.end_line_item("declarations")
+    }
+
+    fn end_line_item(&mut self, kind: &str) -> Result<(), String> {
+        if self.check(TokenType::Semicolon) {
+            self.advance();
+            return Ok(());
+        }
+        if self.check(TokenType::RightBrace) || self.is_at_end() || self.peak().newline_before {
+            return Ok(());
+        }
+        Err(format!("consecutive {} on a line must be separated by ';'", kind))
+    }
+
+    /// whether the tokens at `index` start `precedencegroup` or `infix operator`
+    fn is_operator_declaration(&self, index: usize) -> bool {
+        let token = &self.tokens[index];
+        if token.token_type != TokenType::Identifier || token.is_escaped {
+            return false;
+        }
+        match token.lexeme.as_str() {
+            "precedencegroup" => true,
+            "infix" | "prefix" | "postfix" => self.tokens.get(index + 1)
+                .is_some_and(|next| next.is_keyword("operator")),
+            _ => false,
+        }
+    }
+
+    /// Operators can be used before their declaration, so operator and
+    /// precedence group declarations are parsed up front and removed from the tokens.
+    fn collect_operator_declarations(&mut self) -> Result<(), String> {
+        let mut declarations = Vec::new();
+        let mut depth = 0usize;
+        let mut index = 0;
+        while index < self.tokens.len() {
+            match self.tokens[index].token_type {
+                TokenType::LeftBrace => depth += 1,
+                TokenType::RightBrace => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            if !self.is_operator_declaration(index) {
+                index += 1;
+                continue;
+            }
+            if depth > 0 {
+                return Err("operator and precedence group declarations must be at file scope".to_string());
+            }
+            self.current = index;
+            declarations.push(self.parse_operator_declaration()?);
+            self.tokens.drain(index..self.current);
+        }
+        self.current = 0;
+
+        let mut groups = HashMap::new();
+        for declaration in &declarations {
+            if let OperatorDeclaration::PrecedenceGroup { name, .. } = declaration
+                && (self.precedence_groups.contains_key(name) || groups.insert(name.clone(), declaration).is_some())
+            {
+                return Err(format!("precedence group '{}' redeclared", name));
+            }
+        }
+        for declaration in &declarations {
+            if let OperatorDeclaration::PrecedenceGroup { name, .. } = declaration {
+                self.resolve_precedence_group(name, &groups, &mut Vec::new())?;
+            }
+        }
+        for declaration in declarations {
+            if let OperatorDeclaration::Operator { symbol, fixity, group } = declaration {
+                self.declare_operator(symbol, fixity, group)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Protocols and generic enums can be used before their declaration.
+    fn collect_type_names(&mut self) {
+        self.protocols.insert("Error".to_string());
+        for pair in self.tokens.windows(2) {
+            if pair[0].token_type == TokenType::Protocol && pair[1].token_type == TokenType::Identifier {
+                self.protocols.insert(pair[1].lexeme.clone());
+            }
+        }
+        for triple in self.tokens.windows(3) {
+            if triple[0].token_type == TokenType::Enum
+                && triple[1].token_type == TokenType::Identifier
+                && triple[2].token_type == TokenType::Less
+            {
+                self.generic_enums.insert(triple[1].lexeme.clone());
+            }
+        }
+    }
+
+    /// `infix operator ** : MultiplicationPrecedence`, `prefix operator √` or
+    /// `precedencegroup ExponentiationPrecedence { associativity: right higherThan: MultiplicationPrecedence }`
+    fn parse_operator_declaration(&mut self) -> Result<OperatorDeclaration, String> {
+        let fixity = match self.advance().lexeme.as_str() {
+            "precedencegroup" => return self.parse_precedence_group(),
+            "prefix" => Fixity::Prefix,
+            "postfix" => Fixity::Postfix,
+            _ => Fixity::Infix,
+        };
+        self.advance(); // consume 'operator'
+        let symbol = self.consume(TokenType::Operator, "Expected operator name in operator declaration")?.lexeme;
+        let group = if self.check(TokenType::Colon) {
+            self.advance();
+            if fixity != Fixity::Infix {
+                return Err("only infix operators may declare a precedence".to_string());
+            }
+            Some(self.consume(TokenType::Identifier, "Expected precedence group name after ':'")?.lexeme)
+        } else {
+            None
+        };
+        Ok(OperatorDeclaration::Operator { symbol, fixity, group })
+    }
+
+    fn parse_precedence_group(&mut self) -> Result<OperatorDeclaration, String> {
+        let name = self.consume(TokenType::Identifier, "Expected precedence group name")?.lexeme;
+        self.consume(TokenType::LeftBrace, "Expected '{' after precedence group name")?;
+        let mut higher_than = Vec::new();
+        let mut lower_than = Vec::new();
+        let mut associativity = Associativity::None;
+        while !self.check(TokenType::RightBrace) {
+            let attribute = self.consume(TokenType::Identifier, "Expected attribute in precedence group")?.lexeme;
+            self.consume(TokenType::Colon, &format!("Expected ':' after '{}'", attribute))?;
+            match attribute.as_str() {
+                "associativity" => {
+                    associativity = match self.consume(TokenType::Identifier, "Expected associativity")?.lexeme.as_str() {
+                        "left" => Associativity::Left,
+                        "right" => Associativity::Right,
+                        "none" => Associativity::None,
+                        _ => return Err("expected 'none', 'left', or 'right' after 'associativity'".to_string()),
+                    };
+                }
+                "higherThan" | "lowerThan" => {
+                    let mut names = vec![self.consume(TokenType::Identifier, "Expected precedence group name")?.lexeme];
+                    while self.check(TokenType::Comma) {
+                        self.advance();
+                        names.push(self.consume(TokenType::Identifier, "Expected precedence group name")?.lexeme);
+                    }
+                    if attribute == "higherThan" {
+                        higher_than.extend(names);
+                    } else {
+                        lower_than.extend(names);
+                    }
+                }
+                // only changes how optional chaining folds into the operator, which has no effect here
+                "assignment" => {
+                    if !self.check(TokenType::True) && !self.check(TokenType::False) {
+                        return Err("expected 'true' or 'false' after 'assignment'".to_string());
+                    }
+                    self.advance();
+                }
+                _ => return Err(format!("'{}' is not a valid precedence group attribute", attribute)),
+            }
+        }
+        self.consume(TokenType::RightBrace, "Expected '}' after precedence group")?;
+        Ok(OperatorDeclaration::PrecedenceGroup { name, higher_than, lower_than, associativity })
+    }
+
+    /// Gives a user-defined group the level just above its tightest `higherThan`
+    /// group, which must stay below every `lowerThan` group. Without `higherThan`
+    /// the group sits just above `TernaryPrecedence`, like `DefaultPrecedence`.
+    fn resolve_precedence_group(
+        &mut self,
+        name: &str,
+        declarations: &HashMap<String, &OperatorDeclaration>,
+        resolving: &mut Vec<String>,
+    ) -> Result<u32, String> {
+        if let Some(group) = self.precedence_groups.get(name) {
+            return Ok(group.level);
+        }
+        let Some(OperatorDeclaration::PrecedenceGroup { higher_than, lower_than, associativity, .. }) =
+            declarations.get(name).copied()
+        else {
+            return Err(format!("unknown precedence group '{}'", name));
+        };
+        if resolving.iter().any(|group| group == name) {
+            return Err(format!("cycle in precedence group relations involving '{}'", name));
+        }
+        resolving.push(name.to_string());
+
+        let mut lower = self.precedence_groups["TernaryPrecedence"].level;
+        for group in higher_than {
+            lower = lower.max(self.resolve_precedence_group(group, declarations, resolving)?);
+        }
+        let mut upper = u32::MAX;
+        for group in lower_than {
+            upper = upper.min(self.resolve_precedence_group(group, declarations, resolving)?);
+        }
+        resolving.pop();
+
+        // stay below the next existing group so levels never collide
+        let next = self.precedence_groups.values()
+            .map(|group| group.level)
+            .filter(|level| *level > lower)
+            .min()
+            .unwrap_or(lower + PRECEDENCE_LEVEL_STEP);
+        if upper <= lower {
+            return Err(format!("precedence group '{}' cannot be both higher and lower than the same groups", name));
+        }
+        let level = lower + (next.min(upper) - lower) / 2;
+        if level == lower {
+            return Err(format!("too many precedence groups declared between the neighbours of '{}'", name));
+        }
+        self.precedence_groups.insert(name.to_string(), PrecedenceGroup {
+            name: name.to_string(),
+            level,
+            associativity: *associativity,
+        });
+        Ok(level)
+    }
+
+    fn declare_operator(&mut self, symbol: String, fixity: Fixity, group: Option<String>) -> Result<(), String> {
+        let is_new = match fixity {
+            Fixity::Infix => {
+                let group = group.unwrap_or_else(|| "DefaultPrecedence".to_string());
+                if !self.precedence_groups.contains_key(&group) {
+                    return Err(format!("unknown precedence group '{}'", group));
+                }
+                self.infix_operators.insert(symbol.clone(), group).is_none()
+            }
+            Fixity::Prefix => self.prefix_operators.insert(symbol.clone()),
+            Fixity::Postfix => self.postfix_operators.insert(symbol.clone()),
+        };
+        if !is_new {
+            return Err(format!("operator '{}' redeclared", symbol));
+        }
+        Ok(())
+    }
+
+    fn parse_statement(&mut self) -> Result<Statement, String> {
+        match self.peak().token_type {
+            TokenType::Print => {
+                self.parse_print()
+            }
+            TokenType::Let => {
+                self.parse_declaration()
+            }
+            TokenType::Var => {
+                self.parse_declaration()
+            }
+            TokenType::Enum => {
+                self.parse_enum()
+            }
+            TokenType::Switch => {
+                self.parse_switch()
+            }
+            TokenType::If => {
+                self.parse_if()
+            }
+            TokenType::While => {
+                self.parse_while()
+            }
+            TokenType::For => {
+                self.parse_for()
+            }
+            TokenType::Guard => {
+                self.parse_guard()
+            }
+            TokenType::Func => {
+                self.parse_function()
+            }
+            TokenType::Identifier | TokenType::Static if self.is_function_declaration() => {
+                self.parse_function()
+            }
+            TokenType::Protocol => {
+                self.parse_protocol()
+            }
+            TokenType::Extension => {
+                self.parse_extension()
+            }
+            TokenType::Identifier if self.check_keyword("lazy")
+                && matches!(self.peak_next().token_type, TokenType::Var | TokenType::Let) =>
+            {
+                self.parse_lazy_declaration()
+            }
+            TokenType::Static if matches!(self.peak_next().token_type, TokenType::Var | TokenType::Let) => {
+                Err("static properties may only be declared on a type".to_string())
+            }
+            TokenType::Throw => {
+                self.advance();
+                Ok(Statement::Throw(self.parse_expression()?))
+            }
+            TokenType::Do => self.parse_do(),
+            TokenType::Defer => {
+                self.advance();
+                Ok(Statement::Defer(self.parse_block()?))
+            }
+            TokenType::Return => {
+                self.advance();
+                // a value follows unless the block ends here
+                let value = match self.peak().token_type {
+                    TokenType::RightBrace | TokenType::Case | TokenType::Default | TokenType::Eof
+                    | TokenType::Semicolon => None,
+                    _ => Some(self.parse_expression()?),
+                };
+                Ok(Statement::Return(value))
+            }
+            TokenType::Break => {
+                self.advance();
+                Ok(Statement::Break)
+            }
+            TokenType::Continue => {
+                self.advance();
+                Ok(Statement::Continue)
+            }
+            // `_ = value` evaluates the value and ignores it, like any expression statement
+            TokenType::Underscore if self.peak_next().token_type == TokenType::Assign => {
+                self.advance();
+                self.advance();
+                Ok(Statement::Expression(self.parse_expression()?))
+            }
+            // an expression statement, which is also the target of an assignment
+            TokenType::Identifier | TokenType::Dot | TokenType::LeftParen | TokenType::LeftBracket
+            | TokenType::Minus | TokenType::Bang | TokenType::Tilde | TokenType::Operator
+            | TokenType::Number | TokenType::FloatLiteral | TokenType::StringLiteral | TokenType::InterpolationStart
+            | TokenType::True | TokenType::False | TokenType::Nil | TokenType::Try => {
+                let expr = self.parse_expression()?;
+                match self.assignment_operator() {
+                    Some(operator) => self.parse_assignment(expr, operator),
+                    None => Ok(Statement::Expression(expr)),
+                }
+            }
+            _ => {
+                Err(format!("Unexpected token in statement: {:?}", self.peak()))
+            }
+        }
+    }
+
+    /// parse a type such as `Int`, `Direction`, `Int?`, `Optional<Int>`, `(x: Int, y: Int)` or `(Int) -> Bool`
+    fn parse_type(&mut self) -> Result<Type, String> {
+        let mut ty = if self.check(TokenType::LeftParen) {
+            let elements = self.parse_tuple_type_elements()?;
+            if self.check(TokenType::Arrow) || self.check(TokenType::Throws) {
+                return self.parse_function_type(elements);
+            }
+            Self::tuple_type(elements)?
+        } else if self.check(TokenType::LeftBracket) {
+            self.advance();
+            let element = self.parse_type()?;
+            if self.check(TokenType::Colon) {
+                self.advance();
+                let value = self.parse_type()?;
+                self.consume(TokenType::RightBracket, "Expected ']' in dictionary type")?;
+                Type::Dictionary(Box::new(element), Box::new(value))
+            } else {
+                self.consume(TokenType::RightBracket, "Expected ']' in array type")?;
+                Type::Array(Box::new(element))
+            }
+        } else {
+            self.parse_type_name()?
+        };
+
+        // sugar for Optional: Int?
+        loop {
+            if self.check(TokenType::Question) {
+                ty = Type::Optional(Box::new(ty));
+            } else if self.check(TokenType::QuestionQuestion) {
+                ty = Type::Optional(Box::new(Type::Optional(Box::new(ty))));
+            } else {
+                break;
+            }
+            self.advance();
+        }
+        Ok(ty)
+    }
+
+    /// the parenthesized elements of a tuple type or the parameters of a function type
+    fn parse_tuple_type_elements(&mut self) -> Result<Vec<TupleElement>, String> {
+        self.consume(TokenType::LeftParen, "Expected '(' in type")?;
+        let mut elements = Vec::new();
+        while !self.check(TokenType::RightParen) {
+            let label = if self.check(TokenType::Identifier) && self.peak_next().token_type == TokenType::Colon {
+                let label = self.advance().lexeme;
+                self.advance(); // consume ':'
+                Some(label)
+            } else {
+                None
+            };
+            let element_type = self.parse_type()?;
+            elements.push(TupleElement { label, element_type });
+
+            if !self.check(TokenType::RightParen) {
+                self.consume(TokenType::Comma, "Expected ',' between tuple elements")?;
+            }
+        }
+        self.consume(TokenType::RightParen, "Expected ')' in type")?;
+        Ok(elements)
+    }
+
+    /// `()` is Void and `(Int)` is just Int, anything else is a tuple type
+    fn tuple_type(mut elements: Vec<TupleElement>) -> Result<Type, String> {
+        match elements.len() {
+            0 => Ok(Type::Void),
+            1 if elements[0].label.is_some() => {
+                Err("cannot create a single-element tuple with an element label".to_string())
+            }
+            1 => Ok(elements.remove(0).element_type),
+            _ => Ok(Type::Tuple(elements)),
+        }
+    }
+
+    /// `(Int, Int) throws -> Bool` after its parameter types; the result
+    /// takes the rest of the type, so `() -> Int?` returns an optional
+    fn parse_function_type(&mut self, parameters: Vec<TupleElement>) -> Result<Type, String> {
+        if let Some(label) = parameters.iter().find_map(|parameter| parameter.label.as_ref()) {
+            return Err(format!("function types cannot have argument labels; use '_' before '{}'", label));
+        }
+        let throws = self.check(TokenType::Throws);
+        if throws {
+            self.advance();
+        }
+        self.consume(TokenType::Arrow, "Expected '->' in function type")?;
+        let result = self.parse_type()?;
+        Ok(Type::Function {
+            parameters: parameters.into_iter().map(|parameter| parameter.element_type).collect(),
+            result: Box::new(result),
+            throws,
+        })
+    }
+
+    fn parse_type_name(&mut self) -> Result<Type, String> {
+        let name_token = self.consume(TokenType::Identifier, "Expected type name")?;
+        if let Some(ty) = Type::builtin(&name_token.lexeme) {
+            return Ok(ty);
+        }
+        let ty = match name_token.lexeme.as_str() {
+            "any" | "some" if !name_token.is_escaped && self.check(TokenType::Identifier) => {
+                let ty = self.parse_type_name()?;
+                match ty {
+                    Type::Existential(protocol) if name_token.lexeme == "some" => Type::Opaque(protocol),
+                    Type::Existential(_) => ty,
+                    _ if name_token.lexeme == "any" => return Err(format!("'any' has no effect on concrete type '{}'", ty)),
+                    _ => {
+                        return Err("an 'opaque' type must specify only 'Any', 'AnyObject', protocols, and/or a base class".to_string());
+                    }
+                }
+            }
+            "Optional" => {
+                self.consume(TokenType::Less, "Expected '<' after 'Optional'")?;
+                let wrapped = self.parse_type()?;
+                self.consume_generic_close()?;
+                Type::Optional(Box::new(wrapped))
+            }
+            "Array" => {
+                self.consume(TokenType::Less, "Expected '<' after 'Array'")?;
+                let element = self.parse_type()?;
+                self.consume_generic_close()?;
+                Type::Array(Box::new(element))
+            }
+            "Dictionary" => {
+                self.consume(TokenType::Less, "Expected '<' after 'Dictionary'")?;
+                let key = self.parse_type()?;
+                self.consume(TokenType::Comma, "Expected ',' between the key and value types")?;
+                let value = self.parse_type()?;
+                self.consume_generic_close()?;
+                Type::Dictionary(Box::new(key), Box::new(value))
+            }
+            "Set" => {
+                self.consume(TokenType::Less, "Expected '<' after 'Set'")?;
+                let element = self.parse_type()?;
+                self.consume_generic_close()?;
+                Type::Set(Box::new(element))
+            }
+            name if self.protocols.contains(name) => Type::Existential(name_token.lexeme),
+            // `Box<Int>`, a specialization of a generic enum
+            _ if self.check(TokenType::Less) => {
+                self.advance();
+                let mut arguments = vec![self.parse_type()?];
+                while self.check(TokenType::Comma) {
+                    self.advance();
+                    arguments.push(self.parse_type()?);
+                }
+                self.consume_generic_close()?;
+                Type::Named(name_token.lexeme, arguments)
+            }
+            _ => Type::Named(name_token.lexeme, Vec::new()),
+        };
+        Ok(ty)
+    }
+
+    /// `>` ending a generic argument list; an operator token such as the `>>`
+    /// of `Optional<Optional<Int>>` or `>?` of `Optional<Int>?` is split
+    fn consume_generic_close(&mut self) -> Result<(), String> {
+        let token = self.peak();
+        if matches!(
+            token.token_type,
+            TokenType::GreaterGreater | TokenType::GreaterEqual | TokenType::GreaterGreaterAssign | TokenType::Operator
+        ) && token.lexeme.starts_with('>')
+        {
+            self.tokens[self.current] = Token::operator(&token.lexeme[1..]);
+            return Ok(());
+        }
+        self.consume(TokenType::Greater, "Expected '>' after generic argument")?;
+        Ok(())
+    }
+
+    /// statements between `{` and `}`
+    fn parse_block(&mut self) -> Result<Vec<Statement>, String> {
+        self.consume(TokenType::LeftBrace, "Expected '{'")?;
+        let mut statements = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            statements.push(self.parse_statement()?);
+            self.end_statement()?;
+        }
+        self.consume(TokenType::RightBrace, "Expected '}'")?;
+        Ok(statements)
+    }
+
+    fn parse_if(&mut self) -> Result<Statement, String> {
+        self.consume(TokenType::If, "Expected 'if' keyword")?;
+        let conditions = self.with_trailing_closures(false, Self::parse_conditions)?;
+        let then_branch = self.parse_block()?;
+
+        let else_branch = if self.check(TokenType::Else) {
+            self.advance();
+            if self.check(TokenType::If) {
+                Some(vec![self.parse_if()?])
+            } else {
+                Some(self.parse_block()?)
+            }
+        } else {
+            None
+        };
+
+        Ok(Statement::If {
+            conditions,
+            then_branch,
+            else_branch,
+        })
+    }
+
+    /// `do { ... } catch MyError.notFound { ... } catch { ... }`
+    fn parse_do(&mut self) -> Result<Statement, String> {
+        self.advance(); // consume 'do'
+        let body = self.parse_block()?;
+        let mut catches = Vec::new();
+        while self.check(TokenType::Catch) {
+            self.advance();
+            let (pattern, error_type) = self.parse_catch_pattern()?;
+            let body = self.parse_block()?;
+            catches.push(CatchClause { pattern, error_type, body });
+        }
+        Ok(Statement::Do { body, catches })
+    }
+
+    /// `let e as MyError`, `is MyError`, an enum case pattern such as
+    /// `MyError.invalid(let line)`, or nothing, which binds the error as `error`
+    fn parse_catch_pattern(&mut self) -> Result<(Pattern, Option<Type>), String> {
+        if self.check(TokenType::LeftBrace) {
+            return Ok((Pattern::Binding("error".to_string()), None));
+        }
+        if self.check(TokenType::Is) {
+            self.advance();
+            return Ok((Pattern::Wildcard, Some(self.parse_type()?)));
+        }
+        let pattern = self.parse_pattern(false)?;
+        let error_type = match &pattern {
+            Pattern::EnumCase { enum_name: Some(enum_name), .. } => Some(Type::Named(enum_name.clone(), Vec::new())),
+            Pattern::Binding(_) | Pattern::Wildcard if self.check(TokenType::As) => {
+                self.advance();
+                Some(self.parse_type()?)
+            }
+            _ => None,
+        };
+        Ok((pattern, error_type))
+    }
+
+    fn parse_guard(&mut self) -> Result<Statement, String> {
+        self.consume(TokenType::Guard, "Expected 'guard' keyword")?;
+        let conditions = self.with_trailing_closures(false, Self::parse_conditions)?;
+        self.consume(TokenType::Else, "Expected 'else' after 'guard' condition")?;
+        let else_branch = self.parse_block()?;
+        Ok(Statement::Guard {
+            conditions,
+            else_branch,
+        })
+    }
+
+    /// whether the current token starts a function, possibly after modifiers: `prefix func √`
+    fn is_function_declaration(&self) -> bool {
+        let mut index = self.current;
+        while self.tokens[index].token_type == TokenType::Static
+            || ["prefix", "postfix", "infix"].iter().any(|modifier| self.tokens[index].is_keyword(modifier))
+        {
+            index += 1;
+        }
+        self.tokens[index].token_type == TokenType::Func
+    }
+
+    /// whether the modifiers before `func` include `static`
+    fn has_static_modifier(&self) -> bool {
+        self.tokens[self.current..].iter()
+            .take_while(|token| matches!(token.token_type, TokenType::Identifier | TokenType::Static))
+            .any(|token| token.token_type == TokenType::Static)
+    }
+
+    fn parse_function(&mut self) -> Result<Statement, String> {
+        if self.has_static_modifier() {
+            return Err("static methods may only be declared on a type".to_string());
+        }
+        Ok(Statement::FunctionDecl(self.parse_function_decl()?))
+    }
+
+    /// func name(label parameter: Type, _ other: Type) -> ReturnType { ... }
+    /// or an operator implementation: prefix func √ (value: Double) -> Double { ... }
+    fn parse_function_decl(&mut self) -> Result<FunctionDecl, String> {
+        let mut function = self.parse_function_signature()?;
+        function.body = self.parse_block()?;
+        // inside the function its type parameters stand for any type
+        let bindings = function.generic_parameters.iter()
+            .map(|parameter| (parameter.name.clone(), Type::Generic(parameter.name.clone())))
+            .collect::<Vec<_>>();
+        let generic_parameters = std::mem::take(&mut function.generic_parameters);
+        function = function.substitute(&bindings);
+        function.generic_parameters = generic_parameters;
+        Ok(function)
+    }
+
+    /// `where T: Equatable, U: Shape`
+    fn parse_where_clause(&mut self) -> Result<Vec<(String, String)>, String> {
+        let mut constraints = Vec::new();
+        if self.check_keyword("where") {
+            loop {
+                self.advance(); // consume 'where' or ','
+                let parameter = self.consume(TokenType::Identifier, "Expected generic parameter in 'where' clause")?.lexeme;
+                if !self.check(TokenType::Colon) {
+                    return Err(format!("only conformance requirements such as '{}: Equatable' are supported in 'where' clauses", parameter));
+                }
+                self.advance();
+                let protocol = self.consume(TokenType::Identifier, "Expected protocol name")?.lexeme;
+                constraints.push((parameter, protocol));
+                if !self.check(TokenType::Comma) {
+                    break;
+                }
+            }
+        }
+        Ok(constraints)
+    }
+
+    /// `<T, U: Equatable>` after the name of a generic function
+    fn parse_generic_parameters(&mut self) -> Result<Vec<GenericParameter>, String> {
+        let mut parameters: Vec<GenericParameter> = Vec::new();
+        if !self.check(TokenType::Less) {
+            return Ok(parameters);
+        }
+        self.advance();
+        loop {
+            let name = self.consume(TokenType::Identifier, "Expected generic parameter name")?.lexeme;
+            if parameters.iter().any(|parameter| parameter.name == name) {
+                return Err(format!("invalid redeclaration of '{}'", name));
+            }
+            let mut constraints = Vec::new();
+            if self.check(TokenType::Colon) {
+                self.advance();
+                constraints.push(self.consume(TokenType::Identifier, "Expected protocol name")?.lexeme);
+            }
+            parameters.push(GenericParameter { name, constraints });
+            if !self.check(TokenType::Comma) {
+                break;
+            }
+            self.advance();
+        }
+        self.consume_generic_close()?;
+        Ok(parameters)
+    }
+
+    /// a function declaration up to its body, which is left empty
+    fn parse_function_signature(&mut self) -> Result<FunctionDecl, String> {
+        let doc_comment = self.peak().doc_comment.clone();
+        let mut modifier = None;
+        while self.check(TokenType::Identifier) || self.check(TokenType::Static) {
+            match self.advance().lexeme.as_str() {
+                "prefix" => modifier = Some(Fixity::Prefix),
+                "postfix" => modifier = Some(Fixity::Postfix),
+                "infix" => modifier = Some(Fixity::Infix),
+                _ => {} // `static`, checked by the caller
+            }
+        }
+        self.consume(TokenType::Func, "Expected 'func' keyword")?;
+        let is_operator = self.check(TokenType::Operator);
+        let name = if is_operator {
+            self.advance().lexeme
+        } else {
+            self.consume(TokenType::Identifier, "Expected function name")?.lexeme
+        };
+        if let Some(fixity) = modifier
+            && !is_operator
+        {
+            return Err(format!("'{}' modifier is only valid for operator functions", fixity.modifier().trim_end()));
+        }
+        let mut generic_parameters = if is_operator { Vec::new() } else { self.parse_generic_parameters()? };
+
+        self.consume(TokenType::LeftParen, "Expected '(' after function name")?;
+        let mut parameters = Vec::new();
+        while !self.check(TokenType::RightParen) {
+            // the first name is the argument label when a second name follows
+            let first = if self.check(TokenType::Underscore) {
+                self.advance();
+                None
+            } else {
+                Some(self.consume(TokenType::Identifier, "Expected parameter name")?.lexeme)
+            };
+            let (label, name) = if self.check(TokenType::Identifier) {
+                (first, self.advance().lexeme)
+            } else {
+                match first {
+                    Some(name) => (Some(name.clone()), name),
+                    None => return Err("Expected parameter name after '_'".to_string()),
+                }
+            };
+            self.consume(TokenType::Colon, "Expected ':' after parameter name")?;
+            let is_inout = self.check(TokenType::Inout);
+            if is_inout {
+                self.advance();
+            }
+            // each `some P` is a type parameter of its own constrained to `P`
+            let param_type = self.parse_type()?.replace(&mut |ty| {
+                let Type::Opaque(protocol) = ty else {
+                    return None;
+                };
+                let name = format!("some {}#{}", protocol, generic_parameters.len());
+                generic_parameters.push(GenericParameter { name: name.clone(), constraints: vec![protocol.clone()] });
+                Some(Type::Named(name, Vec::new()))
+            });
+            parameters.push(Parameter { label, name, param_type, is_inout });
+
+            if !self.check(TokenType::RightParen) {
+                self.consume(TokenType::Comma, "Expected ',' between parameters")?;
+            }
+        }
+        self.consume(TokenType::RightParen, "Expected ')' after parameters")?;
+
+        let rethrows = self.check(TokenType::Rethrows);
+        if rethrows && !parameters.iter().any(|parameter| matches!(parameter.param_type, Type::Function { throws: true, .. })) {
+            return Err("'rethrows' function must take a throwing function argument".to_string());
+        }
+        let throws = rethrows || self.check(TokenType::Throws);
+        if throws {
+            self.advance();
+        }
+
+        let return_type = if self.check(TokenType::Arrow) {
+            self.advance();
+            self.parse_type()?
+        } else {
+            Type::Void
+        };
+        let (return_type, opaque_result) = match return_type {
+            Type::Opaque(protocol) => (Type::Existential(protocol), true),
+            ty => (ty, false),
+        };
+
+        for (parameter, protocol) in self.parse_where_clause()? {
+            let Some(generic_parameter) = generic_parameters.iter_mut().find(|generic| generic.name == parameter) else {
+                return Err(format!("cannot find type '{}' in scope", parameter));
+            };
+            generic_parameter.constraints.push(protocol);
+        }
+
+        let operator = if is_operator {
+            Some(self.operator_fixity(&name, modifier, &mut parameters)?)
+        } else {
+            None
+        };
+        Ok(FunctionDecl {
+            name,
+            generic_parameters,
+            parameters,
+            return_type,
+            body: Vec::new(),
+            operator,
+            throws,
+            rethrows,
+            opaque_result,
+            is_mutating: false,
+            doc_comment,
+        })
+    }
+
+    /// The fixity of an operator implementation, checked against its declaration.
+    /// Operands are never labeled, so the parameters lose their labels.
+    fn operator_fixity(&self, symbol: &str, modifier: Option<Fixity>, parameters: &mut [Parameter]) -> Result<Fixity, String> {
+        let fixity = match (parameters.len(), modifier) {
+            (1, Some(Fixity::Prefix)) => Fixity::Prefix,
+            (1, Some(Fixity::Postfix)) => Fixity::Postfix,
+            (1, _) => return Err("unary operator implementation must have a 'prefix' or 'postfix' modifier".to_string()),
+            (2, None | Some(Fixity::Infix)) => Fixity::Infix,
+            (2, Some(fixity)) => {
+                return Err(format!("'{}' modifier is only valid for unary operators", fixity.modifier().trim_end()));
+            }
+            _ => return Err("operators must have one or two arguments".to_string()),
+        };
+        let is_declared = match fixity {
+            Fixity::Prefix => self.prefix_operators.contains(symbol),
+            Fixity::Infix => self.infix_operators.contains_key(symbol),
+            Fixity::Postfix => self.postfix_operators.contains(symbol),
+        };
+        if !is_declared {
+            return Err(format!("operator implementation without matching operator declaration for '{}'", symbol));
+        }
+        for parameter in parameters.iter_mut() {
+            parameter.label = None;
+        }
+        Ok(fixity)
+    }
+
+    /// for name in sequence { ... }
+    fn parse_for(&mut self) -> Result<Statement, String> {
+        self.consume(TokenType::For, "Expected 'for' keyword")?;
+        let pattern = if self.check(TokenType::LeftParen) {
+            ForPattern::Tuple(self.parse_tuple_pattern()?)
+        } else if self.check(TokenType::Underscore) {
+            self.advance();
+            ForPattern::Name(None)
+        } else {
+            ForPattern::Name(Some(self.consume(TokenType::Identifier, "Expected loop variable name after 'for'")?.lexeme))
+        };
+        self.consume(TokenType::In, "Expected 'in' after for-in pattern")?;
+        let sequence = self.with_trailing_closures(false, Self::parse_expression)?;
+        let body = self.parse_block()?;
+        Ok(Statement::ForIn { pattern, sequence, body })
+    }
+
+    fn parse_while(&mut self) -> Result<Statement, String> {
+        self.consume(TokenType::While, "Expected 'while' keyword")?;
+        let conditions = self.with_trailing_closures(false, Self::parse_conditions)?;
+        let body = self.parse_block()?;
+        Ok(Statement::While { conditions, body })
+    }
+
+    /// comma separated conditions: `let x = optional, x > 0`
+    fn parse_conditions(&mut self) -> Result<Vec<Condition>, String> {
+        let mut conditions = Vec::new();
+        loop {
+            if self.check(TokenType::Let) || self.check(TokenType::Var) {
+                let is_mutable = self.advance().token_type == TokenType::Var;
+                let name = self.consume(TokenType::Identifier, "Expected variable name in optional binding")?.lexeme;
+                let type_annotation = if self.check(TokenType::Colon) {
+                    self.advance();
+                    Some(self.parse_type()?)
+                } else {
+                    None
+                };
+                // `if let x {` is shorthand for `if let x = x {`
+                let value = if self.check(TokenType::Assign) {
+                    self.advance();
+                    self.parse_expression()?
+                } else {
+                    Expression::Variable(name.clone())
+                };
+                conditions.push(Condition::OptionalBinding {
+                    name,
+                    is_mutable,
+                    type_annotation,
+                    value,
+                });
+            } else {
+                conditions.push(Condition::Expression(self.parse_expression()?));
+            }
+
+            if self.check(TokenType::Comma) {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        Ok(conditions)
+    }
+
+    fn parse_enum(&mut self) -> Result<Statement, String> {
+        let doc_comment = self.peak().doc_comment.clone();
+        self.consume(TokenType::Enum, "Expected 'enum' keyword")?;
+        let name = self.consume(TokenType::Identifier, "Expected enum name")?.lexeme;
+        let mut generic_parameters = self.parse_generic_parameters()?;
+
+        // inheritance clause: a raw type (: Int) followed by protocols (: Int, Equatable)
+        let mut raw_type = None;
+        let mut conformances = Vec::new();
+        if self.check(TokenType::Colon) {
+            loop {
+                self.advance(); // consume ':' or ','
+                match self.parse_type()? {
+                    Type::Named(protocol, arguments) if arguments.is_empty() => conformances.push(protocol),
+                    Type::Existential(protocol) => conformances.push(protocol),
+                    ty if conformances.is_empty() && raw_type.is_none() => raw_type = Some(ty),
+                    ty => return Err(format!("raw type '{}' must appear first in the enum inheritance clause", ty)),
+                }
+                if !self.check(TokenType::Comma) {
+                    break;
+                }
+            }
+        }
+
+        for (parameter, protocol) in self.parse_where_clause()? {
+            let Some(generic_parameter) = generic_parameters.iter_mut().find(|generic| generic.name == parameter) else {
+                return Err(format!("cannot find type '{}' in scope", parameter));
+            };
+            generic_parameter.constraints.push(protocol);
+        }
+
+        self.consume(TokenType::LeftBrace, "Expected '{' after enum name")?;
+        let mut cases = Vec::new();
+        let mut static_functions = Vec::new();
+        let mut methods = Vec::new();
+        let mut properties = Vec::new();
+        let mut static_properties = Vec::new();
+        let mut static_stored_properties = Vec::new();
+        while !self.check(TokenType::RightBrace) {
+            if self.is_function_declaration() || self.is_property_declaration() {
+                match self.parse_type_member(&name, "enums")? {
+                    TypeMember::Operator(function) => static_functions.push(function),
+                    TypeMember::Method(function) => methods.push(function),
+                    TypeMember::Property(property) => properties.push(property),
+                    TypeMember::StaticProperty(property) => static_properties.push(property),
+                    TypeMember::StaticStoredProperty(property) => static_stored_properties.push(property),
+                }
+                self.end_declaration()?;
+                continue;
+            }
+            self.consume(TokenType::Case, "Expected 'case' in enum body")?;
+            loop {
+                cases.push(self.parse_enum_case()?);
+                if self.check(TokenType::Comma) {
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+            self.end_declaration()?;
+        }
+        self.consume(TokenType::RightBrace, "Expected '}' after enum body")?;
+
+        let decl = EnumDecl {
+            name,
+            generic_parameters,
+            raw_type,
+            conformances,
+            cases,
+            static_functions,
+            methods,
+            properties,
+            static_properties,
+            static_stored_properties,
+            doc_comment,
+        };
+        // inside a generic enum its type parameters stand for any type
+        Ok(Statement::EnumDecl(decl.substitute(&decl.generic_bindings())))
+    }
+
+    /// a function or property in the body of `type_name`; `kind` names the
+    /// declarations that cannot have stored instance properties
+    fn parse_type_member(&mut self, type_name: &str, kind: &str) -> Result<TypeMember, String> {
+        if !self.is_function_declaration() {
+            return self.parse_property_member(kind);
+        }
+        let is_static = self.has_static_modifier();
+        let function = self.parse_function_decl()?;
+        match (function.operator.is_some(), is_static) {
+            (true, true) => Ok(TypeMember::Operator(function)),
+            (true, false) => {
+                Err(format!("operator '{}' declared in type '{}' must be 'static'", function.name, type_name))
+            }
+            (false, true) => {
+                Err(format!("only operator implementations are supported as static functions in '{}'", type_name))
+            }
+            // specializations are emitted per call, which only global functions support
+            (false, false) if !function.generic_parameters.is_empty() => {
+                Err(format!("generic methods are not supported in '{}', declare a generic global function instead", type_name))
+            }
+            (false, false) => Ok(TypeMember::Method(function)),
+        }
+    }
+
+    /// whether the tokens start a `var` or `let`, possibly after `static` or `lazy`
+    fn is_property_declaration(&self) -> bool {
+        let mut index = self.current;
+        while self.tokens[index].token_type == TokenType::Static || self.tokens[index].is_keyword("lazy")
+        {
+            index += 1;
+        }
+        matches!(self.tokens[index].token_type, TokenType::Var | TokenType::Let)
+    }
+
+    /// `var description: String { ... }`, `static let origin = Point.zero`
+    /// or `static var count: Int { get { ... } set { ... } }`
+    fn parse_property_member(&mut self, kind: &str) -> Result<TypeMember, String> {
+        let doc_comment = self.peak().doc_comment.clone();
+        let mut is_static = false;
+        let mut is_lazy = false;
+        while self.check(TokenType::Static) || self.check_keyword("lazy") {
+            match self.advance().token_type {
+                TokenType::Static => is_static = true,
+                _ => is_lazy = true,
+            }
+        }
+        if is_lazy {
+            return Err(format!("{} must not contain stored properties", kind));
+        }
+        let is_var = self.advance().token_type == TokenType::Var;
+        let name = self.consume(TokenType::Identifier, "Expected property name")?.lexeme;
+        let type_annotation = if self.check(TokenType::Colon) {
+            self.advance();
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+
+        if let Some(property_type) = &type_annotation
+            && self.check(TokenType::LeftBrace)
+        {
+            if !is_var {
+                return Err("'let' declarations cannot be computed properties".to_string());
+            }
+            let property = self.parse_accessors(name, property_type.clone(), doc_comment)?;
+            return Ok(if is_static { TypeMember::StaticProperty(property) } else { TypeMember::Property(property) });
+        }
+        if !is_static {
+            return Err(format!("{} must not contain stored properties", kind));
+        }
+        if !self.check(TokenType::Assign) {
+            return Err(format!(
+                "'static {}' declaration requires an initializer expression or an explicitly stated getter",
+                if is_var { "var" } else { "let" }
+            ));
+        }
+        self.advance();
+        let value = self.parse_expression()?;
+        let (will_set, did_set) = self.parse_observers(is_var)?;
+        Ok(TypeMember::StaticStoredProperty(StoredPropertyDecl {
+            name,
+            type_annotation,
+            value,
+            is_mutable: is_var,
+            is_lazy: false,
+            will_set,
+            did_set,
+            doc_comment,
+        }))
+    }
+
+    /// The accessor block of a computed property: `{ get { ... } set { ... } }`
+    /// or just the body of a read-only getter, which may be a single expression.
+    fn parse_accessors(&mut self, name: String, property_type: Type, doc_comment: Option<String>) -> Result<PropertyDecl, String> {
+        if !self.starts_accessor_block(&["get", "set"]) {
+            let getter = self.parse_getter_body()?;
+            return Ok(PropertyDecl { name, property_type, getter, setter: None, doc_comment });
+        }
+        self.advance(); // consume '{'
+        let mut getter = None;
+        let mut setter = None;
+        while !self.check(TokenType::RightBrace) {
+            let keyword = self.consume(TokenType::Identifier, "Expected 'get' or 'set' accessor")?.lexeme;
+            match keyword.as_str() {
+                "get" if getter.is_none() => getter = Some(self.parse_getter_body()?),
+                "set" if setter.is_none() => setter = Some(self.parse_accessor("newValue")?),
+                "get" | "set" => return Err(format!("variable already has a {}ter", keyword)),
+                _ => return Err(format!("Expected 'get' or 'set' accessor, found '{}'", keyword)),
+            }
+        }
+        self.consume(TokenType::RightBrace, "Expected '}' after accessors")?;
+        let Some(getter) = getter else {
+            return Err("variable with a setter must also have a getter".to_string());
+        };
+        Ok(PropertyDecl { name, property_type, getter, setter, doc_comment })
+    }
+
+    fn parse_getter_body(&mut self) -> Result<Vec<Statement>, String> {
+        let mut getter = self.parse_block()?;
+        if let [Statement::Expression(value)] = getter.as_mut_slice() {
+            let value = std::mem::replace(value, Expression::Nil);
+            getter = vec![Statement::Return(Some(value))];
+        }
+        Ok(getter)
+    }
+
+    /// the body of `set`, `willSet` or `didSet`, whose parameter is named in parentheses or `default`
+    fn parse_accessor(&mut self, default: &str) -> Result<Accessor, String> {
+        let parameter = if self.check(TokenType::LeftParen) {
+            self.advance();
+            let name = self.consume(TokenType::Identifier, "Expected parameter name")?.lexeme;
+            self.consume(TokenType::RightParen, "Expected ')' after parameter name")?;
+            name
+        } else {
+            default.to_string()
+        };
+        let body = self.parse_block()?;
+        Ok(Accessor { parameter, body })
+    }
+
+    /// whether the `{` at the current token opens a block of the accessors `names`
+    fn starts_accessor_block(&self, names: &[&str]) -> bool {
+        self.check(TokenType::LeftBrace)
+            && names.iter().any(|name| self.peak_next().is_keyword(name))
+            && matches!(self.tokens[self.current + 2].token_type, TokenType::LeftBrace | TokenType::LeftParen)
+    }
+
+    /// `{ willSet { ... } didSet { ... } }` after the initial value of a stored variable
+    fn parse_observers(&mut self, is_mutable: bool) -> Result<(Option<Accessor>, Option<Accessor>), String> {
+        if !self.starts_accessor_block(&["willSet", "didSet"]) {
+            return Ok((None, None));
+        }
+        if !is_mutable {
+            return Err("'let' declarations cannot be observing properties".to_string());
+        }
+        self.advance(); // consume '{'
+        let mut will_set = None;
+        let mut did_set = None;
+        while !self.check(TokenType::RightBrace) {
+            let keyword = self.consume(TokenType::Identifier, "Expected 'willSet' or 'didSet' observer")?.lexeme;
+            match keyword.as_str() {
+                "willSet" if will_set.is_none() => will_set = Some(self.parse_accessor("newValue")?),
+                "didSet" if did_set.is_none() => did_set = Some(self.parse_accessor("oldValue")?),
+                "willSet" | "didSet" => return Err(format!("variable already has a '{}' observer", keyword)),
+                _ => return Err(format!("Expected 'willSet' or 'didSet' observer, found '{}'", keyword)),
+            }
+        }
+        self.consume(TokenType::RightBrace, "Expected '}' after observers")?;
+        Ok((will_set, did_set))
+    }
+
+    /// `extension Shape { ... }`, `extension Direction: CustomStringConvertible { ... }`
+    /// or `extension Array where Element: Equatable { ... }`
+    fn parse_extension(&mut self) -> Result<Statement, String> {
+        self.advance(); // consume 'extension'
+        let extended_type = self.consume(TokenType::Identifier, "Expected type name after 'extension'")?.lexeme;
+        if self.check(TokenType::Less) {
+            return Err(format!("cannot specialize generic type '{}' in an extension, use a 'where' clause instead", extended_type));
+        }
+
+        let mut conformances = Vec::new();
+        if self.check(TokenType::Colon) {
+            loop {
+                self.advance(); // consume ':' or ','
+                conformances.push(self.consume(TokenType::Identifier, "Expected protocol name")?.lexeme);
+                if !self.check(TokenType::Comma) {
+                    break;
+                }
+            }
+        }
 
-pub struct Parser {
-    tokens: Vec<Token>,
-    current: usize,
-}
+        let constraints = self.parse_where_clause()?;
 
-impl Parser {
-    pub fn new(tokens: Vec<Token>) -> Self {
-        Parser {
-            tokens,
-            current: 0,
+        self.consume(TokenType::LeftBrace, "Expected '{' after extension type")?;
+        let mut static_functions = Vec::new();
+        let mut methods = Vec::new();
+        let mut properties = Vec::new();
+        let mut static_properties = Vec::new();
+        let mut static_stored_properties = Vec::new();
+        while !self.check(TokenType::RightBrace) {
+            if self.check(TokenType::Case) {
+                return Err("enum 'case' is not allowed outside of an enum".to_string());
+            }
+            match self.parse_type_member(&extended_type, "extensions")? {
+                TypeMember::Operator(function) => static_functions.push(function),
+                TypeMember::Method(function) => methods.push(function),
+                TypeMember::Property(property) => properties.push(property),
+                TypeMember::StaticProperty(property) => static_properties.push(property),
+                TypeMember::StaticStoredProperty(property) => static_stored_properties.push(property),
+            }
+            self.end_declaration()?;
         }
-    }
+        self.consume(TokenType::RightBrace, "Expected '}' after extension body")?;
 
-    /// get current token
-    fn peak(&self) -> &Token {
-        &self.tokens[self.current]
+        Ok(Statement::Extension(ExtensionDecl {
+            extended_type,
+            conformances,
+            constraints,
+            static_functions,
+            methods,
+            properties,
+            static_properties,
+            static_stored_properties,
+        }))
     }
 
-    /// check if current token is specified type
-    fn check(&self, token_type: TokenType) -> bool {
-        if self.is_at_end() {
-            false
-        } else {
-            self.peak().token_type == token_type
+    /// protocol Shape { var name: String { get } func area() -> Double }
+    fn parse_protocol(&mut self) -> Result<Statement, String> {
+        let doc_comment = self.peak().doc_comment.clone();
+        self.advance(); // consume 'protocol'
+        let name = self.consume(TokenType::Identifier, "Expected protocol name")?.lexeme;
+        if self.check(TokenType::Colon) {
+            return Err(format!("protocol '{}' cannot inherit from other protocols", name));
+        }
+        self.consume(TokenType::LeftBrace, "Expected '{' after protocol name")?;
+        let mut requirements = Vec::new();
+        while !self.check(TokenType::RightBrace) {
+            if self.is_function_declaration() {
+                if self.has_static_modifier() {
+                    return Err("static requirements are not supported in protocols".to_string());
+                }
+                let method = self.parse_function_signature()?;
+                if method.operator.is_some() {
+                    return Err("operator requirements are not supported in protocols".to_string());
+                }
+                if !method.generic_parameters.is_empty() {
+                    return Err("generic requirements are not supported in protocols".to_string());
+                }
+                if method.opaque_result {
+                    return Err("'some' type cannot be the return type of a protocol requirement; did you mean to add an associated type?".to_string());
+                }
+                if self.check(TokenType::LeftBrace) {
+                    return Err("protocol methods must not have bodies".to_string());
+                }
+                requirements.push(Requirement::Method(method));
+            } else if self.check(TokenType::Var) {
+                self.advance();
+                let name = self.consume(TokenType::Identifier, "Expected property name")?.lexeme;
+                self.consume(TokenType::Colon, "Expected ':' after property name")?;
+                let property_type = self.parse_type()?;
+                self.consume(TokenType::LeftBrace, "property in protocol must have explicit { get } or { get set } specifier")?;
+                let mut accessors = Vec::new();
+                while self.check(TokenType::Identifier) {
+                    accessors.push(self.advance().lexeme);
+                }
+                self.consume(TokenType::RightBrace, "Expected '}' after property accessors")?;
+                let settable = match accessors.join(" ").as_str() {
+                    "get" => false,
+                    "get set" | "set get" => true,
+                    _ => return Err("property in protocol must have explicit { get } or { get set } specifier".to_string()),
+                };
+                requirements.push(Requirement::Property { name, property_type, settable });
+            } else if self.check(TokenType::Let) {
+                return Err("protocols cannot require properties to be immutable; declare read-only properties by using 'var' with a '{ get }' specifier".to_string());
+            } else {
+                return Err(format!("Expected 'func' or 'var' in protocol body at token {:?}", self.peak()));
+            }
+            self.end_declaration()?;
         }
+        self.consume(TokenType::RightBrace, "Expected '}' after protocol body")?;
+        Ok(Statement::ProtocolDecl(ProtocolDecl { name, requirements, doc_comment }))
     }
 
-    /// move to next token
-    fn advance(&mut self) -> Token {
-        if !self.is_at_end() {
-            self.current += 1
+    fn parse_enum_case(&mut self) -> Result<EnumCase, String> {
+        let name = self.consume(TokenType::Identifier, "Expected enum case name")?.lexeme;
+
+        // associated values: case point(x: Int, y: Int)
+        let mut associated_values = Vec::new();
+        if self.check(TokenType::LeftParen) {
+            self.advance();
+            while !self.check(TokenType::RightParen) {
+                let label = if self.check(TokenType::Identifier)
+                    && self.peak_next().token_type == TokenType::Colon
+                {
+                    let label = self.advance().lexeme;
+                    self.advance(); // consume ':'
+                    Some(label)
+                } else {
+                    None
+                };
+                let value_type = self.parse_type()?;
+                associated_values.push(AssociatedValue { label, value_type });
+
+                if !self.check(TokenType::RightParen) {
+                    self.consume(TokenType::Comma, "Expected ',' between associated values")?;
+                }
+            }
+            self.consume(TokenType::RightParen, "Expected ')' after associated values")?;
         }
-        self.tokens[self.current - 1].clone()
-    }
 
-    /// check if moved to EOF
-    fn is_at_end(&self) -> bool {
-        self.peak().token_type == TokenType::EOF
+        // raw value: case north = 1
+        let raw_value = if self.check(TokenType::Assign) {
+            self.advance();
+            Some(self.parse_signed_number()?)
+        } else {
+            None
+        };
+
+        Ok(EnumCase {
+            name,
+            raw_value,
+            associated_values,
+        })
     }
 
-    fn consume(&mut self, token_type: TokenType, message: &str) -> Result<Token, String> {
-        if self.check(token_type) {
-            Ok(self.advance())
+    fn parse_signed_number(&mut self) -> Result<i64, String> {
+        let negative = if self.check(TokenType::Minus) {
+            self.advance();
+            true
         } else {
-            Err(format!("{} at token {:?}", message, self.peak()))
-        }
+            false
+        };
+        let token = self.consume(TokenType::Number, "Expected integer literal")?;
+        let value = Self::parse_integer(&token.lexeme)?;
+        let value = if negative { -value } else { value };
+        i64::try_from(value)
+            .map_err(|_| format!("integer literal '{}' overflows when stored into 'Int'", value))
     }
 
-    pub fn parse(&mut self) -> Result<AstNode, String> {
-        let mut statements = Vec::new();
+    fn parse_switch(&mut self) -> Result<Statement, String> {
+        self.consume(TokenType::Switch, "Expected 'switch' keyword")?;
+        let subject = self.with_trailing_closures(false, Self::parse_expression)?;
+        self.consume(TokenType::LeftBrace, "Expected '{' after switch subject")?;
 
-        while !self.is_at_end() {
-            statements.push(self.parse_statement()?);
+        let mut cases = Vec::new();
+        while !self.check(TokenType::RightBrace) {
+            let patterns = if self.check(TokenType::Default) {
+                self.advance();
+                vec![Pattern::Wildcard]
+            } else {
+                self.consume(TokenType::Case, "Expected 'case' or 'default' in switch")?;
+                let mut patterns = vec![self.parse_pattern(false)?];
+                while self.check(TokenType::Comma) {
+                    self.advance();
+                    patterns.push(self.parse_pattern(false)?);
+                }
+                patterns
+            };
+            self.consume(TokenType::Colon, "Expected ':' after case pattern")?;
+
+            let mut body = Vec::new();
+            while !self.check(TokenType::Case)
+                && !self.check(TokenType::Default)
+                && !self.check(TokenType::RightBrace)
+                && !self.is_at_end()
+            {
+                body.push(self.parse_statement()?);
+                self.end_statement()?;
+            }
+            cases.push(SwitchCase { patterns, body });
         }
+        self.consume(TokenType::RightBrace, "Expected '}' after switch cases")?;
 
-        Ok(AstNode::Program(statements))
+        Ok(Statement::Switch { subject, cases })
     }
 
-    fn parse_statement(&mut self) -> Result<Statement, String> {
+    /// parse a case pattern, `binding` is set inside `let` patterns
+    /// so that bare identifiers introduce new variables
+    fn parse_pattern(&mut self, binding: bool) -> Result<Pattern, String> {
         match self.peak().token_type {
-            TokenType::Print => {
-                self.parse_print()
+            TokenType::Let | TokenType::Var => {
+                self.advance();
+                self.parse_pattern(true)
             }
-            TokenType::Let => {
-                self.parse_declaration()
+            TokenType::Underscore => {
+                self.advance();
+                Ok(Pattern::Wildcard)
             }
-            TokenType::Var => {
-                self.parse_declaration()
+            TokenType::Number | TokenType::Minus => {
+                Ok(Pattern::Number(self.parse_signed_number()?))
             }
-            TokenType::Identifier => {
-                self.parse_assignment()
+            TokenType::Dot => {
+                self.advance();
+                self.parse_enum_case_pattern(None, binding)
             }
-            _ => {
-                Err("Unexpected token in statement".to_string())
+            TokenType::Identifier if self.peak_next().token_type == TokenType::Dot => {
+                let enum_name = self.advance().lexeme;
+                self.advance(); // consume '.'
+                self.parse_enum_case_pattern(Some(enum_name), binding)
             }
+            TokenType::Identifier if binding => {
+                Ok(Pattern::Binding(self.advance().lexeme))
+            }
+            _ => Err(format!("Expected pattern at token {:?}", self.peak())),
         }
     }
 
-    fn parse_assignment(&mut self) -> Result<Statement, String> {
-        let name_token = self.consume(TokenType::Identifier, "Expected variable name")?;
-        let name = name_token.lexeme;
-        self.consume(TokenType::Assign, "Expected '=' in variable declaration")?;
+    fn parse_enum_case_pattern(&mut self, enum_name: Option<String>, binding: bool) -> Result<Pattern, String> {
+        let case_name = self.consume(TokenType::Identifier, "Expected enum case name in pattern")?.lexeme;
 
-        // Parse the value expression
+        let subpatterns = if self.check(TokenType::LeftParen) {
+            self.advance();
+            let mut subpatterns = Vec::new();
+            while !self.check(TokenType::RightParen) {
+                // labels are allowed but not required: .point(x: let a, y: _)
+                if self.check(TokenType::Identifier) && self.peak_next().token_type == TokenType::Colon {
+                    self.advance();
+                    self.advance();
+                }
+                subpatterns.push(self.parse_pattern(binding)?);
+                if !self.check(TokenType::RightParen) {
+                    self.consume(TokenType::Comma, "Expected ',' between patterns")?;
+                }
+            }
+            self.consume(TokenType::RightParen, "Expected ')' after patterns")?;
+            Some(subpatterns)
+        } else {
+            None
+        };
+
+        Ok(Pattern::EnumCase {
+            enum_name,
+            case_name,
+            subpatterns,
+        })
+    }
+
+    /// `=` is `Some(None)`, compound assignments carry their binary operator
+    fn assignment_operator(&self) -> Option<Option<BinaryOperator>> {
+        let operator = match self.peak().token_type {
+            TokenType::Assign => None,
+            TokenType::PlusAssign => Some(BinaryOperator::Add),
+            TokenType::MinusAssign => Some(BinaryOperator::Subtract),
+            TokenType::StarAssign => Some(BinaryOperator::Multiply),
+            TokenType::SlashAssign => Some(BinaryOperator::Divide),
+            TokenType::PercentAssign => Some(BinaryOperator::Remainder),
+            TokenType::AmpAssign => Some(BinaryOperator::BitwiseAnd),
+            TokenType::PipeAssign => Some(BinaryOperator::BitwiseOr),
+            TokenType::CaretAssign => Some(BinaryOperator::BitwiseXor),
+            TokenType::LessLessAssign => Some(BinaryOperator::ShiftLeft),
+            TokenType::GreaterGreaterAssign => Some(BinaryOperator::ShiftRight),
+            _ => return None,
+        };
+        Some(operator)
+    }
+
+    /// the target has already been parsed as an expression; whether it can
+    /// be assigned to is checked by the semantic pass
+    fn parse_assignment(&mut self, target: Expression, operator: Option<BinaryOperator>) -> Result<Statement, String> {
+        self.advance(); // consume the assignment operator
         let value = self.parse_expression()?;
         Ok(Statement::Assignment {
-            name,
+            target,
+            operator,
             value,
         })
     }
-    
+
     fn parse_declaration(&mut self) -> Result<Statement, String> {
+        let doc_comment = self.peak().doc_comment.clone();
         // Check if it's 'let' or 'var'
         let is_mutable = if self.check(TokenType::Let) {
             self.advance();
@@ -104,26 +1631,119 @@ impl Parser {
             return Err("Expected 'let' or 'var' keyword".to_string());
         };
 
+        if self.check(TokenType::LeftParen) {
+            return self.parse_destructure(is_mutable);
+        }
+
         // Get variable name
         let name_token = self.consume(TokenType::Identifier, "Expected variable name")?;
         let name = name_token.lexeme;
 
-        // Skip optional type annotation for now (: Type)
-        if self.check(TokenType::Colon) {
+        // Optional type annotation (: Type)
+        let type_annotation = if self.check(TokenType::Colon) {
             self.advance(); // consume ':'
-            // For now, skip the type identifier
-            if self.check(TokenType::Identifier) {
-                self.advance(); // consume type name
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+        // a computed variable has accessors instead of a value
+        if let Some(property_type) = &type_annotation
+            && self.check(TokenType::LeftBrace)
+        {
+            if !is_mutable {
+                return Err("'let' declarations cannot be computed properties".to_string());
             }
+            return Ok(Statement::ComputedVar(self.parse_accessors(name, property_type.clone(), doc_comment)?));
         }
+
         // Expect assignment
         self.consume(TokenType::Assign, "Expected '=' in variable declaration")?;
 
         // Parse the value expression
         let value = self.parse_expression()?;
 
+        let (will_set, did_set) = self.parse_observers(is_mutable)?;
+        if will_set.is_some() || did_set.is_some() {
+            return Ok(Statement::StoredVar(StoredPropertyDecl {
+                name,
+                type_annotation,
+                value,
+                is_mutable,
+                is_lazy: false,
+                will_set,
+                did_set,
+                doc_comment,
+            }));
+        }
+
         Ok(Statement::VarDecl {
             name,
+            type_annotation,
+            value,
+            is_mutable,
+        })
+    }
+
+    /// `lazy var name = value`, whose value is computed on first access
+    fn parse_lazy_declaration(&mut self) -> Result<Statement, String> {
+        self.advance(); // consume 'lazy'
+        if self.check(TokenType::Let) {
+            return Err("'lazy' cannot be used on a let".to_string());
+        }
+        let doc_comment = self.peak().doc_comment.clone();
+        match self.parse_declaration()? {
+            Statement::VarDecl { name, type_annotation, value, is_mutable } => Ok(Statement::StoredVar(StoredPropertyDecl {
+                name,
+                type_annotation,
+                value,
+                is_mutable,
+                is_lazy: true,
+                will_set: None,
+                did_set: None,
+                doc_comment,
+            })),
+            Statement::StoredVar(_) => Err("lazy properties must not have observers".to_string()),
+            Statement::ComputedVar(_) => Err("'lazy' cannot be used on a computed property".to_string()),
+            _ => Err("'lazy' cannot destructure an initializer".to_string()),
+        }
+    }
+
+    /// `(a, _, c)` where `_` ignores an element
+    fn parse_tuple_pattern(&mut self) -> Result<Vec<Option<String>>, String> {
+        self.consume(TokenType::LeftParen, "Expected '(' in tuple pattern")?;
+        let mut names = Vec::new();
+        while !self.check(TokenType::RightParen) {
+            if self.check(TokenType::Underscore) {
+                self.advance();
+                names.push(None);
+            } else {
+                let name = self.consume(TokenType::Identifier, "Expected variable name in tuple pattern")?.lexeme;
+                names.push(Some(name));
+            }
+
+            if !self.check(TokenType::RightParen) {
+                self.consume(TokenType::Comma, "Expected ',' between tuple pattern elements")?;
+            }
+        }
+        self.consume(TokenType::RightParen, "Expected ')' after tuple pattern")?;
+        Ok(names)
+    }
+
+    /// `let (a, _) = value` after the `let` or `var` keyword
+    fn parse_destructure(&mut self, is_mutable: bool) -> Result<Statement, String> {
+        let names = self.parse_tuple_pattern()?;
+        let type_annotation = if self.check(TokenType::Colon) {
+            self.advance(); // consume ':'
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+        self.consume(TokenType::Assign, "Expected '=' in variable declaration")?;
+        let value = self.parse_expression()?;
+
+        Ok(Statement::Destructure {
+            names,
+            type_annotation,
             value,
             is_mutable,
         })
@@ -141,77 +1761,568 @@ impl Parser {
     }
 
     fn parse_expression(&mut self) -> Result<Expression, String> {
-        self.parse_additive()
+        // `try` covers everything to its right
+        if self.check(TokenType::Try) {
+            self.advance();
+            let wrap: fn(Box<Expression>) -> Expression = match self.peak().token_type {
+                TokenType::Question => Expression::OptionalTry,
+                TokenType::Bang => Expression::ForceTry,
+                _ => Expression::Try,
+            };
+            if matches!(self.peak().token_type, TokenType::Question | TokenType::Bang) {
+                self.advance();
+            }
+            return Ok(wrap(Box::new(self.parse_expression()?)));
+        }
+        // assignments are statements, so an expression is everything above them
+        let ternary = self.precedence_groups["TernaryPrecedence"].level;
+        self.parse_binary(ternary)
+    }
+
+    fn binary(left: Expression, operator: BinaryOperator, right: Expression) -> Expression {
+        Expression::Binary {
+            left: Box::new(left),
+            operator,
+            right: Box::new(right),
+        }
     }
 
-    fn parse_additive(&mut self) -> Result<Expression, String> {
-        let mut left = self.parse_multiplicative()?;
+    /// The infix operator at the current token and its precedence group.
+    /// User-defined operators have no `BinaryOperator` and become calls.
+    fn infix_operator(&self) -> Result<Option<(Option<BinaryOperator>, PrecedenceGroup)>, String> {
+        let token = self.peak();
+        let (operator, group) = match token.token_type {
+            TokenType::LessLess => (Some(BinaryOperator::ShiftLeft), "BitwiseShiftPrecedence"),
+            TokenType::GreaterGreater => (Some(BinaryOperator::ShiftRight), "BitwiseShiftPrecedence"),
+            TokenType::AmpLessLess => (Some(BinaryOperator::MaskingShiftLeft), "BitwiseShiftPrecedence"),
+            TokenType::AmpGreaterGreater => (Some(BinaryOperator::MaskingShiftRight), "BitwiseShiftPrecedence"),
+            TokenType::Star => (Some(BinaryOperator::Multiply), "MultiplicationPrecedence"),
+            TokenType::Slash => (Some(BinaryOperator::Divide), "MultiplicationPrecedence"),
+            TokenType::Percent => (Some(BinaryOperator::Remainder), "MultiplicationPrecedence"),
+            TokenType::Amp => (Some(BinaryOperator::BitwiseAnd), "MultiplicationPrecedence"),
+            TokenType::Plus => (Some(BinaryOperator::Add), "AdditionPrecedence"),
+            TokenType::Minus => (Some(BinaryOperator::Subtract), "AdditionPrecedence"),
+            TokenType::Pipe => (Some(BinaryOperator::BitwiseOr), "AdditionPrecedence"),
+            TokenType::Caret => (Some(BinaryOperator::BitwiseXor), "AdditionPrecedence"),
+            TokenType::QuestionQuestion => (Some(BinaryOperator::NilCoalescing), "NilCoalescingPrecedence"),
+            TokenType::EqualEqual => (Some(BinaryOperator::Equal), "ComparisonPrecedence"),
+            TokenType::BangEqual => (Some(BinaryOperator::NotEqual), "ComparisonPrecedence"),
+            TokenType::Less => (Some(BinaryOperator::Less), "ComparisonPrecedence"),
+            TokenType::LessEqual => (Some(BinaryOperator::LessEqual), "ComparisonPrecedence"),
+            TokenType::Greater => (Some(BinaryOperator::Greater), "ComparisonPrecedence"),
+            TokenType::GreaterEqual => (Some(BinaryOperator::GreaterEqual), "ComparisonPrecedence"),
+            TokenType::AndAnd => (Some(BinaryOperator::And), "LogicalConjunctionPrecedence"),
+            TokenType::OrOr => (Some(BinaryOperator::Or), "LogicalDisjunctionPrecedence"),
+            TokenType::Operator => match self.infix_operators.get(&token.lexeme) {
+                Some(group) => (None, group.as_str()),
+                None if self.postfix_operators.contains(&token.lexeme) => return Ok(None),
+                None => return Err(format!("cannot find operator '{}' in scope", token.lexeme)),
+            },
+            _ => return Ok(None),
+        };
+        Ok(Some((operator, self.precedence_groups[group].clone())))
+    }
 
-        while self.check(TokenType::Plus) || self.check(TokenType::Minus) {
-            let operator = if self.check(TokenType::Plus) {
+    /// Pratt parser over precedence groups: parses operators whose group is
+    /// at least `min_level`, so `a + b * c` groups `b * c` first
+    fn parse_binary(&mut self, min_level: u32) -> Result<Expression, String> {
+        let mut left = self.parse_unary()?;
+        let mut previous = None;
+
+        loop {
+            // `cond ? a : b`, the `?` of optional chaining is consumed as a postfix
+            let ternary = self.precedence_groups["TernaryPrecedence"].level;
+            if self.check(TokenType::Question) && min_level <= ternary {
                 self.advance();
-                BinaryOperator::Add
+                let then_value = self.parse_expression()?;
+                self.consume(TokenType::Colon, "Expected ':' after '? ...' in ternary expression")?;
+                let else_value = self.parse_binary(ternary)?;
+                left = Expression::Ternary {
+                    condition: Box::new(left),
+                    then_value: Box::new(then_value),
+                    else_value: Box::new(else_value),
+                };
+                previous = Some(ternary);
+                continue;
+            }
+
+            // an operator with whitespace only on its left is a prefix operator,
+            // so `let x = 1\n-2` ends the declaration at the line break
+            if self.peak().space_before && !self.peak_next().space_before {
+                break;
+            }
+            let Some((operator, group)) = self.infix_operator()? else {
+                break;
+            };
+            if group.level < min_level {
+                break;
+            }
+            if previous == Some(group.level) && group.associativity == Associativity::None {
+                return Err(format!("adjacent operators are in non-associative precedence group '{}'", group.name));
+            }
+            let symbol = self.advance().lexeme;
+            let right = if group.associativity == Associativity::Right {
+                self.parse_binary(group.level)?
             } else {
-                self.advance();
-                BinaryOperator::Subtract
+                self.parse_binary(group.level + 1)?
             };
-
-            let right = self.parse_multiplicative()?;
-            left = Expression::Binary {
-                left: Box::new(left),
-                operator,
-                right: Box::new(right),
+            left = match operator {
+                Some(operator) => Self::binary(left, operator, right),
+                None => Expression::OperatorCall {
+                    symbol,
+                    fixity: Fixity::Infix,
+                    operands: vec![left, right],
+                },
             };
+            previous = Some(group.level);
         }
 
         Ok(left)
     }
 
-    fn parse_multiplicative(&mut self) -> Result<Expression, String> {
-        let mut left = self.parse_primary()?;
+    /// prefix operators: `-x`, `!flag`, `~mask`
+    fn parse_unary(&mut self) -> Result<Expression, String> {
+        if self.check(TokenType::Try) {
+            return Err("'try' cannot appear to the right of a non-assignment operator".to_string());
+        }
+        if self.check(TokenType::Operator) {
+            let symbol = self.advance().lexeme;
+            if !self.prefix_operators.contains(&symbol) {
+                return Err(format!("'{}' is not a prefix unary operator", symbol));
+            }
+            let operand = self.parse_unary()?;
+            return Ok(Expression::OperatorCall {
+                symbol,
+                fixity: Fixity::Prefix,
+                operands: vec![operand],
+            });
+        }
+        if self.check(TokenType::Amp) {
+            self.advance();
+            return Ok(Expression::InOut(Box::new(self.parse_unary()?)));
+        }
+        let operator = match self.peak().token_type {
+            TokenType::Minus => UnaryOperator::Negate,
+            TokenType::Bang => UnaryOperator::Not,
+            TokenType::Tilde => UnaryOperator::BitwiseNot,
+            _ => return self.parse_postfix(),
+        };
+        self.advance();
+        let operand = self.parse_unary()?;
+        Ok(Expression::Unary {
+            operator,
+            operand: Box::new(operand),
+        })
+    }
+
+    /// member access, calls, force unwrap and optional chaining:
+    /// `Direction.north`, `Shape.point(x: 1, y: 2)`, `value!`, `value?.rawValue`
+    fn parse_postfix(&mut self) -> Result<Expression, String> {
+        let mut expr = self.parse_primary()?;
+        let mut is_optional_chain = false;
 
-        while self.check(TokenType::Star) || self.check(TokenType::Slash) {
-            let operator = if self.check(TokenType::Star) {
+        loop {
+            // `value?.member` or `value?[index]`, whose `?` is attached to the value
+            if self.check(TokenType::Question)
+                && (self.peak_next().token_type == TokenType::Dot
+                    || self.peak_next().token_type == TokenType::LeftBracket && !self.peak().space_before)
+            {
                 self.advance();
-                BinaryOperator::Multiply
-            } else {
+                expr = Expression::BindOptional(Box::new(expr));
+                is_optional_chain = true;
+            } else if self.check(TokenType::Bang) {
                 self.advance();
-                BinaryOperator::Divide
-            };
+                expr = Expression::ForceUnwrap(Box::new(expr));
+            } else if self.check(TokenType::Operator)
+                && self.postfix_operators.contains(&self.peak().lexeme)
+                && !self.infix_operators.contains_key(&self.peak().lexeme)
+            {
+                let symbol = self.advance().lexeme;
+                expr = Expression::OperatorCall {
+                    symbol,
+                    fixity: Fixity::Postfix,
+                    operands: vec![expr],
+                };
+            } else if self.check(TokenType::Dot) {
+                self.advance();
+                // tuple elements are accessed by index: `pair.0`
+                let name = if self.check(TokenType::Number) {
+                    self.advance().lexeme
+                } else {
+                    self.consume(TokenType::Identifier, "Expected member name after '.'")?.lexeme
+                };
+                expr = Expression::Member {
+                    base: Box::new(expr),
+                    name,
+                };
+            } else if self.check(TokenType::LeftBracket) && !self.peak().newline_before {
+                self.advance();
+                let index = self.with_trailing_closures(true, Self::parse_expression)?;
+                self.consume(TokenType::RightBracket, "Expected ']' after subscript index")?;
+                expr = Expression::Subscript {
+                    base: Box::new(expr),
+                    index: Box::new(index),
+                };
+            } else if self.check(TokenType::LeftParen) && !self.peak().newline_before {
+                // `(` on the next line starts a new statement instead of a call
+                let arguments = self.parse_arguments()?;
+                expr = Expression::Call {
+                    callee: Box::new(expr),
+                    arguments,
+                };
+            } else if self.check(TokenType::LeftBrace)
+                && self.trailing_closures
+                && !self.peak().newline_before
+                && !self.starts_accessor_block(&["willSet", "didSet"])
+                && matches!(expr, Expression::Variable(_) | Expression::Member { .. } | Expression::Call { .. })
+            {
+                // a trailing closure is the last argument: `numbers.map { $0 * 2 }`
+                let argument = Argument { label: None, value: self.parse_closure()? };
+                expr = match expr {
+                    Expression::Call { callee, mut arguments } => {
+                        arguments.push(argument);
+                        Expression::Call { callee, arguments }
+                    }
+                    callee => Expression::Call {
+                        callee: Box::new(callee),
+                        arguments: vec![argument],
+                    },
+                };
+            } else {
+                break;
+            }
+        }
+
+        if is_optional_chain {
+            expr = Expression::OptionalEvaluation(Box::new(expr));
+        }
+        Ok(expr)
+    }
 
-            let right = self.parse_primary()?;
-            left = Expression::Binary {
-                left: Box::new(left),
-                operator,
-                right: Box::new(right),
+    fn parse_arguments(&mut self) -> Result<Vec<Argument>, String> {
+        self.consume(TokenType::LeftParen, "Expected '('")?;
+        let mut arguments = Vec::new();
+        while !self.check(TokenType::RightParen) {
+            let label = if self.check(TokenType::Identifier) && self.peak_next().token_type == TokenType::Colon {
+                let label = self.advance().lexeme;
+                self.advance(); // consume ':'
+                Some(label)
+            } else {
+                None
             };
+            let value = self.parse_argument_value()?;
+            arguments.push(Argument { label, value });
+
+            if !self.check(TokenType::RightParen) {
+                self.consume(TokenType::Comma, "Expected ',' between arguments")?;
+            }
         }
+        self.consume(TokenType::RightParen, "Expected ')' after arguments")?;
+        Ok(arguments)
+    }
 
-        Ok(left)
+    /// an argument, which can also be an operator on its own: `reduce(0, +)`
+    fn parse_argument_value(&mut self) -> Result<Expression, String> {
+        let ends_argument = matches!(self.peak_next().token_type, TokenType::RightParen | TokenType::Comma);
+        if ends_argument && let Some((operator, _)) = self.infix_operator()? {
+            let symbol = self.advance().lexeme;
+            return Ok(Expression::operator_closure(|left, right| match operator {
+                Some(operator) => Self::binary(left, operator, right),
+                None => Expression::OperatorCall {
+                    symbol,
+                    fixity: Fixity::Infix,
+                    operands: vec![left, right],
+                },
+            }));
+        }
+        self.with_trailing_closures(true, Self::parse_expression)
+    }
+
+    /// run `parse` with trailing closures allowed or not
+    fn with_trailing_closures<T>(&mut self, allowed: bool, parse: impl FnOnce(&mut Self) -> Result<T, String>) -> Result<T, String> {
+        let saved = std::mem::replace(&mut self.trailing_closures, allowed);
+        let result = parse(self);
+        self.trailing_closures = saved;
+        result
+    }
+
+    /// `{ x in x * 2 }`, `{ (a: Int, b: Int) -> Bool in a < b }` or `{ $0 * 2 }`
+    fn parse_closure(&mut self) -> Result<Expression, String> {
+        self.consume(TokenType::LeftBrace, "Expected '{' to start a closure")?;
+        let signature = if self.has_closure_parameters() {
+            Some(self.parse_closure_parameters()?)
+        } else {
+            None
+        };
+
+        self.anonymous_parameters.push(0);
+        let body = self.with_trailing_closures(true, |parser| {
+            let mut statements = Vec::new();
+            while !parser.check(TokenType::RightBrace) && !parser.is_at_end() {
+                statements.push(parser.parse_statement()?);
+                parser.end_statement()?;
+            }
+            Ok(statements)
+        });
+        let anonymous_count = self.anonymous_parameters.pop().unwrap_or_default();
+        let mut body = body?;
+        self.consume(TokenType::RightBrace, "Expected '}' at end of closure")?;
+
+        let (parameters, return_type) = match signature {
+            Some(_) if anonymous_count > 0 => {
+                return Err("anonymous closure arguments cannot be used inside a closure that has explicit arguments".to_string());
+            }
+            Some(signature) => signature,
+            None => {
+                let parameters = (0..anonymous_count)
+                    .map(|index| ClosureParameter { name: format!("${}", index), param_type: None })
+                    .collect();
+                (parameters, None)
+            }
+        };
+        // a single expression is the result of the closure
+        if let [Statement::Expression(value)] = body.as_mut_slice() {
+            let value = std::mem::replace(value, Expression::Nil);
+            body = vec![Statement::Return(Some(value))];
+        }
+        Ok(Expression::Closure { parameters, return_type, body })
+    }
+
+    /// whether the closure body at the current token starts with `x, y in`, `x -> Int in` or `(x: Int) -> Int in`
+    fn has_closure_parameters(&self) -> bool {
+        let mut index = self.current;
+        if self.tokens[index].token_type == TokenType::LeftParen {
+            let mut depth = 0;
+            loop {
+                match self.tokens[index].token_type {
+                    TokenType::LeftParen => depth += 1,
+                    TokenType::RightParen => depth -= 1,
+                    TokenType::Eof => return false,
+                    _ => {}
+                }
+                index += 1;
+                if depth == 0 {
+                    return matches!(self.tokens[index].token_type, TokenType::In | TokenType::Arrow | TokenType::Throws);
+                }
+            }
+        }
+        loop {
+            if !matches!(self.tokens[index].token_type, TokenType::Identifier | TokenType::Underscore) {
+                return false;
+            }
+            match self.tokens[index + 1].token_type {
+                TokenType::Comma => index += 2,
+                TokenType::In | TokenType::Arrow | TokenType::Throws => return true,
+                _ => return false,
+            }
+        }
+    }
+
+    fn parse_closure_parameters(&mut self) -> Result<(Vec<ClosureParameter>, Option<Type>), String> {
+        let parenthesized = self.check(TokenType::LeftParen);
+        if parenthesized {
+            self.advance();
+        }
+        let mut parameters = Vec::new();
+        while !self.check(TokenType::In) && !self.check(TokenType::RightParen) {
+            let name = if self.check(TokenType::Underscore) {
+                self.advance().lexeme
+            } else {
+                self.consume(TokenType::Identifier, "Expected closure parameter name")?.lexeme
+            };
+            let param_type = if parenthesized && self.check(TokenType::Colon) {
+                self.advance();
+                Some(self.parse_type()?)
+            } else {
+                None
+            };
+            parameters.push(ClosureParameter { name, param_type });
+            if self.check(TokenType::Comma) {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        if parenthesized {
+            self.consume(TokenType::RightParen, "Expected ')' after closure parameters")?;
+        }
+        // whether a closure throws is inferred from its body
+        if self.check(TokenType::Throws) {
+            self.advance();
+        }
+        let return_type = if self.check(TokenType::Arrow) {
+            self.advance();
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+        self.consume(TokenType::In, "Expected 'in' after closure parameters")?;
+        Ok((parameters, return_type))
     }
 
     fn parse_primary(&mut self) -> Result<Expression, String> {
         if self.check(TokenType::Number) {
             let token = self.advance();
-            let value = token.lexeme.parse::<i64>()
-                .map_err(|_| format!("Failed to parse number: {}", token.lexeme))?;
-            return Ok(Expression::Number(value));
+            return Ok(Expression::Number(Self::parse_integer(&token.lexeme)?));
+        }
+
+        if self.check(TokenType::FloatLiteral) {
+            let token = self.advance();
+            return Ok(Expression::FloatLiteral(Self::parse_float(&token.lexeme)?));
+        }
+
+        if self.check(TokenType::StringLiteral) {
+            let token = self.advance();
+            return Ok(Expression::StringLiteral(token.lexeme));
+        }
+
+        if self.check(TokenType::InterpolationStart) {
+            return self.parse_interpolation();
+        }
+
+        if self.check(TokenType::True) || self.check(TokenType::False) {
+            let token = self.advance();
+            return Ok(Expression::Bool(token.token_type == TokenType::True));
+        }
+
+        if self.check(TokenType::Nil) {
+            self.advance();
+            return Ok(Expression::Nil);
         }
 
         if self.check(TokenType::Identifier) {
             let token = self.advance();
+            if let Some(index) = token.lexeme.strip_prefix('$') {
+                let index = index.parse::<usize>()
+                    .map_err(|_| format!("invalid anonymous closure argument '{}'", token.lexeme))?;
+                let Some(count) = self.anonymous_parameters.last_mut() else {
+                    return Err("anonymous closure argument not contained in a closure".to_string());
+                };
+                *count = (*count).max(index + 1);
+            }
+            if self.generic_enums.contains(&token.lexeme) && self.check(TokenType::Less) {
+                self.current -= 1;
+                return Ok(Expression::SpecializedType(self.parse_type_name()?));
+            }
             return Ok(Expression::Variable(token.lexeme));
         }
 
-        if self.check(TokenType::LeftParen) {
+        if self.check(TokenType::LeftBrace) {
+            return self.parse_closure();
+        }
+
+        if self.check(TokenType::Dot) {
+            self.advance();
+            let name = self.consume(TokenType::Identifier, "Expected member name after '.'")?.lexeme;
+            return Ok(Expression::ImplicitMember(name));
+        }
+
+        if self.check(TokenType::LeftBracket) {
             self.advance();
-            let expr = self.parse_expression()?;
-            self.consume(TokenType::RightParen, "')' is required")?;
-            return Ok(expr);
+            // `[:]` is the empty dictionary
+            if self.check(TokenType::Colon) {
+                self.advance();
+                self.consume(TokenType::RightBracket, "Expected ']' after ':' in empty dictionary literal")?;
+                return Ok(Expression::DictionaryLiteral(Vec::new()));
+            }
+            let mut elements = Vec::new();
+            let mut entries = Vec::new();
+            while !self.check(TokenType::RightBracket) {
+                let element = self.with_trailing_closures(true, Self::parse_expression)?;
+                // the first element decides between an array and a dictionary
+                if self.check(TokenType::Colon) && elements.is_empty() {
+                    self.advance();
+                    entries.push((element, self.with_trailing_closures(true, Self::parse_expression)?));
+                } else if entries.is_empty() {
+                    elements.push(element);
+                } else {
+                    return Err("Expected ':' and a value in dictionary literal".to_string());
+                }
+                if !self.check(TokenType::RightBracket) {
+                    self.consume(TokenType::Comma, "Expected ',' between collection elements")?;
+                }
+            }
+            self.consume(TokenType::RightBracket, "Expected ']' after collection elements")?;
+            if !entries.is_empty() {
+                return Ok(Expression::DictionaryLiteral(entries));
+            }
+            return Ok(Expression::ArrayLiteral(elements));
+        }
+
+        if self.check(TokenType::LeftParen) {
+            // `(expr)` only groups, two or more elements make a tuple
+            let mut elements = self.parse_arguments()?;
+            return match elements.len() {
+                0 => Err("expression is expected inside '()'".to_string()),
+                1 if elements[0].label.is_some() => {
+                    Err("cannot create a single-element tuple with an element label".to_string())
+                }
+                1 => Ok(elements.remove(0).value),
+                _ => Ok(Expression::Tuple(elements)),
+            };
         }
 
         Err(format!("expression is expected: {:?}", self.peak()))
     }
+
+    /// value of `42`, `0xFF`, `0o17`, `0b1010` or `1_000_000`; whether it fits
+    /// its type is checked by the semantic pass once the type is known
+    fn parse_integer(lexeme: &str) -> Result<i128, String> {
+        let digits = lexeme.replace('_', "");
+        let (radix, digits) = match digits.get(..2) {
+            Some("0x") => (16, &digits[2..]),
+            Some("0o") => (8, &digits[2..]),
+            Some("0b") => (2, &digits[2..]),
+            _ => (10, digits.as_str()),
+        };
+        i128::from_str_radix(digits, radix).ok()
+            .filter(|value| *value <= u64::MAX as i128)
+            .ok_or_else(|| format!("integer literal '{}' overflows when stored into 'UInt64'", lexeme))
+    }
+
+    /// value of a decimal (`1.5e3`) or hexadecimal (`0x1.8p1`) floating point literal
+    fn parse_float(lexeme: &str) -> Result<f64, String> {
+        let lexeme = &lexeme.replace('_', "");
+        let Some(hex) = lexeme.strip_prefix("0x") else {
+            return lexeme.parse::<f64>()
+                .map_err(|_| format!("Failed to parse number: {}", lexeme));
+        };
+
+        // hexadecimal significand scaled by a binary exponent
+        let (significand, exponent) = hex.split_once(['p', 'P'])
+            .ok_or_else(|| format!("hexadecimal floating point literal must end with an exponent: {}", lexeme))?;
+        let (whole, fraction) = significand.split_once('.').unwrap_or((significand, ""));
+        let mut value = 0.0;
+        for digit in whole.chars().chain(fraction.chars()) {
+            value = value * 16.0 + digit.to_digit(16).unwrap_or_default() as f64;
+        }
+        let exponent = exponent.parse::<i32>()
+            .map_err(|_| format!("Failed to parse number: {}", lexeme))?;
+        Ok(value * 2f64.powi(exponent - 4 * fraction.len() as i32))
+    }
+
+    /// the tokens of an interpolated string, starting at `InterpolationStart`
+    fn parse_interpolation(&mut self) -> Result<Expression, String> {
+        let mut parts = Vec::new();
+        let head = self.advance().lexeme;
+        if !head.is_empty() {
+            parts.push(InterpolationPart::Literal(head));
+        }
+
+        loop {
+            parts.push(InterpolationPart::Expression(self.parse_expression()?));
+            let segment = self.advance();
+            if !segment.lexeme.is_empty() {
+                parts.push(InterpolationPart::Literal(segment.lexeme));
+            }
+            match segment.token_type {
+                TokenType::InterpolationMiddle => continue,
+                TokenType::InterpolationEnd => break,
+                _ => return Err("expected ')' in string interpolation".to_string()),
+            }
+        }
+        Ok(Expression::Interpolation(parts))
+    }
 }
 
 pub fn parse(tokens: Vec<Token>) -> Result<AstNode, String> {
@@ -1,7 +1,3185 @@
-pub struct Semantic {
+use crate::ast::{
+    AstNode, Statement, Expression, BinaryOperator, UnaryOperator, Argument, Type, EnumDecl,
+    SwitchCase, Pattern, Condition, FunctionDecl, TupleElement, InterpolationPart, Fixity, ForPattern,
+    ProtocolDecl, Requirement, ExtensionDecl, PropertyDecl, GenericParameter, CatchClause, StoredPropertyDecl,
+    is_higher_order_method,
+};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+struct Variable {
+    ty: Type,
+    is_mutable: bool,
+    // computed by a getter, immutable ones have no setter
+    is_computed: bool,
+    // a parameter passed `inout` or the `self` of a mutating member, the
+    // caller's variable, which only closures that cannot outlive the call may capture
+    is_inout: bool,
+}
+
+/// where an error thrown by a call or a `throw` statement goes, innermost last
+enum ErrorHandler {
+    /// out of the function being checked when it `throws`; top level code
+    /// may throw too, an error reaching it is a runtime error
+    Function { throws: bool },
+    /// out of a `rethrows` function, only for errors of its throwing function
+    /// parameters, which are listed
+    Rethrows { parameters: Vec<String> },
+    /// the body of a `do` statement, which only handles every error with a catch-all clause
+    Do { exhaustive: bool },
+    /// `try?` and `try!` handle every error of their operand
+    Try,
+    /// the body of a closure, errors make the collection method it is passed
+    /// to throw or the closure's function type a throwing one
+    Closure { throws: bool },
+    /// the body of a `defer` statement, which errors cannot leave
+    Defer,
+}
 
+/// what throws an error, which decides the handlers it may reach
+enum ErrorSource<'a> {
+    Throw,
+    /// a call that throws regardless of its arguments
+    Call,
+    /// a call that throws because the function values it calls or passes
+    /// to a `rethrows` function do
+    Values(Vec<&'a Expression>),
+}
+
+/// Type checker run between parsing and code generation.
+/// Every error is reported as a message in the style of swiftc.
+pub struct Semantic {
+    enums: HashMap<String, EnumDecl>,
+    protocols: HashMap<String, ProtocolDecl>,
+    // extensions of protocols and standard library types; those of enums are merged into the enum
+    extensions: Vec<ExtensionDecl>,
+    // members of generic extensions that have been checked for a type, e.g. `[Int].count(of:)`
+    instantiated: HashSet<String>,
+    functions: HashMap<String, Vec<FunctionDecl>>,
+    scopes: Vec<HashMap<String, Variable>>,
+    // static properties of enums by `Enum.name`
+    static_properties: HashMap<String, Variable>,
+    // return type of the function being checked, None at the top level
+    return_type: Option<Type>,
+    // set inside a closure whose result type is taken from its first `return`
+    infer_return_type: bool,
+    // set with `infer_return_type` for the closure of `compactMap`, whose result is optional
+    optional_result: bool,
+    // set for a closure literal passed as an argument, which cannot outlive the call
+    non_escaping_closure: bool,
+    // for a function declared `-> some P`, its description in errors and the
+    // underlying type its first `return` decides
+    opaque_return: Option<(String, Option<Type>)>,
+    loop_depth: usize,
+    switch_depth: usize,
+    // type parameters of the generic function being checked
+    generic_parameters: Vec<GenericParameter>,
+    error_handlers: Vec<ErrorHandler>,
+    // set inside the operand of `try`, which marks the calls that can throw
+    in_try: bool,
+    // set inside the body of a `defer` statement, which cannot be left early
+    in_defer: bool,
 }
 
 impl Semantic {
+    pub fn new() -> Self {
+        Semantic {
+            enums: HashMap::new(),
+            protocols: HashMap::new(),
+            extensions: Vec::new(),
+            instantiated: HashSet::new(),
+            functions: HashMap::new(),
+            scopes: vec![HashMap::new()],
+            static_properties: HashMap::new(),
+            return_type: None,
+            infer_return_type: false,
+            optional_result: false,
+            non_escaping_closure: false,
+            opaque_return: None,
+            loop_depth: 0,
+            switch_depth: 0,
+            generic_parameters: Vec::new(),
+            error_handlers: vec![ErrorHandler::Function { throws: true }],
+            in_try: false,
+            in_defer: false,
+        }
+    }
+
+    pub fn analyze(&mut self, ast: &AstNode) -> Result<(), String> {
+        match ast {
+            AstNode::Program(statements) => {
+                // types can be used before their declaration
+                self.declare_protocol(&ProtocolDecl::error())?;
+                for statement in statements {
+                    if let Statement::ProtocolDecl(decl) = statement {
+                        self.declare_protocol(decl)?;
+                    }
+                }
+                for statement in statements {
+                    if let Statement::EnumDecl(decl) = statement {
+                        self.declare_enum(decl)?;
+                    }
+                }
+                for statement in statements {
+                    if let Statement::Extension(decl) = statement {
+                        self.declare_extension(decl)?;
+                    }
+                }
+                self.check_recursive_enums()?;
+
+                // functions can be called before their declaration
+                for statement in statements {
+                    match statement {
+                        Statement::FunctionDecl(decl) => self.declare_function(decl)?,
+                        Statement::EnumDecl(decl) => {
+                            for function in &decl.static_functions {
+                                self.declare_function(function)?;
+                            }
+                        }
+                        Statement::Extension(decl) => {
+                            for function in &decl.static_functions {
+                                self.declare_function(function)?;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                // so can static properties, those with a stored value have the type of their value
+                for statement in statements {
+                    match statement {
+                        Statement::EnumDecl(decl) => {
+                            self.declare_static_properties(&decl.name, &decl.static_properties, &decl.static_stored_properties)?;
+                        }
+                        Statement::Extension(decl) if self.enums.contains_key(&decl.extended_type) => {
+                            self.declare_static_properties(&decl.extended_type, &decl.static_properties, &decl.static_stored_properties)?;
+                        }
+                        _ => {}
+                    }
+                }
+
+                for statement in statements {
+                    self.check_statement(statement)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // MARK - declarations
+
+    fn declare_enum(&mut self, decl: &EnumDecl) -> Result<(), String> {
+        if self.enums.contains_key(&decl.name) || self.protocols.contains_key(&decl.name) || decl.name == "Int" {
+            return Err(format!("invalid redeclaration of '{}'", decl.name));
+        }
+
+        let mut member_names = HashSet::new();
+        let names = decl.cases.iter().map(|case| case.name.clone())
+            .chain(decl.properties.iter().map(|property| property.name.clone()))
+            .chain(decl.static_properties.iter().map(|property| property.name.clone()))
+            .chain(decl.static_stored_properties.iter().map(|property| property.name.clone()))
+            .chain(decl.methods.iter().map(FunctionDecl::signature));
+        for name in names {
+            if !member_names.insert(name.clone()) {
+                return Err(format!("invalid redeclaration of '{}'", name));
+            }
+        }
+
+        if !decl.generic_parameters.is_empty() {
+            Self::check_generic_enum(&decl.name, decl.raw_type.as_ref(), &decl.static_functions, &decl.static_properties, &decl.static_stored_properties)?;
+        }
+
+        match &decl.raw_type {
+            Some(Type::Int) => {
+                if decl.has_associated_values() {
+                    return Err(format!(
+                        "enum with raw type cannot have cases with arguments ('{}')",
+                        decl.name
+                    ));
+                }
+                let mut seen = HashSet::new();
+                for (case, value) in decl.cases.iter().zip(decl.raw_values()?) {
+                    if !seen.insert(value) {
+                        return Err(format!(
+                            "raw value for enum case '{}' is not unique",
+                            case.name
+                        ));
+                    }
+                }
+            }
+            Some(other) => {
+                return Err(format!(
+                    "raw type '{}' is not expressible by an integer literal",
+                    other
+                ));
+            }
+            None => {
+                if let Some(case) = decl.cases.iter().find(|case| case.raw_value.is_some()) {
+                    return Err(format!(
+                        "enum case '{}' cannot have a raw value if the enum does not have a raw type",
+                        case.name
+                    ));
+                }
+            }
+        }
+
+        self.enums.insert(decl.name.clone(), decl.clone());
+        Ok(())
+    }
+
+    /// members a generic enum cannot have, since they would need a specialization of their own
+    fn check_generic_enum(
+        name: &str,
+        raw_type: Option<&Type>,
+        static_functions: &[FunctionDecl],
+        static_properties: &[PropertyDecl],
+        static_stored_properties: &[StoredPropertyDecl],
+    ) -> Result<(), String> {
+        if let Some(raw_type) = raw_type {
+            return Err(format!("generic enum '{}' cannot have raw type '{}'", name, raw_type));
+        }
+        if !static_stored_properties.is_empty() {
+            return Err("static stored properties not supported in generic types".to_string());
+        }
+        if let Some(function) = static_functions.first() {
+            return Err(format!("operator implementations in generic enum '{}' are not supported, '{}' must be a global function", name, function.signature()));
+        }
+        if let Some(property) = static_properties.first() {
+            return Err(format!("static property '{}' of generic enum '{}' is not supported", property.name, name));
+        }
+        Ok(())
+    }
+
+    fn declare_protocol(&mut self, decl: &ProtocolDecl) -> Result<(), String> {
+        if self.protocols.contains_key(&decl.name) {
+            return Err(format!("invalid redeclaration of '{}'", decl.name));
+        }
+        let mut names = HashSet::new();
+        for requirement in &decl.requirements {
+            let name = match requirement {
+                Requirement::Method(method) => method.signature(),
+                Requirement::Property { name, .. } => name.clone(),
+            };
+            if !names.insert(name.clone()) {
+                return Err(format!("invalid redeclaration of '{}'", name));
+            }
+        }
+        self.protocols.insert(decl.name.clone(), decl.clone());
+        Ok(())
+    }
+
+    /// Members added to an enum become part of it. Extensions of protocols and
+    /// standard library types are kept apart, they apply to every conforming or
+    /// matching type.
+    fn declare_extension(&mut self, decl: &ExtensionDecl) -> Result<(), String> {
+        let name = &decl.extended_type;
+        if let Some(enum_decl) = self.enums.get_mut(name) {
+            let mut member_names = enum_decl.cases.iter().map(|case| case.name.clone())
+                .chain(enum_decl.properties.iter().map(|property| property.name.clone()))
+                .chain(enum_decl.static_properties.iter().map(|property| property.name.clone()))
+                .chain(enum_decl.static_stored_properties.iter().map(|property| property.name.clone()))
+                .chain(enum_decl.methods.iter().map(FunctionDecl::signature))
+                .collect::<HashSet<_>>();
+            let names = decl.properties.iter().map(|property| property.name.clone())
+                .chain(decl.static_properties.iter().map(|property| property.name.clone()))
+                .chain(decl.static_stored_properties.iter().map(|property| property.name.clone()))
+                .chain(decl.methods.iter().map(FunctionDecl::signature));
+            for member in names {
+                if !member_names.insert(member.clone()) {
+                    return Err(format!("invalid redeclaration of '{}'", member));
+                }
+            }
+            if !enum_decl.generic_parameters.is_empty() {
+                if !decl.constraints.is_empty() {
+                    return Err(format!("constrained extensions of generic enum '{}' are not supported", name));
+                }
+                Self::check_generic_enum(name, None, &decl.static_functions, &decl.static_properties, &decl.static_stored_properties)?;
+            }
+            if !decl.constraints.is_empty() {
+                return Err(format!("trailing 'where' clause for extension of non-generic type '{}'", name));
+            }
+            enum_decl.conformances.extend(decl.conformances.iter().cloned());
+            enum_decl.methods.extend(decl.methods.iter().cloned());
+            enum_decl.properties.extend(decl.properties.iter().cloned());
+            enum_decl.static_properties.extend(decl.static_properties.iter().cloned());
+            enum_decl.static_stored_properties.extend(decl.static_stored_properties.iter().cloned());
+            return Ok(());
+        }
+
+        let is_protocol = self.protocols.contains_key(name);
+        let generic_parameters = decl.generic_parameters();
+        if !is_protocol && Type::builtin(name).is_none() && generic_parameters.is_empty() {
+            return Err(format!("cannot find type '{}' in scope", name));
+        }
+        if let Some(protocol) = decl.conformances.first() {
+            return Err(if is_protocol {
+                format!("extension of protocol '{}' cannot have an inheritance clause", name)
+            } else {
+                format!("only enums can conform to protocols, '{}' cannot conform to '{}'", name, protocol)
+            });
+        }
+        if !decl.static_functions.is_empty() {
+            return Err(format!("operator implementations can only be declared in enums or their extensions, not in '{}'", name));
+        }
+        if !decl.static_properties.is_empty() || !decl.static_stored_properties.is_empty() {
+            return Err(format!("static properties can only be declared in enums or their extensions, not in '{}'", name));
+        }
+        // a setter gets the address of `self`, which only enums support so far
+        if let Some(property) = decl.properties.iter().find(|property| property.setter.is_some()) {
+            return Err(format!("setters are only supported for properties of enums, property '{}' of '{}' must be read-only", property.name, name));
+        }
+        for (parameter, protocol) in &decl.constraints {
+            if generic_parameters.is_empty() {
+                return Err(format!("trailing 'where' clause for extension of non-generic type '{}'", name));
+            }
+            if !generic_parameters.contains(&parameter.as_str()) {
+                return Err(format!("cannot find type '{}' in scope", parameter));
+            }
+            if !Self::is_builtin_protocol(protocol) && !self.protocols.contains_key(protocol) {
+                return Err(format!("cannot find type '{}' in scope", protocol));
+            }
+        }
+
+        // one implementation per member, whatever the constraints
+        let mut member_names = HashSet::new();
+        for existing in self.extensions.iter().filter(|existing| existing.extended_type == *name).chain([decl]) {
+            let names = existing.properties.iter().map(|property| property.name.clone())
+                .chain(existing.methods.iter().map(FunctionDecl::signature));
+            for member in names {
+                if !member_names.insert(member.clone()) {
+                    return Err(format!("invalid redeclaration of '{}'", member));
+                }
+            }
+        }
+        self.extensions.push(decl.clone());
+        Ok(())
+    }
+
+    fn is_builtin_protocol(name: &str) -> bool {
+        matches!(name, "Equatable" | "Hashable" | "Comparable" | "CustomStringConvertible")
+    }
+
+    /// whether `ty` meets the constraint `Element: protocol` of an extension
+    fn satisfies(&self, ty: &Type, protocol: &str) -> bool {
+        if let Type::Generic(_) = ty {
+            return self.is_constrained(ty, protocol) || (protocol == "Equatable" && self.is_equatable(ty));
+        }
+        match protocol {
+            "Equatable" => self.is_equatable(ty),
+            "Hashable" => self.is_hashable(ty),
+            // strings are ordered by their bytes
+            "Comparable" => ty.is_numeric() || *ty == Type::String,
+            _ => matches!(ty, Type::Named(name, _) if self.enums[name].conformances.iter().any(|conformance| conformance == protocol)),
+        }
+    }
+
+    /// the user-defined protocols the type parameter `ty` is constrained to
+    fn constraint_protocols(&self, ty: &Type) -> Vec<String> {
+        self.generic_parameters.iter()
+            .filter(|parameter| Type::Generic(parameter.name.clone()) == *ty)
+            .flat_map(|parameter| parameter.constraints.iter())
+            .filter(|protocol| self.protocols.contains_key(*protocol))
+            .cloned()
+            .collect()
+    }
+
+    /// whether the type parameter `ty` of the generic function being checked is declared as `ty: protocol`
+    fn is_constrained(&self, ty: &Type, protocol: &str) -> bool {
+        self.generic_parameters.iter()
+            .any(|parameter| Type::Generic(parameter.name.clone()) == *ty && parameter.constraints.iter().any(|constraint| constraint == protocol))
+    }
+
+    /// the default implementation of a requirement in an extension of `protocol`
+    fn default_method(&self, protocol: &str, method: &FunctionDecl) -> Option<&FunctionDecl> {
+        self.extensions.iter()
+            .filter(|decl| decl.extended_type == protocol)
+            .flat_map(|decl| &decl.methods)
+            .find(|candidate| candidate.signature() == method.signature())
+    }
+
+    fn default_property(&self, protocol: &str, name: &str) -> Option<&PropertyDecl> {
+        self.extensions.iter()
+            .filter(|decl| decl.extended_type == protocol)
+            .flat_map(|decl| &decl.properties)
+            .find(|property| property.name == name)
+    }
+
+    /// Every requirement needs a member of the same name and type;
+    /// `Equatable` and `Hashable` are satisfied by enums without associated values.
+    fn check_conformances(&self, decl: &EnumDecl) -> Result<(), String> {
+        let mut seen = HashSet::new();
+        for protocol in &decl.conformances {
+            if !seen.insert(protocol) {
+                return Err(format!("redundant conformance of '{}' to protocol '{}'", decl.name, protocol));
+            }
+            let does_not_conform = |requirement: String| {
+                format!("type '{}' does not conform to protocol '{}'; {}", decl.name, protocol, requirement)
+            };
+            match protocol.as_str() {
+                "Equatable" | "Hashable" => {
+                    if decl.has_associated_values() {
+                        return Err(does_not_conform(
+                            "cases with associated values cannot be compared with '=='".to_string()
+                        ));
+                    }
+                }
+                "CustomStringConvertible" => {
+                    if decl.find_property("description").is_none_or(|property| property.property_type != Type::String) {
+                        return Err(does_not_conform(
+                            "protocol requires property 'description' with type 'String'".to_string()
+                        ));
+                    }
+                }
+                name if self.enums.contains_key(name) => {
+                    return Err(format!("raw type '{}' is not expressible by an integer literal", name));
+                }
+                name => {
+                    let Some(protocol_decl) = self.protocols.get(name) else {
+                        return Err(format!("cannot find type '{}' in scope", name));
+                    };
+                    for requirement in &protocol_decl.requirements {
+                        match requirement {
+                            Requirement::Method(method) => {
+                                let witness = decl.methods.iter()
+                                    .find(|witness| witness.signature() == method.signature())
+                                    .or_else(|| self.default_method(name, method))
+                                    .filter(|witness| {
+                                        witness.return_type == method.return_type
+                                            // a requirement that throws can be met by a method that does not
+                                            && (method.throws || !witness.throws)
+                                            && witness.parameters.iter().zip(&method.parameters)
+                                                .all(|(witness, parameter)| witness.param_type == parameter.param_type)
+                                    });
+                                if witness.is_none() {
+                                    let parameter_types = method.parameters.iter()
+                                        .map(|parameter| parameter.param_type.to_string())
+                                        .collect::<Vec<_>>();
+                                    return Err(does_not_conform(format!(
+                                        "protocol requires function '{}' with type '({}){} -> {}'",
+                                        method.signature(), parameter_types.join(", "),
+                                        if method.throws { " throws" } else { "" }, method.return_type
+                                    )));
+                                }
+                            }
+                            Requirement::Property { name, property_type, settable } => {
+                                let witness = decl.find_property(name)
+                                    .or_else(|| self.default_property(protocol, name))
+                                    .filter(|witness| witness.property_type == *property_type);
+                                if witness.is_none() {
+                                    return Err(does_not_conform(format!(
+                                        "protocol requires property '{}' with type '{}'", name, property_type
+                                    )));
+                                }
+                                if *settable {
+                                    return Err(does_not_conform(format!(
+                                        "protocol requires property '{}' to be settable, which is not supported for enum properties", name
+                                    )));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn declare_function(&mut self, decl: &FunctionDecl) -> Result<(), String> {
+        let overloads = self.functions.entry(decl.name.clone()).or_default();
+        if overloads.iter().any(|existing| existing.signature() == decl.signature()) {
+            return Err(format!("invalid redeclaration of '{}'", decl.signature()));
+        }
+        overloads.push(decl.clone());
+
+        let mut names = HashSet::new();
+        for parameter in &decl.parameters {
+            if !names.insert(parameter.name.as_str()) {
+                return Err(format!("invalid redeclaration of '{}'", parameter.name));
+            }
+            if parameter.is_inout && decl.operator.is_some() {
+                return Err(format!("'inout' parameters of operator '{}' are not supported", decl.name));
+            }
+        }
+        if decl.throws && decl.operator.is_some() {
+            return Err(format!("throwing operator '{}' is not supported", decl.name));
+        }
+
+        for generic in &decl.generic_parameters {
+            for protocol in &generic.constraints {
+                if !Self::is_builtin_protocol(protocol) && !self.protocols.contains_key(protocol) {
+                    return Err(format!("cannot find type '{}' in scope", protocol));
+                }
+            }
+            // type arguments are never written out, they come from the arguments
+            let generic_type = Type::Generic(generic.name.clone());
+            if !decl.parameters.iter().any(|parameter| parameter.param_type.mentions(&generic_type)) {
+                return Err(format!("generic parameter '{}' is not used in function signature", generic.name));
+            }
+        }
+        Ok(())
+    }
+
+    /// enums are stored inline, so an enum that contains itself would have infinite size
+    fn check_recursive_enums(&mut self) -> Result<(), String> {
+        for decl in self.enums.values().cloned().collect::<Vec<_>>() {
+            // the associated values of a generic enum can have the types of its type parameters
+            self.generic_parameters = decl.generic_parameters;
+            for case in &decl.cases {
+                for associated in &case.associated_values {
+                    self.resolve_type(&associated.value_type)?;
+                }
+            }
+        }
+        self.generic_parameters.clear();
+
+        for name in self.enums.keys() {
+            let mut stack = vec![name.clone()];
+            let mut visited = HashSet::new();
+            while let Some(current) = stack.pop() {
+                for case in &self.enums[&current].cases {
+                    for associated in &case.associated_values {
+                        let mut ty = &associated.value_type;
+                        while let Type::Optional(wrapped) = ty {
+                            ty = wrapped;
+                        }
+                        if let Type::Named(inner, _) = ty {
+                            if inner == name {
+                                return Err(format!("recursive enum '{}' is not marked 'indirect'", name));
+                            }
+                            if visited.insert(inner.clone()) {
+                                stack.push(inner.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve_type(&self, ty: &Type) -> Result<(), String> {
+        match ty {
+            Type::Int | Type::Int8 | Type::Int16 | Type::Int32 | Type::Int64
+            | Type::UInt | Type::UInt8 | Type::UInt16 | Type::UInt32 | Type::UInt64
+            | Type::Double | Type::Float
+            | Type::Bool | Type::String | Type::Void | Type::Never => Ok(()),
+            Type::Named(name, arguments) => {
+                let Some(decl) = self.enums.get(name) else {
+                    return Err(format!("cannot find type '{}' in scope", name));
+                };
+                let parameters = &decl.generic_parameters;
+                if parameters.is_empty() && !arguments.is_empty() {
+                    return Err(format!("cannot specialize non-generic type '{}'", name));
+                }
+                if arguments.is_empty() && !parameters.is_empty() {
+                    return Err(format!("reference to generic type '{}' requires arguments in <...>", name));
+                }
+                if arguments.len() != parameters.len() {
+                    return Err(format!(
+                        "generic type '{}' specialized with too {} type parameters (got {}, but expected {})",
+                        name, if arguments.len() > parameters.len() { "many" } else { "few" }, arguments.len(), parameters.len()
+                    ));
+                }
+                for (argument, parameter) in arguments.iter().zip(parameters) {
+                    self.resolve_type(argument)?;
+                    if let Some(protocol) = parameter.constraints.iter().find(|protocol| !self.satisfies(argument, protocol)) {
+                        return Err(format!("type '{}' does not conform to protocol '{}'", argument, protocol));
+                    }
+                }
+                Ok(())
+            }
+            Type::Existential(name) => {
+                if self.protocols.contains_key(name) {
+                    Ok(())
+                } else {
+                    Err(format!("cannot find type '{}' in scope", name))
+                }
+            }
+            Type::Generic(name) => {
+                if self.generic_parameters.iter().any(|parameter| parameter.name == *name) {
+                    Ok(())
+                } else {
+                    Err(format!("cannot find type '{}' in scope", name))
+                }
+            }
+            // function signatures replace the `some P` they take
+            Type::Opaque(_) => Err("'some' types are only supported in the parameters and result of functions".to_string()),
+            Type::Optional(wrapped) | Type::Array(wrapped) => self.resolve_type(wrapped),
+            Type::Dictionary(key, value) => {
+                self.resolve_type(key)?;
+                self.expect_hashable(key)?;
+                self.resolve_type(value)
+            }
+            Type::Set(element) => {
+                self.resolve_type(element)?;
+                self.expect_hashable(element)
+            }
+            Type::Tuple(elements) => {
+                for element in elements {
+                    self.resolve_type(&element.element_type)?;
+                }
+                Ok(())
+            }
+            Type::Function { parameters, result, .. } => {
+                for parameter in parameters {
+                    self.resolve_type(parameter)?;
+                }
+                self.resolve_type(result)
+            }
+        }
+    }
+
+    // MARK - scopes
+
+    fn lookup(&self, name: &str) -> Option<&Variable> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn declare_variable(&mut self, name: &str, ty: Type, is_mutable: bool) -> Result<(), String> {
+        let scope = self.scopes.last_mut().expect("scope stack is never empty");
+        if scope.contains_key(name) {
+            return Err(format!("invalid redeclaration of '{}'", name));
+        }
+        scope.insert(name.to_string(), Variable { ty, is_mutable, is_computed: false, is_inout: false });
+        Ok(())
+    }
+
+    /// `self` of a mutating member or an `inout` parameter, which refers to the caller's variable
+    fn declare_inout_variable(&mut self, name: &str, ty: Type) -> Result<(), String> {
+        self.declare_variable(name, ty, true)?;
+        let scope = self.scopes.last_mut().expect("scope stack is never empty");
+        scope.get_mut(name).expect("just declared").is_inout = true;
+        Ok(())
+    }
+
+    /// a computed variable, which is mutable when it has a setter
+    fn declare_computed_variable(&mut self, name: &str, ty: Type, has_setter: bool) -> Result<(), String> {
+        self.declare_variable(name, ty, has_setter)?;
+        let scope = self.scopes.last_mut().expect("scope stack is never empty");
+        scope.get_mut(name).expect("just declared").is_computed = true;
+        Ok(())
+    }
+
+    /// `(a, _)` binds the elements of a tuple of the same length
+    fn declare_tuple_pattern(&mut self, names: &[Option<String>], ty: &Type, is_mutable: bool) -> Result<(), String> {
+        let Type::Tuple(elements) = ty else {
+            return Err(format!(
+                "tuple pattern cannot match values of the non-tuple type '{}'",
+                ty
+            ));
+        };
+        if elements.len() != names.len() {
+            return Err(format!(
+                "tuple pattern has the wrong length for tuple type '{}'",
+                ty
+            ));
+        }
+        for (name, element) in names.iter().zip(elements) {
+            if let Some(name) = name {
+                self.declare_variable(name, element.element_type.clone(), is_mutable)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// a bare identifier refers to a type only when no variable shadows it
+    fn enum_named(&self, expr: &Expression) -> Option<&EnumDecl> {
+        match expr {
+            Expression::Variable(name) if self.lookup(name).is_none() => self.enums.get(name),
+            Expression::SpecializedType(Type::Named(name, _)) => self.enums.get(name),
+            _ => None,
+        }
+    }
+
+    /// the type arguments written after a generic enum take the place of the contextual type
+    fn specialized_type<'a>(&self, base: &'a Expression, expected: Option<&'a Type>) -> Result<Option<&'a Type>, String> {
+        match base {
+            Expression::SpecializedType(ty) => {
+                self.resolve_type(ty)?;
+                Ok(Some(ty))
+            }
+            _ => Ok(expected),
+        }
+    }
+
+    /// the declaration of the enum `ty`, with the type arguments of a generic enum in its members
+    fn enum_decl(&self, ty: &Type) -> Cow<'_, EnumDecl> {
+        let Type::Named(name, arguments) = ty else {
+            unreachable!("'{}' is not an enum", ty);
+        };
+        match arguments.is_empty() {
+            true => Cow::Borrowed(&self.enums[name]),
+            false => Cow::Owned(self.enums[name].specialize(arguments)),
+        }
+    }
+
+    /// the type of `self` inside a method or getter
+    fn self_type(&self) -> Option<Type> {
+        Some(self.lookup("self")?.ty.clone())
+    }
+
+    /// whether a bare `name` inside a method stands for `self.name`
+    fn is_self_property(&self, name: &str) -> bool {
+        let Some(ty) = self.self_type() else {
+            return false;
+        };
+        let own = match &ty {
+            Type::Named(enum_name, _) => {
+                let decl = &self.enums[enum_name];
+                decl.find_property(name).is_some() || (name == "rawValue" && decl.raw_type.is_some())
+            }
+            Type::Existential(protocol) => self.protocols[protocol].find_requirement(name, None).is_some(),
+            Type::Array(_) | Type::Dictionary(..) | Type::Set(_) => name == "count" || name == "isEmpty",
+            _ => false,
+        };
+        own || self.applicable_extensions(&ty).iter()
+            .any(|(decl, _)| decl.properties.iter().any(|property| property.name == name))
+    }
+
+    /// whether a bare call of `name` inside a method calls a method of `self`
+    fn is_self_method(&self, name: &str) -> bool {
+        self.self_type().is_some_and(|ty| self.has_method(&ty, name))
+    }
+
+    /// `self.name`, what a bare member name stands for inside a method
+    fn self_member(name: &str) -> Expression {
+        Expression::Member {
+            base: Box::new(Expression::Variable("self".to_string())),
+            name: name.to_string(),
+        }
+    }
+
+    /// Extensions that add members to values of `ty`, with the generic
+    /// arguments they are specialized with: those of the protocols an enum
+    /// conforms to, of the protocol of an existential, or of the standard
+    /// library type.
+    fn applicable_extensions(&self, ty: &Type) -> Vec<(&ExtensionDecl, Vec<(String, Type)>)> {
+        self.extensions.iter()
+            .filter_map(|decl| {
+                let applies = match ty {
+                    Type::Named(enum_name, _) => self.enums[enum_name].conformances.contains(&decl.extended_type),
+                    Type::Existential(protocol) => decl.extended_type == *protocol,
+                    Type::Generic(_) => self.is_constrained(ty, &decl.extended_type),
+                    _ => return decl.bindings(ty).map(|bindings| (decl, bindings)),
+                };
+                applies.then(|| (decl, Vec::new()))
+            })
+            .collect()
+    }
+
+    fn has_method(&self, ty: &Type, name: &str) -> bool {
+        let own = match ty {
+            Type::Named(enum_name, _) => self.enums[enum_name].methods.iter().any(|method| method.name == name),
+            Type::Existential(protocol) => self.protocols[protocol].requirements.iter()
+                .any(|requirement| matches!(requirement, Requirement::Method(method) if method.name == name)),
+            Type::Generic(_) => self.constraint_protocols(ty).iter()
+                .any(|protocol| self.has_method(&Type::Existential(protocol.clone()), name)),
+            _ => false,
+        };
+        own || self.applicable_extensions(ty).iter()
+            .any(|(decl, _)| decl.methods.iter().any(|method| method.name == name))
+    }
+
+    /// the first constraint of a standard library extension that `ty` does not meet
+    fn unmet_constraint(&self, decl: &ExtensionDecl, bindings: &[(String, Type)]) -> Option<(Type, String)> {
+        decl.constraints.iter().find_map(|(parameter, protocol)| {
+            let (_, argument) = bindings.iter().find(|(name, _)| name == parameter)?;
+            (!self.satisfies(argument, protocol)).then(|| (argument.clone(), protocol.clone()))
+        })
+    }
+
+    /// Checks a member of a standard library extension once for every type it
+    /// is used with, the generic parameters replaced by the actual types.
+    fn instantiate(&mut self, decl: &FunctionDecl, self_type: &Type, kind: &str) -> Result<(), String> {
+        if !self.instantiated.insert(format!("{}.{}", self_type, decl.signature())) {
+            return Ok(());
+        }
+        // the body sees the globals only, not the scopes of the caller
+        let locals = self.scopes.split_off(1);
+        let infer_return_type = std::mem::take(&mut self.infer_return_type);
+        let result = self.check_function_body(decl, Some(self_type), kind);
+        self.scopes.extend(locals);
+        self.infer_return_type = infer_return_type;
+        result
+    }
+
+    /// Methods named `name` that can be called on a value of `ty`. The
+    /// requirements of a protocol and the methods of an enum come first, so they
+    /// win over default implementations in protocol extensions.
+    fn methods_of(&mut self, ty: &Type, name: &str) -> Result<Vec<FunctionDecl>, String> {
+        let mut methods = match ty {
+            Type::Named(..) => self.enum_decl(ty).methods.iter()
+                .filter(|method| method.name == name)
+                .cloned()
+                .collect::<Vec<_>>(),
+            Type::Existential(protocol) => self.protocols[protocol].requirements.iter()
+                .filter_map(|requirement| match requirement {
+                    Requirement::Method(method) if method.name == name => Some(method.clone()),
+                    _ => None,
+                })
+                .collect(),
+            Type::Generic(_) => self.constraint_protocols(ty).iter()
+                .flat_map(|protocol| self.protocols[protocol].requirements.iter())
+                .filter_map(|requirement| match requirement {
+                    Requirement::Method(method) if method.name == name => Some(method.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let mut unmet = None;
+        let mut instances = Vec::new();
+        for (decl, bindings) in self.applicable_extensions(ty) {
+            for method in decl.methods.iter().filter(|method| method.name == name) {
+                if methods.iter().any(|existing| existing.signature() == method.signature()) {
+                    continue;
+                }
+                if let Some(constraint) = self.unmet_constraint(decl, &bindings) {
+                    unmet.get_or_insert((method.signature(), decl.extended_type.clone(), constraint));
+                    continue;
+                }
+                let method = method.substitute(&bindings);
+                if !bindings.is_empty() {
+                    instances.push(method.clone());
+                }
+                methods.push(method);
+            }
+        }
+        if methods.is_empty() {
+            return Err(match unmet {
+                Some((signature, extended_type, (argument, protocol))) => format!(
+                    "referencing instance method '{}' on '{}' requires that '{}' conform to '{}'",
+                    signature, extended_type, argument, protocol
+                ),
+                None => format!("value of type '{}' has no member '{}'", ty, name),
+            });
+        }
+        // members of extensions of generic types are checked per instance
+        for method in &instances {
+            self.instantiate(method, ty, "instance method")?;
+        }
+        Ok(methods)
+    }
+
+    /// the type of a computed property added to `ty` by an extension
+    fn extension_property_type(&mut self, ty: &Type, name: &str) -> Result<Option<Type>, String> {
+        let found = self.applicable_extensions(ty).into_iter().find_map(|(decl, bindings)| {
+            let property = decl.properties.iter().find(|property| property.name == name)?;
+            Some((decl.extended_type.clone(), property.getter_function(), self.unmet_constraint(decl, &bindings), bindings))
+        });
+        let Some((extended_type, getter, unmet, bindings)) = found else {
+            return Ok(None);
+        };
+        if let Some((argument, protocol)) = unmet {
+            return Err(format!(
+                "property '{}' requires that '{}' conform to '{}' in extension of '{}'",
+                name, argument, protocol, extended_type
+            ));
+        }
+        let getter = getter.substitute(&bindings);
+        if !bindings.is_empty() {
+            self.instantiate(&getter, ty, "getter")?;
+        }
+        Ok(Some(getter.return_type))
+    }
+
+    fn type_of_method_call(&mut self, name: &str, methods: &[FunctionDecl], arguments: &[Argument]) -> Result<Type, String> {
+        let decl = self.resolve_function(name, methods, arguments)?;
+        self.check_arguments(&decl, arguments)
+    }
+
+    /// Check the arguments of a call to `decl` and return the result type. The
+    /// type parameters of a generic function are inferred from the arguments.
+    fn check_arguments(&mut self, decl: &FunctionDecl, arguments: &[Argument]) -> Result<Type, String> {
+        let bindings = self.infer_generic_arguments(decl, arguments)?;
+        let decl = decl.substitute(&bindings);
+
+        let mut inout_roots = HashSet::new();
+        let mut rethrown = Vec::new();
+        for (argument, parameter) in arguments.iter().zip(&decl.parameters) {
+            if !parameter.is_inout {
+                self.non_escaping_closure = matches!(argument.value, Expression::Closure { .. });
+                let actual = self.type_of(&argument.value, Some(&parameter.param_type))?;
+                if !self.is_convertible(&actual, &parameter.param_type) {
+                    return Err(self.conversion_error(&actual, &parameter.param_type));
+                }
+                if matches!(actual, Type::Function { throws: true, .. }) {
+                    rethrown.push(&argument.value);
+                }
+                continue;
+            }
+            let Expression::InOut(target) = &argument.value else {
+                let ty = self.type_of(&argument.value, Some(&parameter.param_type))?;
+                return Err(format!("passing value of type '{}' to an inout parameter requires explicit '&'", ty));
+            };
+            if let Expression::Subscript { base, .. } = target.as_ref()
+                && let Type::Dictionary(..) = self.type_of(base, None)?
+            {
+                return Err("dictionary subscripts cannot be passed as inout arguments".to_string());
+            }
+            let ty = self.assignable_type(target).map_err(|error| {
+                ["cannot assign to value", "cannot assign to property", "cannot assign through subscript"]
+                    .iter()
+                    .fold(error, |error, wording| error.replace(wording, "cannot pass immutable value as inout argument"))
+            })?;
+            // the argument is passed by address, so no conversion can take place
+            if ty != parameter.param_type {
+                return Err(format!(
+                    "cannot convert value of type '{}' to expected argument type '{}'",
+                    ty, parameter.param_type
+                ));
+            }
+            let root = Self::root_variable(target);
+            if !inout_roots.insert(root) {
+                return Err(format!(
+                    "overlapping accesses to '{}', but modification requires exclusive access; consider copying to a local variable",
+                    root
+                ));
+            }
+        }
+        if decl.rethrows && !rethrown.is_empty() {
+            self.check_throwing_call(ErrorSource::Values(rethrown))?;
+        } else if decl.throws && !decl.rethrows {
+            self.check_throwing_call(ErrorSource::Call)?;
+        }
+        Ok(decl.return_type)
+    }
+
+    /// a call that can throw must be marked with `try` and its errors handled
+    fn check_throwing_call(&mut self, source: ErrorSource) -> Result<(), String> {
+        if !self.in_try {
+            return Err("call can throw but is not marked with 'try'".to_string());
+        }
+        self.handle_error(source)
+    }
+
+    /// Find the handler of an error thrown here by `source`. Errors pass `do`
+    /// statements without a catch-all and leave throwing functions.
+    fn handle_error(&mut self, source: ErrorSource) -> Result<(), String> {
+        let mut not_exhaustive = false;
+        for handler in self.error_handlers.iter_mut().rev() {
+            match handler {
+                ErrorHandler::Do { exhaustive: false } => not_exhaustive = true,
+                ErrorHandler::Closure { throws } => {
+                    *throws = true;
+                    return Ok(());
+                }
+                ErrorHandler::Rethrows { parameters } => {
+                    let rethrown = match &source {
+                        ErrorSource::Values(values) => values.iter().all(|value| {
+                            matches!(value, Expression::Variable(name) if parameters.contains(name))
+                        }),
+                        _ => false,
+                    };
+                    if rethrown {
+                        return Ok(());
+                    }
+                    return Err("a function declared 'rethrows' may only throw if its parameter does".to_string());
+                }
+                ErrorHandler::Function { throws: false } => break,
+                ErrorHandler::Defer => return Err("errors cannot be thrown out of a defer body".to_string()),
+                _ => return Ok(()),
+            }
+        }
+        let reason = match not_exhaustive {
+            true => "the enclosing catch is not exhaustive",
+            false => "the enclosing function is not declared 'throws'",
+        };
+        Err(match (matches!(source, ErrorSource::Throw), not_exhaustive) {
+            (true, _) => format!("error is not handled because {}", reason),
+            (false, true) => format!("errors thrown from here are not handled because {}", reason),
+            (false, false) => "errors thrown from here are not handled".to_string(),
+        })
+    }
+
+    /// the variable an assignable expression such as `pairs[0].x` is part of
+    fn root_variable(target: &Expression) -> &str {
+        match target {
+            Expression::Member { base, .. } | Expression::Subscript { base, .. } => Self::root_variable(base),
+            Expression::Variable(name) => name,
+            _ => unreachable!("assignable expression"),
+        }
+    }
+
+    /// types of the type parameters of `decl` for a call with `arguments`, which must meet the constraints
+    fn infer_generic_arguments(&mut self, decl: &FunctionDecl, arguments: &[Argument]) -> Result<Vec<(String, Type)>, String> {
+        let bindings = decl.infer_generic_arguments(arguments, |value, hint| self.type_of(value, hint))?;
+        for generic in &decl.generic_parameters {
+            let Some((_, ty)) = bindings.iter().find(|(name, _)| *name == generic.name) else {
+                return Err(format!("generic parameter '{}' could not be inferred", Type::Generic(generic.name.clone())));
+            };
+            if let Some(protocol) = generic.constraints.iter().find(|protocol| !self.satisfies(ty, protocol)) {
+                return Err(format!(
+                    "global function '{}' requires that '{}' conform to '{}'",
+                    decl.name, ty, protocol
+                ));
+            }
+        }
+        Ok(bindings)
+    }
+
+    // MARK - statements
+
+    fn check_statement(&mut self, statement: &Statement) -> Result<(), String> {
+        match statement {
+            Statement::Print(expr) => {
+                self.type_of(expr, None)?;
+                Ok(())
+            }
+            Statement::VarDecl { name, type_annotation, value, is_mutable } => {
+                let ty = match type_annotation {
+                    Some(annotation) => {
+                        self.resolve_type(annotation)?;
+                        self.expect_type(value, annotation)?;
+                        annotation.clone()
+                    }
+                    None => self.type_of(value, None)?,
+                };
+                self.declare_variable(name, ty, *is_mutable)
+            }
+            Statement::Destructure { names, type_annotation, value, is_mutable } => {
+                let ty = match type_annotation {
+                    Some(annotation) => {
+                        self.resolve_type(annotation)?;
+                        self.expect_type(value, annotation)?;
+                        annotation.clone()
+                    }
+                    None => self.type_of(value, None)?,
+                };
+                self.declare_tuple_pattern(names, &ty, *is_mutable)
+            }
+            Statement::Assignment { target, operator, value } => {
+                let ty = self.assignable_type(target)?;
+                let Some(operator) = operator else {
+                    return self.expect_type(value, &ty);
+                };
+                // `x += 1` is checked like `x = x + 1`
+                let result_type = self.type_of_binary(target, operator, value, Some(&ty))?;
+                if result_type == ty {
+                    Ok(())
+                } else {
+                    Err(self.conversion_error(&result_type, &ty))
+                }
+            }
+            Statement::ComputedVar(property) => {
+                if self.scopes.len() > 1 {
+                    return Err(format!(
+                        "computed variable '{}' must be declared at the top level",
+                        property.name
+                    ));
+                }
+                self.resolve_type(&property.property_type)?;
+                // declared first, so that the accessors can use it
+                self.declare_computed_variable(&property.name, property.property_type.clone(), property.setter.is_some())?;
+                self.check_property(property, None)
+            }
+            Statement::StoredVar(decl) => {
+                if self.scopes.len() > 1 {
+                    return Err(match decl.is_lazy {
+                        true => format!("'lazy' variable '{}' must be declared at the top level", decl.name),
+                        false => format!("variable '{}' with observers must be declared at the top level", decl.name),
+                    });
+                }
+                let ty = self.check_initializer(decl)?;
+                self.declare_variable(&decl.name, ty.clone(), decl.is_mutable)?;
+                self.check_observers(decl, &ty)
+            }
+            Statement::EnumDecl(decl) => {
+                if self.scopes.len() > 1 {
+                    return Err(format!(
+                        "enum '{}' must be declared at the top level",
+                        decl.name
+                    ));
+                }
+                // the members of a generic enum are checked once, for any type arguments meeting its constraints
+                let generic_parameters = std::mem::replace(&mut self.generic_parameters, decl.generic_parameters.clone());
+                let result = self.check_enum(decl);
+                self.generic_parameters = generic_parameters;
+                result
+            }
+            Statement::Extension(decl) => {
+                if self.scopes.len() > 1 {
+                    return Err("declaration is only valid at file scope".to_string());
+                }
+                let self_type = if let Some(enum_decl) = self.enums.get(&decl.extended_type) {
+                    let enum_type = enum_decl.declared_type();
+                    for function in &decl.static_functions {
+                        if !function.parameters.iter().any(|parameter| parameter.param_type == enum_type) {
+                            return Err(format!(
+                                "member operator '{}' must have at least one argument of type '{}'",
+                                function.signature(),
+                                decl.extended_type
+                            ));
+                        }
+                        self.check_function(function)?;
+                    }
+                    enum_type
+                } else if self.protocols.contains_key(&decl.extended_type) {
+                    Type::Existential(decl.extended_type.clone())
+                } else if let Some(ty) = Type::builtin(&decl.extended_type) {
+                    ty
+                } else {
+                    // members of `Array` and friends are checked for each element type they are used with
+                    return Ok(());
+                };
+                // those of a generic enum see its type parameters
+                let enum_parameters = match &self_type {
+                    Type::Named(name, _) => self.enums[name].generic_parameters.clone(),
+                    _ => Vec::new(),
+                };
+                let generic_parameters = std::mem::replace(&mut self.generic_parameters, enum_parameters);
+                let result = decl.methods.iter()
+                    .try_for_each(|method| self.check_function_body(method, Some(&self_type), "instance method"))
+                    .and_then(|_| decl.properties.iter().try_for_each(|property| self.check_property(property, Some(&self_type))));
+                self.generic_parameters = generic_parameters;
+                result?;
+                self.check_static_properties(&decl.extended_type, &decl.static_properties, &decl.static_stored_properties)
+            }
+            Statement::ProtocolDecl(decl) => {
+                if self.scopes.len() > 1 {
+                    return Err(format!(
+                        "protocol '{}' must be declared at the top level",
+                        decl.name
+                    ));
+                }
+                for requirement in &decl.requirements {
+                    match requirement {
+                        Requirement::Method(method) => {
+                            for parameter in &method.parameters {
+                                if parameter.is_inout {
+                                    return Err(format!("'inout' parameters are not supported in protocol requirements such as '{}'", method.signature()));
+                                }
+                                self.resolve_type(&parameter.param_type)?;
+                            }
+                            self.resolve_type(&method.return_type)?;
+                        }
+                        Requirement::Property { property_type, .. } => self.resolve_type(property_type)?,
+                    }
+                }
+                Ok(())
+            }
+            Statement::Switch { subject, cases } => {
+                let subject_type = self.type_of(subject, None)?;
+                self.check_switch(&subject_type, cases)
+            }
+            Statement::If { conditions, then_branch, else_branch } => {
+                // bindings of the conditions are only visible in the then branch
+                self.scopes.push(HashMap::new());
+                let result = self.check_conditions(conditions)
+                    .and_then(|_| self.check_block(then_branch));
+                self.scopes.pop();
+                result?;
+
+                match else_branch {
+                    Some(else_branch) => self.check_block(else_branch),
+                    None => Ok(()),
+                }
+            }
+            Statement::While { conditions, body } => {
+                self.scopes.push(HashMap::new());
+                self.loop_depth += 1;
+                let result = self.check_conditions(conditions)
+                    .and_then(|_| self.check_block(body));
+                self.loop_depth -= 1;
+                self.scopes.pop();
+                result
+            }
+            Statement::ForIn { pattern, sequence, body } => {
+                let element_type = match self.type_of(sequence, None)? {
+                    Type::Array(element_type) | Type::Set(element_type) => *element_type,
+                    Type::Dictionary(key_type, value_type) => Type::dictionary_element(*key_type, *value_type),
+                    other => return Err(format!("for-in loop requires '{}' to conform to 'Sequence'", other)),
+                };
+                self.scopes.push(HashMap::new());
+                self.loop_depth += 1;
+                let result = match pattern {
+                    ForPattern::Name(Some(name)) => self.declare_variable(name, element_type, false),
+                    ForPattern::Name(None) => Ok(()),
+                    ForPattern::Tuple(names) => self.declare_tuple_pattern(names, &element_type, false),
+                }
+                .and_then(|_| self.check_block(body));
+                self.loop_depth -= 1;
+                self.scopes.pop();
+                result
+            }
+            Statement::Guard { conditions, else_branch } => {
+                // bindings are checked in a scope of their own so that the
+                // else branch cannot see them, then moved to the enclosing scope
+                self.scopes.push(HashMap::new());
+                let result = self.check_conditions(conditions);
+                let bindings = self.scopes.pop().unwrap_or_default();
+                result?;
+
+                self.check_block(else_branch)?;
+                if !self.statements_exit(else_branch) {
+                    return Err(
+                        "'guard' body must not fall through, consider using a 'return' or 'throw' to exit the scope".to_string()
+                    );
+                }
+
+                let mut bindings = bindings.into_iter().collect::<Vec<_>>();
+                bindings.sort_by(|a, b| a.0.cmp(&b.0));
+                for (name, variable) in bindings {
+                    self.declare_variable(&name, variable.ty, variable.is_mutable)?;
+                }
+                Ok(())
+            }
+            Statement::FunctionDecl(decl) => {
+                if self.scopes.len() > 1 {
+                    return Err(format!(
+                        "function '{}' must be declared at the top level",
+                        decl.name
+                    ));
+                }
+                self.check_function(decl)
+            }
+            Statement::Return(value) => {
+                if self.infer_return_type {
+                    self.infer_return_type = false;
+                    let return_type = match value {
+                        Some(value) => self.type_of(value, None)?,
+                        None => Type::Void,
+                    };
+                    self.return_type = Some(match return_type {
+                        ty @ Type::Optional(_) => ty,
+                        ty if self.optional_result => Type::Optional(Box::new(ty)),
+                        ty => ty,
+                    });
+                    return Ok(());
+                }
+                if self.in_defer {
+                    return Err("'return' cannot transfer control out of a defer statement".to_string());
+                }
+                let Some(return_type) = self.return_type.clone() else {
+                    return Err("return invalid outside of a func".to_string());
+                };
+                match (value, &return_type) {
+                    (Some(_), Type::Never) => {
+                        Err("cannot return from a function with uninhabited return type 'Never'".to_string())
+                    }
+                    (Some(value), Type::Void) => {
+                        match self.type_of(value, Some(&Type::Void))? {
+                            Type::Void => Ok(()),
+                            _ => Err("unexpected non-void return value in void function".to_string()),
+                        }
+                    }
+                    (Some(value), _) if self.opaque_return.is_some() => self.check_opaque_return(value, &return_type),
+                    (Some(value), _) => self.expect_type(value, &return_type),
+                    (None, Type::Void) => Ok(()),
+                    (None, _) => Err("non-void function should return a value".to_string()),
+                }
+            }
+            Statement::Throw(value) => {
+                let ty = self.type_of(value, Some(&Type::error()))?;
+                if !self.is_convertible(&ty, &Type::error()) {
+                    return Err(format!("thrown expression type '{}' does not conform to 'Error'", ty));
+                }
+                self.handle_error(ErrorSource::Throw)
+            }
+            Statement::Do { body, catches } => {
+                if !catches.is_empty() {
+                    let exhaustive = catches.iter().any(CatchClause::is_exhaustive);
+                    self.error_handlers.push(ErrorHandler::Do { exhaustive });
+                }
+                let result = self.check_block(body);
+                if !catches.is_empty() {
+                    self.error_handlers.pop();
+                }
+                result?;
+                // errors thrown by a catch clause go to the handlers around the `do`
+                catches.iter().try_for_each(|clause| self.check_catch_clause(clause))
+            }
+            Statement::Defer(body) => {
+                // loops and switches around the `defer` cannot be left from its body
+                let saved = (
+                    std::mem::take(&mut self.loop_depth),
+                    std::mem::take(&mut self.switch_depth),
+                    std::mem::replace(&mut self.in_defer, true),
+                );
+                self.error_handlers.push(ErrorHandler::Defer);
+                let result = self.check_block(body);
+                self.error_handlers.pop();
+                (self.loop_depth, self.switch_depth, self.in_defer) = saved;
+                result
+            }
+            Statement::Break => {
+                if self.loop_depth == 0 && self.switch_depth == 0 && self.in_defer {
+                    return Err("'break' cannot transfer control out of a defer statement".to_string());
+                }
+                if self.loop_depth == 0 && self.switch_depth == 0 {
+                    return Err("'break' is only allowed inside a loop, if, do, or switch".to_string());
+                }
+                Ok(())
+            }
+            Statement::Continue => {
+                if self.loop_depth == 0 && self.in_defer {
+                    return Err("'continue' cannot transfer control out of a defer statement".to_string());
+                }
+                if self.loop_depth == 0 {
+                    return Err("'continue' is only allowed inside a loop".to_string());
+                }
+                Ok(())
+            }
+            Statement::Expression(expr) => {
+                self.type_of(expr, None)?;
+                Ok(())
+            }
+        }
+    }
+
+    /// the operators, members and conformances of an enum declaration
+    fn check_enum(&mut self, decl: &EnumDecl) -> Result<(), String> {
+        let enum_type = decl.declared_type();
+        for function in &decl.static_functions {
+            if !function.parameters.iter().any(|parameter| parameter.param_type == enum_type) {
+                return Err(format!(
+                    "member operator '{}' must have at least one argument of type '{}'",
+                    function.signature(),
+                    decl.name
+                ));
+            }
+            self.check_function(function)?;
+        }
+        // conformances can be declared and satisfied by extensions
+        let merged = self.enums[&decl.name].clone();
+        self.check_conformances(&merged)?;
+        for method in &decl.methods {
+            self.check_function_body(method, Some(&enum_type), "instance method")?;
+        }
+        for property in &decl.properties {
+            self.check_property(property, Some(&enum_type))?;
+        }
+        self.check_static_properties(&decl.name, &decl.static_properties, &decl.static_stored_properties)
+    }
+
+    /// the pattern of a catch clause matches the error as any `Error`, or as the enum it names
+    fn check_catch_clause(&mut self, clause: &CatchClause) -> Result<(), String> {
+        let error_type = match &clause.error_type {
+            Some(ty) => {
+                self.resolve_type(ty)?;
+                if !matches!(ty, Type::Named(..)) || !self.satisfies(ty, "Error") {
+                    return Err(format!("catching errors as '{}' is not supported, only enums conforming to 'Error' can be matched", ty));
+                }
+                ty.clone()
+            }
+            None => Type::error(),
+        };
+        let mut bindings = Vec::new();
+        self.check_pattern(&clause.pattern, &error_type, &mut bindings)?;
+
+        self.scopes.push(HashMap::new());
+        let result = bindings.into_iter()
+            .try_for_each(|(name, ty)| self.declare_variable(&name, ty, false))
+            .and_then(|_| self.check_block(&clause.body));
+        self.scopes.pop();
+        result
+    }
+
+    /// the getter and setter of a computed property, which see the value they belong to as `self`
+    fn check_property(&mut self, property: &PropertyDecl, self_type: Option<&Type>) -> Result<(), String> {
+        self.check_function_body(&property.getter_function(), self_type, "getter")?;
+        match property.setter_function() {
+            Some(setter) => self.check_function_body(&setter, self_type, "setter"),
+            None => Ok(()),
+        }
+    }
+
+    /// The type of the value of a stored property, which is computed in a
+    /// function of its own, so errors cannot be thrown out of it.
+    fn check_initializer(&mut self, decl: &StoredPropertyDecl) -> Result<Type, String> {
+        let saved_handlers = std::mem::replace(&mut self.error_handlers, vec![ErrorHandler::Function { throws: false }]);
+        let result = match &decl.type_annotation {
+            Some(annotation) => self.resolve_type(annotation)
+                .and_then(|_| self.expect_type(&decl.value, annotation))
+                .map(|_| annotation.clone()),
+            None => self.type_of(&decl.value, None),
+        };
+        self.error_handlers = saved_handlers;
+        result
+    }
+
+    /// `willSet` and `didSet` are functions taking the new and the old value
+    fn check_observers(&mut self, decl: &StoredPropertyDecl, ty: &Type) -> Result<(), String> {
+        for (observer, kind) in [(&decl.will_set, "willSet"), (&decl.did_set, "didSet")] {
+            if let Some(observer) = observer {
+                self.check_function_body(&observer.function(&decl.name, ty), None, kind)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn declare_static_properties(&mut self, type_name: &str, properties: &[PropertyDecl], stored: &[StoredPropertyDecl]) -> Result<(), String> {
+        for property in properties {
+            self.resolve_type(&property.property_type)?;
+            self.static_properties.insert(format!("{}.{}", type_name, property.name), Variable {
+                ty: property.property_type.clone(),
+                is_mutable: property.setter.is_some(),
+                is_computed: true,
+                is_inout: false,
+            });
+        }
+        for property in stored {
+            let ty = self.check_initializer(property)?;
+            self.static_properties.insert(format!("{}.{}", type_name, property.name), Variable {
+                ty,
+                is_mutable: property.is_mutable,
+                is_computed: false,
+                is_inout: false,
+            });
+        }
+        Ok(())
+    }
+
+    /// the accessors of static properties, whose values were checked with their declaration
+    fn check_static_properties(&mut self, type_name: &str, properties: &[PropertyDecl], stored: &[StoredPropertyDecl]) -> Result<(), String> {
+        for property in properties {
+            self.check_property(property, None)?;
+        }
+        for property in stored {
+            let ty = self.static_properties[&format!("{}.{}", type_name, property.name)].ty.clone();
+            self.check_observers(property, &ty)?;
+        }
+        Ok(())
+    }
+
+    fn check_function(&mut self, decl: &FunctionDecl) -> Result<(), String> {
+        // the body of a generic function is checked once, for any types meeting its constraints
+        let generic_parameters = std::mem::replace(&mut self.generic_parameters, decl.generic_parameters.clone());
+        let result = self.check_function_body(decl, None, "global function");
+        self.generic_parameters = generic_parameters;
+        result
+    }
+
+    fn function_error_handler(decl: &FunctionDecl) -> ErrorHandler {
+        if !decl.rethrows {
+            return ErrorHandler::Function { throws: decl.throws };
+        }
+        let parameters = decl.parameters.iter()
+            .filter(|parameter| matches!(parameter.param_type, Type::Function { throws: true, .. }))
+            .map(|parameter| parameter.name.clone())
+            .collect();
+        ErrorHandler::Rethrows { parameters }
+    }
 
-}
\ No newline at end of file
+    /// `kind` names the function in the missing return error; methods
+    /// and getters see the value they are called on as `self`
+    fn check_function_body(&mut self, decl: &FunctionDecl, self_type: Option<&Type>, kind: &str) -> Result<(), String> {
+        for parameter in &decl.parameters {
+            self.resolve_type(&parameter.param_type)?;
+        }
+        self.resolve_type(&decl.return_type)?;
+
+        // a function body starts outside of any loop, switch, `try` or `defer` of the caller
+        let saved = (
+            self.return_type.replace(decl.return_type.clone()),
+            std::mem::take(&mut self.loop_depth),
+            std::mem::take(&mut self.switch_depth),
+            std::mem::replace(&mut self.error_handlers, vec![Self::function_error_handler(decl)]),
+            std::mem::take(&mut self.in_try),
+            std::mem::take(&mut self.in_defer),
+            std::mem::replace(
+                &mut self.opaque_return,
+                decl.opaque_result.then(|| (format!("{} '{}'", kind, decl.signature()), None)),
+            ),
+        );
+        self.scopes.push(HashMap::new());
+        match self_type {
+            Some(self_type) if decl.is_mutating => self.declare_inout_variable("self", self_type.clone())?,
+            Some(self_type) => self.declare_variable("self", self_type.clone(), false)?,
+            None => {}
+        }
+        let mut result = decl.parameters.iter()
+            .try_for_each(|parameter| match parameter.is_inout {
+                true => self.declare_inout_variable(&parameter.name, parameter.param_type.clone()),
+                false => self.declare_variable(&parameter.name, parameter.param_type.clone(), false),
+            })
+            .and_then(|_| self.check_block(&decl.body));
+        self.scopes.pop();
+        let opaque_return = std::mem::replace(&mut self.opaque_return, saved.6);
+        (self.return_type, self.loop_depth, self.switch_depth, self.error_handlers, self.in_try, self.in_defer, _) = saved;
+        if result.is_ok()
+            && let Some((_, None)) = opaque_return
+        {
+            result = Err(
+                "function declares an opaque return type, but has no return statements in its body from which to infer an underlying type"
+                    .to_string()
+            );
+        }
+
+        if result.is_ok() && !self.statements_exit(&decl.body) {
+            match decl.return_type {
+                Type::Void => {}
+                Type::Never => {
+                    result = Err(format!(
+                        "function '{}' with uninhabited return type 'Never' is missing call to another never-returning function on all paths",
+                        decl.name
+                    ));
+                }
+                _ => {
+                    result = Err(format!(
+                        "missing return in {} expected to return '{}'",
+                        kind, decl.return_type
+                    ));
+                }
+            }
+        }
+        result
+    }
+
+    /// Every `return` of a function declared `-> some P` returns a value of
+    /// the same type conforming to `P`, which callers see as `any P`.
+    fn check_opaque_return(&mut self, value: &Expression, return_type: &Type) -> Result<(), String> {
+        let Some((function, underlying)) = self.opaque_return.clone() else {
+            unreachable!("function with an opaque result");
+        };
+        let Type::Existential(protocol) = return_type else {
+            unreachable!("opaque result returned as an existential");
+        };
+        let ty = self.type_of(value, underlying.as_ref())?;
+        if let Type::Existential(_) = ty {
+            return Err(format!("type '{}' cannot conform to '{}'", ty, protocol));
+        }
+        if !self.is_convertible(&ty, return_type) {
+            return Err(format!("return type of {} requires that '{}' conform to '{}'", function, ty, protocol));
+        }
+        match underlying {
+            Some(underlying) if underlying != ty => Err(format!(
+                "function declares an opaque return type 'some {}', but the return statements in its body do not have matching underlying types",
+                protocol
+            )),
+            Some(_) => Ok(()),
+            None => {
+                self.opaque_return = Some((function, Some(ty)));
+                Ok(())
+            }
+        }
+    }
+
+    /// whether control can never fall off the end of `statements`
+    fn statements_exit(&self, statements: &[Statement]) -> bool {
+        self.block_exits(statements, true)
+    }
+
+    /// `break_exits` is false in `switch` cases, where `break` only leaves the switch
+    fn block_exits(&self, statements: &[Statement], break_exits: bool) -> bool {
+        statements.iter().any(|statement| match statement {
+            Statement::Return(_) | Statement::Continue | Statement::Throw(_) => true,
+            Statement::Break => break_exits,
+            Statement::Expression(expr) => self.is_never_call(expr),
+            Statement::If { then_branch, else_branch: Some(else_branch), .. } => {
+                self.block_exits(then_branch, break_exits) && self.block_exits(else_branch, break_exits)
+            }
+            Statement::Switch { cases, .. } => {
+                cases.iter().all(|case| self.block_exits(&case.body, false))
+            }
+            // errors no clause catches are thrown on
+            Statement::Do { body, catches } => {
+                self.block_exits(body, break_exits)
+                    && catches.iter().all(|clause| self.block_exits(&clause.body, break_exits))
+            }
+            // `while true` is only left by a `break`
+            Statement::While { conditions, body } => {
+                matches!(conditions.as_slice(), [Condition::Expression(Expression::Bool(true))])
+                    && !Self::breaks_out(body)
+            }
+            _ => false,
+        })
+    }
+
+    /// whether `statements` contain a `break` out of the loop they are the body of
+    fn breaks_out(statements: &[Statement]) -> bool {
+        statements.iter().any(|statement| match statement {
+            Statement::Break => true,
+            Statement::If { then_branch, else_branch, .. } => {
+                Self::breaks_out(then_branch) || else_branch.as_deref().is_some_and(Self::breaks_out)
+            }
+            Statement::Guard { else_branch, .. } => Self::breaks_out(else_branch),
+            Statement::Do { body, catches } => {
+                Self::breaks_out(body) || catches.iter().any(|clause| Self::breaks_out(&clause.body))
+            }
+            // nested loops and switches are what their own `break`s leave
+            _ => false,
+        })
+    }
+
+    /// calls to functions returning `Never`, like `fatalError()`
+    fn is_never_call(&self, expr: &Expression) -> bool {
+        let Expression::Call { callee, arguments } = expr else {
+            return false;
+        };
+        let Expression::Variable(name) = callee.as_ref() else {
+            return false;
+        };
+        match self.functions.get(name) {
+            Some(overloads) => overloads.iter()
+                .any(|decl| decl.matches_labels(arguments) && decl.return_type == Type::Never),
+            None => name == "fatalError",
+        }
+    }
+
+    fn check_block(&mut self, statements: &[Statement]) -> Result<(), String> {
+        self.scopes.push(HashMap::new());
+        let result = statements.iter().try_for_each(|statement| self.check_statement(statement));
+        self.scopes.pop();
+        result
+    }
+
+    /// check an `if`/`while` condition list, declaring optional bindings in the current scope
+    fn check_conditions(&mut self, conditions: &[Condition]) -> Result<(), String> {
+        for condition in conditions {
+            match condition {
+                Condition::Expression(expr) => self.expect_condition(expr)?,
+                Condition::OptionalBinding { name, is_mutable, type_annotation, value } => {
+                    let wrapped = self.unwrapped_binding_type(value, type_annotation.as_ref())?;
+                    self.declare_variable(name, wrapped, *is_mutable)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn expect_condition(&mut self, expr: &Expression) -> Result<(), String> {
+        match self.type_of(expr, Some(&Type::Bool))? {
+            Type::Bool => Ok(()),
+            ty @ Type::Optional(_) => Err(format!(
+                "optional type '{}' cannot be used as a boolean; test for '!= nil' instead",
+                ty
+            )),
+            ty => Err(format!(
+                "type '{}' cannot be used as a boolean; test for '!= 0' instead",
+                ty
+            )),
+        }
+    }
+
+    /// type bound by `let name = value` in an optional binding condition
+    fn unwrapped_binding_type(&mut self, value: &Expression, annotation: Option<&Type>) -> Result<Type, String> {
+        let expected = annotation.map(|ty| Type::Optional(Box::new(ty.clone())));
+        let value_type = self.type_of(value, expected.as_ref())?;
+        let Type::Optional(wrapped) = value_type else {
+            return Err(format!(
+                "initializer for conditional binding must have Optional type, not '{}'",
+                value_type
+            ));
+        };
+        if let Some(annotation) = annotation {
+            self.resolve_type(annotation)?;
+            if *annotation != *wrapped {
+                return Err(self.conversion_error(&wrapped, annotation));
+            }
+        }
+        Ok(*wrapped)
+    }
+
+    fn check_switch(&mut self, subject_type: &Type, cases: &[SwitchCase]) -> Result<(), String> {
+        if cases.is_empty() {
+            return Err("'switch' statement body must have at least one 'case' or 'default' block".to_string());
+        }
+
+        for (index, case) in cases.iter().enumerate() {
+            if case.patterns == [Pattern::Wildcard] && index + 1 != cases.len() {
+                return Err("additional 'case' blocks cannot appear after the 'default' block of a 'switch'".to_string());
+            }
+
+            let mut bindings: Option<Vec<(String, Type)>> = None;
+            for pattern in &case.patterns {
+                let mut pattern_bindings = Vec::new();
+                self.check_pattern(pattern, subject_type, &mut pattern_bindings)?;
+                pattern_bindings.sort_by(|a, b| a.0.cmp(&b.0));
+                match &bindings {
+                    Some(previous) if *previous != pattern_bindings => {
+                        return Err(
+                            "all patterns of a 'case' must bind the same variables with the same types".to_string()
+                        );
+                    }
+                    _ => bindings = Some(pattern_bindings),
+                }
+            }
+
+            if case.body.is_empty() {
+                return Err("'case' label in a 'switch' must have at least one executable statement".to_string());
+            }
+
+            self.scopes.push(HashMap::new());
+            self.switch_depth += 1;
+            let result = bindings.unwrap_or_default().into_iter()
+                .try_for_each(|(name, ty)| self.declare_variable(&name, ty, false))
+                .and_then(|_| case.body.iter().try_for_each(|statement| self.check_statement(statement)));
+            self.switch_depth -= 1;
+            self.scopes.pop();
+            result?;
+        }
+
+        let rows = cases.iter()
+            .flat_map(|case| case.patterns.iter().map(|pattern| vec![pattern.clone()]))
+            .collect::<Vec<_>>();
+        if let Some(witness) = self.missing_pattern(&rows, std::slice::from_ref(subject_type)) {
+            return Err(format!(
+                "switch must be exhaustive; add missing case: '{}'",
+                witness[0]
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_pattern(&self, pattern: &Pattern, ty: &Type, bindings: &mut Vec<(String, Type)>) -> Result<(), String> {
+        match pattern {
+            Pattern::Wildcard => Ok(()),
+            Pattern::Binding(name) => {
+                if bindings.iter().any(|(bound, _)| bound == name) {
+                    return Err(format!("invalid redeclaration of '{}'", name));
+                }
+                bindings.push((name.clone(), ty.clone()));
+                Ok(())
+            }
+            Pattern::Number(value) => {
+                if ty.is_integer() {
+                    Self::check_integer_literal(*value as i128, ty)
+                } else {
+                    Err(format!(
+                        "expression pattern of type 'Int' cannot match values of type '{}'",
+                        ty
+                    ))
+                }
+            }
+            Pattern::EnumCase { enum_name, case_name, subpatterns } => {
+                let payload = self.case_payload(ty, case_name)
+                    .ok_or_else(|| format!("type '{}' has no member '{}'", ty, case_name))?;
+                if let Some(enum_name) = enum_name
+                    && !matches!(ty, Type::Named(name, _) if name == enum_name)
+                {
+                    return Err(format!(
+                        "enum case '{}' is not a member of type '{}'",
+                        case_name, ty
+                    ));
+                }
+                if let Some(subpatterns) = subpatterns {
+                    if subpatterns.len() != payload.len() {
+                        return Err(format!(
+                            "tuple pattern has the wrong length for enum case '{}': expected {}, got {}",
+                            case_name, payload.len(), subpatterns.len()
+                        ));
+                    }
+                    for (subpattern, field_type) in subpatterns.iter().zip(&payload) {
+                        self.check_pattern(subpattern, field_type, bindings)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// types of the associated values of `case_name`, or None if `ty` has no such case
+    fn case_payload(&self, ty: &Type, case_name: &str) -> Option<Vec<Type>> {
+        match ty {
+            Type::Named(..) => {
+                let decl = self.enum_decl(ty);
+                let (_, case) = decl.find_case(case_name)?;
+                Some(case.associated_values.iter().map(|value| value.value_type.clone()).collect())
+            }
+            Type::Optional(wrapped) => match case_name {
+                "some" => Some(vec![(**wrapped).clone()]),
+                "none" => Some(Vec::new()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// every case constructor of `ty`, or None when the type has unbounded values (Int)
+    fn constructors(&self, ty: &Type) -> Option<Vec<(String, Vec<Type>)>> {
+        match ty {
+            Type::Named(..) => Some(
+                self.enum_decl(ty).cases.iter()
+                    .map(|case| (
+                        case.name.clone(),
+                        case.associated_values.iter().map(|value| value.value_type.clone()).collect(),
+                    ))
+                    .collect()
+            ),
+            Type::Optional(wrapped) => Some(vec![
+                ("some".to_string(), vec![(**wrapped).clone()]),
+                ("none".to_string(), Vec::new()),
+            ]),
+            _ => None,
+        }
+    }
+
+    /// Exhaustiveness check over a pattern matrix (each row is one pattern per column).
+    /// Returns an example of a value that no row matches, rendered per column.
+    fn missing_pattern(&self, rows: &[Vec<Pattern>], types: &[Type]) -> Option<Vec<String>> {
+        if types.is_empty() {
+            return if rows.is_empty() { Some(Vec::new()) } else { None };
+        }
+
+        let is_irrefutable = |pattern: &Pattern| matches!(pattern, Pattern::Wildcard | Pattern::Binding(_));
+
+        let Some(constructors) = self.constructors(&types[0]) else {
+            // only a catch-all pattern can cover every integer
+            let defaults = rows.iter()
+                .filter(|row| is_irrefutable(&row[0]))
+                .map(|row| row[1..].to_vec())
+                .collect::<Vec<_>>();
+            return self.missing_pattern(&defaults, &types[1..]).map(|mut witness| {
+                witness.insert(0, "_".to_string());
+                witness
+            });
+        };
+
+        for (name, payload) in constructors {
+            let mut specialized = Vec::new();
+            for row in rows {
+                match &row[0] {
+                    Pattern::EnumCase { case_name, subpatterns, .. } if *case_name == name => {
+                        let mut new_row = match subpatterns {
+                            Some(subpatterns) => subpatterns.clone(),
+                            None => vec![Pattern::Wildcard; payload.len()],
+                        };
+                        new_row.extend_from_slice(&row[1..]);
+                        specialized.push(new_row);
+                    }
+                    pattern if is_irrefutable(pattern) => {
+                        let mut new_row = vec![Pattern::Wildcard; payload.len()];
+                        new_row.extend_from_slice(&row[1..]);
+                        specialized.push(new_row);
+                    }
+                    _ => {}
+                }
+            }
+
+            let mut column_types = payload.clone();
+            column_types.extend_from_slice(&types[1..]);
+            if let Some(mut witness) = self.missing_pattern(&specialized, &column_types) {
+                let rest = witness.split_off(payload.len());
+                let mut pattern = format!(".{}", name);
+                if !payload.is_empty() {
+                    pattern.push_str(&format!("({})", witness.join(", ")));
+                }
+                let mut result = vec![pattern];
+                result.extend(rest);
+                return Some(result);
+            }
+        }
+        None
+    }
+
+    // MARK - expressions
+
+    fn expect_type(&mut self, expr: &Expression, expected: &Type) -> Result<(), String> {
+        let actual = self.type_of(expr, Some(expected))?;
+        if !self.is_convertible(&actual, expected) {
+            return Err(self.conversion_error(&actual, expected));
+        }
+        Ok(())
+    }
+
+    /// values are implicitly wrapped when an optional is expected (`let x: Int? = 1`),
+    /// tuples convert element-wise as long as their labels do not conflict,
+    /// an enum converts to the protocols it conforms to and a function to the
+    /// throwing function type of the same signature
+    fn is_convertible(&self, actual: &Type, expected: &Type) -> bool {
+        if actual == expected {
+            return true;
+        }
+        match (actual, expected) {
+            (Type::Tuple(actual_elements), Type::Tuple(expected_elements)) => {
+                actual_elements.len() == expected_elements.len()
+                    && actual_elements.iter().zip(expected_elements).all(|(actual, expected)| {
+                        (actual.label.is_none() || expected.label.is_none() || actual.label == expected.label)
+                            && self.is_convertible(&actual.element_type, &expected.element_type)
+                    })
+            }
+            (_, Type::Optional(wrapped)) => self.is_convertible(actual, wrapped),
+            (Type::Named(name, _), Type::Existential(protocol)) => self.enums[name].conformances.contains(protocol),
+            (Type::Generic(_), Type::Existential(protocol)) => self.is_constrained(actual, protocol),
+            // a function that doesn't throw can be used where a throwing one is expected
+            (
+                Type::Function { parameters, result, throws: false },
+                Type::Function { parameters: expected_parameters, result: expected_result, .. },
+            ) => parameters == expected_parameters && result == expected_result,
+            _ => false,
+        }
+    }
+
+    fn conversion_error(&self, actual: &Type, expected: &Type) -> String {
+        match actual {
+            Type::Optional(wrapped) if self.is_convertible(wrapped, expected) => format!(
+                "value of optional type '{}' must be unwrapped to a value of type '{}'",
+                actual, wrapped
+            ),
+            Type::Function { throws: true, .. } if matches!(expected.innermost_wrapped(), Type::Function { throws: false, .. }) => format!(
+                "invalid conversion from throwing function of type '{}' to non-throwing function type '{}'",
+                actual, expected.innermost_wrapped()
+            ),
+            _ => format!(
+                "cannot convert value of type '{}' to specified type '{}'",
+                actual, expected
+            ),
+        }
+    }
+
+    /// type of an assignment target, which is a `var` or a tuple element of one
+    fn assignable_type(&mut self, target: &Expression) -> Result<Type, String> {
+        match target {
+            Expression::Variable(name) => {
+                let Some(variable) = self.lookup(name) else {
+                    if self.is_self_property(name) {
+                        return self.assignable_type(&Self::self_member(name));
+                    }
+                    return Err(format!("cannot find '{}' in scope", name));
+                };
+                if !variable.is_mutable {
+                    return Err(match (variable.is_computed, name.as_str()) {
+                        (true, _) => format!("cannot assign to value: '{}' is a get-only property", name),
+                        (false, "self") => "cannot assign to value: 'self' is immutable".to_string(),
+                        (false, _) => format!("cannot assign to value: '{}' is a 'let' constant", name),
+                    });
+                }
+                Ok(variable.ty.clone())
+            }
+            Expression::Member { base, name } if self.enum_named(base).is_some() => {
+                let ty = self.type_of(target, None)?;
+                let enum_name = &self.enum_named(base).expect("an enum").name;
+                match self.static_properties.get(&format!("{}.{}", enum_name, name)) {
+                    Some(property) if property.is_mutable => Ok(ty),
+                    Some(property) if property.is_computed => {
+                        Err(format!("cannot assign to property: '{}' is a get-only property", name))
+                    }
+                    Some(_) => Err(format!("cannot assign to property: '{}' is a 'let' constant", name)),
+                    None => Err("cannot assign to value: expression is not assignable".to_string()),
+                }
+            }
+            Expression::Member { base, name } => {
+                let ty = self.type_of(target, None)?;
+                let is_settable = match self.type_of(base, None)? {
+                    Type::Tuple(_) => true,
+                    enum_type @ Type::Named(..) => match self.enum_decl(&enum_type).find_property(name) {
+                        Some(property) => property.setter.is_some(),
+                        None => return Err("cannot assign to value: expression is not assignable".to_string()),
+                    },
+                    _ => false,
+                };
+                if !is_settable {
+                    return Err(format!("cannot assign to property: '{}' is a get-only property", name));
+                }
+                // a setter changes the value it is called on
+                self.assignable_type(base)
+                    .map_err(|error| error.replace("cannot assign to value", "cannot assign to property"))?;
+                Ok(ty)
+            }
+            Expression::Subscript { base, .. } => {
+                let ty = self.type_of(target, None)?;
+                self.assignable_type(base)
+                    .map_err(|error| error.replace("cannot assign to value", "cannot assign through subscript"))?;
+                Ok(ty)
+            }
+            // `counts[key]! += 1` changes the value stored for the key
+            Expression::ForceUnwrap(operand) => {
+                let ty = self.type_of(target, None)?;
+                self.assignable_type(operand)?;
+                Ok(ty)
+            }
+            _ => {
+                // report unknown members and the like before the assignment itself
+                self.type_of(target, None)?;
+                Err("cannot assign to value: expression is not assignable".to_string())
+            }
+        }
+    }
+
+    fn is_equatable(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Int | Type::Int8 | Type::Int16 | Type::Int32 | Type::Int64
+            | Type::UInt | Type::UInt8 | Type::UInt16 | Type::UInt32 | Type::UInt64
+            | Type::Double | Type::Float
+            | Type::Bool | Type::String => true,
+            Type::Named(name, _) => !self.enums[name].has_associated_values(),
+            Type::Generic(_) => ["Equatable", "Hashable", "Comparable"].iter().any(|protocol| self.is_constrained(ty, protocol)),
+            Type::Array(_) | Type::Dictionary(..) | Type::Set(_) | Type::Existential(_) => false,
+            Type::Optional(wrapped) => self.is_equatable(wrapped),
+            Type::Tuple(elements) => elements.iter().all(|element| self.is_equatable(&element.element_type)),
+            Type::Void | Type::Never | Type::Function { .. } | Type::Opaque(_) => false,
+        }
+    }
+
+    /// types usable as dictionary keys and set elements, which the support library hashes
+    fn is_hashable(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Int | Type::Int8 | Type::Int16 | Type::Int32 | Type::Int64
+            | Type::UInt | Type::UInt8 | Type::UInt16 | Type::UInt32 | Type::UInt64
+            | Type::Double | Type::Float
+            | Type::Bool | Type::String => true,
+            Type::Named(name, _) => !self.enums[name].has_associated_values(),
+            Type::Generic(_) => self.is_constrained(ty, "Hashable"),
+            Type::Optional(wrapped) => self.is_hashable(wrapped),
+            Type::Array(_) | Type::Dictionary(..) | Type::Set(_) | Type::Tuple(_) | Type::Existential(_)
+            | Type::Void | Type::Never | Type::Function { .. } | Type::Opaque(_) => false,
+        }
+    }
+
+    fn expect_hashable(&self, ty: &Type) -> Result<(), String> {
+        if self.is_hashable(ty) {
+            Ok(())
+        } else {
+            Err(format!("type '{}' does not conform to protocol 'Hashable'", ty))
+        }
+    }
+
+    fn operand_error(&self, operator: &BinaryOperator, left_type: &Type, right_type: &Type) -> String {
+        for ty in [left_type, right_type] {
+            if let Type::Optional(wrapped) = ty
+                && (wrapped.is_numeric() || matches!(**wrapped, Type::Bool | Type::String))
+            {
+                return self.conversion_error(ty, wrapped);
+            }
+        }
+        format!(
+            "binary operator '{}' cannot be applied to operands of type '{}' and '{}'",
+            operator.symbol(), left_type, right_type
+        )
+    }
+
+    fn type_of_binary(
+        &mut self,
+        left: &Expression,
+        operator: &BinaryOperator,
+        right: &Expression,
+        expected: Option<&Type>,
+    ) -> Result<Type, String> {
+        match operator {
+            BinaryOperator::Equal | BinaryOperator::NotEqual => {
+                // comparing against nil only needs an optional on the other side
+                if *left == Expression::Nil || *right == Expression::Nil {
+                    let other = if *left == Expression::Nil { right } else { left };
+                    return match self.type_of(other, None)? {
+                        Type::Optional(_) => Ok(Type::Bool),
+                        ty => Err(format!(
+                            "comparing non-optional value of type '{}' to 'nil' always returns {}",
+                            ty,
+                            *operator == BinaryOperator::NotEqual
+                        )),
+                    };
+                }
+                // the non-optional side is promoted: `optional == 1`
+                let left_type = self.type_of(left, None)?;
+                let right_type = self.type_of(right, Some(&left_type))?;
+                let common_type = if self.is_convertible(&right_type, &left_type) {
+                    &left_type
+                } else {
+                    &right_type
+                };
+                if self.is_convertible(&left_type, common_type)
+                    && self.is_convertible(&right_type, common_type)
+                    && self.is_equatable(common_type)
+                {
+                    Ok(Type::Bool)
+                } else {
+                    Err(self.operand_error(operator, &left_type, &right_type))
+                }
+            }
+            BinaryOperator::And | BinaryOperator::Or => {
+                let left_type = self.type_of(left, Some(&Type::Bool))?;
+                let right_type = self.type_of(right, Some(&Type::Bool))?;
+                if left_type == Type::Bool && right_type == Type::Bool {
+                    Ok(Type::Bool)
+                } else {
+                    Err(self.operand_error(operator, &left_type, &right_type))
+                }
+            }
+            BinaryOperator::NilCoalescing => {
+                let left_type = self.type_of(left, None)?;
+                let Type::Optional(wrapped) = &left_type else {
+                    return Err(format!(
+                        "left side of nil coalescing operator '??' has non-optional type '{}', so the right side is never used",
+                        left_type
+                    ));
+                };
+                let right_type = self.type_of(right, Some(wrapped))?;
+                if self.is_convertible(&right_type, wrapped) {
+                    Ok((**wrapped).clone())
+                } else if right_type == left_type {
+                    Ok(left_type)
+                } else {
+                    Err(self.operand_error(operator, &left_type, &right_type))
+                }
+            }
+            _ if operator.is_shift() => {
+                // the amount can be of any integer type: `byte << count`
+                let expected = expected.map(Type::innermost_wrapped).filter(|ty| ty.is_integer());
+                let left_type = self.type_of(left, expected)?;
+                let right_type = self.type_of(right, None)?;
+                if left_type.is_integer() && right_type.is_integer() {
+                    Ok(left_type)
+                } else {
+                    Err(self.operand_error(operator, &left_type, &right_type))
+                }
+            }
+            _ => {
+                // the result type of arithmetic is the operand type
+                let expected = expected.filter(|_| !operator.is_comparison());
+                let (left_type, right_type) = self.numeric_operand_types(left, right, expected)?;
+                // `+` also concatenates strings
+                if *operator == BinaryOperator::Add && left_type == Type::String && right_type == Type::String {
+                    return Ok(Type::String);
+                }
+                if *operator == BinaryOperator::Remainder && left_type == right_type && left_type.is_floating_point() {
+                    return Err("'%' is unavailable: For floating point numbers use truncatingRemainder instead".to_string());
+                }
+                if operator.is_comparison() && left_type == right_type && self.satisfies(&left_type, "Comparable") {
+                    return Ok(Type::Bool);
+                }
+                let is_integer_only = matches!(
+                    operator,
+                    BinaryOperator::Remainder | BinaryOperator::BitwiseAnd | BinaryOperator::BitwiseOr | BinaryOperator::BitwiseXor
+                );
+                if left_type != right_type || !left_type.is_numeric() || (is_integer_only && !left_type.is_integer()) {
+                    return Err(self.operand_error(operator, &left_type, &right_type));
+                }
+                if operator.is_comparison() {
+                    Ok(Type::Bool)
+                } else {
+                    Self::check_constant_arithmetic(left, operator, right, &left_type)?;
+                    Ok(left_type)
+                }
+            }
+        }
+    }
+
+    /// arithmetic on integer literals is evaluated at compile time, where it must not overflow
+    fn check_constant_arithmetic(left: &Expression, operator: &BinaryOperator, right: &Expression, ty: &Type) -> Result<(), String> {
+        let (Some((min, max)), Some(left), Some(right)) = (ty.integer_range(), left.constant_integer(), right.constant_integer()) else {
+            return Ok(());
+        };
+        let result = match operator {
+            BinaryOperator::Add => left.checked_add(right),
+            BinaryOperator::Subtract => left.checked_sub(right),
+            BinaryOperator::Multiply => left.checked_mul(right),
+            _ => return Ok(()),
+        };
+        match result {
+            Some(value) if (min..=max).contains(&value) => Ok(()),
+            _ => Err(format!(
+                "arithmetic operation '{} {} {}' (on type '{}') results in an overflow",
+                left, operator.symbol(), right, ty
+            )),
+        }
+    }
+
+    /// Operand types of an arithmetic or comparison operator. The right side
+    /// is inferred against the left one, and a literal on the left adopts the
+    /// numeric type of the right: `1 + x` where `x` is a Double or a UInt8.
+    fn numeric_operand_types(&mut self, left: &Expression, right: &Expression, expected: Option<&Type>) -> Result<(Type, Type), String> {
+        let expected = expected.map(Type::innermost_wrapped).filter(|ty| ty.is_numeric());
+        let left_type = self.type_of(left, expected)?;
+        let right_type = self.type_of(right, Some(&left_type))?;
+        if left.is_number_literal() && right_type.is_numeric() && left_type != right_type {
+            return Ok((self.type_of(left, Some(&right_type))?, right_type));
+        }
+        Ok((left_type, right_type))
+    }
+
+    /// type of a number literal: `Int` or `Double` unless the context asks for
+    /// another numeric type, as in `let x: Float = 1` or `let y: UInt8 = 1`;
+    /// floating point literals never become integers
+    fn literal_type(expected: Option<&Type>, default: Type) -> Type {
+        match expected.map(Type::innermost_wrapped) {
+            Some(ty) if ty.is_floating_point() || (ty.is_integer() && default.is_integer()) => ty.clone(),
+            _ => default,
+        }
+    }
+
+    /// Both branches of `? :` must agree on a type. The else branch is inferred
+    /// against the then branch, and a literal adopts the other branch's type.
+    /// Without a contextual type, `flag ? value : nil` is an optional.
+    fn ternary_type(&mut self, then_value: &Expression, else_value: &Expression, expected: Option<&Type>) -> Result<Type, String> {
+        if expected.is_none()
+            && let Some(value) = Expression::non_nil_branch(then_value, else_value)
+        {
+            let ty = match self.type_of(value, None)? {
+                ty @ Type::Optional(_) => ty,
+                ty => Type::Optional(Box::new(ty)),
+            };
+            return self.ternary_type(then_value, else_value, Some(&ty));
+        }
+        let mut then_type = self.type_of(then_value, expected)?;
+        let else_type = self.type_of(else_value, expected.or(Some(&then_type)))?;
+        if then_value.is_number_literal() && else_type.is_numeric() && then_type != else_type {
+            then_type = self.type_of(then_value, Some(&else_type))?;
+        }
+        if self.is_convertible(&else_type, &then_type) {
+            Ok(then_type)
+        } else if self.is_convertible(&then_type, &else_type) {
+            Ok(else_type)
+        } else {
+            Err(format!(
+                "result values in '? :' expression have mismatching types '{}' and '{}'",
+                then_type, else_type
+            ))
+        }
+    }
+
+    /// integer literals must fit the type they are stored into
+    fn check_integer_literal(value: i128, ty: &Type) -> Result<(), String> {
+        let Some((min, max)) = ty.integer_range() else {
+            return Ok(());
+        };
+        if value < 0 && min == 0 {
+            Err(format!("negative integer '{}' overflows when stored into unsigned type '{}'", value, ty))
+        } else if value < min || value > max {
+            Err(format!("integer literal '{}' overflows when stored into '{}'", value, ty))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// infer the type of `expr`; `expected` is the contextual type used
+    /// to resolve implicit member expressions such as `.north`
+    fn type_of(&mut self, expr: &Expression, expected: Option<&Type>) -> Result<Type, String> {
+        match expr {
+            Expression::Number(value) => {
+                let ty = Self::literal_type(expected, Type::Int);
+                Self::check_integer_literal(*value, &ty)?;
+                Ok(ty)
+            }
+            Expression::FloatLiteral(_) => Ok(Self::literal_type(expected, Type::Double)),
+            Expression::Bool(_) => Ok(Type::Bool),
+            Expression::StringLiteral(_) => Ok(Type::String),
+            Expression::Interpolation(parts) => {
+                // any value can be interpolated through its textual representation
+                for part in parts {
+                    if let InterpolationPart::Expression(expr) = part {
+                        self.type_of(expr, None)?;
+                    }
+                }
+                Ok(Type::String)
+            }
+            Expression::Nil => match expected {
+                Some(ty @ Type::Optional(_)) => Ok(ty.clone()),
+                Some(ty) => Err(format!("'nil' cannot be assigned to type '{}'", ty)),
+                None => Err("'nil' requires a contextual type".to_string()),
+            },
+            Expression::SpecializedType(ty) => Err(format!(
+                "expected member name or constructor call after type name '{}'",
+                ty
+            )),
+            Expression::Variable(name) => {
+                if let Some(variable) = self.lookup(name) {
+                    return Ok(variable.ty.clone());
+                }
+                if self.is_self_property(name) {
+                    return self.type_of(&Self::self_member(name), None);
+                }
+                if self.enums.contains_key(name) {
+                    return Err(format!(
+                        "expected member name or constructor call after type name '{}'",
+                        name
+                    ));
+                }
+                if let Some(overloads) = self.functions.get(name) {
+                    return Self::referenced_function(name, overloads, expected).map(|decl| decl.function_type());
+                }
+                Err(format!("cannot find '{}' in scope", name))
+            }
+            Expression::Unary { operator: UnaryOperator::Negate, operand } if matches!(**operand, Expression::Number(_)) => {
+                // `-128` is a single literal, so it fits into Int8
+                let Expression::Number(value) = **operand else {
+                    unreachable!("integer literal");
+                };
+                let ty = Self::literal_type(expected, Type::Int);
+                Self::check_integer_literal(-value, &ty)?;
+                Ok(ty)
+            }
+            Expression::Unary { operator, operand } => {
+                let (expected_operand, accepts): (Option<&Type>, fn(&Type) -> bool) = match operator {
+                    UnaryOperator::Negate => (expected, |ty| ty.is_numeric() && !ty.is_unsigned_integer()),
+                    UnaryOperator::BitwiseNot => (expected, Type::is_integer),
+                    UnaryOperator::Not => (Some(&Type::Bool), |ty| *ty == Type::Bool),
+                };
+                let operand_type = self.type_of(operand, expected_operand)?;
+                if accepts(&operand_type) {
+                    return Ok(operand_type);
+                }
+                if let Type::Optional(wrapped) = &operand_type
+                    && accepts(wrapped)
+                {
+                    return Err(self.conversion_error(&operand_type, wrapped));
+                }
+                Err(format!(
+                    "unary operator '{}' cannot be applied to an operand of type '{}'",
+                    operator.symbol(), operand_type
+                ))
+            }
+            Expression::Binary { left, operator, right } => self.type_of_binary(left, operator, right, expected),
+            Expression::ForceUnwrap(operand) => {
+                match self.type_of(operand, None)? {
+                    Type::Optional(wrapped) => Ok(*wrapped),
+                    ty => Err(format!("cannot force unwrap value of non-optional type '{}'", ty)),
+                }
+            }
+            Expression::BindOptional(operand) => {
+                match self.type_of(operand, None)? {
+                    Type::Optional(wrapped) => Ok(*wrapped),
+                    ty => Err(format!("cannot use optional chaining on non-optional value of type '{}'", ty)),
+                }
+            }
+            Expression::OptionalEvaluation(chain) => {
+                // the result of an optional chain is optional, but never doubly so
+                match self.type_of(chain, None)? {
+                    ty @ Type::Optional(_) => Ok(ty),
+                    ty => Ok(Type::Optional(Box::new(ty))),
+                }
+            }
+            Expression::Member { base, name } => {
+                if let Some(decl) = self.enum_named(base) {
+                    if let Some(property) = self.static_properties.get(&format!("{}.{}", decl.name, name)) {
+                        return Ok(property.ty.clone());
+                    }
+                    let decl = decl.clone();
+                    let expected = self.specialized_type(base, expected)?;
+                    let enum_type = self.enum_case_type(&decl, name, &[], expected)?;
+                    return self.enum_case_reference(&enum_type, name);
+                }
+
+                let base_type = self.type_of(base, None)?;
+                if let Some((_, element_type)) = base_type.tuple_element(name) {
+                    return Ok(element_type.clone());
+                }
+                if let Type::Tuple(_) = &base_type {
+                    return Err(format!("value of tuple type '{}' has no member '{}'", base_type, name));
+                }
+                if let Type::Named(..) = &base_type
+                    && let Some(property) = self.enum_decl(&base_type).find_property(name)
+                {
+                    return Ok(property.property_type.clone());
+                }
+                if let Type::Existential(protocol) = &base_type
+                    && let Some((_, Requirement::Property { property_type, .. })) =
+                        self.protocols[protocol].find_requirement(name, None)
+                {
+                    return Ok(property_type.clone());
+                }
+                let requirement = self.constraint_protocols(&base_type).iter().find_map(|protocol| {
+                    match self.protocols[protocol].find_requirement(name, None) {
+                        Some((_, Requirement::Property { property_type, .. })) => Some(property_type.clone()),
+                        _ => None,
+                    }
+                });
+                if let Some(property_type) = requirement {
+                    return Ok(property_type);
+                }
+                if let Type::Named(enum_name, _) = &base_type
+                    && name == "rawValue"
+                    && self.enums[enum_name].raw_type.is_some()
+                {
+                    return Ok(Type::Int);
+                }
+                if let Some(property_type) = self.extension_property_type(&base_type, name)? {
+                    return Ok(property_type);
+                }
+                match (&base_type, name.as_str()) {
+                    (Type::Array(_) | Type::Dictionary(..) | Type::Set(_), "count") => Ok(Type::Int),
+                    (Type::Array(_) | Type::Dictionary(..) | Type::Set(_), "isEmpty") => Ok(Type::Bool),
+                    (Type::Dictionary(key_type, _), "keys") => Ok(Type::Array(key_type.clone())),
+                    (Type::Dictionary(_, value_type), "values") => Ok(Type::Array(value_type.clone())),
+                    _ => Err(format!("value of type '{}' has no member '{}'", base_type, name)),
+                }
+            }
+            Expression::ArrayLiteral(elements) => self.type_of_array_literal(elements, expected),
+            Expression::DictionaryLiteral(entries) => self.type_of_dictionary_literal(entries, expected),
+            Expression::Subscript { base, index } => match self.type_of(base, None)? {
+                Type::Array(element_type) => {
+                    self.expect_type(index, &Type::Int)?;
+                    Ok(*element_type)
+                }
+                // a missing key reads as nil, and assigning nil removes the key
+                Type::Dictionary(key_type, value_type) => {
+                    self.expect_type(index, &key_type)?;
+                    Ok(Type::Optional(value_type))
+                }
+                base_type => Err(format!("value of type '{}' has no subscripts", base_type)),
+            },
+            Expression::ImplicitMember(name) => {
+                let enum_type = self.contextual_base(expected, name)?;
+                self.enum_case_reference(&enum_type, name)
+            }
+            Expression::Call { callee, arguments } => self.type_of_call(callee, arguments, expected),
+            Expression::OperatorCall { symbol, fixity, operands } => {
+                let decl = self.resolve_operator(symbol, *fixity, operands)?;
+                for (operand, parameter) in operands.iter().zip(&decl.parameters) {
+                    self.expect_type(operand, &parameter.param_type)?;
+                }
+                Ok(decl.return_type)
+            }
+            Expression::Ternary { condition, then_value, else_value } => {
+                self.expect_condition(condition)?;
+                self.ternary_type(then_value, else_value, expected)
+            }
+            Expression::Tuple(elements) => {
+                // element types are inferred against the expected tuple type, if any
+                let mut expected_elements = expected;
+                while let Some(Type::Optional(wrapped)) = expected_elements {
+                    expected_elements = Some(wrapped);
+                }
+                let expected_elements = match expected_elements {
+                    Some(Type::Tuple(expected_elements)) if expected_elements.len() == elements.len() => {
+                        Some(expected_elements.clone())
+                    }
+                    _ => None,
+                };
+
+                let mut element_types = Vec::new();
+                for (index, element) in elements.iter().enumerate() {
+                    let expected_element = expected_elements.as_ref().map(|elements| &elements[index].element_type);
+                    element_types.push(TupleElement {
+                        label: element.label.clone(),
+                        element_type: self.type_of(&element.value, expected_element)?,
+                    });
+                }
+                Ok(Type::Tuple(element_types))
+            }
+            Expression::InOut(_) => Err("'&' may only be used to pass an argument to inout parameter".to_string()),
+            Expression::Try(value) => {
+                let in_try = std::mem::replace(&mut self.in_try, true);
+                let result = self.type_of(value, expected);
+                self.in_try = in_try;
+                result
+            }
+            // `try?` and `try!` handle the errors of their operand themselves
+            Expression::OptionalTry(value) | Expression::ForceTry(value) => {
+                let is_optional = matches!(expr, Expression::OptionalTry(_));
+                let expected = match expected {
+                    Some(Type::Optional(wrapped)) if is_optional => Some(wrapped.as_ref()),
+                    expected => expected,
+                };
+                self.error_handlers.push(ErrorHandler::Try);
+                let in_try = std::mem::replace(&mut self.in_try, true);
+                let result = self.type_of(value, expected);
+                self.in_try = in_try;
+                self.error_handlers.pop();
+                // `try?` does not wrap a value that is optional already
+                Ok(match result? {
+                    ty @ Type::Optional(_) => ty,
+                    ty if is_optional => Type::Optional(Box::new(ty)),
+                    ty => ty,
+                })
+            }
+            Expression::Closure { .. } => self.type_of_closure(expr, expected),
+        }
+    }
+
+    /// the function a name used as a value refers to, e.g. `let f = double`
+    fn referenced_function(name: &str, overloads: &[FunctionDecl], expected: Option<&Type>) -> Result<FunctionDecl, String> {
+        let decl = match FunctionDecl::referenced(overloads, expected).as_slice() {
+            [decl] => (*decl).clone(),
+            [] => return Err(format!("cannot find '{}' in scope", name)),
+            _ => return Err(format!("ambiguous use of '{}'", name)),
+        };
+        if let Some(generic) = decl.generic_parameters.first() {
+            return Err(format!("generic parameter '{}' could not be inferred", Type::Generic(generic.name.clone())));
+        }
+        if decl.parameters.iter().any(|parameter| parameter.is_inout) {
+            return Err(format!("function '{}' with an 'inout' parameter must be called", name));
+        }
+        Ok(decl)
+    }
+
+    fn contextual_base(&self, expected: Option<&Type>, name: &str) -> Result<Type, String> {
+        match expected {
+            // `.north` where a `Direction?` is expected refers to Direction
+            Some(Type::Optional(wrapped)) if name != "some" && name != "none" => {
+                self.contextual_base(Some(wrapped), name)
+            }
+            Some(ty @ Type::Named(..)) | Some(ty @ Type::Optional(_)) => Ok(ty.clone()),
+            _ => Err(format!(
+                "cannot infer contextual base in reference to member '{}'",
+                name
+            )),
+        }
+    }
+
+    /// pick the overload whose argument labels match the call
+    fn resolve_function(&self, name: &str, overloads: &[FunctionDecl], arguments: &[Argument]) -> Result<FunctionDecl, String> {
+        if let Some(decl) = overloads.iter().find(|decl| decl.matches_labels(arguments)) {
+            return Ok(decl.clone());
+        }
+        if let [decl] = overloads {
+            if decl.parameters.len() > arguments.len() {
+                return Err(format!(
+                    "missing argument for parameter '{}' in call to '{}'",
+                    decl.parameters[arguments.len()].name,
+                    decl.signature()
+                ));
+            }
+            if decl.parameters.len() < arguments.len() {
+                return Err(format!("extra argument in call to '{}'", decl.signature()));
+            }
+            let labels = |labels: Vec<Option<&str>>| labels.iter()
+                .map(|label| format!("{}:", label.unwrap_or("_")))
+                .collect::<String>();
+            return Err(format!(
+                "incorrect argument labels in call (have '{}', expected '{}')",
+                labels(arguments.iter().map(|argument| argument.label.as_deref()).collect()),
+                labels(decl.parameters.iter().map(|parameter| parameter.label.as_deref()).collect())
+            ));
+        }
+        Err(format!("no exact matches in call to global function '{}'", name))
+    }
+
+    /// The enum of `Enum.case` or `Enum.case(arguments)`. A generic enum takes
+    /// its type arguments from the expected type, or infers them from the
+    /// associated values like a generic function does from its arguments.
+    fn enum_case_type(&mut self, decl: &EnumDecl, case_name: &str, arguments: &[Argument], expected: Option<&Type>) -> Result<Type, String> {
+        if decl.generic_parameters.is_empty() {
+            return Ok(decl.declared_type());
+        }
+        if let Some(ty @ Type::Named(name, _)) = expected.map(Type::innermost_wrapped)
+            && *name == decl.name
+        {
+            return Ok(ty.clone());
+        }
+        // a case that doesn't exist or takes other arguments is reported by the caller
+        let constructor = match decl.find_case(case_name) {
+            Some((_, case)) if case.associated_values.len() == arguments.len() => decl.case_constructor(case),
+            _ => return Ok(Type::Named(decl.name.clone(), Vec::new())),
+        };
+        let bindings = constructor.infer_generic_arguments(arguments, |value, hint| self.type_of(value, hint))?;
+        let mut type_arguments = Vec::new();
+        for parameter in &decl.generic_parameters {
+            let Some((_, ty)) = bindings.iter().find(|(name, _)| *name == parameter.name) else {
+                return Err(format!("generic parameter '{}' could not be inferred", parameter.name));
+            };
+            if let Some(protocol) = parameter.constraints.iter().find(|protocol| !self.satisfies(ty, protocol)) {
+                return Err(format!("generic enum '{}' requires that '{}' conform to '{}'", decl.name, ty, protocol));
+            }
+            type_arguments.push(ty.clone());
+        }
+        Ok(Type::Named(decl.name.clone(), type_arguments))
+    }
+
+    /// `Direction.north` without arguments
+    fn enum_case_reference(&self, enum_type: &Type, name: &str) -> Result<Type, String> {
+        match self.case_payload(enum_type, name) {
+            Some(payload) if payload.is_empty() => Ok(enum_type.clone()),
+            Some(payload) => Err(format!(
+                "member '{}' expects {} associated value(s)",
+                name,
+                payload.len()
+            )),
+            None => Err(format!("type '{}' has no member '{}'", enum_type, name)),
+        }
+    }
+
+    /// Operator implementations are overloaded by type. A single implementation
+    /// gives the operands their context, otherwise the operands' own types pick
+    /// the first implementation taking exactly them, then the first one they
+    /// convert to, where a number literal fits any parameter it can be stored in.
+    fn resolve_operator(&mut self, symbol: &str, fixity: Fixity, operands: &[Expression]) -> Result<FunctionDecl, String> {
+        let candidates = self.functions.get(symbol).into_iter().flatten()
+            .filter(|decl| decl.operator == Some(fixity))
+            .cloned()
+            .collect::<Vec<_>>();
+        if let [decl] = candidates.as_slice() {
+            return Ok(decl.clone());
+        }
+
+        let types = operands.iter()
+            .map(|operand| self.type_of(operand, None))
+            .collect::<Result<Vec<_>, _>>()?;
+        let exact = candidates.iter().find(|decl| {
+            decl.parameters.iter().zip(&types).all(|(parameter, ty)| parameter.param_type == *ty)
+        });
+        let convertible = || candidates.iter().find(|decl| {
+            decl.parameters.iter().zip(operands).zip(&types).all(|((parameter, operand), ty)| {
+                let expected = &parameter.param_type;
+                self.is_convertible(ty, expected)
+                    || (operand.is_number_literal()
+                        && Self::literal_type(Some(expected), ty.clone()) == *expected.innermost_wrapped())
+            })
+        });
+        if let Some(decl) = exact.or_else(convertible) {
+            return Ok(decl.clone());
+        }
+        Err(match types.as_slice() {
+            [left, right] => format!(
+                "binary operator '{}' cannot be applied to operands of type '{}' and '{}'",
+                symbol, left, right
+            ),
+            _ => format!(
+                "unary operator '{}' cannot be applied to an operand of type '{}'",
+                symbol, types[0]
+            ),
+        })
+    }
+
+    /// `[1, 2]` takes its element type from the context, otherwise from its
+    /// elements, where number literals are `Double` if any of them is fractional.
+    /// A set is written as an array literal whose context is a `Set`.
+    fn type_of_array_literal(&mut self, elements: &[Expression], expected: Option<&Type>) -> Result<Type, String> {
+        match expected.map(Type::innermost_wrapped) {
+            Some(collection_type @ (Type::Array(element_type) | Type::Set(element_type))) => {
+                for element in elements {
+                    let actual = self.type_of(element, Some(element_type))?;
+                    if !self.is_convertible(&actual, element_type) {
+                        return Err(format!(
+                            "cannot convert value of type '{}' to expected element type '{}'",
+                            actual, element_type
+                        ));
+                    }
+                }
+                return Ok(collection_type.clone());
+            }
+            Some(Type::Dictionary(..)) if elements.is_empty() => {
+                return Err("use [:] to get an empty dictionary literal".to_string());
+            }
+            _ => {}
+        }
+
+        let elements = elements.iter().collect::<Vec<_>>();
+        let element_type = self.infer_element_type(&elements)?;
+        if !self.all_convertible(&elements, &element_type)? {
+            return Err(
+                "heterogeneous collection literal could only be inferred to '[Any]'; add explicit type annotation if this is intentional".to_string()
+            );
+        }
+        Ok(Type::Array(Box::new(element_type)))
+    }
+
+    /// `["a": 1]` is inferred like an array literal, separately for the keys and the values
+    fn type_of_dictionary_literal(&mut self, entries: &[(Expression, Expression)], expected: Option<&Type>) -> Result<Type, String> {
+        if let Some(dictionary_type @ Type::Dictionary(key_type, value_type)) = expected.map(Type::innermost_wrapped) {
+            for (key, value) in entries {
+                for (expr, expected_type, role) in [(key, key_type, "key"), (value, value_type, "value")] {
+                    let actual = self.type_of(expr, Some(expected_type))?;
+                    if !self.is_convertible(&actual, expected_type) {
+                        return Err(format!(
+                            "cannot convert value of type '{}' to expected dictionary {} type '{}'",
+                            actual, role, expected_type
+                        ));
+                    }
+                }
+            }
+            return Ok(dictionary_type.clone());
+        }
+
+        let keys = entries.iter().map(|(key, _)| key).collect::<Vec<_>>();
+        let values = entries.iter().map(|(_, value)| value).collect::<Vec<_>>();
+        let key_type = self.infer_element_type(&keys)?;
+        let value_type = self.infer_element_type(&values)?;
+        let keys_match = self.all_convertible(&keys, &key_type)?;
+        let values_match = self.all_convertible(&values, &value_type)?;
+        if !keys_match || !values_match {
+            let key_name = if keys_match { key_type.to_string() } else { "AnyHashable".to_string() };
+            let value_name = if values_match { value_type.to_string() } else { "Any".to_string() };
+            return Err(format!(
+                "heterogeneous collection literal could only be inferred to '[{} : {}]'; add explicit type annotation if this is intentional",
+                key_name, value_name
+            ));
+        }
+        self.expect_hashable(&key_type)?;
+        Ok(Type::Dictionary(Box::new(key_type), Box::new(value_type)))
+    }
+
+    /// the type of the first element that is not a number literal, or the
+    /// default type of the literals
+    fn infer_element_type(&mut self, elements: &[&Expression]) -> Result<Type, String> {
+        match elements.iter().find(|element| !element.is_number_literal()) {
+            Some(element) => self.type_of(element, None),
+            None if elements.iter().any(|element| element.is_float_literal()) => Ok(Type::Double),
+            None if !elements.is_empty() => Ok(Type::Int),
+            None => Err("empty collection literal requires an explicit type".to_string()),
+        }
+    }
+
+    fn all_convertible(&mut self, elements: &[&Expression], ty: &Type) -> Result<bool, String> {
+        for element in elements {
+            let actual = self.type_of(element, Some(ty))?;
+            if !self.is_convertible(&actual, ty) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Methods of arrays, dictionaries and sets. All of them but `contains(_:)`
+    /// mutate the collection and need a `var`.
+    fn type_of_collection_method(&mut self, base: &Expression, name: &str, collection_type: &Type, arguments: &[Argument]) -> Result<Type, String> {
+        let (parameters, return_type) = match (collection_type, name) {
+            (Type::Array(element_type), "append") => (vec![(None, (**element_type).clone())], Type::Void),
+            (Type::Array(element_type), "insert") => {
+                (vec![(None, (**element_type).clone()), (Some("at"), Type::Int)], Type::Void)
+            }
+            (Type::Array(element_type), "remove") => (vec![(Some("at"), Type::Int)], (**element_type).clone()),
+            (Type::Dictionary(key_type, value_type), "removeValue") => {
+                (vec![(Some("forKey"), (**key_type).clone())], Type::Optional(value_type.clone()))
+            }
+            (Type::Set(element_type), "insert") => {
+                let result = Type::Tuple(vec![
+                    TupleElement { label: Some("inserted".to_string()), element_type: Type::Bool },
+                    TupleElement { label: Some("memberAfterInsert".to_string()), element_type: (**element_type).clone() },
+                ]);
+                (vec![(None, (**element_type).clone())], result)
+            }
+            (Type::Set(element_type), "remove") => (vec![(None, (**element_type).clone())], Type::Optional(element_type.clone())),
+            (Type::Set(element_type), "contains") => (vec![(None, (**element_type).clone())], Type::Bool),
+            _ => return Err(format!("value of type '{}' has no member '{}'", collection_type, name)),
+        };
+        let labels = |labels: Vec<Option<&str>>| {
+            labels.iter().map(|label| format!("{}:", label.unwrap_or("_"))).collect::<String>()
+        };
+        let expected_labels = labels(parameters.iter().map(|(label, _)| *label).collect());
+        let actual_labels = labels(arguments.iter().map(|argument| argument.label.as_deref()).collect());
+        if expected_labels != actual_labels {
+            return Err(format!(
+                "incorrect argument labels in call (have '{}', expected '{}')",
+                actual_labels, expected_labels
+            ));
+        }
+        if name != "contains" {
+            self.assignable_type(base).map_err(|error| {
+                error.replace("cannot assign to value", "cannot use mutating member on immutable value")
+                    .replace("cannot assign to property", "cannot use mutating member on immutable value")
+                    .replace("cannot assign through subscript", "cannot use mutating member on immutable value")
+            })?;
+        }
+        for (argument, (_, parameter_type)) in arguments.iter().zip(&parameters) {
+            self.expect_type(&argument.value, parameter_type)?;
+        }
+        Ok(return_type)
+    }
+
+    /// `map`, `filter`, `reduce` and the other methods taking a closure, which
+    /// is checked with the element type of the collection as its parameter type
+    fn type_of_higher_order_method(&mut self, name: &str, collection_type: &Type, arguments: &[Argument], expected: Option<&Type>) -> Result<Type, String> {
+        let element_type = collection_type.sequence_element().expect("collections are sequences");
+        let expected_labels = match name {
+            "reduce" => vec!["_", "_"],
+            "sorted" if arguments.is_empty() => vec![],
+            "sorted" => vec!["by"],
+            "contains" | "first" => vec!["where"],
+            _ => vec!["_"],
+        };
+        // a trailing closure has no label
+        let actual_labels = arguments.iter().enumerate()
+            .map(|(index, argument)| match &argument.label {
+                None if index + 1 == arguments.len() && index < expected_labels.len() => expected_labels[index],
+                label => label.as_deref().unwrap_or("_"),
+            })
+            .collect::<Vec<_>>();
+        if actual_labels != expected_labels {
+            let labels = |labels: &[&str]| labels.iter().map(|label| format!("{}:", label)).collect::<String>();
+            return Err(format!(
+                "incorrect argument labels in call (have '{}', expected '{}')",
+                labels(&actual_labels), labels(&expected_labels)
+            ));
+        }
+
+        let Some(closure) = arguments.last().map(|argument| &argument.value) else {
+            // sorted()
+            if !self.satisfies(&element_type, "Comparable") {
+                return Err(format!(
+                    "referencing instance method 'sorted()' on 'Sequence' requires that '{}' conform to 'Comparable'",
+                    element_type
+                ));
+            }
+            return Ok(Type::Array(Box::new(element_type)));
+        };
+        match name {
+            "map" => {
+                let result_type = self.check_closure(closure, &[element_type], None)?;
+                Ok(Type::Array(Box::new(result_type)))
+            }
+            "compactMap" => {
+                // the values returned are optional, so `$0 > 0 ? $0 : nil` drops the others
+                self.optional_result = matches!(closure, Expression::Closure { .. });
+                let result_type = self.check_closure(closure, &[element_type], None);
+                self.optional_result = false;
+                match result_type? {
+                    Type::Optional(wrapped) => Ok(Type::Array(wrapped)),
+                    result_type => Ok(Type::Array(Box::new(result_type))),
+                }
+            }
+            "filter" => {
+                self.check_closure(closure, &[element_type], Some(&Type::Bool))?;
+                Ok(collection_type.clone())
+            }
+            "forEach" => {
+                self.check_closure(closure, &[element_type], Some(&Type::Void))?;
+                Ok(Type::Void)
+            }
+            "reduce" => {
+                // `reduce(0, +)` over Doubles starts from `0.0`
+                let initial = &arguments[0].value;
+                let hint = if initial.is_number_literal() && element_type.is_numeric() {
+                    Some(&element_type)
+                } else {
+                    expected
+                };
+                let result_type = self.type_of(initial, hint)?;
+                self.check_closure(closure, &[result_type.clone(), element_type], Some(&result_type))?;
+                Ok(result_type)
+            }
+            "sorted" => {
+                self.check_closure(closure, &[element_type.clone(), element_type.clone()], Some(&Type::Bool))?;
+                Ok(Type::Array(Box::new(element_type)))
+            }
+            "contains" => {
+                self.check_closure(closure, &[element_type], Some(&Type::Bool))?;
+                Ok(Type::Bool)
+            }
+            _ => {
+                // first(where:)
+                self.check_closure(closure, std::slice::from_ref(&element_type), Some(&Type::Bool))?;
+                Ok(Type::Optional(Box::new(element_type)))
+            }
+        }
+    }
+
+    /// Check a closure argument called with `parameter_types` and return its
+    /// result type, which is `result_type` when the method fixes it and is
+    /// inferred from the closure body otherwise. A function value such as
+    /// `double` in `values.map(double)` may be passed instead of a closure.
+    fn check_closure(&mut self, closure: &Expression, parameter_types: &[Type], result_type: Option<&Type>) -> Result<Type, String> {
+        let (result, throws) = match closure {
+            Expression::Closure { .. } => self.check_closure_body(closure, parameter_types, result_type)?,
+            _ => {
+                let expected = result_type.map(|result| Type::Function {
+                    parameters: parameter_types.to_vec(),
+                    result: Box::new(result.clone()),
+                    throws: true,
+                });
+                match self.type_of(closure, expected.as_ref())? {
+                    Type::Function { parameters, result, throws }
+                        if parameters == parameter_types && result_type.is_none_or(|expected| *result == *expected) =>
+                    {
+                        (*result, throws)
+                    }
+                    actual => {
+                        return Err(format!(
+                            "cannot convert value of type '{}' to expected argument type '{}'",
+                            actual, Self::closure_type_name(parameter_types, result_type)
+                        ));
+                    }
+                }
+            }
+        };
+        // the collection methods rethrow the errors of their closure
+        if throws {
+            let source = match closure {
+                Expression::Closure { .. } => ErrorSource::Call,
+                _ => ErrorSource::Values(vec![closure]),
+            };
+            self.check_throwing_call(source)?;
+        }
+        Ok(result)
+    }
+
+    /// `(Int, Int) -> Bool`, with `T` for a result type that is left to the closure
+    fn closure_type_name(parameter_types: &[Type], result_type: Option<&Type>) -> String {
+        let parameters = parameter_types.iter().map(Type::to_string).collect::<Vec<_>>();
+        let result = result_type.map_or("T".to_string(), Type::to_string);
+        format!("({}) -> {}", parameters.join(", "), result)
+    }
+
+    /// A closure used as a value. Its parameter types come from the expected
+    /// function type or from its annotations, and an error thrown in its body
+    /// makes it a throwing function.
+    fn type_of_closure(&mut self, closure: &Expression, expected: Option<&Type>) -> Result<Type, String> {
+        let Expression::Closure { parameters, .. } = closure else {
+            unreachable!("closure expression");
+        };
+        let (parameter_types, result_type) = match expected.map(Type::innermost_wrapped) {
+            Some(Type::Function { parameters, result, .. }) => {
+                (parameters.clone(), Some((**result).clone()).filter(|result| *result != Type::inferred()))
+            }
+            _ => {
+                let parameter_types = parameters.iter()
+                    .map(|parameter| parameter.param_type.clone().ok_or_else(|| format!(
+                        "unable to infer type of a closure parameter '{}' in the current context",
+                        parameter.name
+                    )))
+                    .collect::<Result<Vec<_>, _>>()?;
+                (parameter_types, None)
+            }
+        };
+        if !std::mem::take(&mut self.non_escaping_closure) {
+            self.check_escaping_captures(closure)?;
+        }
+        let (result, throws) = self.check_closure_body(closure, &parameter_types, result_type.as_ref())?;
+        Ok(Type::Function { parameters: parameter_types, result: Box::new(result), throws })
+    }
+
+    /// A closure that can outlive the call it is created in, by being stored
+    /// or returned, cannot capture the caller's variables passed `inout`.
+    fn check_escaping_captures(&self, closure: &Expression) -> Result<(), String> {
+        let Expression::Closure { parameters, body, .. } = closure else {
+            unreachable!("closure expression");
+        };
+        let mut used = Vec::new();
+        for statement in body {
+            statement.walk_expressions(&mut |expr| {
+                if let Expression::Variable(name) = expr {
+                    used.push(name.clone());
+                }
+            });
+        }
+        let captured = used.iter()
+            .filter(|name| !parameters.iter().any(|parameter| parameter.name == **name))
+            .find(|name| self.lookup(name).is_some_and(|variable| variable.is_inout));
+        match captured.map(String::as_str) {
+            Some("self") => Err("escaping closure captures mutating 'self' parameter".to_string()),
+            Some(name) => Err(format!("escaping closure captures 'inout' parameter '{}'", name)),
+            None => Ok(()),
+        }
+    }
+
+    /// check the body of a closure literal, returning its result type and whether it throws
+    fn check_closure_body(&mut self, closure: &Expression, parameter_types: &[Type], result_type: Option<&Type>) -> Result<(Type, bool), String> {
+        let closure_type_name = || Self::closure_type_name(parameter_types, result_type);
+        let Expression::Closure { parameters, return_type, body } = closure else {
+            unreachable!("closure expression");
+        };
+
+        let expected_count = parameter_types.len();
+        let plural = if expected_count == 1 { "" } else { "s" };
+        if parameters.is_empty() && expected_count > 0 {
+            return Err(format!(
+                "contextual type for closure argument list expects {} argument{}, which cannot be implicitly ignored",
+                expected_count, plural
+            ));
+        }
+        if parameters.len() != expected_count {
+            return Err(format!(
+                "contextual closure type '{}' expects {} argument{}, but {} {} used in closure body",
+                closure_type_name(), expected_count, plural, parameters.len(),
+                if parameters.len() == 1 { "was" } else { "were" }
+            ));
+        }
+        for (parameter, parameter_type) in parameters.iter().zip(parameter_types) {
+            if let Some(annotation) = &parameter.param_type {
+                self.resolve_type(annotation)?;
+                if annotation != parameter_type {
+                    return Err(format!(
+                        "cannot convert value of type '{}' to expected closure parameter type '{}'",
+                        annotation, parameter_type
+                    ));
+                }
+            }
+        }
+        if let Some(annotation) = return_type {
+            self.resolve_type(annotation)?;
+            if let Some(result_type) = result_type.filter(|result_type| *result_type != annotation) {
+                return Err(format!(
+                    "cannot convert value of type '{}' to closure result type '{}'",
+                    annotation, result_type
+                ));
+            }
+        }
+
+        // the value of a single expression is discarded where nothing is returned
+        let body = match (result_type, body.as_slice()) {
+            (Some(Type::Void), [Statement::Return(Some(value))]) => vec![Statement::Expression(value.clone())],
+            _ => body.clone(),
+        };
+        let declared_result = return_type.clone().or(result_type.cloned());
+        let optional_result = std::mem::take(&mut self.optional_result) && declared_result.is_none();
+        // a closure body starts outside of any loop, switch, `try` or `defer` around it
+        let saved = (
+            self.return_type.replace(declared_result.clone().unwrap_or(Type::Void)),
+            std::mem::replace(&mut self.infer_return_type, declared_result.is_none()),
+            std::mem::replace(&mut self.optional_result, optional_result),
+            std::mem::take(&mut self.loop_depth),
+            std::mem::take(&mut self.switch_depth),
+            std::mem::take(&mut self.in_try),
+            std::mem::take(&mut self.in_defer),
+            self.opaque_return.take(),
+        );
+        self.error_handlers.push(ErrorHandler::Closure { throws: false });
+        self.scopes.push(HashMap::new());
+        let outcome = parameters.iter().zip(parameter_types)
+            .filter(|(parameter, _)| parameter.name != "_")
+            .try_for_each(|(parameter, ty)| self.declare_variable(&parameter.name, ty.clone(), false))
+            .and_then(|_| self.check_block(&body));
+        self.scopes.pop();
+        let closure_result = match self.infer_return_type {
+            true => Type::Void,
+            false => self.return_type.clone().unwrap_or(Type::Void),
+        };
+        (
+            self.return_type, self.infer_return_type, self.optional_result,
+            self.loop_depth, self.switch_depth, self.in_try, self.in_defer, self.opaque_return,
+        ) = saved;
+        let throws = matches!(self.error_handlers.pop(), Some(ErrorHandler::Closure { throws: true }));
+        outcome?;
+
+        if !matches!(closure_result, Type::Void | Type::Never) && !self.statements_exit(&body) {
+            return Err(format!("missing return in closure expected to return '{}'", closure_result));
+        }
+        Ok((closure_result, throws))
+    }
+
+    /// call of a closure or function value, whose arguments have no labels
+    fn type_of_function_value_call(&mut self, callee: &Expression, callee_type: &Type, arguments: &[Argument]) -> Result<Type, String> {
+        let Type::Function { parameters, result, throws } = callee_type else {
+            return Err(format!("cannot call value of non-function type '{}'", callee_type));
+        };
+        if let Some(label) = arguments.iter().find_map(|argument| argument.label.as_ref()) {
+            return Err(format!("extraneous argument label '{}:' in call", label));
+        }
+        if arguments.len() > parameters.len() {
+            return Err("extra argument in call".to_string());
+        }
+        if arguments.len() < parameters.len() {
+            return Err(format!("missing argument for parameter #{} in call", arguments.len() + 1));
+        }
+        for (argument, parameter) in arguments.iter().zip(parameters) {
+            self.non_escaping_closure = matches!(argument.value, Expression::Closure { .. });
+            self.expect_type(&argument.value, parameter)?;
+        }
+        if *throws {
+            self.check_throwing_call(ErrorSource::Values(vec![callee]))?;
+        }
+        Ok((**result).clone())
+    }
+
+    fn type_of_call(&mut self, callee: &Expression, arguments: &[Argument], expected: Option<&Type>) -> Result<Type, String> {
+        if let Expression::Variable(name) = callee
+            && let Some(variable) = self.lookup(name)
+        {
+            let callee_type = variable.ty.clone();
+            return self.type_of_function_value_call(callee, &callee_type, arguments);
+        }
+        if let Expression::Variable(name) = callee
+            && self.lookup(name).is_none()
+        {
+            // methods of `self` shadow global functions of the same name
+            if self.is_self_method(name)
+                && let Some(self_type) = self.self_type()
+            {
+                let methods = self.methods_of(&self_type, name)?;
+                return self.type_of_method_call(name, &methods, arguments);
+            }
+            if let Some(overloads) = self.functions.get(name) {
+                let decl = self.resolve_function(name, overloads, arguments)?;
+                return self.check_arguments(&decl, arguments);
+            }
+            // numeric conversions: Double(1), Int(2.5), UInt8(x)
+            if let Some(target) = Type::builtin(name).filter(Type::is_numeric) {
+                if arguments.len() != 1 || arguments[0].label.is_some() {
+                    return Err(format!("no exact matches in call to initializer of '{}'", target));
+                }
+                // literals take the target type, so `UInt8(300)` does not compile
+                let argument_type = self.type_of(&arguments[0].value, Some(&target))?;
+                if !argument_type.is_numeric() {
+                    return Err(format!(
+                        "no exact matches in call to initializer of '{}' with an argument of type '{}'",
+                        target, argument_type
+                    ));
+                }
+                return Ok(target);
+            }
+            if name == "fatalError" {
+                if arguments.len() > 1 {
+                    return Err("extra argument in call".to_string());
+                }
+                if let Some(argument) = arguments.first() {
+                    if let Some(label) = &argument.label {
+                        return Err(format!("extraneous argument label '{}:' in call", label));
+                    }
+                    let ty = self.type_of(&argument.value, Some(&Type::String))?;
+                    if ty != Type::String {
+                        return Err(format!("cannot convert value of type '{}' to expected argument type 'String'", ty));
+                    }
+                }
+                return Ok(Type::Never);
+            }
+        }
+
+        // Direction(rawValue: 1) -> Direction?
+        if let Some(decl) = self.enum_named(callee) {
+            let name = decl.name.clone();
+            if decl.raw_type.is_none() {
+                return Err(format!("'{}' cannot be constructed because it has no initializers", name));
+            }
+            if arguments.len() != 1 || arguments[0].label.as_deref() != Some("rawValue") {
+                return Err(format!("missing argument label 'rawValue:' in call to '{}'", name));
+            }
+            self.expect_type(&arguments[0].value, &Type::Int)?;
+            return Ok(Type::Optional(Box::new(Type::Named(name, Vec::new()))));
+        }
+
+        if let Expression::Member { base, name } = callee
+            && self.enum_named(base).is_none()
+            && let base_type = self.type_of(base, None)?
+            && (matches!(base_type, Type::Named(..) | Type::Existential(_) | Type::Generic(_)) || self.has_method(&base_type, name))
+        {
+            let methods = self.methods_of(&base_type, name)?;
+            return self.type_of_method_call(name, &methods, arguments);
+        }
+
+        if let Expression::Member { base, name } = callee
+            && self.enum_named(base).is_none()
+            && let collection_type @ (Type::Array(_) | Type::Dictionary(..) | Type::Set(_)) = self.type_of(base, None)?
+        {
+            if is_higher_order_method(name, arguments) {
+                return self.type_of_higher_order_method(name, &collection_type, arguments, expected);
+            }
+            return self.type_of_collection_method(base, name, &collection_type, arguments);
+        }
+
+        let (enum_type, case_name) = match callee {
+            Expression::Member { base, name } => match self.enum_named(base) {
+                Some(decl) => {
+                    let decl = decl.clone();
+                    let expected = self.specialized_type(base, expected)?;
+                    (self.enum_case_type(&decl, name, arguments, expected)?, name)
+                }
+                None => return Err("cannot call value of non-function type".to_string()),
+            },
+            Expression::ImplicitMember(name) => (self.contextual_base(expected, name)?, name),
+            // `makeAdder(1)(2)` or `handlers[0]()`
+            _ => {
+                let callee_type = self.type_of(callee, None)?;
+                return self.type_of_function_value_call(callee, &callee_type, arguments);
+            }
+        };
+
+        let labels = match &enum_type {
+            Type::Named(enum_name, _) => match self.enums[enum_name].find_case(case_name) {
+                Some((_, case)) => case.associated_values.iter().map(|value| value.label.clone()).collect(),
+                None => Vec::new(),
+            },
+            _ => vec![None],
+        };
+        let payload = self.case_payload(&enum_type, case_name)
+            .ok_or_else(|| format!("type '{}' has no member '{}'", enum_type, case_name))?;
+        if payload.is_empty() {
+            return Err(format!("enum case '{}' has no associated values", case_name));
+        }
+        if arguments.len() != payload.len() {
+            return Err(format!(
+                "enum case '{}' expects {} argument(s) but {} were given",
+                case_name,
+                payload.len(),
+                arguments.len()
+            ));
+        }
+        for ((argument, field_type), label) in arguments.iter().zip(&payload).zip(labels) {
+            if argument.label != label {
+                return Err(match label {
+                    Some(label) => format!("missing argument label '{}:' in call", label),
+                    None => format!(
+                        "extraneous argument label '{}:' in call",
+                        argument.label.as_deref().unwrap_or("_")
+                    ),
+                });
+            }
+            self.expect_type(&argument.value, field_type)?;
+        }
+        Ok(enum_type)
+    }
+}
+
+pub fn analyze(ast: &AstNode) -> Result<(), String> {
+    let mut semantic = Semantic::new();
+    semantic.analyze(ast)
+}
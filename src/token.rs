@@ -2,17 +2,96 @@
 pub enum TokenType {
     Print,
     Number,
+    FloatLiteral, // 1.5, 1e3, 0x1p-2
+    StringLiteral,
+    // `"a\(x)b\(y)c"` is lexed as InterpolationStart("a"), tokens of x,
+    // InterpolationMiddle("b"), tokens of y, InterpolationEnd("c")
+    InterpolationStart,
+    InterpolationMiddle,
+    InterpolationEnd,
     LeftParen, // (
     RightParen, // )
-    EOF,
+    LeftBrace, // {
+    RightBrace, // }
+    LeftBracket, // [
+    RightBracket, // ]
+    Eof,
     Plus,
     Minus,
     Star,
     Slash,
+    Percent, // %
+    Bang, // !
+    Question, // ?
+    QuestionQuestion, // ??
+    EqualEqual, // ==
+    BangEqual, // !=
+    Less, // <
+    LessEqual, // <=
+    Greater, // >
+    GreaterEqual, // >=
+    AndAnd, // &&
+    OrOr, // ||
+    Amp, // &
+    Pipe, // |
+    Caret, // ^
+    Tilde, // ~
+    LessLess, // <<
+    GreaterGreater, // >>
+    AmpLessLess, // &<<
+    AmpGreaterGreater, // &>>
+    Arrow, // ->
     Let,
     Var,
+    Enum,
+    Case,
+    Switch,
+    Default,
+    If,
+    Else,
+    While,
+    For,
+    In,
+    Guard,
+    Func,
+    Return,
+    Break,
+    Continue,
+    Throw,
+    Throws,
+    Rethrows,
+    Try,
+    Do,
+    Catch,
+    Is,
+    As,
+    Inout,
+    Defer,
+    Protocol,
+    Extension,
+    Static,
+    True,
+    False,
+    Nil,
     Assign, // =
+    /// any other operator such as a user-defined `**`
+    Operator,
+    // compound assignments
+    PlusAssign, // +=
+    MinusAssign, // -=
+    StarAssign, // *=
+    SlashAssign, // /=
+    PercentAssign, // %=
+    AmpAssign, // &=
+    PipeAssign, // |=
+    CaretAssign, // ^=
+    LessLessAssign, // <<=
+    GreaterGreaterAssign, // >>=
     Colon, // :
+    Semicolon, // ;
+    Comma, // ,
+    Dot, // .
+    Underscore, // _
     Identifier,
 }
 
@@ -20,10 +99,67 @@ pub enum TokenType {
 pub struct Token {
     pub token_type: TokenType,
     pub lexeme: String,
+    /// text of the `///` or `/** */` comments right before this token
+    pub doc_comment: Option<String>,
+    /// whether whitespace or a comment precedes the token
+    pub space_before: bool,
+    /// whether a line break precedes the token, which can end a statement
+    pub newline_before: bool,
+    /// whether the identifier was written in backticks, which makes a keyword a plain name
+    pub is_escaped: bool,
 }
 
 impl Token {
     pub fn new(token_type: TokenType, lexeme: String) -> Self {
-        Token { token_type, lexeme}
+        Token { token_type, lexeme, doc_comment: None, space_before: false, newline_before: false, is_escaped: false }
     }
-}
\ No newline at end of file
+
+    /// whether this is the contextual keyword `keyword`, rather than an identifier spelled the same
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        self.token_type == TokenType::Identifier && !self.is_escaped && self.lexeme == keyword
+    }
+
+    /// token for a run of operator characters
+    pub fn operator(lexeme: &str) -> Self {
+        let token_type = match lexeme {
+            "+" => TokenType::Plus,
+            "-" => TokenType::Minus,
+            "*" => TokenType::Star,
+            "/" => TokenType::Slash,
+            "%" => TokenType::Percent,
+            "!" => TokenType::Bang,
+            "?" => TokenType::Question,
+            "??" => TokenType::QuestionQuestion,
+            "==" => TokenType::EqualEqual,
+            "!=" => TokenType::BangEqual,
+            "<" => TokenType::Less,
+            "<=" => TokenType::LessEqual,
+            ">" => TokenType::Greater,
+            ">=" => TokenType::GreaterEqual,
+            "&&" => TokenType::AndAnd,
+            "||" => TokenType::OrOr,
+            "&" => TokenType::Amp,
+            "|" => TokenType::Pipe,
+            "^" => TokenType::Caret,
+            "~" => TokenType::Tilde,
+            "<<" => TokenType::LessLess,
+            ">>" => TokenType::GreaterGreater,
+            "&<<" => TokenType::AmpLessLess,
+            "&>>" => TokenType::AmpGreaterGreater,
+            "->" => TokenType::Arrow,
+            "=" => TokenType::Assign,
+            "+=" => TokenType::PlusAssign,
+            "-=" => TokenType::MinusAssign,
+            "*=" => TokenType::StarAssign,
+            "/=" => TokenType::SlashAssign,
+            "%=" => TokenType::PercentAssign,
+            "&=" => TokenType::AmpAssign,
+            "|=" => TokenType::PipeAssign,
+            "^=" => TokenType::CaretAssign,
+            "<<=" => TokenType::LessLessAssign,
+            ">>=" => TokenType::GreaterGreaterAssign,
+            _ => TokenType::Operator,
+        };
+        Token::new(token_type, lexeme.to_string())
+    }
+}